@@ -3,6 +3,8 @@
 use crate::arch::i386::syscalls::calls::*;
 use crate::syscall;
 
+pub mod net;
+
 pub fn sleep(milliseconds: usize) {
     unsafe {syscall!(SLEEP, milliseconds);}
 }
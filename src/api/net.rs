@@ -0,0 +1,46 @@
+//! Sockets, Each Call Returns `None` If The Kernel Refused It
+
+use crate::arch::i386::syscalls::calls::*;
+use crate::syscall;
+
+pub use crate::arch::i386::syscalls::calls::{SOCKET_TCP, SOCKET_UDP};
+
+fn checked(res: usize) -> Option<usize> {
+    if res == usize::MAX { None } else { Some(res) }
+}
+
+/// Opens A Socket Of `kind` ([SOCKET_TCP] Or [SOCKET_UDP]), Returning Its Id
+pub fn socket(kind: usize) -> Option<usize> {
+    checked(unsafe { syscall!(SOCKET, kind) })
+}
+
+pub fn bind(socket: usize, port: u16) -> Option<()> {
+    checked(unsafe { syscall!(BIND, socket, port) }).map(|_| ())
+}
+
+pub fn listen(socket: usize) -> Option<()> {
+    checked(unsafe { syscall!(LISTEN, socket) }).map(|_| ())
+}
+
+/// Waits For A Connection, Returning The Connected Socket's Id
+pub fn accept(socket: usize) -> Option<usize> {
+    checked(unsafe { syscall!(ACCEPT, socket) })
+}
+
+pub fn connect(socket: usize, addr: [u8; 4], port: u16) -> Option<()> {
+    checked(unsafe { syscall!(CONNECT, socket, u32::from_be_bytes(addr), port) }).map(|_| ())
+}
+
+/// Returns How Many Bytes Were Queued
+pub fn send(socket: usize, data: &[u8]) -> Option<usize> {
+    checked(unsafe { syscall!(SEND, socket, data.as_ptr(), data.len()) })
+}
+
+/// Returns How Many Bytes Were Read, 0 Once A TCP Peer Has Closed
+pub fn recv(socket: usize, buf: &mut [u8]) -> Option<usize> {
+    checked(unsafe { syscall!(RECV, socket, buf.as_mut_ptr(), buf.len()) })
+}
+
+pub fn close(socket: usize) -> Option<()> {
+    checked(unsafe { syscall!(CLOSE, socket) }).map(|_| ())
+}
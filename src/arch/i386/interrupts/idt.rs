@@ -1,5 +1,7 @@
 //! Handles Interacting With the IDT
 
+use core::sync::atomic::{AtomicUsize, Ordering};
+
 use spin::Mutex;
 use x86_64::{instructions::{interrupts, port::Port}, structures::idt::{InterruptStackFrame, InterruptDescriptorTable, PageFaultErrorCode}};
 use crate::{arch::i386::syscalls, debug, inb, println, serial, serial_print, sys::{self, keyboard}};
@@ -38,26 +40,50 @@ pub enum InterruptIndex {
 }
 
 
-#[allow(dead_code)]
 fn interrupt_index(irq: u8) -> u8 {
     super::pics::PIC_1_OFFSET + irq
 }
 
-#[allow(unused)]
+/// Handlers For The Lines PCI Devices Get Routed To, Dispatching Through [IRQ_HANDLERS]
 macro_rules! irq_handler {
     ($handler:ident, $irq:expr) => {
         pub extern "x86-interrupt" fn $handler(_stack_frame: InterruptStackFrame) {
-            let handlers = IRQ_HANDLERS.lock();
-            handlers[$irq]();
-            unsafe { sys::pic::PICS.lock().notify_end_of_interrupt(interrupt_index($irq)); }
+            count_irq($irq);
+            let handler = IRQ_HANDLERS.lock()[$irq];
+            handler();
+            send_eoi(interrupt_index($irq));
         }
     };
 }
 
+irq_handler!(on_irq9, 9);
+irq_handler!(on_irq10, 10);
+irq_handler!(on_irq11, 11);
+
 
 
 
+/// How Many Times Each Of The 16 PIC Lines Has Fired
+static IRQ_COUNTS: [AtomicUsize; 16] = [const { AtomicUsize::new(0) }; 16];
+
+fn count_irq(irq: u8) {
+    IRQ_COUNTS[irq as usize].fetch_add(1, Ordering::Relaxed);
+}
+
+/// Per IRQ Counters Since Boot, Indexed By IRQ Line
+pub fn irq_counts() -> [usize; 16] {
+    let mut counts = [0; 16];
+    for (count, irq) in counts.iter_mut().zip(IRQ_COUNTS.iter()) {
+        *count = irq.load(Ordering::Relaxed);
+    }
+    counts
+}
+
 impl InterruptIndex {
+    fn irq(self) -> u8 {
+        self.as_u8() - PIC_1_OFFSET
+    }
+
     fn as_u8(self) -> u8 {
         self as u8
     }
@@ -85,6 +111,9 @@ lazy_static! {
         idt[InterruptIndex::Lpt1.as_usize()].set_handler_fn(on_spurious_irq);
         idt[InterruptIndex::PrimaryAta.as_usize()].set_handler_fn(on_ata_bus0_rdy);
         idt[InterruptIndex::SecondaryAta.as_usize()].set_handler_fn(on_ata_bus1_rdy);
+        idt[InterruptIndex::Free1.as_usize()].set_handler_fn(on_irq9);
+        idt[InterruptIndex::Free2.as_usize()].set_handler_fn(on_irq10);
+        idt[InterruptIndex::Free3.as_usize()].set_handler_fn(on_irq11);
 
         unsafe {
         idt[0x80].
@@ -125,17 +154,20 @@ extern "x86-interrupt" fn on_page_fault(stack_frame: InterruptStackFrame, ec: Pa
 extern "x86-interrupt" fn on_timer_tick(
     _stack_frame: InterruptStackFrame)
 {
+    count_irq(InterruptIndex::Timer.irq());
     crate::sys::timer::increment();
 	send_eoi(InterruptIndex::Timer.as_u8());
 }
 
 extern "x86-interrupt" fn on_com1_ready(_: InterruptStackFrame) {
+    count_irq(InterruptIndex::Com1.irq());
     serial_print!("Serial Line: {}", serial::read());
     send_eoi(InterruptIndex::Com1.as_u8());
 }
 
 extern "x86-interrupt" fn on_key(_: InterruptStackFrame)
 {
+    count_irq(InterruptIndex::Keyboard.irq());
     use pc_keyboard::{layouts, HandleControl, Keyboard, ScancodeSet1};
 
     lazy_static! {
@@ -158,6 +190,7 @@ extern "x86-interrupt" fn on_key(_: InterruptStackFrame)
 }
 
 extern "x86-interrupt" fn on_spurious_irq(_: InterruptStackFrame) {
+    count_irq(InterruptIndex::Lpt1.irq());
     if !pics::is_spurious(InterruptIndex::Lpt1.as_u8()) {
         send_eoi(InterruptIndex::Lpt1.as_u8());
     }
@@ -166,10 +199,12 @@ extern "x86-interrupt" fn on_spurious_irq(_: InterruptStackFrame) {
 
 
 extern "x86-interrupt" fn on_ata_bus0_rdy(_: InterruptStackFrame) {
+    count_irq(InterruptIndex::PrimaryAta.irq());
 	send_eoi(14);
 }
 
 extern "x86-interrupt" fn on_ata_bus1_rdy(_: InterruptStackFrame) {
+    count_irq(InterruptIndex::SecondaryAta.irq());
     send_eoi(15);
 }
 
@@ -183,13 +218,16 @@ fn default_irq_handler() {
 }
 
 
-/// Set The IRQ's Handler.
+/// Set The IRQ's Handler. Only IRQs 9 - 11 Dispatch Through [IRQ_HANDLERS], The Rest Have
+/// Their Own Entries In The IDT.
 pub fn set_irq_handler(irq: u8, handler: fn()) {
     interrupts::without_interrupts(|| {
         let mut handlers = IRQ_HANDLERS.lock();
         handlers[irq as usize] = handler;
 
         clear_irq_mask(irq);
+        // Lines On The Second PIC Only Get Through If The Cascade Is Unmasked Too
+        if irq >= 8 { clear_irq_mask(InterruptIndex::Cascade.irq()); }
     });
 }
 
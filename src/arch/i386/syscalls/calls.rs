@@ -5,6 +5,18 @@ pub const PRINT_STR: usize =    0b0000010;
 pub const OPEN_FILE: usize =    0b0001000;
 pub const CLOSE_FILE: usize =   0b0001001;
 pub const READ_FILE:  usize =   0b0001010;
+pub const SOCKET: usize =       0b0010000;
+pub const BIND: usize =         0b0010001;
+pub const LISTEN: usize =       0b0010010;
+pub const ACCEPT: usize =       0b0010011;
+pub const CONNECT: usize =      0b0010100;
+pub const SEND: usize =         0b0010101;
+pub const RECV: usize =         0b0010110;
+pub const CLOSE: usize =        0b0010111;
+
+/// `SOCKET` Kinds
+pub const SOCKET_TCP: usize = 0;
+pub const SOCKET_UDP: usize = 1;
 
 
 
@@ -1,7 +1,8 @@
 //use core::ops::RangeInclusive;
-use crate::{print};
+use crate::{KResult, print, sys::net::socket::{self, Kind}};
+use smoltcp::wire::Ipv4Address;
 
-use self::calls::{PRINT_BYTE, PRINT_STR, SLEEP};
+use self::calls::*;
 
 pub mod calls;
 
@@ -60,7 +61,12 @@ pub unsafe fn syscall3(n: usize, arg1: usize, arg2: usize, arg3: usize) -> usize
 }
 
 
-pub fn dispatch(n: usize, arg1: usize, arg2: usize, _arg3: usize) -> usize {
+/// Failed Calls Return `usize::MAX`, Like Unknown Ones
+fn result(res: KResult<usize>) -> usize {
+    res.unwrap_or(usize::MAX)
+}
+
+pub fn dispatch(n: usize, arg1: usize, arg2: usize, arg3: usize) -> usize {
     match n {
         SLEEP => {crate::sys::timer::pause((arg1 as f64) / 1000.0); 0}
         PRINT_BYTE => {print!("{}", (arg1 as u8) as char); 0},
@@ -76,6 +82,19 @@ pub fn dispatch(n: usize, arg1: usize, arg2: usize, _arg3: usize) -> usize {
             }
             0
         },
+        SOCKET => result(match arg1 {
+            SOCKET_TCP => socket::create(Kind::Tcp),
+            SOCKET_UDP => socket::create(Kind::Udp),
+            _ => Err("Unknown Socket Kind"),
+        }),
+        BIND => result(socket::bind(arg1, arg2 as u16).map(|_| 0)),
+        LISTEN => result(socket::listen(arg1).map(|_| 0)),
+        ACCEPT => result(socket::accept(arg1)),
+        // The Address Is An IPv4 Address In Network Order, e.g. 0x0A000202 For 10.0.2.2
+        CONNECT => result(socket::connect(arg1, Ipv4Address::from_bytes(&(arg2 as u32).to_be_bytes()).into(), arg3 as u16).map(|_| 0)),
+        SEND => result(socket::send(arg1, unsafe { core::slice::from_raw_parts(arg2 as *const u8, arg3) })),
+        RECV => result(socket::recv(arg1, unsafe { core::slice::from_raw_parts_mut(arg2 as *mut u8, arg3) })),
+        CLOSE => result(socket::close(arg1).map(|_| 0)),
         _ => {usize::MAX}
     }
 }
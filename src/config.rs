@@ -1,24 +1,190 @@
+//! Boot Configuration
+//!
+//! The Files Under `configs/` Are Built Into The Kernel. There's No TOML Crate That Works
+//! Without `std`, So They're Read By A Small Parser Covering What They Use: `[table]` &
+//! `[[table]]` Headers, `key = value` Pairs With String, Boolean, Integer Or String Array
+//! Values, & `#` Comments.
+
 use alloc::{string::String, vec::Vec};
-use serde::Deserialize;
+use smoltcp::wire::{Ipv4Address, Ipv4Cidr};
+
+use crate::KResult;
 
 const NETWORK_CONF: &'static str = include_str!("./configs/network.toml");
 
-// #[derive(Debug, Clone, Deserialize)]
-// pub struct Interface {
-//     name: String, 
-//     device: String,
-//     dev_type: String,
-    
-//     default_gateway_v4: Option<String>,
-//     dns_v4: Option<Vec<String>>,
-
-//     ipv4: Option<String>,
-//     pingable: Option<bool>,
-// }
-
-// impl Interface {
-//     pub fn get() -> Option<Interface> {
-//         None
-//         //toml::from_str(NETWORK_CONF)
-//     }
-// }
+/// Prefix Length Used When An Address Is Given Without One
+const DEFAULT_PREFIX: u8 = 24;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Value {
+    String(String),
+    Bool(bool),
+    Integer(i64),
+    Array(Vec<String>),
+}
+
+/// One `[name]` Or `[[name]]` Table & Its Pairs, In File Order
+#[derive(Debug, Clone, PartialEq)]
+pub struct Table {
+    pub name: String,
+    pub pairs: Vec<(String, Value)>,
+}
+
+impl Table {
+    pub fn get(&self, key: &str) -> Option<&Value> {
+        self.pairs.iter().find(|(k, _)| k == key).map(|(_, value)| value)
+    }
+
+    fn string(&self, key: &str) -> KResult<Option<&str>> {
+        match self.get(key) {
+            Some(Value::String(s)) => Ok(Some(s)),
+            Some(_) => Err("Config Value Should Be A String"),
+            None => Ok(None),
+        }
+    }
+
+    fn bool(&self, key: &str) -> KResult<Option<bool>> {
+        match self.get(key) {
+            Some(Value::Bool(b)) => Ok(Some(*b)),
+            Some(_) => Err("Config Value Should Be true Or false"),
+            None => Ok(None),
+        }
+    }
+}
+
+/// Strips A Comment, Leaving Any `#` Inside A String Alone
+fn strip_comment(line: &str) -> &str {
+    let mut quoted = false;
+    for (i, c) in line.char_indices() {
+        match c {
+            '"' => quoted = !quoted,
+            '#' if !quoted => return &line[..i],
+            _ => {},
+        }
+    }
+    line
+}
+
+fn parse_string(s: &str) -> KResult<String> {
+    let inner = s.strip_prefix('"').and_then(|s| s.strip_suffix('"')).ok_or("Unterminated Config String")?;
+    if inner.contains('"') { return Err("Unterminated Config String"); }
+    Ok(String::from(inner))
+}
+
+fn parse_value(s: &str) -> KResult<Value> {
+    match s {
+        "true" => return Ok(Value::Bool(true)),
+        "false" => return Ok(Value::Bool(false)),
+        _ => {},
+    }
+    if s.starts_with('"') { return parse_string(s).map(Value::String); }
+    if let Some(inner) = s.strip_prefix('[') {
+        let inner = inner.strip_suffix(']').ok_or("Unterminated Config Array")?.trim();
+        let inner = inner.strip_suffix(',').unwrap_or(inner);
+        if inner.trim().is_empty() { return Ok(Value::Array(Vec::new())); }
+        return inner.split(',').map(|item| parse_string(item.trim())).collect::<KResult<_>>().map(Value::Array);
+    }
+    s.parse().map(Value::Integer).map_err(|_| "Invalid Config Value")
+}
+
+/// Splits A Config File Into Its Tables, Pairs Before Any Header Go In One Named ""
+pub fn parse(src: &str) -> KResult<Vec<Table>> {
+    let mut tables = alloc::vec![Table { name: String::new(), pairs: Vec::new() }];
+    for line in src.lines() {
+        let line = strip_comment(line).trim();
+        if line.is_empty() { continue; }
+        if let Some(header) = line.strip_prefix('[') {
+            let name = header.strip_prefix('[').and_then(|h| h.strip_suffix("]]")).or_else(|| header.strip_suffix(']')).ok_or("Bad Config Table Header")?;
+            tables.push(Table { name: String::from(name.trim()), pairs: Vec::new() });
+            continue;
+        }
+        let (key, value) = line.split_once('=').ok_or("Expected key = value In Config")?;
+        let key = key.trim();
+        if key.is_empty() || key.contains(char::is_whitespace) { return Err("Bad Config Key"); }
+        let table = tables.last_mut().expect("Always At Least One Table");
+        if table.get(key).is_some() { return Err("Duplicate Config Key"); }
+        table.pairs.push((String::from(key), parse_value(value.trim())?));
+    }
+    if tables[0].pairs.is_empty() { tables.remove(0); }
+    Ok(tables)
+}
+
+/// Reads `a.b.c.d/prefix`, Or A Bare Address With The [DEFAULT_PREFIX]
+pub fn parse_cidr(s: &str) -> KResult<Ipv4Cidr> {
+    let (addr, prefix) = match s.split_once('/') {
+        Some((addr, prefix)) => (addr, prefix.parse::<u8>().map_err(|_| "Invalid Prefix Length")?),
+        None => (s, DEFAULT_PREFIX),
+    };
+    if prefix > 32 { return Err("Invalid Prefix Length"); }
+    Ok(Ipv4Cidr::new(addr.parse().map_err(|_| "Invalid IPv4 Address")?, prefix))
+}
+
+fn parse_addr(s: &str) -> KResult<Ipv4Address> {
+    s.parse().map_err(|_| "Invalid IPv4 Address")
+}
+
+/// An `[interface]` Table From `configs/network.toml`
+#[derive(Debug, Clone, PartialEq)]
+pub struct Interface {
+    pub name: String,
+    /// The Device To Look For, As `BUS/VENDOR/DEVICE`. Repeat It For Several Cards Of One Model,
+    /// They Take The Entries In PCI Order.
+    pub device: String,
+    pub dev_type: String,
+    /// Whether To Ask A DHCP Server, Defaults To Doing So Unless A Static Address Is Given.
+    /// Static Gateway & DNS Settings Still Win Over The Lease's.
+    pub dhcp: bool,
+    pub ipv4: Option<Ipv4Cidr>,
+    pub default_gateway_v4: Option<Ipv4Address>,
+    pub dns_v4: Vec<Ipv4Address>,
+    pub pingable: bool,
+}
+
+impl Interface {
+    fn from_table(table: &Table) -> KResult<Self> {
+        let ipv4 = table.string("ipv4")?.map(parse_cidr).transpose()?;
+        Ok(Self {
+            name: String::from(table.string("name")?.ok_or("Interface Needs A name")?),
+            device: String::from(table.string("device")?.ok_or("Interface Needs A device")?),
+            dev_type: String::from(table.string("dev_type")?.ok_or("Interface Needs A dev_type")?),
+            dhcp: table.bool("dhcp")?.unwrap_or(ipv4.is_none()),
+            ipv4,
+            default_gateway_v4: table.string("default_gateway_v4")?.map(parse_addr).transpose()?,
+            dns_v4: match table.get("dns_v4") {
+                Some(Value::Array(servers)) => servers.iter().map(|server| parse_addr(server)).collect::<KResult<_>>()?,
+                Some(_) => return Err("dns_v4 Should Be An Array"),
+                None => Vec::new(),
+            },
+            pingable: table.bool("pingable")?.unwrap_or(true),
+        })
+    }
+
+    /// Every Interface In `src`
+    pub fn parse(src: &str) -> KResult<Vec<Interface>> {
+        parse(src)?.iter().filter(|table| table.name == "interface").map(Self::from_table).collect()
+    }
+
+    /// Every Interface In The Built In `network.toml`
+    pub fn all() -> KResult<Vec<Interface>> {
+        Self::parse(NETWORK_CONF)
+    }
+
+    /// The First Interface Configured For `device`
+    pub fn get(device: &str) -> Option<Interface> {
+        Self::all().ok()?.into_iter().find(|iface| iface.device == device)
+    }
+}
+
+#[test_case]
+fn config_parses_interfaces() {
+    let src = "# Comment\n[interface]\n    name = \"eth0\" # Trailing\n    device = \"PCI/REALTEK/RTL8139\"\n    dev_type = \"Auto\"\n    ipv4 = \"192.168.1.7/16\"\n    dns_v4 = [\"1.1.1.1\", \"9.9.9.9\",]\n    pingable = false\n[[interface]]\nname = \"eth1\"\ndevice = \"PCI/INTEL/82540EM\"\ndev_type = \"Ethernet\"\n";
+    let ifaces = Interface::parse(src).expect("");
+    assert_eq!(ifaces.len(), 2);
+    assert_eq!(ifaces[0].ipv4, Some(Ipv4Cidr::new(Ipv4Address::new(192, 168, 1, 7), 16)));
+    assert_eq!(ifaces[0].dns_v4, alloc::vec![Ipv4Address::new(1, 1, 1, 1), Ipv4Address::new(9, 9, 9, 9)]);
+    assert!(!ifaces[0].dhcp && !ifaces[0].pingable);
+    assert!(ifaces[1].dhcp && ifaces[1].pingable && ifaces[1].default_gateway_v4.is_none());
+    assert_eq!(parse("a = 1\na = 2"), Err("Duplicate Config Key"));
+    assert_eq!(parse_cidr("10.0.2.15"), Ok(Ipv4Cidr::new(Ipv4Address::new(10, 0, 2, 15), 24)));
+    assert!(Interface::all().is_ok());
+}
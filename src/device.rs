@@ -1,27 +1,50 @@
 //! Handles Device IO, Supports:
-//! - Block Devices - 'dev/null' ([NullDevice])
-//! - Character Devices - 'dev/null' ([NullDevice]), 'dev/tty' | 'dev/comm' ([SerialPort])
+//! - Block Devices - 'dev/null' ([NullDevice]), 'dev/ata/<bus>/<drive>' ([Disk]), 'dev/sata/<port>' ([SataDisk]), 'dev/virtio/<n>' ([VirtioDisk]), 'dev/nvme/<n>' ([NvmeDisk]), 'dev/loop/<n>' ([LoopDisk]), 'dev/md/<n>' ([MirrorDisk])
+//! - Character Devices - 'dev/null' ([NullDevice]), 'dev/zero' ([ZeroDevice]), 'dev/random' ([RandomDevice]), 'dev/fb' ([FramebufferDevice]), 'dev/rtl8139' | 'dev/e1000' | 'dev/virtio-net', Then 'dev/e1000-1' & So On ([NicDevice]), 'dev/tty' | 'dev/comm' ([SerialPort])
+
+use core::sync::atomic::{AtomicU64, Ordering};
 
 use alloc::vec::Vec;
 use uart_16550::SerialPort;
+use x86_64::instructions::random::RdRand;
 
-use crate::{KResult, sys::{ata}};
+use crate::{KResult, sys::{ahci, ata, loopdev, net, nvme, raid, virtio}};
 
 pub struct Device;
 pub struct Disk(u8, u8);
+pub struct SataDisk(u8);
+pub struct VirtioDisk(u8);
+pub struct NvmeDisk(u8);
+pub struct LoopDisk(u8);
+pub struct MirrorDisk(u8);
 
 pub enum DeviceHandle {
     Serial(SerialPort),
     Null(NullDevice),
-    Ata(Disk)
+    Ata(Disk),
+    Sata(SataDisk),
+    Virtio(VirtioDisk),
+    Nvme(NvmeDisk),
+    Loop(LoopDisk),
+    Mirror(MirrorDisk),
+    Zero(ZeroDevice),
+    Random(RandomDevice),
+    Framebuffer(FramebufferDevice),
+    Nic(NicDevice),
 }
 
 impl DeviceHandle {
     pub fn as_block_device(&self) -> Option<&dyn BlockDevice> {
         match self {
             Self::Ata(dev) => Some(dev),
+            Self::Sata(dev) => Some(dev),
+            Self::Virtio(dev) => Some(dev),
+            Self::Nvme(dev) => Some(dev),
+            Self::Loop(dev) => Some(dev),
+            Self::Mirror(dev) => Some(dev),
             Self::Null(dev) => Some(dev),
-            Self::Serial(_) => None
+            Self::Serial(_) => None,
+            Self::Zero(_) | Self::Random(_) | Self::Framebuffer(_) | Self::Nic(_) => None
         }
     }
 }
@@ -31,7 +54,16 @@ impl CharDevice for DeviceHandle {
         match self {
             Self::Null(dev) => dev.read_u8(addr),
             Self::Serial(dev) => dev.read_u8(addr),
+            Self::Zero(dev) => dev.read_u8(addr),
+            Self::Random(dev) => dev.read_u8(addr),
+            Self::Framebuffer(dev) => dev.read_u8(addr),
+            Self::Nic(dev) => dev.read_u8(addr),
             Self::Ata(_) => None,
+            Self::Sata(_) => None,
+            Self::Virtio(_) => None,
+            Self::Nvme(_) => None,
+            Self::Loop(_) => None,
+            Self::Mirror(_) => None,
         }
     }
 
@@ -39,7 +71,16 @@ impl CharDevice for DeviceHandle {
         match self {
             Self::Null(dev) => dev.read_u16(addr),
             Self::Serial(dev) => dev.read_u16(addr),
+            Self::Zero(dev) => dev.read_u16(addr),
+            Self::Random(dev) => dev.read_u16(addr),
+            Self::Framebuffer(dev) => dev.read_u16(addr),
+            Self::Nic(dev) => dev.read_u16(addr),
             Self::Ata(_) => None,
+            Self::Sata(_) => None,
+            Self::Virtio(_) => None,
+            Self::Nvme(_) => None,
+            Self::Loop(_) => None,
+            Self::Mirror(_) => None,
         }
     }
 
@@ -47,7 +88,16 @@ impl CharDevice for DeviceHandle {
         match self {
             Self::Null(dev) => dev.read_u32(addr),
             Self::Serial(dev) => dev.read_u32(addr),
+            Self::Zero(dev) => dev.read_u32(addr),
+            Self::Random(dev) => dev.read_u32(addr),
+            Self::Framebuffer(dev) => dev.read_u32(addr),
+            Self::Nic(dev) => dev.read_u32(addr),
             Self::Ata(_) => None,
+            Self::Sata(_) => None,
+            Self::Virtio(_) => None,
+            Self::Nvme(_) => None,
+            Self::Loop(_) => None,
+            Self::Mirror(_) => None,
         }
     }
 
@@ -55,7 +105,16 @@ impl CharDevice for DeviceHandle {
         match self {
             Self::Null(dev) => dev.read_u64(addr),
             Self::Serial(dev) => dev.read_u64(addr),
+            Self::Zero(dev) => dev.read_u64(addr),
+            Self::Random(dev) => dev.read_u64(addr),
+            Self::Framebuffer(dev) => dev.read_u64(addr),
+            Self::Nic(dev) => dev.read_u64(addr),
             Self::Ata(_) => None,
+            Self::Sata(_) => None,
+            Self::Virtio(_) => None,
+            Self::Nvme(_) => None,
+            Self::Loop(_) => None,
+            Self::Mirror(_) => None,
         }
     }
 
@@ -63,7 +122,16 @@ impl CharDevice for DeviceHandle {
         match self {
             Self::Null(dev) => dev.read_u128(addr),
             Self::Serial(dev) => dev.read_u128(addr),
+            Self::Zero(dev) => dev.read_u128(addr),
+            Self::Random(dev) => dev.read_u128(addr),
+            Self::Framebuffer(dev) => dev.read_u128(addr),
+            Self::Nic(dev) => dev.read_u128(addr),
             Self::Ata(_) => None,
+            Self::Sata(_) => None,
+            Self::Virtio(_) => None,
+            Self::Nvme(_) => None,
+            Self::Loop(_) => None,
+            Self::Mirror(_) => None,
         }
     }
 
@@ -71,7 +139,16 @@ impl CharDevice for DeviceHandle {
         match self {
             Self::Null(dev) => dev.write_u8(addr, value),
             Self::Serial(dev) => dev.write_u8(addr, value),
+            Self::Zero(dev) => dev.write_u8(addr, value),
+            Self::Random(dev) => dev.write_u8(addr, value),
+            Self::Framebuffer(dev) => dev.write_u8(addr, value),
+            Self::Nic(dev) => dev.write_u8(addr, value),
             Self::Ata(_) => Err("Cannot Use An Ata Device As A Character Device"),
+            Self::Sata(_) => Err("Cannot Use A Sata Device As A Character Device"),
+            Self::Virtio(_) => Err("Cannot Use A Virtio Device As A Character Device"),
+            Self::Nvme(_) => Err("Cannot Use An NVMe Device As A Character Device"),
+            Self::Loop(_) => Err("Cannot Use A Loop Device As A Character Device"),
+            Self::Mirror(_) => Err("Cannot Use A Mirror As A Character Device"),
         }
     }
 
@@ -79,7 +156,16 @@ impl CharDevice for DeviceHandle {
         match self {
             Self::Null(dev) => dev.size(),
             Self::Serial(dev) => dev.size(),
+            Self::Zero(dev) => dev.size(),
+            Self::Random(dev) => dev.size(),
+            Self::Framebuffer(dev) => dev.size(),
+            Self::Nic(dev) => dev.size(),
             Self::Ata(_) => None,
+            Self::Sata(_) => None,
+            Self::Virtio(_) => None,
+            Self::Nvme(_) => None,
+            Self::Loop(_) => None,
+            Self::Mirror(_) => None,
         }
     }
 
@@ -87,7 +173,16 @@ impl CharDevice for DeviceHandle {
         match self {
             Self::Null(dev) => dev.slice(),
             Self::Serial(dev) => dev.slice(),
+            Self::Zero(dev) => dev.slice(),
+            Self::Random(dev) => dev.slice(),
+            Self::Framebuffer(dev) => dev.slice(),
+            Self::Nic(dev) => dev.slice(),
             Self::Ata(_) => None,
+            Self::Sata(_) => None,
+            Self::Virtio(_) => None,
+            Self::Nvme(_) => None,
+            Self::Loop(_) => None,
+            Self::Mirror(_) => None,
         }
     }
 
@@ -95,7 +190,16 @@ impl CharDevice for DeviceHandle {
         match self {
             Self::Null(dev) => dev.slice_mut(),
             Self::Serial(dev) => dev.slice_mut(),
+            Self::Zero(dev) => dev.slice_mut(),
+            Self::Random(dev) => dev.slice_mut(),
+            Self::Framebuffer(dev) => dev.slice_mut(),
+            Self::Nic(dev) => dev.slice_mut(),
             Self::Ata(_) => None,
+            Self::Sata(_) => None,
+            Self::Virtio(_) => None,
+            Self::Nvme(_) => None,
+            Self::Loop(_) => None,
+            Self::Mirror(_) => None,
         }
     }
 }
@@ -105,7 +209,13 @@ impl BlockDevice for DeviceHandle {
         match self {
             DeviceHandle::Null(_) => Ok(()),
             &DeviceHandle::Serial(_) => Err("Serial Line Is Not A Block Device"),
+            DeviceHandle::Zero(_) | DeviceHandle::Random(_) | DeviceHandle::Framebuffer(_) | DeviceHandle::Nic(_) => Err("Not A Block Device"),
             DeviceHandle::Ata(dev) => dev.read(addr, buf),
+            DeviceHandle::Sata(dev) => dev.read(addr, buf),
+            DeviceHandle::Virtio(dev) => dev.read(addr, buf),
+            DeviceHandle::Nvme(dev) => dev.read(addr, buf),
+            DeviceHandle::Loop(dev) => dev.read(addr, buf),
+            DeviceHandle::Mirror(dev) => dev.read(addr, buf),
         }
     }
 
@@ -113,7 +223,13 @@ impl BlockDevice for DeviceHandle {
         match self {
             DeviceHandle::Null(_) => Ok(()),
             DeviceHandle::Serial(_) => Err("Serial Line Is Not A Block Device"),
+            DeviceHandle::Zero(_) | DeviceHandle::Random(_) | DeviceHandle::Framebuffer(_) | DeviceHandle::Nic(_) => Err("Not A Block Device"),
             DeviceHandle::Ata(dev) => dev.write(addr, buf),
+            DeviceHandle::Sata(dev) => dev.write(addr, buf),
+            DeviceHandle::Virtio(dev) => dev.write(addr, buf),
+            DeviceHandle::Nvme(dev) => dev.write(addr, buf),
+            DeviceHandle::Loop(dev) => dev.write(addr, buf),
+            DeviceHandle::Mirror(dev) => dev.write(addr, buf),
         }
     }
 
@@ -121,7 +237,13 @@ impl BlockDevice for DeviceHandle {
         match self {
             DeviceHandle::Null(_) => None,
             DeviceHandle::Serial(_) => None,
+            DeviceHandle::Zero(_) | DeviceHandle::Random(_) | DeviceHandle::Framebuffer(_) | DeviceHandle::Nic(_) => None,
             DeviceHandle::Ata(dev) => dev.block_count(),
+            DeviceHandle::Sata(dev) => dev.block_count(),
+            DeviceHandle::Virtio(dev) => dev.block_count(),
+            DeviceHandle::Nvme(dev) => dev.block_count(),
+            DeviceHandle::Loop(dev) => dev.block_count(),
+            DeviceHandle::Mirror(dev) => dev.block_count(),
         }
     }
 }
@@ -137,7 +259,13 @@ impl Device {
                 let mut tty = unsafe {SerialPort::new(0x3F8)};
                 tty.init();
                 Ok(DeviceHandle::Serial(tty))},
-            _ => Err("Not A Valid Char Device Type"),
+            "zero" => Ok(DeviceHandle::Zero(ZeroDevice)),
+            "random" => Ok(DeviceHandle::Random(RandomDevice)),
+            "fb" => Ok(DeviceHandle::Framebuffer(FramebufferDevice)),
+            name => match net::nic_index(name) {
+                Some(index) => Ok(DeviceHandle::Nic(NicDevice(index))),
+                None => Err("Not A Valid Char Device Type"),
+            },
         }
     }
     pub fn open_block_dev(path: &str) -> KResult<DeviceHandle> {
@@ -151,6 +279,26 @@ impl Device {
                     let disk: u8 = sections[3].parse().expect("Expected A Byte");
                     Ok(DeviceHandle::Ata(Disk(bus, disk)))
                 },
+            "sata" => {
+                    let port: u8 = sections[2].parse().expect("Expected A Byte");
+                    Ok(DeviceHandle::Sata(SataDisk(port)))
+                },
+            "virtio" => {
+                    let index: u8 = sections[2].parse().expect("Expected A Byte");
+                    Ok(DeviceHandle::Virtio(VirtioDisk(index)))
+                },
+            "nvme" => {
+                    let index: u8 = sections[2].parse().expect("Expected A Byte");
+                    Ok(DeviceHandle::Nvme(NvmeDisk(index)))
+                },
+            "loop" => {
+                    let index: u8 = sections[2].parse().expect("Expected A Byte");
+                    Ok(DeviceHandle::Loop(LoopDisk(index)))
+                },
+            "md" => {
+                    let index: u8 = sections[2].parse().expect("Expected A Byte");
+                    Ok(DeviceHandle::Mirror(MirrorDisk(index)))
+                },
             _ => Err("Not A Valid Block Device Type"),
         }
     }
@@ -299,6 +447,206 @@ impl BlockDevice for NullDevice {
     }
 }
 
+/// Reads `N` Consecutive Bytes, Most Significant First To Match The `write_*` Defaults
+fn read_bytes<const N: usize>(dev: &dyn CharDevice, addr: usize) -> Option<[u8; N]> {
+    let mut bytes = [0; N];
+    for (index, byte) in bytes.iter_mut().enumerate() {
+        *byte = dev.read_u8(addr + index)?;
+    }
+    Some(bytes)
+}
+
+/// Endless Zeroes, Writes Are Discarded
+pub struct ZeroDevice;
+
+impl CharDevice for ZeroDevice {
+    fn read_u8(&self, _: usize) ->   Option<u8> {
+        Some(0)
+    }
+
+    fn read_u16(&self, _: usize) ->  Option<u16> {
+        Some(0)
+    }
+
+    fn read_u32(&self, _: usize) ->  Option<u32> {
+        Some(0)
+    }
+
+    fn read_u64(&self, _: usize) ->  Option<u64> {
+        Some(0)
+    }
+
+    fn read_u128(&self, _: usize) -> Option<u128> {
+        Some(0)
+    }
+
+    fn write_u8  (&mut self, _: usize, _: u8)   -> KResult<()> {
+        Ok(())
+    }
+
+    fn size(&self) -> Option<usize> {
+        None
+    }
+
+    fn slice(&self) -> Option<&[u8]> {
+        None
+    }
+
+    fn slice_mut(&mut self) -> Option<&mut [u8]> {
+        None
+    }
+}
+
+/// Xorshift State For CPUs Without RDRAND, Seeded From The Timestamp Counter On First Use
+static RANDOM_STATE: AtomicU64 = AtomicU64::new(0);
+
+/// Random Bytes From RDRAND, Falling Back To Xorshift. Not Fit For Key Material On The
+/// Fallback Path.
+pub struct RandomDevice;
+
+impl RandomDevice {
+    fn next(&self) -> u64 {
+        if let Some(value) = RdRand::new().and_then(|rdrand| rdrand.get_u64()) {
+            return value;
+        }
+
+        let mut x = RANDOM_STATE.load(Ordering::Relaxed);
+        if x == 0 { x = unsafe { core::arch::x86_64::_rdtsc() } | 1; }
+        x ^= x << 13;
+        x ^= x >> 7;
+        x ^= x << 17;
+        RANDOM_STATE.store(x, Ordering::Relaxed);
+        x
+    }
+}
+
+impl CharDevice for RandomDevice {
+    fn read_u8(&self, _: usize) ->   Option<u8> {
+        Some(self.next() as u8)
+    }
+
+    fn read_u16(&self, _: usize) ->  Option<u16> {
+        Some(self.next() as u16)
+    }
+
+    fn read_u32(&self, _: usize) ->  Option<u32> {
+        Some(self.next() as u32)
+    }
+
+    fn read_u64(&self, _: usize) ->  Option<u64> {
+        Some(self.next())
+    }
+
+    fn read_u128(&self, _: usize) -> Option<u128> {
+        Some((self.next() as u128) << 64 | self.next() as u128)
+    }
+
+    fn write_u8  (&mut self, _: usize, _: u8)   -> KResult<()> {
+        Ok(())
+    }
+
+    fn size(&self) -> Option<usize> {
+        None
+    }
+
+    fn slice(&self) -> Option<&[u8]> {
+        None
+    }
+
+    fn slice_mut(&mut self) -> Option<&mut [u8]> {
+        None
+    }
+}
+
+const FRAMEBUFFER_ADDR: usize = 0xb8000;
+/// 80x25 Cells Of Character & Colour Bytes
+const FRAMEBUFFER_SIZE: usize = 80 * 25 * 2;
+
+/// The Raw VGA Text Buffer, Writes Show Up On Screen But Bypass The Console's Cursor
+pub struct FramebufferDevice;
+
+impl CharDevice for FramebufferDevice {
+    fn read_u8(&self, addr: usize) ->   Option<u8> {
+        self.slice()?.get(addr).copied()
+    }
+
+    fn read_u16(&self, addr: usize) ->  Option<u16> {
+        read_bytes(self, addr).map(u16::from_be_bytes)
+    }
+
+    fn read_u32(&self, addr: usize) ->  Option<u32> {
+        read_bytes(self, addr).map(u32::from_be_bytes)
+    }
+
+    fn read_u64(&self, addr: usize) ->  Option<u64> {
+        read_bytes(self, addr).map(u64::from_be_bytes)
+    }
+
+    fn read_u128(&self, addr: usize) -> Option<u128> {
+        read_bytes(self, addr).map(u128::from_be_bytes)
+    }
+
+    fn write_u8  (&mut self, addr: usize, value: u8)   -> KResult<()> {
+        let byte = self.slice_mut().and_then(|fb| fb.get_mut(addr)).ok_or("Write Past End Of Framebuffer")?;
+        unsafe { core::ptr::write_volatile(byte, value) };
+        Ok(())
+    }
+
+    fn size(&self) -> Option<usize> {
+        Some(FRAMEBUFFER_SIZE)
+    }
+
+    fn slice(&self) -> Option<&[u8]> {
+        Some(unsafe { core::slice::from_raw_parts(FRAMEBUFFER_ADDR as *const u8, FRAMEBUFFER_SIZE) })
+    }
+
+    fn slice_mut(&mut self) -> Option<&mut [u8]> {
+        Some(unsafe { core::slice::from_raw_parts_mut(FRAMEBUFFER_ADDR as *mut u8, FRAMEBUFFER_SIZE) })
+    }
+}
+
+/// A Network Card, By Interface Index. Reads Give Its MAC Address, Frames Go Through `sys::net`.
+pub struct NicDevice(usize);
+
+impl CharDevice for NicDevice {
+    fn read_u8(&self, addr: usize) ->   Option<u8> {
+        let ifaces = net::IFACES.lock();
+        ifaces.get(self.0)?.iface.ethernet_addr().as_bytes().get(addr).copied()
+    }
+
+    fn read_u16(&self, addr: usize) ->  Option<u16> {
+        read_bytes(self, addr).map(u16::from_be_bytes)
+    }
+
+    fn read_u32(&self, addr: usize) ->  Option<u32> {
+        read_bytes(self, addr).map(u32::from_be_bytes)
+    }
+
+    fn read_u64(&self, addr: usize) ->  Option<u64> {
+        read_bytes(self, addr).map(u64::from_be_bytes)
+    }
+
+    fn read_u128(&self, addr: usize) -> Option<u128> {
+        read_bytes(self, addr).map(u128::from_be_bytes)
+    }
+
+    fn write_u8  (&mut self, _: usize, _: u8)   -> KResult<()> {
+        Err("Frames Must Be Sent Through The Network Stack")
+    }
+
+    fn size(&self) -> Option<usize> {
+        Some(6)
+    }
+
+    fn slice(&self) -> Option<&[u8]> {
+        None
+    }
+
+    fn slice_mut(&mut self) -> Option<&mut [u8]> {
+        None
+    }
+}
+
 impl BlockDevice for Disk {
     fn read(&self, addr: usize,buf: &mut [u8]) -> KResult<()> {
         ata::read(self.0, self.1, addr as u32, buf);
@@ -315,6 +663,75 @@ impl BlockDevice for Disk {
     }
 }
 
+impl BlockDevice for SataDisk {
+    fn read(&self, addr: usize,buf: &mut [u8]) -> KResult<()> {
+        ahci::read(self.0, addr as u32, buf)
+    }
+
+    fn write(&mut self, addr: usize, buf: &[u8]) -> KResult<()> {
+        ahci::write(self.0, addr as u32, buf)
+    }
+
+    fn block_count(&self) -> Option<usize> {
+        Some(ahci::sector_count(self.0) as usize)
+    }
+}
+
+impl BlockDevice for VirtioDisk {
+    fn read(&self, addr: usize,buf: &mut [u8]) -> KResult<()> {
+        virtio::blk::read(self.0, addr as u32, buf)
+    }
+
+    fn write(&mut self, addr: usize, buf: &[u8]) -> KResult<()> {
+        virtio::blk::write(self.0, addr as u32, buf)
+    }
+
+    fn block_count(&self) -> Option<usize> {
+        Some(virtio::blk::sector_count(self.0) as usize)
+    }
+}
+
+impl BlockDevice for NvmeDisk {
+    fn read(&self, addr: usize,buf: &mut [u8]) -> KResult<()> {
+        nvme::read(self.0, addr as u32, buf)
+    }
+
+    fn write(&mut self, addr: usize, buf: &[u8]) -> KResult<()> {
+        nvme::write(self.0, addr as u32, buf)
+    }
+
+    fn block_count(&self) -> Option<usize> {
+        Some(nvme::sector_count(self.0) as usize)
+    }
+}
+
+impl BlockDevice for LoopDisk {
+    fn read(&self, addr: usize,buf: &mut [u8]) -> KResult<()> {
+        loopdev::read(self.0, addr as u32, buf)
+    }
+
+    fn write(&mut self, addr: usize, buf: &[u8]) -> KResult<()> {
+        loopdev::write(self.0, addr as u32, buf)
+    }
+
+    fn block_count(&self) -> Option<usize> {
+        Some(loopdev::sector_count(self.0) as usize)
+    }
+}
+
+impl BlockDevice for MirrorDisk {
+    fn read(&self, addr: usize,buf: &mut [u8]) -> KResult<()> {
+        raid::read(self.0, addr as u32, buf)
+    }
+
+    fn write(&mut self, addr: usize, buf: &[u8]) -> KResult<()> {
+        raid::write(self.0, addr as u32, buf)
+    }
+
+    fn block_count(&self) -> Option<usize> {
+        Some(raid::sector_count(self.0) as usize)
+    }
+}
 
 
 #[test_case]
@@ -348,6 +765,14 @@ fn comm_device() {
     dev.write_u8(0, b'A').expect("");
 }
 
+#[test_case]
+fn zero_and_random_devices() {
+    let dev = Device::open_char_dev("dev/zero").expect("");
+    assert_eq!(dev.read_u64(0), Some(0));
+    let dev = Device::open_char_dev("dev/random").expect("");
+    assert!(dev.read_u128(0).is_some());
+}
+
 /// Should Fail, 'dev/invalid' Doesn't Exist
 #[test_case]
 fn bad_device() {
@@ -26,6 +26,7 @@ pub mod macros;
 pub mod arch;
 pub mod sys;
 pub mod device;
+pub mod config;
 
 pub mod api;
 
@@ -89,6 +90,10 @@ pub extern "C" fn _start(boot_info: &'static bootloader::BootInfo) -> ! {
 	pci::init();
 	net::init();
 	sys::ata::init();
+	sys::ahci::init();
+	sys::virtio::blk::init();
+	sys::nvme::init();
+	sys::vfs::init();
     test_main();
     loop {}
 }
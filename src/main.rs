@@ -42,6 +42,10 @@ pub fn kernel_main(boot_info: &'static BootInfo) -> ! {
 	pci::init();
 	net::init();
 	ata::init();
+	ahci::init();
+	virtio::blk::init();
+	nvme::init();
+	vfs::init();
 
 	
 	
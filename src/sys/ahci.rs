@@ -0,0 +1,354 @@
+//! AHCI SATA Controller Driver
+//!
+//! Controllers Are Discovered Through [pci] (Class 01:06), Every Implemented Port With An
+//! ATA Drive Attached Is Given A Command List, FIS Receive Area & A Single Command Table.
+//! Commands Are Issued One At A Time In Slot 0 (No NCQ).
+
+use alloc::string::String;
+use alloc::vec::Vec;
+use bit_field::BitField;
+use core::hint::spin_loop;
+use core::ptr::{read_volatile, write_volatile};
+use lazy_static::lazy_static;
+use spin::Mutex;
+use x86_64::PhysAddr;
+use x86_64::instructions::interrupts::without_interrupts;
+
+use crate::{KResult, print, sys::{mem::{self, allocator::PhysBuf}, pci}};
+
+const CLASS_MASS_STORAGE: u8 = 0x01;
+const SUBCLASS_SATA: u8 = 0x06;
+
+// Generic Host Control Registers
+const HBA_GHC: usize = 0x04; // Global Host Control
+const HBA_PI: usize = 0x0C;  // Ports Implemented
+const GHC_AE: u32 = 1 << 31; // AHCI Enable
+
+// Port Registers, Port N Starts At 0x100 + (N * 0x80)
+const PORT_BASE: usize = 0x100;
+const PORT_SIZE: usize = 0x80;
+const PORT_CLB: usize = 0x00;  // Command List Base Address
+const PORT_CLBU: usize = 0x04; // Command List Base Address Upper 32-Bits
+const PORT_FB: usize = 0x08;   // FIS Base Address
+const PORT_FBU: usize = 0x0C;  // FIS Base Address Upper 32-Bits
+const PORT_IS: usize = 0x10;   // Interrupt Status
+const PORT_CMD: usize = 0x18;  // Command And Status
+const PORT_TFD: usize = 0x20;  // Task File Data
+const PORT_SIG: usize = 0x24;  // Signature
+const PORT_SSTS: usize = 0x28; // SATA Status
+const PORT_SERR: usize = 0x30; // SATA Error
+const PORT_CI: usize = 0x38;   // Command Issue
+
+const CMD_ST: u32 = 1 << 0;   // Start
+const CMD_FRE: u32 = 1 << 4;  // FIS Receive Enable
+const CMD_FR: u32 = 1 << 14;  // FIS Receive Running
+const CMD_CR: u32 = 1 << 15;  // Command List Running
+
+const TFD_ERR: u32 = 1 << 0;
+const TFD_DRQ: u32 = 1 << 3;
+const TFD_BSY: u32 = 1 << 7;
+
+const IS_TFES: u32 = 1 << 30; // Task File Error Status
+
+const SSTS_DET_PRESENT: u32 = 0x3;
+const SIG_ATA: u32 = 0x0000_0101;
+
+const FIS_TYPE_REG_H2D: u8 = 0x27;
+
+const CMD_LIST_LEN: usize = 32 * 32; // 32 Headers Of 32 Bytes
+const FIS_LEN: usize = 256;
+const CMD_TABLE_LEN: usize = 0x80 + 16; // Header + 1 PRDT Entry
+const SECTOR_SIZE: usize = 512;
+
+/// Register Polls Before A Wait Gives Up. Reads & Writes Poll With Interrupts Off, Where The
+/// Timer Doesn't Tick, So Waits Are Bounded By Count. Each Poll Crosses The Bus, Which Keeps
+/// This Around A Second.
+const POLL_LIMIT: u32 = 1_000_000;
+
+// IDENTIFY Words
+const ID_COMMAND_SET_2: usize = 83;
+const ID_LBA48_SUPPORTED: usize = 10; // Bit Of Word 83
+const ID_LBA28_SECTORS: usize = 60;  // Words 60-61
+const ID_LBA48_SECTORS: usize = 100; // Words 100-103
+
+#[repr(u8)]
+enum Command {
+    ReadDmaExt = 0x25,
+    WriteDmaExt = 0x35,
+    Identify = 0xEC,
+}
+
+#[derive(Debug, Clone)]
+pub struct Port {
+    id: u8,
+    base: u64,
+
+    cmd_list: PhysBuf,
+    fis: PhysBuf,
+    cmd_table: PhysBuf,
+    data: PhysBuf,
+
+    model: String,
+    serial: String,
+    sectors: u32,
+}
+
+impl Port {
+    pub fn new(id: u8, abar: u64) -> Self {
+        Self {
+            id,
+            base: abar + (PORT_BASE + id as usize * PORT_SIZE) as u64,
+
+            cmd_list: PhysBuf::new_aligned(CMD_LIST_LEN, 1024),
+            fis: PhysBuf::new_aligned(FIS_LEN, 256),
+            cmd_table: PhysBuf::new_aligned(CMD_TABLE_LEN, 128),
+            data: PhysBuf::new_aligned(SECTOR_SIZE, 128),
+
+            model: String::new(),
+            serial: String::new(),
+            sectors: 0,
+        }
+    }
+
+    fn read_reg(&self, reg: usize) -> u32 {
+        unsafe { read_volatile((self.base + reg as u64) as *const u32) }
+    }
+
+    fn write_reg(&mut self, reg: usize, value: u32) {
+        unsafe { write_volatile((self.base + reg as u64) as *mut u32, value) }
+    }
+
+    fn is_ata_drive(&self) -> bool {
+        self.read_reg(PORT_SSTS).get_bits(0..4) == SSTS_DET_PRESENT && self.read_reg(PORT_SIG) == SIG_ATA
+    }
+
+    /// Waits For The `bits` Of PxCMD To Clear, The Spec Allows 500ms
+    fn wait_cmd_clear(&self, bits: u32) -> KResult<()> {
+        for _ in 0..POLL_LIMIT {
+            if self.read_reg(PORT_CMD) & bits == 0 { return Ok(()); }
+            spin_loop();
+        }
+        Err("AHCI Port Won't Stop")
+    }
+
+    fn stop(&mut self) -> KResult<()> {
+        let cmd = self.read_reg(PORT_CMD);
+        self.write_reg(PORT_CMD, cmd & !(CMD_ST | CMD_FRE));
+        self.wait_cmd_clear(CMD_FR | CMD_CR)
+    }
+
+    fn start(&mut self) -> KResult<()> {
+        self.wait_cmd_clear(CMD_CR)?;
+        let cmd = self.read_reg(PORT_CMD);
+        self.write_reg(PORT_CMD, cmd | CMD_FRE | CMD_ST);
+        Ok(())
+    }
+
+    /// Points The Port At Our Command List & FIS Receive Area, Then Restarts It
+    fn rebase(&mut self) -> KResult<()> {
+        self.stop()?;
+
+        let clb = self.cmd_list.addr();
+        self.write_reg(PORT_CLB, clb.get_bits(0..32) as u32);
+        self.write_reg(PORT_CLBU, clb.get_bits(32..64) as u32);
+
+        let fb = self.fis.addr();
+        self.write_reg(PORT_FB, fb.get_bits(0..32) as u32);
+        self.write_reg(PORT_FBU, fb.get_bits(32..64) as u32);
+
+        // Command Header 0 Always Points At Our Single Command Table
+        let ctba = self.cmd_table.addr();
+        self.cmd_list[8..12].copy_from_slice(&(ctba.get_bits(0..32) as u32).to_le_bytes());
+        self.cmd_list[12..16].copy_from_slice(&(ctba.get_bits(32..64) as u32).to_le_bytes());
+
+        self.write_reg(PORT_SERR, 0xFFFF_FFFF);
+        self.write_reg(PORT_IS, 0xFFFF_FFFF);
+        self.start()
+    }
+
+    fn wait_ready(&mut self) -> KResult<()> {
+        for _ in 0..POLL_LIMIT {
+            if self.read_reg(PORT_TFD) & (TFD_BSY | TFD_DRQ) == 0 { return Ok(()); }
+            spin_loop();
+        }
+        Err("AHCI Port Is Busy")
+    }
+
+    /// Builds A Register H2D FIS In Slot 0 And Waits For It To Complete.
+    fn issue(&mut self, cmd: Command, block: u64, count: u16, write: bool) -> KResult<()> {
+        self.wait_ready()?;
+
+        // Command Header: CFL = 5 DWORDs, W = Direction, PRDTL = 1
+        let mut flags: u32 = 5;
+        flags.set_bit(6, write);
+        flags.set_bits(16..32, 1);
+        self.cmd_list[0..4].copy_from_slice(&flags.to_le_bytes());
+        self.cmd_list[4..8].copy_from_slice(&0u32.to_le_bytes()); // PRDBC
+
+        for byte in self.cmd_table.iter_mut() {
+            *byte = 0;
+        }
+
+        let fis = &mut self.cmd_table[0..20];
+        fis[0] = FIS_TYPE_REG_H2D;
+        fis[1] = 1 << 7; // Command, Not Control
+        fis[2] = cmd as u8;
+        fis[4] = block.get_bits(0..8) as u8;
+        fis[5] = block.get_bits(8..16) as u8;
+        fis[6] = block.get_bits(16..24) as u8;
+        fis[7] = 1 << 6; // LBA Mode
+        fis[8] = block.get_bits(24..32) as u8;
+        fis[9] = block.get_bits(32..40) as u8;
+        fis[10] = block.get_bits(40..48) as u8;
+        fis[12] = count.get_bits(0..8) as u8;
+        fis[13] = count.get_bits(8..16) as u8;
+
+        let dba = self.data.addr();
+        let prdt = &mut self.cmd_table[0x80..0x90];
+        prdt[0..4].copy_from_slice(&(dba.get_bits(0..32) as u32).to_le_bytes());
+        prdt[4..8].copy_from_slice(&(dba.get_bits(32..64) as u32).to_le_bytes());
+        prdt[12..16].copy_from_slice(&((SECTOR_SIZE - 1) as u32).to_le_bytes());
+
+        self.write_reg(PORT_IS, 0xFFFF_FFFF);
+        self.write_reg(PORT_CI, 1);
+
+        let mut polls = 0;
+        while self.read_reg(PORT_CI) & 1 != 0 {
+            if self.read_reg(PORT_IS) & IS_TFES != 0 {
+                return Err("AHCI Task File Error");
+            }
+            polls += 1;
+            if polls > POLL_LIMIT { // Hanged
+                return Err("AHCI Command Timed Out");
+            }
+            spin_loop();
+        }
+
+        if self.read_reg(PORT_IS) & IS_TFES != 0 || self.read_reg(PORT_TFD) & TFD_ERR != 0 {
+            return Err("AHCI Task File Error");
+        }
+        Ok(())
+    }
+
+    pub fn identify_drive(&mut self) -> Option<[u16; 256]> {
+        self.issue(Command::Identify, 0, 0, false).ok()?;
+        let mut res = [0; 256];
+        for i in 0..256 {
+            res[i] = u16::from_le_bytes([self.data[i * 2], self.data[i * 2 + 1]]);
+        }
+        Some(res)
+    }
+
+    pub fn read(&mut self, block: u32, buf: &mut [u8]) -> KResult<()> {
+        assert!(buf.len() == SECTOR_SIZE);
+        self.issue(Command::ReadDmaExt, block as u64, 1, false)?;
+        buf.copy_from_slice(&self.data[0..SECTOR_SIZE]);
+        Ok(())
+    }
+
+    pub fn write(&mut self, block: u32, buf: &[u8]) -> KResult<()> {
+        assert!(buf.len() == SECTOR_SIZE);
+        self.data[0..SECTOR_SIZE].copy_from_slice(buf);
+        self.issue(Command::WriteDmaExt, block as u64, 1, true)
+    }
+}
+
+lazy_static! {
+    pub static ref PORTS: Mutex<Vec<Port>> = Mutex::new(Vec::new());
+}
+
+fn identify_string(buf: &[u16; 256], words: core::ops::Range<usize>) -> String {
+    let mut text = String::new();
+    for i in words {
+        for &b in &buf[i].to_be_bytes() {
+            text.push(b as char);
+        }
+    }
+    text.trim().into()
+}
+
+/// The Drive's Size From IDENTIFY, Through The 48-Bit Count When The Drive Supports It As
+/// Reads & Writes Use The EXT Commands. Block Numbers Are 32-Bit, So Anything Past 2 TiB Is Cut Off.
+fn identify_sectors(buf: &[u16; 256]) -> u32 {
+    if buf[ID_COMMAND_SET_2].get_bit(ID_LBA48_SUPPORTED) {
+        let sectors = buf[ID_LBA48_SECTORS..ID_LBA48_SECTORS + 4].iter().rev().fold(0u64, |sectors, word| sectors << 16 | *word as u64);
+        if sectors != 0 { return sectors.min(u32::MAX as u64) as u32; }
+    }
+    (buf[ID_LBA28_SECTORS + 1] as u32) << 16 | (buf[ID_LBA28_SECTORS] as u32)
+}
+
+fn disk_size(sectors: u32) -> (u32, String) {
+    let bytes = sectors as u64 * SECTOR_SIZE as u64;
+    if bytes >> 20 < 1000 {
+        ((bytes >> 20) as u32, String::from("MB"))
+    } else {
+        ((bytes >> 30) as u32, String::from("GB"))
+    }
+}
+
+pub fn init() {
+    for mut controller in pci::find_class(CLASS_MASS_STORAGE, SUBCLASS_SATA) {
+        controller.enable_bus_mastering();
+
+        let abar = (controller.base_addresses[5] & 0xFFFF_FFF0) as u64;
+        let abar = mem::phys_to_virt(PhysAddr::new(abar)).as_u64();
+
+        unsafe {
+            let ghc = (abar + HBA_GHC as u64) as *mut u32;
+            write_volatile(ghc, read_volatile(ghc) | GHC_AE);
+        }
+        let implemented = unsafe { read_volatile((abar + HBA_PI as u64) as *const u32) };
+
+        for id in 0..32 {
+            if !implemented.get_bit(id) { continue; }
+            let mut port = Port::new(id as u8, abar);
+            if !port.is_ata_drive() { continue; }
+
+            if let Err(e) = port.rebase() {
+                print!("AHCI {} {}\n", id, e);
+                continue;
+            }
+            if let Some(buf) = port.identify_drive() {
+                port.serial = identify_string(&buf, 10..20);
+                port.model = identify_string(&buf, 27..47);
+                port.sectors = identify_sectors(&buf);
+                PORTS.lock().push(port);
+            }
+        }
+    }
+
+    for (port, model, serial, size, unit, _) in list() {
+        print!("AHCI {} {} {} ({} {})\n", port, model, serial, size, unit);
+    }
+}
+
+pub fn list() -> Vec<(u8, String, String, u32, String, u32)> {
+    let ports = PORTS.lock();
+    let mut res = Vec::new();
+    for port in ports.iter() {
+        let (size, unit) = disk_size(port.sectors);
+        res.push((port.id, port.model.clone(), port.serial.clone(), size, unit, port.sectors));
+    }
+    res
+}
+
+pub fn sector_count(port: u8) -> u32 {
+    let ports = PORTS.lock();
+    ports.iter().find(|p| p.id == port).map(|p| p.sectors).unwrap_or(0)
+}
+
+pub fn read(port: u8, block: u32, buf: &mut [u8]) -> KResult<()> {
+    without_interrupts(|| {
+        let mut ports = PORTS.lock();
+        let port = ports.iter_mut().find(|p| p.id == port).ok_or("No Such AHCI Port")?;
+        port.read(block, buf)
+    })
+}
+
+pub fn write(port: u8, block: u32, buf: &[u8]) -> KResult<()> {
+    without_interrupts(|| {
+        let mut ports = PORTS.lock();
+        let port = ports.iter_mut().find(|p| p.id == port).ok_or("No Such AHCI Port")?;
+        port.write(block, buf)
+    })
+}
@@ -289,6 +289,8 @@ pub fn list() -> Vec<(u8, u8, String, String, u32, String, u32)> {
     res
 }
 
+/// The 28-Bit Count From IDENTIFY Words 60-61, Which Is All The PIO Read & Write Commands Used
+/// Here Can Reach
 pub fn sector_count(bus: u8, drive: u8) -> u32 {
     let mut buses = BUSES.lock();
     if let Some(buf) = buses[bus as usize].identify_drive(drive) {
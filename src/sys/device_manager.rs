@@ -1,5 +1,5 @@
 use alloc::vec::Vec;
-use super::{pci::{*, self}, pci_details, storage::fs::dev_handle::{AtaDevice, DeviceHandle, MemDevice}};
+use super::{pci::{*, self}, pci_details, storage::fs::dev_handle::{AhciDevice, AtaDevice, DeviceHandle, MemDevice, NvmeDevice, VirtioDevice}};
 
 pub enum Device {
     PCIDev(DeviceConfig),
@@ -25,6 +25,9 @@ impl Device {
 /// Converts A Path Formatted in <Device Type>/ID
 /// Example:
 ///     ATA/0/0
+///     AHCI/0
+///     VIRTIO/0
+///     NVME/0
 ///     MEM/B8000/A0000
 ///     PCI/REALTEK/RTL8139
 pub fn get_device(path: &str) -> Option<Device> {
@@ -33,6 +36,9 @@ pub fn get_device(path: &str) -> Option<Device> {
     match sections[0] {
         "PCI" => build_pci(&sections),
         "ATA" => build_ata(&sections),
+        "AHCI" => build_ahci(&sections),
+        "VIRTIO" => build_virtio(&sections),
+        "NVME" => build_nvme(&sections),
         "MEM" => build_mem(),
         _ => None,
     }
@@ -63,6 +69,27 @@ fn build_ata(id: &Vec<&str>) -> Option<Device> {
     return Some(Device::BlockDev(DeviceHandle::AtaBlockDevice(AtaDevice::new(bus, drive))))
 }
 
+/// id[0] => (Ignored)
+/// id[1] => Port Index
+fn build_ahci(id: &Vec<&str>) -> Option<Device> {
+    let port: u8 = id[1].parse().expect("Failed To Parse Value");
+    return Some(Device::BlockDev(DeviceHandle::AhciBlockDevice(AhciDevice::new(port))))
+}
+
+/// id[0] => (Ignored)
+/// id[1] => Virtio Block Device Index
+fn build_virtio(id: &Vec<&str>) -> Option<Device> {
+    let index: u8 = id[1].parse().expect("Failed To Parse Value");
+    return Some(Device::BlockDev(DeviceHandle::VirtioBlockDevice(VirtioDevice::new(index))))
+}
+
+/// id[0] => (Ignored)
+/// id[1] => NVMe Device Index
+fn build_nvme(id: &Vec<&str>) -> Option<Device> {
+    let index: u8 = id[1].parse().expect("Failed To Parse Value");
+    return Some(Device::BlockDev(DeviceHandle::NvmeBlockDevice(NvmeDevice::new(index))))
+}
+
 
 #[test_case]
 pub fn test_pci() {
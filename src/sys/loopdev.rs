@@ -0,0 +1,150 @@
+//! Loop Devices
+//!
+//! Exposes A File As A Block Device, So An Image Stored On A Filesystem Can Be Mounted Or Worked
+//! On With `dsk` Like Any Other Drive. Absolute Paths Go Through The Mount Table & Only The
+//! Sectors Asked For Are Read Or Written. Relative Paths Name A File On The ustar/ext2 Root Of
+//! The Mounted Device, Which Can't Be Written In Place, So Those Images Are Loaded Whole & Their
+//! Writes Only Last Until The Loop Is Detached.
+
+use alloc::{string::String, vec::Vec};
+use lazy_static::lazy_static;
+use spin::Mutex;
+
+use crate::{KResult, sys::{storage::fs::{HANDLE, is_mounted, dev_handle::DeviceHandle}, vfs::{self, mount}}};
+
+const SECTOR_SIZE: usize = 512;
+
+/// Most Loops That Can Be Attached At Once
+pub const MAX_LOOPS: usize = 8;
+
+enum Backing {
+    /// An Absolute Path Served By The Mount Table
+    Mounted(String),
+    /// A Root Filesystem Image Held In Memory
+    Memory(Vec<u8>),
+}
+
+struct Loop {
+    path: String,
+    backing: Backing,
+    sectors: u32,
+}
+
+lazy_static! {
+    static ref LOOPS: Mutex<Vec<Option<Loop>>> = Mutex::new(Vec::new());
+}
+
+fn disk_size(sectors: u32) -> (u32, String) {
+    let bytes = sectors as usize * SECTOR_SIZE;
+    if bytes >> 20 == 0 {
+        ((bytes >> 10) as u32, String::from("KB"))
+    } else {
+        ((bytes >> 20) as u32, String::from("MB"))
+    }
+}
+
+/// Attaches `path` To The First Free Loop, Returning Its Id. Any Bytes Past The Last Whole
+/// Sector Are Ignored.
+pub fn attach(path: &str) -> KResult<u8> {
+    let (backing, size) = if path.starts_with('/') {
+        let stat = mount::stat(path)?;
+        if stat.is_dir() { return Err("Is A Directory"); }
+        (Backing::Mounted(mount::normalize(path)), stat.size)
+    } else {
+        if !is_mounted() { return Err("No Device Is Mounted"); }
+        let mut data = Vec::new();
+        vfs::load(path, &mut data)?;
+        let size = data.len();
+        (Backing::Memory(data), size)
+    };
+    let sectors = (size / SECTOR_SIZE) as u32;
+    if sectors == 0 { return Err("File Is Smaller Than One Sector"); }
+
+    let mut loops = LOOPS.lock();
+    let id = match loops.iter().position(|slot| slot.is_none()) {
+        Some(id) => id,
+        None if loops.len() < MAX_LOOPS => { loops.push(None); loops.len() - 1 },
+        None => return Err("No Free Loop Devices"),
+    };
+    loops[id] = Some(Loop { path: String::from(path), backing, sectors });
+    Ok(id as u8)
+}
+
+/// Refuses To Detach The Loop Currently Mounted As The Storage Device
+pub fn detach(id: u8) -> KResult<()> {
+    if let Some(DeviceHandle::LoopBlockDevice(dev)) = HANDLE.lock().as_ref() {
+        if dev.id() == id { return Err("Loop Device Is Mounted"); }
+    }
+    let mut loops = LOOPS.lock();
+    match loops.get_mut(id as usize) {
+        Some(slot) if slot.is_some() => { *slot = None; Ok(()) },
+        _ => Err("No Such Loop Device"),
+    }
+}
+
+/// Every Attached Loop As `(id, backing path, size, unit, sectors)`
+pub fn list() -> Vec<(u8, String, u32, String, u32)> {
+    let loops = LOOPS.lock();
+    loops.iter().enumerate().filter_map(|(id, slot)| slot.as_ref().map(|dev| {
+        let (size, unit) = disk_size(dev.sectors);
+        (id as u8, dev.path.clone(), size, unit, dev.sectors)
+    })).collect()
+}
+
+pub fn sector_count(id: u8) -> u32 {
+    let loops = LOOPS.lock();
+    loops.get(id as usize).and_then(|slot| slot.as_ref()).map(|dev| dev.sectors).unwrap_or(0)
+}
+
+/// Runs `f` On Loop `id` With The Byte Offset Of `block`, Once The Access Is Known To Fit.
+/// Callers Only Touch The Mount Table After This Returns, So The Loop Table Isn't Held Then.
+fn with_loop<T>(id: u8, block: u32, len: usize, f: impl FnOnce(&mut Loop, usize) -> KResult<T>) -> KResult<T> {
+    let mut loops = LOOPS.lock();
+    let dev = loops.get_mut(id as usize).and_then(|slot| slot.as_mut()).ok_or("No Such Loop Device")?;
+    if block as usize * SECTOR_SIZE + len > dev.sectors as usize * SECTOR_SIZE { return Err("Access Past End Of Device"); }
+    f(dev, block as usize * SECTOR_SIZE)
+}
+
+pub fn read(id: u8, block: u32, buf: &mut [u8]) -> KResult<()> {
+    let mounted = with_loop(id, block, buf.len(), |dev, offset| Ok(match &dev.backing {
+        Backing::Mounted(path) => Some(path.clone()),
+        Backing::Memory(data) => { buf.copy_from_slice(&data[offset..offset + buf.len()]); None },
+    }))?;
+    if let Some(path) = mounted {
+        let read = mount::read_at(&path, block as usize * SECTOR_SIZE, buf)?;
+        if read < buf.len() { return Err("Loop Backing File Shrank"); }
+    }
+    Ok(())
+}
+
+pub fn write(id: u8, block: u32, buf: &[u8]) -> KResult<()> {
+    let mounted = with_loop(id, block, buf.len(), |dev, offset| Ok(match &mut dev.backing {
+        Backing::Mounted(path) => Some(path.clone()),
+        Backing::Memory(data) => { data[offset..offset + buf.len()].copy_from_slice(buf); None },
+    }))?;
+    if let Some(path) = mounted {
+        mount::write_at(&path, block as usize * SECTOR_SIZE, buf)?;
+    }
+    Ok(())
+}
+
+#[test_case]
+fn loop_reads_and_writes_through_mount() {
+    use alloc::boxed::Box;
+    use crate::sys::vfs::tmpfs::TmpFs;
+
+    mount::mount("/looptest", Box::new(TmpFs::new(64 * 1024))).expect("");
+    mount::write("/looptest/disk.img", &[0; SECTOR_SIZE * 4 + 100]).expect("");
+    let id = attach("/looptest/disk.img").expect("");
+    assert_eq!(sector_count(id), 4);
+
+    write(id, 2, &[0xAB; SECTOR_SIZE]).expect("");
+    let mut buf = [0; SECTOR_SIZE];
+    read(id, 2, &mut buf).expect("");
+    assert!(buf.iter().all(|byte| *byte == 0xAB));
+    assert!(read(id, 4, &mut buf).is_err());
+
+    detach(id).expect("");
+    assert!(read(id, 0, &mut buf).is_err());
+    mount::unmount("/looptest").expect("");
+}
@@ -23,6 +23,8 @@ unsafe impl GlobalAlloc for NullAllocator {
 #[derive(Debug, Clone)]
 pub struct PhysBuf {
     buf: Arc<Mutex<Vec<u8>>>,
+    offset: usize,
+    len: usize,
 }
 
 impl PhysBuf {
@@ -30,19 +32,30 @@ impl PhysBuf {
         Self::from(vec![0; len])
     }
 
+    /// Allocates a buffer of `len` bytes whose physical address is a multiple of `align`,
+    /// for DMA structures (command lists, descriptor rings, queues) that need it.
+    pub fn new_aligned(len: usize, align: usize) -> Self {
+        let mut buf = Self::from(vec![0; len + align]);
+        let addr = buf.addr() as usize;
+        buf.offset = (align - (addr % align)) % align;
+        buf.len = len;
+        buf
+    }
+
     // Realloc vec until it uses a chunk of contiguous physical memory
     fn from(vec: Vec<u8>) -> Self {
         let buffer_len = vec.len() - 1;
         let memory_len = phys_addr(&vec[buffer_len]) - phys_addr(&vec[0]);
         if buffer_len == memory_len as usize {
-            Self { buf: Arc::new(Mutex::new(vec)) }
+            let len = vec.len();
+            Self { buf: Arc::new(Mutex::new(vec)), offset: 0, len }
         } else {
             Self::from(vec.clone()) // Clone vec and try again
         }
     }
 
     pub fn addr(&self) -> u64 {
-        phys_addr(&self.buf.lock()[0])
+        phys_addr(&self.buf.lock()[self.offset])
     }
 }
 
@@ -74,13 +87,13 @@ impl core::ops::Deref for PhysBuf {
 
     fn deref(&self) -> &[u8] {
         let vec = self.buf.lock();
-        unsafe { alloc::slice::from_raw_parts(vec.as_ptr(), vec.len()) }
+        unsafe { alloc::slice::from_raw_parts(vec.as_ptr().add(self.offset), self.len) }
     }
 }
 
 impl core::ops::DerefMut for PhysBuf {
     fn deref_mut(&mut self) -> &mut [u8] {
         let mut vec = self.buf.lock();
-        unsafe { alloc::slice::from_raw_parts_mut(vec.as_mut_ptr(), vec.len()) }
+        unsafe { alloc::slice::from_raw_parts_mut(vec.as_mut_ptr().add(self.offset), self.len) }
     }
 }
\ No newline at end of file
@@ -7,6 +7,11 @@ pub mod vga;
 pub mod mem;
 pub mod shell;
 pub mod ata;
+pub mod ahci;
+pub mod nvme;
+pub mod virtio;
+pub mod loopdev;
+pub mod raid;
 pub mod acpi;
 pub mod pci;
 pub mod pci_details;
@@ -22,6 +27,7 @@ use crate::serial_println;
 
 pub fn shutdown() -> ! {
     serial_println!("[SYS]: Shutting System Down!");
+    net::shutdown();
     acpi::shutdown();
 }
 
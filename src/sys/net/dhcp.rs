@@ -1,97 +1,437 @@
-use core::time::Duration;
-
-use alloc::string::ToString;
-use alloc::vec::{Vec};
-use alloc::vec;
-use smoltcp::dhcp::Dhcpv4Client;
-use smoltcp::socket::{RawPacketMetadata, RawSocketBuffer, SocketSet};
-use smoltcp::time::{Instant};
-use smoltcp::wire::{IpCidr, Ipv4Address, Ipv4Cidr};
-use crate::{breakpoint, println, warn};
-use crate::sys::clock::realtime;
-use crate::sys::timer::pause;
-
-use super::IFACE;
-pub fn init() {
-    let mut guard = IFACE.lock();
-    let iface = guard.as_mut();
-    if let Some(mut iface) = iface {
-        let mut sockets = SocketSet::new(vec![]);
-        let dhcp_rx_buffer = RawSocketBuffer::new([RawPacketMetadata::EMPTY; 1], vec![0; 1500]);
-        let dhcp_tx_buffer = RawSocketBuffer::new([RawPacketMetadata::EMPTY; 1], vec![0; 1500]);
-
-        let timestamp = Instant::from_secs((realtime()) as i64);
-        let mut dhcp = Dhcpv4Client::new(&mut sockets, dhcp_rx_buffer, dhcp_tx_buffer, timestamp);
-    
-        let prev_cidr = match iface.ip_addrs().first() {
-            Some(IpCidr::Ipv4(ip_addr)) => *ip_addr,
-            _ => Ipv4Cidr::new(Ipv4Address::UNSPECIFIED, 0),
+//! DHCP Client
+//!
+//! Follows The RFC 2131 States, Driven By The Network Service: [poll] Runs After Every Poll Of
+//! The Interface & [next_poll] Feeds Its Deadline. A Lease Is Renewed With Its Server At T1,
+//! Rebound With Anyone At T2 & Dropped When It Runs Out, A NAK At Any Point Starts Discovery
+//! Again. smoltcp's Own Client Can't Release A Lease Or Tell How Long It Lasts, So Packets Are
+//! Built Here & Sent Through A Raw Socket, Which Also Works Before There's An Address. Leases
+//! Don't Carry T1/T2 Options Through smoltcp's Parser, So They're Always Half & 7/8 Of It.
+//! Each Interface Gets Its Own Client, Whose Router Goes In The [super::route] Table Under
+//! A Static Gateway. DNS Servers Set Statically Through [super::CONFIG] Are Left In Place.
+
+use alloc::{collections::BTreeMap, string::ToString, vec, vec::Vec};
+use lazy_static::lazy_static;
+use smoltcp::{phy::{ChecksumCapabilities, Device}, socket::{RawPacketMetadata, RawSocket, RawSocketBuffer, SocketHandle, SocketSet}, time::{Duration, Instant}, wire::{DhcpMessageType, DhcpPacket, DhcpRepr, EthernetAddress, IpCidr, IpProtocol, IpVersion, Ipv4Address, Ipv4Cidr, Ipv4Packet, Ipv4Repr, UdpPacket, UdpRepr}};
+use spin::Mutex;
+
+use crate::{KResult, println, sys::timer};
+
+use super::{CONFIG, EthernetInterface, IFACES, Interface, route::{self, Origin, Route}, service};
+
+const CLIENT_PORT: u16 = 68;
+const SERVER_PORT: u16 = 67;
+
+/// Subnet Mask, Router, DNS Servers
+const PARAMETER_REQUEST_LIST: &[u8] = &[1, 3, 6];
+
+/// How Long The Boot Waits For The Leases
+const BOOT_TIMEOUT: f64 = 30.0;
+
+/// Seconds Between Discovers, Doubling Up To [DISCOVER_BACKOFF_MAX]
+const DISCOVER_BACKOFF_START: u64 = 4;
+const DISCOVER_BACKOFF_MAX: u64 = 64;
+const REQUEST_RETRY: u64 = 4;
+const REQUEST_ATTEMPTS: u8 = 5;
+/// Shortest Gap In Seconds Between Retransmissions While Renewing Or Rebinding
+const RENEW_RETRY_MIN: u64 = 60;
+
+const BUFFER_LEN: usize = 1500;
+const PACKET_LEN: usize = 576;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum State {
+    /// Broadcasting Discovers, Waiting For An Offer
+    Selecting,
+    /// Asked For An Offered Address, Waiting For The ACK
+    Requesting,
+    Bound,
+    /// Past T1, Asking The Leasing Server To Extend
+    Renewing,
+    /// Past T2, Asking Any Server To Extend
+    Rebinding,
+    /// Released, Nothing Happens Until A Renew
+    Stopped,
+}
+
+#[derive(Debug, Clone)]
+pub struct Lease {
+    pub address: Ipv4Cidr,
+    pub router: Option<Ipv4Address>,
+    pub dns_servers: Vec<Ipv4Address>,
+    pub server: Ipv4Address,
+    pub acquired: Instant,
+    pub duration: Duration,
+}
+
+impl Lease {
+    pub fn t1(&self) -> Instant { self.acquired + self.duration / 2 }
+    pub fn t2(&self) -> Instant { self.acquired + self.duration * 7 / 8 }
+    pub fn expires(&self) -> Instant { self.acquired + self.duration }
+}
+
+struct Client {
+    /// The Interface This Client Configures
+    index: usize,
+    handle: SocketHandle,
+    state: State,
+    transaction_id: u32,
+    /// The Address & Server From The Offer Being Requested
+    offer: Option<(Ipv4Address, Ipv4Address)>,
+    lease: Option<Lease>,
+    next_send: Instant,
+    backoff: Duration,
+    attempts: u8,
+}
+
+lazy_static! {
+    /// Clients By Interface Index
+    static ref CLIENTS: Mutex<BTreeMap<usize, Client>> = Mutex::new(BTreeMap::new());
+}
+
+/// What An Incoming Message Asks The Client To Do
+enum Reply {
+    Offer(Ipv4Address, Ipv4Address),
+    Ack(Lease),
+    Nak,
+}
+
+fn next_transaction_id() -> u32 {
+    (timer::uptime_millis() as u32).wrapping_mul(2654435761) ^ 0x5A5A_0000
+}
+
+impl Client {
+    fn new(index: usize, sockets: &mut SocketSet<'static>, now: Instant) -> Self {
+        let buffer = || RawSocketBuffer::new(vec![RawPacketMetadata::EMPTY; 4], vec![0; BUFFER_LEN]);
+        let handle = sockets.add(RawSocket::new(IpVersion::Ipv4, IpProtocol::Udp, buffer(), buffer()));
+        let mut client = Self {
+            index, handle, state: State::Stopped, transaction_id: 0, offer: None, lease: None,
+            next_send: now, backoff: Duration::from_secs(DISCOVER_BACKOFF_START), attempts: 0,
         };
+        client.discover(now);
+        client
+    }
+
+    fn discover(&mut self, now: Instant) {
+        self.state = State::Selecting;
+        self.transaction_id = next_transaction_id();
+        self.offer = None;
+        self.next_send = now;
+        self.backoff = Duration::from_secs(DISCOVER_BACKOFF_START);
+        self.attempts = 0;
+    }
+
+    fn repr(&self, message_type: DhcpMessageType, mac: EthernetAddress) -> DhcpRepr<'static> {
+        DhcpRepr {
+            message_type,
+            transaction_id: self.transaction_id,
+            client_hardware_address: mac,
+            client_ip: Ipv4Address::UNSPECIFIED,
+            your_ip: Ipv4Address::UNSPECIFIED,
+            server_ip: Ipv4Address::UNSPECIFIED,
+            router: None,
+            subnet_mask: None,
+            relay_agent_ip: Ipv4Address::UNSPECIFIED,
+            broadcast: false,
+            requested_ip: None,
+            client_identifier: Some(mac),
+            server_identifier: None,
+            parameter_request_list: Some(PARAMETER_REQUEST_LIST),
+            max_size: Some(BUFFER_LEN as u16),
+            lease_duration: None,
+            dns_servers: None,
+        }
+    }
 
-        println!("DHCP Discover transmitted");
+    fn send(&self, sockets: &mut SocketSet<'static>, repr: &DhcpRepr, src: Ipv4Address, dst: Ipv4Address) -> KResult<()> {
+        let mut payload = [0; PACKET_LEN];
+        let payload = &mut payload[..repr.buffer_len()];
+        repr.emit(&mut DhcpPacket::new_unchecked(&mut payload[..])).map_err(|_| "Failed To Build DHCP Packet")?;
 
-        let begin_time = realtime();
+        let caps = ChecksumCapabilities::default();
+        let udp = UdpRepr { src_port: CLIENT_PORT, dst_port: SERVER_PORT, payload };
+        let ip = Ipv4Repr { src_addr: src, dst_addr: dst, protocol: IpProtocol::Udp, payload_len: udp.buffer_len(), hop_limit: 64 };
+        let mut socket = sockets.get::<RawSocket>(self.handle);
+        let packet = socket.send(ip.buffer_len() + udp.buffer_len()).map_err(|_| "DHCP Send Buffer Full")?;
+        ip.emit(&mut Ipv4Packet::new_unchecked(&mut packet[..]), &caps);
+        udp.emit(&mut UdpPacket::new_unchecked(&mut packet[ip.buffer_len()..]), &src.into(), &dst.into(), &caps);
+        Ok(())
+    }
+
+    fn parse(&self, payload: &[u8], mac: EthernetAddress, now: Instant) -> Option<Reply> {
+        let caps = ChecksumCapabilities::default();
+        let ip_packet = Ipv4Packet::new_checked(payload).ok()?;
+        let ip = Ipv4Repr::parse(&ip_packet, &caps).ok()?;
+        let udp_packet = UdpPacket::new_checked(ip_packet.payload()).ok()?;
+        let udp = UdpRepr::parse(&udp_packet, &ip.src_addr.into(), &ip.dst_addr.into(), &caps).ok()?;
+        if udp.src_port != SERVER_PORT || udp.dst_port != CLIENT_PORT { return None; }
+        let dhcp = DhcpRepr::parse(&DhcpPacket::new_checked(udp.payload).ok()?).ok()?;
+        if dhcp.transaction_id != self.transaction_id || dhcp.client_hardware_address != mac { return None; }
+
+        let server = dhcp.server_identifier.unwrap_or(ip.src_addr);
+        let waiting = matches!(self.state, State::Requesting | State::Renewing | State::Rebinding);
+        match dhcp.message_type {
+            DhcpMessageType::Offer if self.state == State::Selecting => Some(Reply::Offer(dhcp.your_ip, server)),
+            DhcpMessageType::Ack if waiting => {
+                let prefix = dhcp.subnet_mask.and_then(|mask| Ipv4Cidr::from_netmask(dhcp.your_ip, mask).ok()).map(|cidr| cidr.prefix_len()).unwrap_or(24);
+                Some(Reply::Ack(Lease {
+                    address: Ipv4Cidr::new(dhcp.your_ip, prefix),
+                    router: dhcp.router,
+                    dns_servers: dhcp.dns_servers.iter().flatten().filter_map(|server| *server).collect(),
+                    server,
+                    acquired: now,
+                    duration: Duration::from_secs(dhcp.lease_duration.unwrap_or(u32::MAX) as u64),
+                }))
+            },
+            DhcpMessageType::Nak if waiting => Some(Reply::Nak),
+            _ => None,
+        }
+    }
+
+    fn poll<D>(&mut self, iface: &mut EthernetInterface<D>, sockets: &mut SocketSet<'static>, now: Instant) where D: for<'d> Device<'d> {
+        let mac = iface.ethernet_addr();
         loop {
-            if realtime() - begin_time > 30.0 {
-                println!("Timeout reached");
-                return;
+            let reply = {
+                let mut socket = sockets.get::<RawSocket>(self.handle);
+                match socket.recv() {
+                    Ok(payload) => self.parse(payload, mac, now),
+                    Err(_) => break,
+                }
+            };
+            match reply {
+                Some(Reply::Offer(address, server)) => {
+                    self.offer = Some((address, server));
+                    self.state = State::Requesting;
+                    self.next_send = now;
+                    self.attempts = 0;
+                },
+                Some(Reply::Ack(lease)) => {
+                    bind(self.index, iface, &lease);
+                    self.lease = Some(lease);
+                    self.state = State::Bound;
+                    self.offer = None;
+                },
+                Some(Reply::Nak) => {
+                    println!("DHCP NAK Received, Restarting Discovery");
+                    self.drop_lease(iface);
+                    self.discover(now);
+                },
+                None => {},
             }
+        }
 
-            let timestamp = Instant::from_secs((realtime()) as i64);
+        if let Err(e) = self.timers(iface, sockets, now) {
+            println!("DHCP Error: {}", e);
+        }
+    }
 
-            match iface.poll(&mut sockets, timestamp) {
-                Err(smoltcp::Error::Unrecognized) => {
-                    warn!("Unrecognised Error!");
-                }
-                Err(e) => {
-                    println!("Network Error: {}", e);
-                }
-                Ok(_) => {
-                }
+    /// Moves Through The Lease's Deadlines & Sends Whatever The State Calls For
+    fn timers<D>(&mut self, iface: &mut EthernetInterface<D>, sockets: &mut SocketSet<'static>, now: Instant) -> KResult<()> where D: for<'d> Device<'d> {
+        if let Some(lease) = self.lease.clone() {
+            if now >= lease.expires() && self.state != State::Stopped {
+                println!("DHCP Lease On {} Expired", lease.address);
+                self.drop_lease(iface);
+                self.discover(now);
+            } else if now >= lease.t2() && matches!(self.state, State::Bound | State::Renewing) {
+                self.state = State::Rebinding;
+                self.next_send = now;
+            } else if now >= lease.t1() && self.state == State::Bound {
+                self.state = State::Renewing;
+                self.transaction_id = next_transaction_id();
+                self.next_send = now;
             }
+        }
+        if now < self.next_send { return Ok(()); }
 
-            breakpoint!();
-            let res = dhcp.poll(&mut iface, &mut sockets, timestamp).unwrap_or_else(|e| {
-                println!("DHCP Error: {:?}", e);
-                None
-            });
-            breakpoint!();
-
-            if let Some(config) = res {
-                println!("DHCP Offer received");
-                if let Some(cidr) = config.address {
-                    if cidr != prev_cidr {
-                        iface.update_ip_addrs(|addrs| {
-                            if let Some(addr) = addrs.iter_mut().next() {
-                                *addr = IpCidr::Ipv4(cidr);
-                            }
-                        });
-                        println!("Leased: {}", cidr);
-                    }
+        let mac = iface.ethernet_addr();
+        match self.state {
+            State::Selecting => {
+                let mut repr = self.repr(DhcpMessageType::Discover, mac);
+                repr.broadcast = true;
+                self.send(sockets, &repr, Ipv4Address::UNSPECIFIED, Ipv4Address::BROADCAST)?;
+                self.next_send = now + self.backoff;
+                self.backoff = (self.backoff * 2).min(Duration::from_secs(DISCOVER_BACKOFF_MAX));
+            },
+            State::Requesting => {
+                let (address, server) = self.offer.ok_or("No Offer To Request")?;
+                if self.attempts >= REQUEST_ATTEMPTS {
+                    self.discover(now);
+                    return Ok(());
                 }
+                let mut repr = self.repr(DhcpMessageType::Request, mac);
+                repr.broadcast = true;
+                repr.requested_ip = Some(address);
+                repr.server_identifier = Some(server);
+                self.send(sockets, &repr, Ipv4Address::UNSPECIFIED, Ipv4Address::BROADCAST)?;
+                self.attempts += 1;
+                self.next_send = now + Duration::from_secs(REQUEST_RETRY);
+            },
+            State::Renewing | State::Rebinding => {
+                let lease = self.lease.clone().ok_or("No Lease To Renew")?;
+                let mut repr = self.repr(DhcpMessageType::Request, mac);
+                repr.client_ip = lease.address.address();
+                let (dst, deadline) = match self.state {
+                    State::Renewing => (lease.server, lease.t2()),
+                    _ => (Ipv4Address::BROADCAST, lease.expires()),
+                };
+                self.send(sockets, &repr, lease.address.address(), dst)?;
+                // RFC 2131 4.4.5: Wait Half The Time Left, But At Least A Minute
+                self.next_send = now + ((deadline - now) / 2).max(Duration::from_secs(RENEW_RETRY_MIN));
+            },
+            State::Bound | State::Stopped => {
+                self.next_send = self.lease.as_ref().map(|lease| lease.t1()).unwrap_or(now + Duration::from_secs(3600));
+            },
+        }
+        Ok(())
+    }
 
-                config.router.map(|router| {
-                    iface.routes_mut().add_default_ipv4_route(router).unwrap()
-                });
-                iface.routes_mut().update(|routes_map| {
-                    let unspecified = IpCidr::new(Ipv4Address::UNSPECIFIED.into(), 0);
-                    if let Some(default_route) = routes_map.get(&unspecified) {
-                        println!("Router: {}", default_route.via_router);
-                    }
-                });
-
-                let dns_servers: Vec<_> = config.dns_servers.iter().filter_map(|s| *s).map(|s| s.to_string()).collect();
-                if !dns_servers.is_empty() {
-                    println!("DNS: {}", dns_servers.join(", "));
-                }
+    fn drop_lease<D>(&mut self, iface: &mut EthernetInterface<D>) where D: for<'d> Device<'d> {
+        if let Some(lease) = self.lease.take() {
+            unbind(self.index, iface, &lease);
+        }
+    }
+}
 
-                return;
-            }
+/// Puts A Lease's Address, Default Route & DNS Servers In Place On Interface `index`
+fn bind<D>(index: usize, iface: &mut EthernetInterface<D>, lease: &Lease) where D: for<'d> Device<'d> {
+    let current = iface.ipv4_addr();
+    iface.update_ip_addrs(|addrs| {
+        if let Some(addr) = addrs.iter_mut().next() {
+            *addr = IpCidr::Ipv4(lease.address);
+        }
+    });
+    route::set_link(index, lease.address);
+    if current != Some(lease.address.address()) {
+        println!("Leased: {} For {}s", lease.address, lease.duration.secs());
+    }
+    match lease.router {
+        Some(router) => {
+            let default = Route { cidr: Ipv4Cidr::new(Ipv4Address::UNSPECIFIED, 0), gateway: Some(router), iface: index, metric: route::DHCP_METRIC, origin: Origin::Dhcp };
+            if route::add(default) { println!("Router: {}", router); }
+        },
+        None => route::flush(index, Origin::Dhcp),
+    }
+    route::apply(index, iface);
+    let mut config = CONFIG.lock();
+    if !config.static_dns && config.dns_servers != lease.dns_servers {
+        let names: Vec<_> = lease.dns_servers.iter().map(|server| server.to_string()).collect();
+        println!("DNS: {}", names.join(", "));
+        config.dns_servers = lease.dns_servers.clone();
+    }
+}
 
-            if let Some(wait_duration) = iface.poll_delay(&sockets, timestamp) {
-                let wait_duration: Duration = wait_duration.into();
-                pause(wait_duration.as_secs_f64());
-            }
+/// Takes A Lease's Address & Routes Off Interface `index`, Its DNS Servers Too If Nothing Has
+/// Replaced Them Since
+fn unbind<D>(index: usize, iface: &mut EthernetInterface<D>, lease: &Lease) where D: for<'d> Device<'d> {
+    iface.update_ip_addrs(|addrs| {
+        if let Some(addr) = addrs.iter_mut().next() {
+            *addr = IpCidr::Ipv4(Ipv4Cidr::new(Ipv4Address::UNSPECIFIED, 0));
+        }
+    });
+    route::flush(index, Origin::Link);
+    route::flush(index, Origin::Dhcp);
+    route::apply(index, iface);
+    let mut config = CONFIG.lock();
+    if !config.static_dns && config.dns_servers == lease.dns_servers {
+        config.dns_servers.clear();
+    }
+}
+
+/// Called By The Network Service After Each Poll Of Interface `index`
+pub fn poll<D>(index: usize, iface: &mut EthernetInterface<D>, sockets: &mut SocketSet<'static>, now: Instant) where D: for<'d> Device<'d> {
+    if let Some(mut clients) = CLIENTS.try_lock() {
+        if let Some(client) = clients.get_mut(&index) {
+            client.poll(iface, sockets, now);
         }
-    } 
-}
\ No newline at end of file
+    }
+}
+
+/// When A Client Next Needs Polling, If Any Are Running
+pub fn next_poll() -> Option<Instant> {
+    CLIENTS.try_lock().and_then(|clients| clients.values().map(|client| client.next_send).min())
+}
+
+/// Starts Discovery On Interface `index`, Creating Its Client If It Hasn't Run Yet
+pub fn start(index: usize) -> KResult<()> {
+    let mut ifaces = IFACES.lock();
+    let iface = ifaces.get_mut(index).ok_or("No Such Interface")?;
+    let mut clients = CLIENTS.lock();
+    match clients.get_mut(&index) {
+        Some(client) => client.discover(service::now()),
+        None => { clients.insert(index, Client::new(index, &mut iface.sockets, service::now())); },
+    }
+    service::wake();
+    Ok(())
+}
+
+/// Stops Interface `index`'s Client Without Releasing, The Address Stays Until Something
+/// Replaces It
+pub fn stop(index: usize) {
+    if let Some(client) = CLIENTS.lock().get_mut(&index) {
+        client.lease = None;
+        client.offer = None;
+        client.state = State::Stopped;
+    }
+}
+
+/// Waits Up To [BOOT_TIMEOUT] For Every Started Client To Get A Lease
+pub fn init() {
+    println!("DHCP Discover transmitted");
+
+    let begin = timer::uptime_seconds();
+    let waiting = || CLIENTS.lock().values().any(|client| matches!(client.state, State::Selecting | State::Requesting));
+    while waiting() {
+        if timer::uptime_seconds() - begin > BOOT_TIMEOUT {
+            println!("Timeout reached, DHCP Carries On In The Background");
+            return;
+        }
+        timer::pause(0.1);
+    }
+}
+
+/// Extends Interface `index`'s Lease Now Instead Of Waiting For T1, Or Starts Over If There
+/// Isn't One
+pub fn renew(index: usize) -> KResult<()> {
+    if !CLIENTS.lock().contains_key(&index) { return start(index); }
+    let mut clients = CLIENTS.lock();
+    let client = clients.get_mut(&index).ok_or("DHCP Isn't Running")?;
+    let now = service::now();
+    match client.state {
+        State::Bound | State::Renewing | State::Rebinding => {
+            client.state = State::Renewing;
+            client.transaction_id = next_transaction_id();
+            client.next_send = now;
+        },
+        State::Selecting | State::Requesting => {},
+        State::Stopped => client.discover(now),
+    }
+    service::wake();
+    Ok(())
+}
+
+/// Gives Interface `index`'s Address Back To The Server & Stops Its Client Until The Next Renew
+pub fn release(index: usize) -> KResult<()> {
+    let mut ifaces = IFACES.lock();
+    let Interface { iface, sockets, .. } = ifaces.get_mut(index).ok_or("No Such Interface")?;
+    let mut clients = CLIENTS.lock();
+    let client = clients.get_mut(&index).ok_or("DHCP Isn't Running")?;
+    let lease = client.lease.clone().ok_or("No Lease To Release")?;
+
+    let mut repr = client.repr(DhcpMessageType::Release, iface.ethernet_addr());
+    repr.client_ip = lease.address.address();
+    repr.server_identifier = Some(lease.server);
+    repr.parameter_request_list = None;
+    repr.max_size = None;
+    client.send(sockets, &repr, lease.address.address(), lease.server)?;
+    // The Release Has To Go Out While The Address Is Still Ours
+    let _ = iface.poll(sockets, service::now());
+
+    client.drop_lease(iface);
+    client.state = State::Stopped;
+    println!("Released {}", lease.address);
+    Ok(())
+}
+
+/// Interface `index`'s Client State & Current Lease, `None` If It Isn't Running
+pub fn status(index: usize) -> Option<(State, Option<Lease>)> {
+    CLIENTS.lock().get(&index).map(|client| (client.state, client.lease.clone()))
+}
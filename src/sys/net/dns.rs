@@ -0,0 +1,253 @@
+//! DNS Stub Resolver
+//!
+//! Asks The Servers In [super::CONFIG] For A Records Over UDP, Following CNAMEs Either Within
+//! One Response Or With A Fresh Query When The Server Only Sent The Alias. Answers Are Cached
+//! Until The Shortest TTL Along The Chain Runs Out. Each Server Is Tried [ATTEMPTS] Times,
+//! Waiting [TIMEOUT_MS] For Each Reply, Before Moving On To The Next.
+
+use core::sync::atomic::{AtomicU16, Ordering};
+
+use alloc::{collections::BTreeMap, string::String, vec::Vec};
+use lazy_static::lazy_static;
+use smoltcp::wire::Ipv4Address;
+use spin::Mutex;
+
+use crate::{KResult, sys::timer};
+
+use super::{CONFIG, socket::{self, Kind}};
+
+pub const PORT: u16 = 53;
+
+const TIMEOUT_MS: u128 = 2000;
+const ATTEMPTS: usize = 3;
+/// Longest Chain Of Aliases Followed Before Giving Up
+const MAX_CNAMES: usize = 8;
+/// Biggest Response Read, Anything Longer Would Come Over TCP
+const MAX_MESSAGE: usize = 512;
+
+const TYPE_A: u16 = 1;
+const TYPE_CNAME: u16 = 5;
+const CLASS_IN: u16 = 1;
+
+/// Recursion Desired
+const FLAG_RD: u16 = 1 << 8;
+const FLAG_QR: u16 = 1 << 15;
+const RCODE_NXDOMAIN: u16 = 3;
+
+#[derive(Debug, Clone, PartialEq)]
+enum Data {
+    A(Ipv4Address),
+    Cname(String),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct Record {
+    name: String,
+    ttl: u32,
+    data: Data,
+}
+
+struct Cached {
+    addrs: Vec<Ipv4Address>,
+    /// Uptime In Milliseconds When The Entry Goes Stale
+    expires: u128,
+}
+
+lazy_static! {
+    static ref CACHE: Mutex<BTreeMap<String, Cached>> = Mutex::new(BTreeMap::new());
+}
+
+static NEXT_ID: AtomicU16 = AtomicU16::new(1);
+
+fn query(id: u16, name: &str) -> Vec<u8> {
+    let mut msg = Vec::with_capacity(MAX_MESSAGE);
+    for field in [id, FLAG_RD, 1, 0, 0, 0].iter() {
+        msg.extend_from_slice(&field.to_be_bytes());
+    }
+    for label in name.trim_end_matches('.').split('.') {
+        msg.push(label.len() as u8);
+        msg.extend_from_slice(label.as_bytes());
+    }
+    msg.push(0);
+    msg.extend_from_slice(&TYPE_A.to_be_bytes());
+    msg.extend_from_slice(&CLASS_IN.to_be_bytes());
+    msg
+}
+
+fn read_u16(msg: &[u8], offset: usize) -> KResult<u16> {
+    msg.get(offset..offset + 2).map(|bytes| u16::from_be_bytes([bytes[0], bytes[1]])).ok_or("Truncated DNS Message")
+}
+
+fn read_u32(msg: &[u8], offset: usize) -> KResult<u32> {
+    Ok((read_u16(msg, offset)? as u32) << 16 | read_u16(msg, offset + 2)? as u32)
+}
+
+/// Reads A Possibly Compressed Name, Returning It & The Offset After It
+fn read_name(msg: &[u8], mut offset: usize) -> KResult<(String, usize)> {
+    let mut name = String::new();
+    let mut end = None;
+    // Every Pointer Has To Go Backwards, So This Many Jumps Means A Loop
+    for _ in 0..msg.len() {
+        let len = *msg.get(offset).ok_or("Truncated DNS Message")? as usize;
+        if len & 0xC0 == 0xC0 {
+            let target = (read_u16(msg, offset)? & 0x3FFF) as usize;
+            if target >= offset { return Err("Bad DNS Name Pointer"); }
+            end.get_or_insert(offset + 2);
+            offset = target;
+            continue;
+        }
+        if len == 0 { return Ok((name, end.unwrap_or(offset + 1))); }
+        let label = msg.get(offset + 1..offset + 1 + len).ok_or("Truncated DNS Message")?;
+        if !name.is_empty() { name.push('.'); }
+        name.extend(label.iter().map(|byte| byte.to_ascii_lowercase() as char));
+        offset += 1 + len;
+    }
+    Err("Bad DNS Name Pointer")
+}
+
+/// Pulls The A & CNAME Records Out Of The Answer Section Of A Reply To Query `id`
+fn parse(msg: &[u8], id: u16) -> KResult<Vec<Record>> {
+    if read_u16(msg, 0)? != id { return Err("Mismatched DNS Reply"); }
+    let flags = read_u16(msg, 2)?;
+    if flags & FLAG_QR == 0 { return Err("Not A DNS Reply"); }
+    match flags & 0xF {
+        0 => {},
+        RCODE_NXDOMAIN => return Err("No Such Host"),
+        _ => return Err("DNS Server Failure"),
+    }
+    let (questions, answers) = (read_u16(msg, 4)?, read_u16(msg, 6)?);
+
+    let mut offset = 12;
+    for _ in 0..questions {
+        offset = read_name(msg, offset)?.1 + 4;
+    }
+    let mut records = Vec::new();
+    for _ in 0..answers {
+        let (name, next) = read_name(msg, offset)?;
+        let (kind, class, ttl, len) = (read_u16(msg, next)?, read_u16(msg, next + 2)?, read_u32(msg, next + 4)?, read_u16(msg, next + 8)? as usize);
+        let data = next + 10;
+        if msg.len() < data + len { return Err("Truncated DNS Message"); }
+        match (kind, class) {
+            (TYPE_A, CLASS_IN) if len == 4 => records.push(Record { name, ttl, data: Data::A(Ipv4Address::from_bytes(&msg[data..data + 4])) }),
+            (TYPE_CNAME, CLASS_IN) => records.push(Record { name, ttl, data: Data::Cname(read_name(msg, data)?.0) }),
+            _ => {},
+        }
+        offset = data + len;
+    }
+    Ok(records)
+}
+
+/// Follows `name` Through `records`, Returning Its Addresses Or The Alias The Chain Ends On,
+/// Along With The Shortest TTL Seen
+fn follow(records: &[Record], name: &str) -> (Vec<Ipv4Address>, Option<String>, u32) {
+    let mut current = String::from(name);
+    let mut ttl = u32::MAX;
+    for _ in 0..MAX_CNAMES {
+        let addrs: Vec<Ipv4Address> = records.iter().filter(|record| record.name == current).filter_map(|record| match record.data {
+            Data::A(addr) => { ttl = ttl.min(record.ttl); Some(addr) },
+            _ => None,
+        }).collect();
+        if !addrs.is_empty() { return (addrs, None, ttl); }
+        let alias = records.iter().find_map(|record| match &record.data {
+            Data::Cname(target) if record.name == current => { ttl = ttl.min(record.ttl); Some(target.clone()) },
+            _ => None,
+        });
+        match alias {
+            Some(alias) => current = alias,
+            None => break,
+        }
+    }
+    (Vec::new(), if current == name { None } else { Some(current) }, ttl)
+}
+
+/// Asks `server` About `name`, Retrying On Timeouts
+fn ask(server: Ipv4Address, name: &str) -> KResult<Vec<Record>> {
+    let id = socket::create(Kind::Udp)?;
+    let res = (|| {
+        socket::connect(id, server.into(), PORT)?;
+        let mut buf = [0; MAX_MESSAGE];
+        for _ in 0..ATTEMPTS {
+            let query_id = NEXT_ID.fetch_add(1, Ordering::Relaxed) ^ (timer::uptime_millis() as u16);
+            socket::send(id, &query(query_id, name))?;
+            let start = timer::uptime_millis();
+            // Stray Or Late Replies To Earlier Attempts Don't Use Up The Attempt
+            loop {
+                let elapsed = timer::uptime_millis() - start;
+                if elapsed >= TIMEOUT_MS { break; }
+                let (len, from) = match socket::recv_from_timeout(id, &mut buf, TIMEOUT_MS.saturating_sub(elapsed)) {
+                    Ok(reply) => reply,
+                    Err("Timed Out") => break,
+                    Err(e) => return Err(e),
+                };
+                if from.addr != server.into() { continue; }
+                match parse(&buf[..len], query_id) {
+                    Err("Mismatched DNS Reply") => continue,
+                    res => return res,
+                }
+            }
+        }
+        Err("DNS Server Didn't Answer")
+    })();
+    socket::close(id)?;
+    res
+}
+
+/// Every Address `name` Resolves To. IPv4 Literals Are Returned As They Are.
+pub fn lookup(name: &str) -> KResult<Vec<Ipv4Address>> {
+    if let Ok(addr) = name.parse::<Ipv4Address>() { return Ok(alloc::vec![addr]); }
+    let name = name.trim_end_matches('.').to_ascii_lowercase();
+    if name.is_empty() || name.split('.').any(|label| label.is_empty() || label.len() > 63) { return Err("Invalid Host Name"); }
+
+    let now = timer::uptime_millis();
+    if let Some(cached) = CACHE.lock().get(&name) {
+        if cached.expires > now { return Ok(cached.addrs.clone()); }
+    }
+
+    let servers = CONFIG.lock().dns_servers.clone();
+    if servers.is_empty() { return Err("No DNS Servers Configured"); }
+
+    let mut current = name.clone();
+    let mut ttl = u32::MAX;
+    for _ in 0..MAX_CNAMES {
+        let mut res = Err("No DNS Servers Configured");
+        for server in servers.iter() {
+            res = ask(*server, &current);
+            // A Server That Answered Is Believed, Only Silence Or Failure Moves On
+            if matches!(res, Ok(_) | Err("No Such Host")) { break; }
+        }
+        let (addrs, alias, chain_ttl) = follow(&res?, &current);
+        ttl = ttl.min(chain_ttl);
+        if !addrs.is_empty() {
+            CACHE.lock().insert(name, Cached { addrs: addrs.clone(), expires: now + ttl as u128 * 1000 });
+            return Ok(addrs);
+        }
+        current = alias.ok_or("No Such Host")?;
+    }
+    Err("Too Many DNS Aliases")
+}
+
+/// The First Address `name` Resolves To
+pub fn resolve(name: &str) -> KResult<Ipv4Address> {
+    lookup(name).map(|addrs| addrs[0])
+}
+
+pub fn flush_cache() {
+    CACHE.lock().clear();
+}
+
+#[test_case]
+fn dns_parses_compressed_cname_chains() {
+    // www.example.com CNAME example.com, example.com A 93.184.216.34
+    let mut msg = query(0x1234, "www.example.com");
+    msg[2..4].copy_from_slice(&(FLAG_QR | FLAG_RD).to_be_bytes());
+    msg[6..8].copy_from_slice(&2u16.to_be_bytes());
+    msg.extend_from_slice(&[0xC0, 12, 0, 5, 0, 1, 0, 0, 0, 60, 0, 2, 0xC0, 16]);
+    msg.extend_from_slice(&[0xC0, 16, 0, 1, 0, 1, 0, 0, 1, 0, 0, 4, 93, 184, 216, 34]);
+
+    assert_eq!(read_name(&msg, 12), Ok((String::from("www.example.com"), 29)));
+    let records = parse(&msg, 0x1234).expect("");
+    assert_eq!(records.len(), 2);
+    assert_eq!(follow(&records, "www.example.com"), (alloc::vec![Ipv4Address::new(93, 184, 216, 34)], None, 60));
+    assert_eq!(follow(&records[..1], "www.example.com"), (Vec::new(), Some(String::from("example.com")), 60));
+    assert_eq!(parse(&msg, 0x4321), Err("Mismatched DNS Reply"));
+}
@@ -0,0 +1,396 @@
+//! Intel 8254x (e1000) Network Driver
+//!
+//! Written Against The 82540EM QEMU Emulates By Default. Frames Move Through Two Descriptor
+//! Rings In Memory The Card Reaches By DMA: Receive Descriptors Each Point At A 2 KiB Buffer
+//! The Card Fills & Marks Done, Transmit Descriptors At One The Driver Has Filled. The Tail
+//! Registers Tell The Card How Far Along The Rings It May Go.
+
+use core::ptr::{read_volatile, write_volatile};
+use core::sync::atomic::{AtomicU64, Ordering};
+
+use alloc::vec::Vec;
+use smoltcp::{phy::{self, Device, DeviceCapabilities}, wire::EthernetAddress};
+use x86_64::PhysAddr;
+
+use crate::{KResult, arch::i386::interrupts::idt, log, sys::{self, mem::{self, allocator::PhysBuf}, pci, timer}};
+
+use super::nic::{self, MAX_CARDS};
+
+/// The Name In `network.toml`
+pub const DEVICE: &str = "PCI/INTEL/82540EM";
+
+const VENDOR_ID: u16 = 0x8086;
+const DEVICE_ID: u16 = 0x100E;
+
+// Registers
+const REG_CTRL: u64 = 0x0000;
+const REG_EERD: u64 = 0x0014;
+const REG_ICR: u64 = 0x00C0;
+const REG_IMS: u64 = 0x00D0;
+const REG_IMC: u64 = 0x00D8;
+const REG_RCTL: u64 = 0x0100;
+const REG_TCTL: u64 = 0x0400;
+const REG_TIPG: u64 = 0x0410;
+const REG_RDBAL: u64 = 0x2800;
+const REG_RDBAH: u64 = 0x2804;
+const REG_RDLEN: u64 = 0x2808;
+const REG_RDH: u64 = 0x2810;
+const REG_RDT: u64 = 0x2818;
+const REG_TDBAL: u64 = 0x3800;
+const REG_TDBAH: u64 = 0x3804;
+const REG_TDLEN: u64 = 0x3808;
+const REG_TDH: u64 = 0x3810;
+const REG_TDT: u64 = 0x3818;
+const REG_MTA: u64 = 0x5200; // Multicast Table Array, 128 Entries
+const REG_RAL: u64 = 0x5400;
+const REG_RAH: u64 = 0x5404;
+
+const CTRL_ASDE: u32 = 1 << 5; // Auto Speed Detection
+const CTRL_SLU: u32 = 1 << 6; // Set Link Up
+const CTRL_RST: u32 = 1 << 26;
+
+const EERD_START: u32 = 1 << 0;
+const EERD_DONE: u32 = 1 << 4;
+
+const RCTL_EN: u32 = 1 << 1;
+const RCTL_BAM: u32 = 1 << 15; // Accept Broadcast
+const RCTL_SECRC: u32 = 1 << 26; // Strip The CRC
+// Buffer Size Bits Left At 00 For 2048 Bytes
+
+const TCTL_EN: u32 = 1 << 1;
+const TCTL_PSP: u32 = 1 << 3; // Pad Short Packets
+const TCTL_CT: u32 = 0x10 << 4; // Collision Threshold
+const TCTL_COLD: u32 = 0x40 << 12; // Collision Distance, Full Duplex
+
+/// Recommended Inter Packet Gap For The 82540EM
+const TIPG_DEFAULT: u32 = 0x0060_200A;
+
+const RAH_AV: u32 = 1 << 31; // Address Valid
+
+// Interrupt Causes
+const ICR_TXDW: u32 = 1 << 0; // Transmit Descriptor Written Back
+const ICR_LSC: u32 = 1 << 2; // Link Status Change
+const ICR_RXDMT0: u32 = 1 << 4; // Receive Descriptors Running Low
+const ICR_RXO: u32 = 1 << 6; // Receiver Overrun
+const ICR_RXT0: u32 = 1 << 7; // Receiver Timer
+
+const DESC_DD: u8 = 1 << 0; // Descriptor Done
+const RX_EOP: u8 = 1 << 1; // End Of Packet
+const TX_CMD_EOP: u8 = 1 << 0;
+const TX_CMD_IFCS: u8 = 1 << 1; // Insert The CRC
+const TX_CMD_RS: u8 = 1 << 3; // Report Status
+
+const DESC_LEN: usize = 16;
+const RX_DESCS: usize = 32;
+const TX_DESCS: usize = 8;
+const BUFFER_LEN: usize = 2048;
+/// Rings Must Start On A 16 Byte Boundary & Be A Multiple Of 128 Bytes Long
+const RING_ALIGN: usize = 128;
+
+/// Ethernet Header + 1500 Bytes Of Payload
+const MTU: usize = 1514;
+
+/// Seconds To Wait For A Reset Or An EEPROM Read Before Giving Up On The Card
+const TIMEOUT: f64 = 1.0;
+
+/// Each Card's Register Base, So The Interrupt Handler Can Acknowledge Them Without Locking The
+/// Interfaces. Empty Slots Are 0.
+static BASES: [AtomicU64; MAX_CARDS] = [const { AtomicU64::new(0) }; MAX_CARDS];
+
+unsafe fn reg_read(base: u64, reg: u64) -> u32 {
+    read_volatile((base + reg) as *const u32)
+}
+
+unsafe fn reg_write(base: u64, reg: u64, value: u32) {
+    write_volatile((base + reg) as *mut u32, value)
+}
+
+/// A Descriptor Ring & The Buffer Behind Each Entry
+#[derive(Debug, Clone)]
+struct Ring {
+    descs: PhysBuf,
+    buffers: Vec<PhysBuf>,
+    /// The Next Descriptor The Driver Looks At
+    next: usize,
+}
+
+impl Ring {
+    fn new(len: usize) -> Self {
+        Self {
+            descs: PhysBuf::new_aligned(len * DESC_LEN, RING_ALIGN),
+            buffers: (0..len).map(|_| PhysBuf::new(BUFFER_LEN)).collect(),
+            next: 0,
+        }
+    }
+
+    fn len(&self) -> usize {
+        self.buffers.len()
+    }
+
+    fn ptr<T>(&self, index: usize, offset: usize) -> *mut T {
+        self.descs[index * DESC_LEN + offset..].as_ptr() as *mut T
+    }
+
+    /// The Status Byte, At The Same Offset In Both Descriptor Layouts
+    fn status(&self, index: usize) -> u8 {
+        unsafe { read_volatile(self.ptr::<u8>(index, 12)) }
+    }
+
+    fn set_status(&mut self, index: usize, status: u8) {
+        unsafe { write_volatile(self.ptr::<u8>(index, 12), status) }
+    }
+
+    fn set_addr(&mut self, index: usize) {
+        let addr = self.buffers[index].addr();
+        unsafe { write_volatile(self.ptr::<u64>(index, 0), addr) }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct E1000 {
+    /// Under `/dev`, See [nic::card_name]
+    name: &'static str,
+    base: u64,
+    rx: Ring,
+    tx: Ring,
+    eth_addr: EthernetAddress,
+}
+
+impl E1000 {
+    pub fn new(base: u64, name: &'static str) -> Self {
+        Self {
+            name,
+            base,
+            rx: Ring::new(RX_DESCS),
+            tx: Ring::new(TX_DESCS),
+            eth_addr: EthernetAddress([0; 6]),
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        self.name
+    }
+
+    fn read(&self, reg: u64) -> u32 {
+        unsafe { reg_read(self.base, reg) }
+    }
+
+    fn write(&mut self, reg: u64, value: u32) {
+        unsafe { reg_write(self.base, reg, value) }
+    }
+
+    fn read_eeprom(&mut self, word: u8) -> KResult<u16> {
+        self.write(REG_EERD, (word as u32) << 8 | EERD_START);
+        let start = timer::uptime_seconds();
+        loop {
+            let data = self.read(REG_EERD);
+            if data & EERD_DONE != 0 { return Ok((data >> 16) as u16); }
+            if timer::uptime_seconds() - start > TIMEOUT { return Err("E1000 EEPROM Read Timed Out"); }
+        }
+    }
+
+    /// The Address In The First Receive Address Register, Or The EEPROM If That's Empty
+    fn read_mac(&mut self) -> KResult<EthernetAddress> {
+        let (low, high) = (self.read(REG_RAL), self.read(REG_RAH));
+        if high & RAH_AV != 0 && low != 0 {
+            let low = low.to_le_bytes();
+            let high = high.to_le_bytes();
+            return Ok(EthernetAddress([low[0], low[1], low[2], low[3], high[0], high[1]]));
+        }
+        let mut mac = [0; 6];
+        for word in 0..3 {
+            mac[word * 2..word * 2 + 2].copy_from_slice(&self.read_eeprom(word as u8)?.to_le_bytes());
+        }
+        Ok(EthernetAddress(mac))
+    }
+
+    pub fn init(&mut self) -> KResult<()> {
+        // Reset, Masking Interrupts Either Side As The Manual Asks
+        self.write(REG_IMC, u32::MAX);
+        let ctrl = self.read(REG_CTRL);
+        self.write(REG_CTRL, ctrl | CTRL_RST);
+        timer::pause(0.01);
+        let start = timer::uptime_seconds();
+        while self.read(REG_CTRL) & CTRL_RST != 0 {
+            if timer::uptime_seconds() - start > TIMEOUT { return Err("E1000 Reset Timed Out"); }
+        }
+        self.write(REG_IMC, u32::MAX);
+        self.read(REG_ICR);
+
+        let ctrl = self.read(REG_CTRL);
+        self.write(REG_CTRL, ctrl | CTRL_SLU | CTRL_ASDE);
+
+        self.eth_addr = self.read_mac()?;
+        let mac = self.eth_addr.0;
+        self.write(REG_RAL, u32::from_le_bytes([mac[0], mac[1], mac[2], mac[3]]));
+        self.write(REG_RAH, u32::from_le_bytes([mac[4], mac[5], 0, 0]) | RAH_AV);
+        for i in 0..128 {
+            self.write(REG_MTA + i * 4, 0);
+        }
+
+        // Every Receive Descriptor Starts Out Owned By The Card
+        for i in 0..self.rx.len() {
+            self.rx.set_addr(i);
+            self.rx.set_status(i, 0);
+        }
+        let addr = self.rx.descs.addr();
+        self.write(REG_RDBAL, addr as u32);
+        self.write(REG_RDBAH, (addr >> 32) as u32);
+        self.write(REG_RDLEN, (self.rx.len() * DESC_LEN) as u32);
+        self.write(REG_RDH, 0);
+        self.write(REG_RDT, self.rx.len() as u32 - 1);
+        self.write(REG_RCTL, RCTL_EN | RCTL_BAM | RCTL_SECRC);
+
+        // Every Transmit Descriptor Starts Out Done, So Free For The Driver
+        for i in 0..self.tx.len() {
+            self.tx.set_addr(i);
+            self.tx.set_status(i, DESC_DD);
+        }
+        let addr = self.tx.descs.addr();
+        self.write(REG_TDBAL, addr as u32);
+        self.write(REG_TDBAH, (addr >> 32) as u32);
+        self.write(REG_TDLEN, (self.tx.len() * DESC_LEN) as u32);
+        self.write(REG_TDH, 0);
+        self.write(REG_TDT, 0);
+        self.write(REG_TCTL, TCTL_EN | TCTL_PSP | TCTL_CT | TCTL_COLD);
+        self.write(REG_TIPG, TIPG_DEFAULT);
+
+        self.write(REG_IMS, ICR_RXT0 | ICR_RXO | ICR_RXDMT0 | ICR_LSC | ICR_TXDW);
+        Ok(())
+    }
+
+    pub fn ethernet_addr(&self) -> EthernetAddress {
+        self.eth_addr
+    }
+
+    /// Takes The Next Frame Out Of The Receive Ring, Handing Its Descriptor Back To The Card
+    fn next_frame(&mut self) -> Option<Vec<u8>> {
+        loop {
+            let index = self.rx.next;
+            let status = self.rx.status(index);
+            if status & DESC_DD == 0 { return None; }
+
+            let len = unsafe { read_volatile(self.rx.ptr::<u16>(index, 8)) } as usize;
+            let errors = unsafe { read_volatile(self.rx.ptr::<u8>(index, 13)) };
+            // Frames Never Span Buffers Without Long Packets Enabled, Anything Else Is Dropped
+            let frame = if status & RX_EOP != 0 && errors == 0 {
+                Some(self.rx.buffers[index][..len.min(BUFFER_LEN)].to_vec())
+            } else {
+                None
+            };
+
+            self.rx.set_status(index, 0);
+            self.rx.next = (index + 1) % self.rx.len();
+            self.write(REG_RDT, index as u32);
+            if frame.is_some() { return frame; }
+        }
+    }
+
+    /// One Descriptor Always Stays Empty, A Tail Equal To The Head Would Read As An Empty Ring
+    fn tx_free(&self) -> bool {
+        let next = (self.tx.next + 1) % self.tx.len();
+        next != self.read(REG_TDH) as usize && self.tx.status(self.tx.next) & DESC_DD != 0
+    }
+}
+
+#[doc(hidden)]
+pub struct RxToken {
+    buffer: Vec<u8>,
+}
+
+#[doc(hidden)]
+pub struct TxToken<'a> {
+    device: &'a mut E1000,
+}
+
+impl<'a> Device<'a> for E1000 {
+    type RxToken = RxToken;
+    type TxToken = TxToken<'a>;
+
+    fn receive(&'a mut self) -> Option<(Self::RxToken, Self::TxToken)> {
+        let buffer = loop {
+            let frame = self.next_frame()?;
+            if sys::net::accept(self.name, &frame) { break frame; }
+        };
+        Some((RxToken { buffer }, TxToken { device: self }))
+    }
+
+    fn transmit(&'a mut self) -> Option<Self::TxToken> {
+        if !self.tx_free() { return None; }
+        Some(TxToken { device: self })
+    }
+
+    fn capabilities(&self) -> DeviceCapabilities {
+        let mut caps = DeviceCapabilities::default();
+        caps.max_transmission_unit = MTU;
+        caps.max_burst_size = Some(TX_DESCS - 1);
+        caps
+    }
+}
+
+impl<'a> phy::TxToken for TxToken<'a> {
+    fn consume<R, F>(mut self, _: smoltcp::time::Instant, len: usize, f: F) -> smoltcp::Result<R>
+        where F: FnOnce(&mut [u8]) -> smoltcp::Result<R> {
+            if len > BUFFER_LEN { return Err(smoltcp::Error::Truncated); }
+            if !self.device.tx_free() { return Err(smoltcp::Error::Exhausted); }
+
+            let tx = &mut self.device.tx;
+            let index = tx.next;
+            let res = f(&mut tx.buffers[index][..len])?;
+            unsafe {
+                write_volatile(tx.ptr::<u16>(index, 8), len as u16);
+                write_volatile(tx.ptr::<u8>(index, 11), TX_CMD_EOP | TX_CMD_IFCS | TX_CMD_RS);
+            }
+            tx.set_status(index, 0);
+            tx.next = (index + 1) % tx.len();
+            let tail = tx.next as u32;
+            // The Card Starts On Everything Up To The New Tail
+            self.device.write(REG_TDT, tail);
+            Ok(res)
+    }
+}
+
+impl phy::RxToken for RxToken {
+    fn consume<R, F>(mut self, _: smoltcp::time::Instant, f: F) -> smoltcp::Result<R>
+        where F: FnOnce(&mut [u8]) -> smoltcp::Result<R> {
+            f(&mut self.buffer)
+    }
+}
+
+/// Brings Up Every Card Found, Skipping Any That Fail
+pub fn init() -> Vec<E1000> {
+    let mut devices = Vec::new();
+    for mut pci_device in pci::find_devices(VENDOR_ID, DEVICE_ID) {
+        if devices.len() == MAX_CARDS {
+            log!("NET E1000 {:02}:{:02} Ignored, Only {} Cards Are Supported\n", pci_device.bus, pci_device.device, MAX_CARDS);
+            continue;
+        }
+        pci_device.enable_bus_mastering();
+
+        let base = mem::phys_to_virt(PhysAddr::new(pci_device.bar_addr(0))).as_u64();
+        let mut device = E1000::new(base, nic::card_name("e1000", devices.len()));
+        if let Err(e) = device.init() {
+            log!("NET E1000 {:02}:{:02} {}\n", pci_device.bus, pci_device.device, e);
+            continue;
+        }
+        log!("NET {} MAC {}\n", device.name, device.ethernet_addr());
+
+        BASES[devices.len()].store(base, Ordering::Release);
+        log!("NET {} IRQ {}\n", device.name, pci_device.interrupt_line);
+        idt::set_irq_handler(pci_device.interrupt_line, interrupt_handler);
+        devices.push(device);
+    }
+    devices
+}
+
+/// Acknowledges Every Card, As They May Share The Line, & Leaves The Actual Work To The
+/// Network Service
+pub fn interrupt_handler() {
+    for base in BASES.iter().map(|base| base.load(Ordering::Acquire)).filter(|base| *base != 0) {
+        // Reading The Cause Clears It
+        let cause = unsafe { reg_read(base, REG_ICR) };
+        if cause & (ICR_RXT0 | ICR_RXO | ICR_RXDMT0 | ICR_TXDW) != 0 {
+            sys::net::service::wake();
+        }
+    }
+}
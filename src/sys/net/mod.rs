@@ -1,37 +1,230 @@
 pub mod dhcp;
+pub mod dns;
+pub mod e1000;
+pub mod nic;
+pub mod ping;
+pub mod route;
+pub mod service;
+pub mod socket;
 
 use core::fmt::Write;
 
-use alloc::string::String;
+use alloc::{collections::BTreeMap, format, string::String, vec::Vec};
 use lazy_static::lazy_static;
-use smoltcp::{socket::TcpSocket, };
+use smoltcp::{iface::{EthernetInterfaceBuilder, NeighborCache, Routes}, socket::SocketSet, wire::{EthernetFrame, EthernetProtocol, Icmpv4Message, Icmpv4Packet, IpCidr, IpProtocol, Ipv4Address, Ipv4Cidr, Ipv4Packet}};
 use spin::Mutex;
 
+use crate::{KResult, config, println};
+
+use nic::Nic;
+
 pub type EthernetInterface<T> = smoltcp::iface::EthernetInterface<'static, T>;
 
 pub mod rtl8139;
 
-// pub fn local_endpoint() -> IpAddress {
-//     (*IFACE.lock()).unwrap().ip_addrs()[0].address()
-// }
-
 lazy_static! {
-    pub static ref IFACE: Mutex<Option<EthernetInterface<rtl8139::RTL8139>>> = Mutex::new(None);
+    /// Every Interface Brought Up At Boot, Routes Refer To Them By Their Index Here
+    pub static ref IFACES: Mutex<Vec<Interface>> = Mutex::new(Vec::new());
+    pub static ref CONFIG: Mutex<Config> = Mutex::new(Config::default());
+    /// Names Of Cards That Don't Answer Echo Requests, `pingable = false` In `network.toml`
+    static ref UNPINGABLE: Mutex<Vec<&'static str>> = Mutex::new(Vec::new());
 }
 
+/// A Card & The Sockets Whose Traffic Leaves Through It
+pub struct Interface {
+    /// From `network.toml`, Or `eth<n>` For Cards It Doesn't Mention
+    pub name: String,
+    pub iface: EthernetInterface<Nic>,
+    /// Polled Alongside `iface` By The [service], [route::lookup] Decides Which Set A Socket Is In
+    pub sockets: SocketSet<'static>,
+}
+
+/// Settings Shared By Every Interface, Gateways Live In The [route] Table
+#[derive(Debug, Clone, Default)]
+pub struct Config {
+    pub dns_servers: Vec<Ipv4Address>,
+    /// Set When The DNS Servers Came From `network.toml` Or `net set dns`, DHCP Keeps Its Own
+    pub static_dns: bool,
+}
+
+pub use dns::resolve;
+
+/// Brings Up Every Supported Card Found, Those Named In `network.toml` First So They Get The
+/// Lowest Indices. Each Entry Goes To One Card, Several Of The Same Model Take Them In PCI
+/// Order. Cards It Doesn't Mention Use DHCP.
 pub fn init() {
-    rtl8139::init();
-    dhcp::init();
+    let confs = config::Interface::all().unwrap_or_else(|e| { println!("network.toml: {}", e); Vec::new() });
+    let mut drivers = nic::DRIVERS.to_vec();
+    drivers.sort_by_key(|(device, _)| !confs.iter().any(|conf| conf.device == *device));
+    let mut unclaimed: Vec<&config::Interface> = confs.iter().collect();
+    let mut cards = Vec::new();
+    let mut ifaces = IFACES.lock();
+    for nic in drivers.iter().flat_map(|(_, probe)| probe()) {
+        let conf = unclaimed.iter().position(|conf| conf.device == nic.device()).map(|i| unclaimed.remove(i));
+        let name = match conf {
+            Some(conf) => conf.name.clone(),
+            None => (0..).map(|n| format!("eth{}", n))
+                .find(|name| !ifaces.iter().any(|iface| &iface.name == name) && !confs.iter().any(|conf| &conf.name == name))
+                .expect("Names Run Out"),
+        };
+        println!("{}: {} {}", name, nic.name(), nic.ethernet_addr());
+        cards.push((nic.name(), conf));
+        ifaces.push(Interface { name, iface: interface(nic), sockets: SocketSet::new(Vec::new()) });
+    }
+    if ifaces.is_empty() { println!("No Supported Network Card"); return; }
+    drop(ifaces);
+    for conf in unclaimed {
+        println!("network.toml: No {} Left For {}", conf.device, conf.name);
+    }
+
+    // The DHCP Clients Are Driven By The Service, So It Has To Be Running First
+    service::start();
+    let mut dhcp = false;
+    for (index, (card, conf)) in cards.into_iter().enumerate() {
+        dhcp |= match conf {
+            Some(conf) => configure(index, card, conf),
+            None => dhcp::start(index).is_ok(),
+        };
+    }
+    if dhcp { dhcp::init(); }
+}
+
+/// An Interface Over `nic` With No Address Yet
+fn interface(nic: Nic) -> EthernetInterface<Nic> {
+    let eth_addr = nic.ethernet_addr();
+    let ip_addrs = [
+        IpCidr::new(Ipv4Address::UNSPECIFIED.into(), 0),
+    ];
+    EthernetInterfaceBuilder::new(nic).
+        ethernet_addr(eth_addr).
+        neighbor_cache(NeighborCache::new(BTreeMap::new())).
+        ip_addrs(ip_addrs).
+        routes(Routes::new(BTreeMap::new())).
+        finalize()
+}
+
+/// Runs `f` On Interface `index`
+pub fn with_iface<T>(index: usize, f: impl FnOnce(&mut Interface) -> T) -> KResult<T> {
+    IFACES.lock().get_mut(index).map(f).ok_or("No Such Interface")
+}
+
+/// The Index Of The Interface Called `name`
+pub fn index(name: &str) -> Option<usize> {
+    IFACES.lock().iter().position(|iface| iface.name == name)
+}
+
+/// The Name Of Interface `index`
+pub fn name(index: usize) -> Option<String> {
+    IFACES.lock().get(index).map(|iface| iface.name.clone())
+}
+
+/// How Many Interfaces Came Up
+pub fn count() -> usize {
+    IFACES.lock().len()
 }
 
-pub struct NetworkDevice<'a> {
-    _socket: TcpSocket<'a>,
+/// The Cards' Names Under `/dev`, In Interface Order
+pub fn nic_names() -> Vec<&'static str> {
+    IFACES.lock().iter().map(|iface| iface.iface.device().name()).collect()
+}
+
+/// The Index Of The Interface Whose Card Is `dev/<name>`
+pub fn nic_index(name: &str) -> Option<usize> {
+    nic_names().iter().position(|nic| *nic == name)
+}
+
+/// Applies An Interface's Static Settings, Then Leaves The Rest To DHCP If It's Enabled.
+/// Returns Whether DHCP Was Started.
+fn configure(index: usize, card: &'static str, conf: &config::Interface) -> bool {
+    println!("Configuring {} From network.toml", conf.name);
+    if !conf.pingable { UNPINGABLE.lock().push(card); }
+    if let Some(gateway) = conf.default_gateway_v4 {
+        if let Err(e) = set_gateway(index, gateway) { println!("Gateway {}: {}", gateway, e); }
+    }
+    if !conf.dns_v4.is_empty() {
+        set_dns(conf.dns_v4.clone());
+    }
+    match (conf.dhcp, conf.ipv4) {
+        (true, _) => return dhcp::start(index).is_ok(),
+        (false, Some(address)) => if let Err(e) = set_address(index, address) { println!("Address {}: {}", address, e); },
+        (false, None) => println!("{} Has Neither DHCP Nor A Static Address", conf.name),
+    }
+    false
+}
+
+/// Gives Interface `index` A Static Address, Stopping Its DHCP Client So It Doesn't Get Replaced
+pub fn set_address(index: usize, address: Ipv4Cidr) -> KResult<()> {
+    dhcp::stop(index);
+    let name = with_iface(index, |iface| {
+        iface.iface.update_ip_addrs(|addrs| {
+            if let Some(addr) = addrs.iter_mut().next() {
+                *addr = IpCidr::Ipv4(address);
+            }
+        });
+        iface.name.clone()
+    })?;
+    route::set_link(index, address);
+    println!("{}: Address {}", name, address);
+    Ok(())
+}
+
+/// Routes Everything Without A More Specific Route Through `gateway` On Interface `index`,
+/// Whatever DHCP Says
+pub fn set_gateway(index: usize, gateway: Ipv4Address) -> KResult<()> {
+    if index >= count() { return Err("No Such Interface"); }
+    route::add(route::Route {
+        cidr: Ipv4Cidr::new(Ipv4Address::UNSPECIFIED, 0),
+        gateway: Some(gateway),
+        iface: index,
+        metric: route::DEFAULT_METRIC,
+        origin: route::Origin::Static,
+    });
+    apply_routes(index)
+}
+
+/// Hands Interface `index` The Gateway Routes It Has In The [route] Table
+pub fn apply_routes(index: usize) -> KResult<()> {
+    with_iface(index, |iface| route::apply(index, &mut iface.iface))
+}
+
+/// Resolves Names Through `servers`, Whatever DHCP Says
+pub fn set_dns(servers: Vec<Ipv4Address>) {
+    let mut config = CONFIG.lock();
+    config.dns_servers = servers;
+    config.static_dns = true;
+    drop(config);
+    dns::flush_cache();
+}
+
+/// Whether A Frame Received By The Card Named `card` Should Be Handed To smoltcp, Which Answers
+/// Every Echo Request Itself. Drivers Check Each Frame Here Before Passing It Up.
+pub fn accept(card: &str, frame: &[u8]) -> bool {
+    if !UNPINGABLE.lock().iter().any(|unpingable| *unpingable == card) { return true; }
+    let frame = match EthernetFrame::new_checked(frame) { Ok(frame) => frame, Err(_) => return true };
+    if frame.ethertype() != EthernetProtocol::Ipv4 { return true; }
+    let packet = match Ipv4Packet::new_checked(frame.payload()) { Ok(packet) => packet, Err(_) => return true };
+    if packet.protocol() != IpProtocol::Icmp { return true; }
+    match Icmpv4Packet::new_checked(packet.payload()) {
+        Ok(icmp) => icmp.msg_type() != Icmpv4Message::EchoRequest,
+        Err(_) => true,
+    }
+}
+
+/// Hands Every Lease Back Before The Machine Goes Down
+pub fn shutdown() {
+    for index in 0..count() {
+        if let Some((_, Some(_))) = dhcp::status(index) {
+            if let Err(e) = dhcp::release(index) {
+                println!("DHCP Release Failed: {}", e);
+            }
+        }
+    }
 }
 
-pub fn mac() -> Option<MacAddress> {
-    let guard = IFACE.lock();
-    if let Some(iface) = &*guard {
-        return Some(MacAddress::new(iface.ethernet_addr().as_bytes()));
+pub fn mac(index: usize) -> Option<MacAddress> {
+    let guard = IFACES.lock();
+    if let Some(iface) = guard.get(index) {
+        return Some(MacAddress::new(iface.iface.ethernet_addr().as_bytes()));
     } else {
         None
     }
@@ -0,0 +1,151 @@
+//! Network Cards
+//!
+//! smoltcp's Interface Is Generic Over One `phy::Device`, So Every Supported Card Is Wrapped In
+//! [Nic] & Its Tokens Dispatched To Whichever Driver Is Underneath. Each Driver Brings Up Every
+//! Card It Finds, Named By [card_name] So Two Of The Same Model Don't Collide In `/dev`.
+
+use alloc::{boxed::Box, format, vec::Vec};
+use smoltcp::{phy::{self, Device, DeviceCapabilities}, time::Instant, wire::EthernetAddress};
+
+use crate::sys::virtio::net::{self as virtio_net, VirtioNet};
+
+use super::{e1000::{self, E1000}, rtl8139::{self, RTL8139}};
+
+pub enum Nic {
+    Rtl8139(RTL8139),
+    E1000(E1000),
+    VirtioNet(VirtioNet),
+}
+
+/// Most Cards One Driver Brings Up, Their Interrupt Handlers Keep A Slot For Each
+pub const MAX_CARDS: usize = 4;
+
+/// Every Supported Card As (Name In `network.toml`, Probe), Tried In This Order. A Probe
+/// Returns Every Card Of Its Kind, In PCI Order.
+pub const DRIVERS: &[(&str, fn() -> Vec<Nic>)] = &[
+    (virtio_net::DEVICE, probe_virtio_net),
+    (e1000::DEVICE, probe_e1000),
+    (rtl8139::DEVICE, probe_rtl8139),
+];
+
+fn probe_virtio_net() -> Vec<Nic> {
+    virtio_net::init().into_iter().map(Nic::VirtioNet).collect()
+}
+
+fn probe_e1000() -> Vec<Nic> {
+    e1000::init().into_iter().map(Nic::E1000).collect()
+}
+
+fn probe_rtl8139() -> Vec<Nic> {
+    rtl8139::init().into_iter().map(Nic::Rtl8139).collect()
+}
+
+/// The `/dev` Name Of A Driver's `n`th Card. The First Keeps The Driver's Name & The Rest Get
+/// `-<n>`, Like `e1000`, `e1000-1`. Cards Stay Up Until Shutdown, So The Name Is Leaked To Be
+/// Shared Like A Fixed One.
+pub fn card_name(driver: &'static str, n: usize) -> &'static str {
+    match n {
+        0 => driver,
+        n => Box::leak(format!("{}-{}", driver, n).into_boxed_str()),
+    }
+}
+
+impl Nic {
+    pub fn ethernet_addr(&self) -> EthernetAddress {
+        match self {
+            Self::Rtl8139(device) => device.ethernet_addr(),
+            Self::E1000(device) => device.ethernet_addr(),
+            Self::VirtioNet(device) => device.ethernet_addr(),
+        }
+    }
+
+    /// The Card's Name Under `/dev`, Unique Among The Cards
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Rtl8139(device) => device.name(),
+            Self::E1000(device) => device.name(),
+            Self::VirtioNet(device) => device.name(),
+        }
+    }
+
+    /// The Card's Name In `network.toml`
+    pub fn device(&self) -> &'static str {
+        match self {
+            Self::Rtl8139(_) => rtl8139::DEVICE,
+            Self::E1000(_) => e1000::DEVICE,
+            Self::VirtioNet(_) => virtio_net::DEVICE,
+        }
+    }
+}
+
+#[doc(hidden)]
+pub enum RxToken {
+    Rtl8139(rtl8139::RxToken),
+    E1000(e1000::RxToken),
+    VirtioNet(virtio_net::RxToken),
+}
+
+#[doc(hidden)]
+pub enum TxToken<'a> {
+    Rtl8139(rtl8139::TxToken),
+    E1000(e1000::TxToken<'a>),
+    VirtioNet(virtio_net::TxToken<'a>),
+}
+
+impl<'a> Device<'a> for Nic {
+    type RxToken = RxToken;
+    type TxToken = TxToken<'a>;
+
+    fn receive(&'a mut self) -> Option<(Self::RxToken, Self::TxToken)> {
+        match self {
+            Self::Rtl8139(device) => device.receive().map(|(rx, tx)| (RxToken::Rtl8139(rx), TxToken::Rtl8139(tx))),
+            Self::E1000(device) => device.receive().map(|(rx, tx)| (RxToken::E1000(rx), TxToken::E1000(tx))),
+            Self::VirtioNet(device) => device.receive().map(|(rx, tx)| (RxToken::VirtioNet(rx), TxToken::VirtioNet(tx))),
+        }
+    }
+
+    fn transmit(&'a mut self) -> Option<Self::TxToken> {
+        match self {
+            Self::Rtl8139(device) => device.transmit().map(TxToken::Rtl8139),
+            Self::E1000(device) => device.transmit().map(TxToken::E1000),
+            Self::VirtioNet(device) => device.transmit().map(TxToken::VirtioNet),
+        }
+    }
+
+    fn capabilities(&self) -> DeviceCapabilities {
+        match self {
+            Self::Rtl8139(device) => device.capabilities(),
+            Self::E1000(device) => device.capabilities(),
+            Self::VirtioNet(device) => device.capabilities(),
+        }
+    }
+}
+
+impl phy::RxToken for RxToken {
+    fn consume<R, F>(self, timestamp: Instant, f: F) -> smoltcp::Result<R>
+        where F: FnOnce(&mut [u8]) -> smoltcp::Result<R> {
+            match self {
+                Self::Rtl8139(token) => token.consume(timestamp, f),
+                Self::E1000(token) => token.consume(timestamp, f),
+                Self::VirtioNet(token) => token.consume(timestamp, f),
+            }
+    }
+}
+
+impl<'a> phy::TxToken for TxToken<'a> {
+    fn consume<R, F>(self, timestamp: Instant, len: usize, f: F) -> smoltcp::Result<R>
+        where F: FnOnce(&mut [u8]) -> smoltcp::Result<R> {
+            match self {
+                Self::Rtl8139(token) => token.consume(timestamp, len, f),
+                Self::E1000(token) => token.consume(timestamp, len, f),
+                Self::VirtioNet(token) => token.consume(timestamp, len, f),
+            }
+    }
+}
+
+#[test_case]
+fn card_names_are_unique() {
+    assert_eq!(card_name("e1000", 0), "e1000");
+    assert_eq!(card_name("e1000", 1), "e1000-1");
+    assert_ne!(card_name("virtio-net", 1), card_name("virtio-net", 2));
+}
@@ -0,0 +1,95 @@
+//! ICMP Echo
+//!
+//! A [Ping] Owns An ICMP Socket Bound To Its Own Identifier, So Replies To Different Pings
+//! Don't Get Mixed Up, On The Interface The [route] Table Picks For Its Target. Each Request
+//! Carries The Uptime It Was Sent At, Which Is What The Round Trip Time Is Worked Out From.
+
+use core::sync::atomic::{AtomicU16, Ordering};
+
+use alloc::vec;
+use smoltcp::{phy::ChecksumCapabilities, socket::{IcmpEndpoint, IcmpPacketMetadata, IcmpSocket, IcmpSocketBuffer, SocketHandle}, wire::{Icmpv4Packet, Icmpv4Repr, IpAddress, Ipv4Address}};
+
+use crate::{KResult, sys::timer};
+
+use super::{IFACES, route, service};
+
+/// Bytes Of Payload In Each Request, The First 8 Hold The Send Time
+pub const PAYLOAD_LEN: usize = 56;
+
+const BUFFER_PACKETS: usize = 4;
+const BUFFER_LEN: usize = 1024;
+
+static NEXT_IDENT: AtomicU16 = AtomicU16::new(0x2200);
+
+pub struct Ping {
+    /// Whose Socket Set `handle` Is In
+    iface: usize,
+    handle: SocketHandle,
+    ident: u16,
+    target: Ipv4Address,
+}
+
+/// Uptime In Milliseconds, Finer Than A Tick Would Give From `uptime_millis`
+fn now() -> f64 {
+    timer::uptime_seconds() * 1000.0
+}
+
+impl Ping {
+    pub fn new(target: Ipv4Address) -> KResult<Self> {
+        let iface = route::lookup(target).ok_or("No Route To Host")?.iface;
+        let ident = NEXT_IDENT.fetch_add(1, Ordering::Relaxed);
+        let buffer = || IcmpSocketBuffer::new(vec![IcmpPacketMetadata::EMPTY; BUFFER_PACKETS], vec![0; BUFFER_LEN]);
+        let mut socket = IcmpSocket::new(buffer(), buffer());
+        socket.bind(IcmpEndpoint::Ident(ident)).map_err(|_| "Bind Failed")?;
+        let handle = IFACES.lock().get_mut(iface).ok_or("No Network Interface")?.sockets.add(socket);
+        Ok(Self { iface, handle, ident, target })
+    }
+
+    /// Queues Echo Request `seq`, It Goes Out At The Next Poll
+    pub fn send(&mut self, seq: u16) -> KResult<()> {
+        let mut data = [0; PAYLOAD_LEN];
+        data[..8].copy_from_slice(&now().to_bits().to_be_bytes());
+        let repr = Icmpv4Repr::EchoRequest { ident: self.ident, seq_no: seq, data: &data };
+
+        let mut ifaces = IFACES.lock();
+        let mut socket = ifaces.get_mut(self.iface).ok_or("No Network Interface")?.sockets.get::<IcmpSocket>(self.handle);
+        let buf = socket.send(repr.buffer_len(), IpAddress::Ipv4(self.target)).map_err(|_| "Send Buffer Full")?;
+        repr.emit(&mut Icmpv4Packet::new_unchecked(buf), &ChecksumCapabilities::default());
+        drop(ifaces);
+        service::wake();
+        Ok(())
+    }
+
+    /// Waits Up To `timeout_ms` For The Reply To `seq`, Returning `(bytes, round trip ms)`.
+    /// Replies To Earlier Requests That Turn Up Late Are Dropped.
+    pub fn reply(&mut self, seq: u16, timeout_ms: f64) -> Option<(usize, f64)> {
+        let start = now();
+        while now() - start < timeout_ms {
+            let mut ifaces = IFACES.lock();
+            let mut socket = ifaces.get_mut(self.iface)?.sockets.get::<IcmpSocket>(self.handle);
+            while socket.can_recv() {
+                let (payload, from) = match socket.recv() { Ok(packet) => packet, Err(_) => break };
+                if from != IpAddress::Ipv4(self.target) { continue; }
+                let packet = match Icmpv4Packet::new_checked(payload) { Ok(packet) => packet, Err(_) => continue };
+                if let Ok(Icmpv4Repr::EchoReply { ident, seq_no, data }) = Icmpv4Repr::parse(&packet, &ChecksumCapabilities::default()) {
+                    if ident != self.ident || seq_no != seq || data.len() < 8 { continue; }
+                    let mut sent = [0; 8];
+                    sent.copy_from_slice(&data[..8]);
+                    return Some((payload.len(), now() - f64::from_bits(u64::from_be_bytes(sent))));
+                }
+            }
+            drop(socket);
+            drop(ifaces);
+            timer::pause(0.001);
+        }
+        None
+    }
+}
+
+impl Drop for Ping {
+    fn drop(&mut self) {
+        if let Some(iface) = IFACES.lock().get_mut(self.iface) {
+            iface.sockets.remove(self.handle);
+        }
+    }
+}
@@ -0,0 +1,167 @@
+//! Kernel Routing Table
+//!
+//! Decides Which Interface Traffic Leaves Through. Every smoltcp Interface Is Polled With Its
+//! Own Socket Set, So Sockets Are Placed On Whichever Interface [lookup] Picks For Their
+//! Destination, & Each Interface's smoltcp Routes Are Rebuilt From Its Gateway Routes Here So
+//! It Knows Where To Forward. The Most Specific Route Wins, Then The Lowest Metric.
+
+use core::cmp::Reverse;
+
+use alloc::{format, string::String, vec::Vec};
+use lazy_static::lazy_static;
+use smoltcp::{iface, phy::Device, wire::{IpCidr, Ipv4Address, Ipv4Cidr}};
+use spin::Mutex;
+
+use crate::KResult;
+
+use super::EthernetInterface;
+
+/// Metric Given To Static Routes Unless Another Is Asked For
+pub const DEFAULT_METRIC: u32 = 100;
+/// Above [DEFAULT_METRIC], So A Static Gateway Wins Over The Lease's
+pub const DHCP_METRIC: u32 = 200;
+
+/// Where A Route Came From, DHCP & Link Routes Come & Go With The Address
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Origin {
+    /// The Network An Interface's Own Address Is On
+    Link,
+    Static,
+    Dhcp,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Route {
+    pub cidr: Ipv4Cidr,
+    /// `None` When The Destination Is Directly Reachable
+    pub gateway: Option<Ipv4Address>,
+    pub iface: usize,
+    pub metric: u32,
+    pub origin: Origin,
+}
+
+lazy_static! {
+    static ref ROUTES: Mutex<Vec<Route>> = Mutex::new(Vec::new());
+}
+
+fn on_link(routes: &[Route], iface: usize, cidr: Ipv4Cidr) -> bool {
+    routes.iter().any(|link| link.origin == Origin::Link && link.iface == iface
+        && link.cidr.prefix_len() <= cidr.prefix_len() && link.cidr.contains_addr(&cidr.address()))
+}
+
+/// smoltcp Only Reaches Hosts Without A Gateway When They're On The Interface's Own Network,
+/// So Routes Without One Elsewhere Are Skipped Until The Address Covers Them Again
+fn usable(routes: &[Route], route: &Route) -> bool {
+    route.gateway.is_some() || route.origin == Origin::Link || on_link(routes, route.iface, route.cidr)
+}
+
+fn best(routes: &[Route], addr: Ipv4Address) -> Option<Route> {
+    routes.iter().filter(|route| route.cidr.contains_addr(&addr) && usable(routes, route)).min_by_key(|route| (Reverse(route.cidr.prefix_len()), route.metric, route.iface)).copied()
+}
+
+/// The Route Traffic To `addr` Takes
+pub fn lookup(addr: Ipv4Address) -> Option<Route> {
+    best(&ROUTES.lock(), addr)
+}
+
+/// The Interface Holding The Best Default Route, Where Sockets Without A Destination Live
+pub fn default_iface() -> Option<usize> {
+    ROUTES.lock().iter().filter(|route| route.cidr.prefix_len() == 0).min_by_key(|route| (route.metric, route.iface)).map(|route| route.iface)
+}
+
+/// Whether `cidr` Is Within The Network `iface`'s Own Address Is On, Where A Route Doesn't
+/// Need A Gateway
+pub fn is_on_link(iface: usize, cidr: Ipv4Cidr) -> bool {
+    on_link(&ROUTES.lock(), iface, cidr)
+}
+
+/// Adds `route`, Replacing One From The Same Place For The Same Network On The Same Interface.
+/// Returns Whether The Table Changed.
+pub fn add(route: Route) -> bool {
+    let mut routes = ROUTES.lock();
+    if routes.contains(&route) { return false; }
+    routes.retain(|other| !(other.cidr == route.cidr && other.iface == route.iface && other.origin == route.origin));
+    routes.push(route);
+    true
+}
+
+/// Removes The Routes To `cidr`, Only Through `iface` If One's Given
+pub fn remove(cidr: Ipv4Cidr, iface: Option<usize>) -> KResult<()> {
+    let mut routes = ROUTES.lock();
+    let before = routes.len();
+    routes.retain(|route| !(route.cidr == cidr && iface.map_or(true, |iface| route.iface == iface)));
+    if routes.len() == before { Err("No Such Route") } else { Ok(()) }
+}
+
+/// Removes Every Route On `iface` That Came From `origin`
+pub fn flush(iface: usize, origin: Origin) {
+    ROUTES.lock().retain(|route| !(route.iface == iface && route.origin == origin));
+}
+
+/// Replaces `iface`'s Link Route With One For The Network `address` Is On
+pub fn set_link(iface: usize, address: Ipv4Cidr) {
+    flush(iface, Origin::Link);
+    if address.address().is_unspecified() || address.prefix_len() == 0 { return; }
+    add(Route { cidr: address.network(), gateway: None, iface, metric: 0, origin: Origin::Link });
+}
+
+/// Every Route, Most Specific First
+pub fn list() -> Vec<Route> {
+    let mut routes = ROUTES.lock().clone();
+    routes.sort_by_key(|route| (Reverse(route.cidr.prefix_len()), route.metric, route.iface));
+    routes
+}
+
+/// A Route As `route` & `/proc/net/route` Show It, Like `0.0.0.0/0 via 10.0.2.2 dev eth0 metric
+/// 200 (Dhcp)`. Looks Up The Interface's Name, So [super::IFACES] Mustn't Be Held.
+pub fn describe(route: &Route) -> String {
+    let dev = super::name(route.iface).unwrap_or_else(|| format!("#{}", route.iface));
+    match route.gateway {
+        Some(gateway) => format!("{} via {} dev {} metric {} ({:?})", route.cidr, gateway, dev, route.metric, route.origin),
+        None => format!("{} dev {} metric {} ({:?})", route.cidr, dev, route.metric, route.origin),
+    }
+}
+
+/// Rebuilds `iface`'s smoltcp Routes From Its Gateway Routes In The Table
+pub fn apply<D>(index: usize, iface: &mut EthernetInterface<D>) where D: for<'d> Device<'d> {
+    let routes = ROUTES.lock();
+    let mut gateways: Vec<&Route> = routes.iter().filter(|route| route.iface == index && route.gateway.is_some()).collect();
+    // smoltcp Keeps One Route Per Network, So The Best Goes In Last
+    gateways.sort_by_key(|route| Reverse(route.metric));
+    iface.routes_mut().update(|map| {
+        map.clear();
+        for route in gateways {
+            let gateway = route.gateway.expect("Filtered Above");
+            let _ = map.insert(IpCidr::Ipv4(route.cidr), iface::Route::new_ipv4_gateway(gateway));
+        }
+    });
+}
+
+#[test_case]
+fn routes_prefer_specific_then_metric() {
+    let net = |a, b, c, d, prefix| Ipv4Cidr::new(Ipv4Address::new(a, b, c, d), prefix);
+    let routes = [
+        Route { cidr: net(0, 0, 0, 0, 0), gateway: Some(Ipv4Address::new(10, 0, 2, 2)), iface: 0, metric: DHCP_METRIC, origin: Origin::Dhcp },
+        Route { cidr: net(0, 0, 0, 0, 0), gateway: Some(Ipv4Address::new(192, 168, 1, 1)), iface: 1, metric: DEFAULT_METRIC, origin: Origin::Static },
+        Route { cidr: net(10, 0, 2, 0, 24), gateway: None, iface: 0, metric: 0, origin: Origin::Link },
+    ];
+    assert_eq!(best(&routes, Ipv4Address::new(10, 0, 2, 15)).map(|route| route.iface), Some(0));
+    assert_eq!(best(&routes, Ipv4Address::new(8, 8, 8, 8)).map(|route| route.iface), Some(1));
+    assert_eq!(best(&routes[..1], Ipv4Address::new(8, 8, 8, 8)).and_then(|route| route.gateway), Some(Ipv4Address::new(10, 0, 2, 2)));
+}
+
+#[test_case]
+fn routes_without_gateway_need_link() {
+    let net = |a, b, c, d, prefix| Ipv4Cidr::new(Ipv4Address::new(a, b, c, d), prefix);
+    let routes = [
+        Route { cidr: net(0, 0, 0, 0, 0), gateway: Some(Ipv4Address::new(10, 0, 2, 2)), iface: 0, metric: DHCP_METRIC, origin: Origin::Dhcp },
+        Route { cidr: net(10, 0, 2, 0, 24), gateway: None, iface: 0, metric: 0, origin: Origin::Link },
+        Route { cidr: net(10, 0, 2, 128, 25), gateway: None, iface: 0, metric: DEFAULT_METRIC, origin: Origin::Static },
+        Route { cidr: net(172, 16, 0, 0, 16), gateway: None, iface: 0, metric: DEFAULT_METRIC, origin: Origin::Static },
+    ];
+    assert!(on_link(&routes, 0, net(10, 0, 2, 128, 25)));
+    assert!(!on_link(&routes, 0, net(10, 0, 0, 0, 16)));
+    assert!(!on_link(&routes, 1, net(10, 0, 2, 128, 25)));
+    assert_eq!(best(&routes, Ipv4Address::new(10, 0, 2, 200)).map(|route| route.origin), Some(Origin::Static));
+    assert_eq!(best(&routes, Ipv4Address::new(172, 16, 0, 1)).and_then(|route| route.gateway), Some(Ipv4Address::new(10, 0, 2, 2)));
+}
@@ -1,12 +1,14 @@
-use core::convert::TryInto;
+use core::{convert::TryInto, sync::atomic::{AtomicU16, Ordering}};
 
-use alloc::{collections::BTreeMap, vec::Vec};
+use alloc::vec::Vec;
 use array_macro::array;
 
-use smoltcp::{iface::{EthernetInterfaceBuilder, NeighborCache, Routes}, phy::{self, Device, DeviceCapabilities}, wire::{EthernetAddress, IpCidr, Ipv4Address, Ipv4Packet}};
+use smoltcp::{phy::{self, Device, DeviceCapabilities}, wire::{EthernetAddress, Ipv4Packet}};
 use x86_64::instructions::port::Port;
 
-use crate::{breakpoint, debug, log, sys::{self, device_manager, mem::allocator::PhysBuf}};
+use crate::{arch::i386::interrupts::idt, breakpoint, debug, log, sys::{self, mem::allocator::PhysBuf, pci}};
+
+use super::nic::{self, MAX_CARDS};
 
 
 //const CRS: u32 = 1 << 31; // Carrier Sense Lost
@@ -26,6 +28,12 @@ const RX_BUFFER_IDX: usize = 0b00;
 
 const MTU: usize = 1500;
 
+/// The Name In `network.toml`
+pub const DEVICE: &str = "PCI/REALTEK/RTL8139";
+
+const VENDOR_ID: u16 = 0x10EC;
+const DEVICE_ID: u16 = 0x8139;
+
 const RX_BUFFER_PAD: usize = 16;
 const RX_BUFFER_LEN: usize = (8129 << RX_BUFFER_IDX) + RX_BUFFER_PAD;
 
@@ -72,6 +80,10 @@ const TCR_MXDMA2: u32 = 1 << 10;
 const IMR_TOK: u16 = 1 << 2; // Transmit OK Interrupt
 const IMR_ROK: u16 = 1 << 0; // Receive OK Interrupt
 
+/// Each Card's I/O Base, So The Interrupt Handler Can Acknowledge Them Without Locking The
+/// Interfaces. Empty Slots Are 0.
+static IO_BASES: [AtomicU16; MAX_CARDS] = [const { AtomicU16::new(0) }; MAX_CARDS];
+
 #[derive(Debug, Clone)]
 pub struct Ports {
     pub mac: [Port<u8>; 6],                      // ID Registers (IDR0 ... IDR5)
@@ -139,6 +151,8 @@ impl Ports {
 
 #[derive(Debug, Clone)]
 pub struct RTL8139 {
+    /// Under `/dev`, See [nic::card_name]
+    name: &'static str,
     ports: Ports,
     rx_buffer: PhysBuf,
     rx_offset: usize,
@@ -149,8 +163,9 @@ pub struct RTL8139 {
 }
 
 impl RTL8139 {
-    pub fn new(io_base: u16) -> Self {
+    pub fn new(io_base: u16, name: &'static str) -> Self {
         Self {
+            name,
             ports: Ports::new(io_base),
             rx_buffer: PhysBuf::new(MTU + RX_BUFFER_LEN),
             tx_buffers: array![PhysBuf::new(TX_BUFFER_LEN); TX_BUFFERS_COUNT],
@@ -222,6 +237,14 @@ impl RTL8139 {
     pub fn recv(&mut self, _data: &mut [u8]) -> usize {
         todo!()
     }
+
+    pub fn ethernet_addr(&self) -> EthernetAddress {
+        self.eth_addr.unwrap_or(EthernetAddress([0; 6]))
+    }
+
+    pub fn name(&self) -> &'static str {
+        self.name
+    }
 }
 
 
@@ -236,11 +259,9 @@ pub struct TxToken {
 }
 
 
-impl<'a> Device<'a> for RTL8139 {
-    type RxToken = RxToken;
-    type TxToken = TxToken;
-
-    fn receive(&'a mut self) -> Option<(Self::RxToken, Self::TxToken)> {
+impl RTL8139 {
+    /// Takes The Next Frame Out Of The Receive Ring
+    fn next_frame(&mut self) -> Option<Vec<u8>> {
         let cmd = unsafe {self.ports.cmd.read()};
         if cmd & CR_BUFE == CR_BUFE {return None};
         let capr = unsafe { self.ports.capr.read() };
@@ -275,8 +296,21 @@ impl<'a> Device<'a> for RTL8139 {
             self.ports.capr.write((self.rx_offset - RX_BUFFER_PAD) as u16);
         }
 
+        Some(self.rx_buffer[(offset + 4)..(offset + n)].to_vec())
+    }
+}
+
+impl<'a> Device<'a> for RTL8139 {
+    type RxToken = RxToken;
+    type TxToken = TxToken;
+
+    fn receive(&'a mut self) -> Option<(Self::RxToken, Self::TxToken)> {
+        let buffer = loop {
+            let frame = self.next_frame()?;
+            if sys::net::accept(self.name, &frame) { break frame; }
+        };
         let rx = RxToken {
-            buffer: self.rx_buffer[(offset + 4)..(offset + n)].to_vec()
+            buffer
         };
         let tx = TxToken {
             device: self.clone()
@@ -361,47 +395,48 @@ impl phy::RxToken for RxToken {
     }
 }
 
-pub fn init() {
-    if let Some(device_manager::Device::PCIDev(mut pci_device)) = device_manager::get_device("PCI:REALTEK:RTL8139") {
+/// Brings Up Every Card Found
+pub fn init() -> Vec<RTL8139> {
+    let mut devices = Vec::new();
+    for mut pci_device in pci::find_devices(VENDOR_ID, DEVICE_ID) {
+        if devices.len() == MAX_CARDS {
+            log!("NET RTL8139 {:02}:{:02} Ignored, Only {} Cards Are Supported\n", pci_device.bus, pci_device.device, MAX_CARDS);
+            continue;
+        }
         pci_device.enable_bus_mastering();
 
         let io_base = (pci_device.base_addresses[0] as u16) & 0xFFF0;
-        let mut net_device = RTL8139::new(io_base);
+        let mut net_device = RTL8139::new(io_base, nic::card_name("rtl8139", devices.len()));
 
         unsafe {
             net_device.init();
         }
 
         if let Some(eth_addr) = net_device.eth_addr {
-            log!("NET RTL8139 MAC {}\n", eth_addr);
-
-            let neighbor_cache = NeighborCache::new(BTreeMap::new());
-            let routes = Routes::new(BTreeMap::new());
-            let ip_addrs = [
-                IpCidr::new(Ipv4Address::UNSPECIFIED.into(), 0),
-            ];
-            let iface = EthernetInterfaceBuilder::new(net_device).
-                ethernet_addr(eth_addr).
-                neighbor_cache(neighbor_cache).
-                ip_addrs(ip_addrs).
-                routes(routes).
-                finalize();
+            log!("NET {} MAC {}\n", net_device.name, eth_addr);
 
-
-            //log!("Found {} IPs [{:?}]", iface.ip_addrs().len(), iface.ip_addrs());
-
-            *sys::net::IFACE.lock() = Some(iface);
+            IO_BASES[devices.len()].store(io_base, Ordering::Release);
+            log!("NET {} IRQ {}\n", net_device.name, pci_device.interrupt_line);
+            idt::set_irq_handler(pci_device.interrupt_line, interrupt_handler);
+            devices.push(net_device);
         }
     }
+    devices
 }
 
 
 
+/// Acknowledges Every Card, As They May Share The Line, & Leaves The Actual Work To The
+/// Network Service. The Interfaces Can't Be Polled Here, They May Already Be Locked By Whatever
+/// Was Interrupted.
 pub fn interrupt_handler() {
-    debug!("RTL8139 interrupt!\n");
-    if let Some(mut guard) = sys::net::IFACE.try_lock() {
-        if let Some(ref mut iface) = *guard {
-            unsafe { iface.device_mut().ports.isr.write(0xffff) } // Clear the interrupt
+    for io_base in IO_BASES.iter().map(|io_base| io_base.load(Ordering::Acquire)).filter(|io_base| *io_base != 0) {
+        let mut isr = Ports::new(io_base).isr;
+        let status = unsafe { isr.read() };
+        unsafe { isr.write(status) } // Writing The Bits Back Clears Them
+        debug!("RTL8139 Interrupt, ISR 0x{:04x}", status);
+        if status & (IMR_ROK | IMR_TOK) != 0 {
+            sys::net::service::wake();
         }
     }
-}
\ No newline at end of file
+}
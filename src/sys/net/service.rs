@@ -0,0 +1,89 @@
+//! Network Stack Service
+//!
+//! Keeps The Interfaces Moving After Boot. There's No Scheduler To Run A Real Task, So The
+//! Service Runs Wherever The Kernel Idles: [crate::sys::timer::pause] Calls [poll] After Every
+//! Tick. It Only Does Work When A NIC Has Raised ROK/TOK Since The Last Poll, Or When The
+//! Earliest Deadline smoltcp Asked For Through `poll_delay` Has Passed, & Then Polls Every
+//! Interface With Its Own Sockets. Interrupt Handlers Only Ever
+//! Call [wake], Polling Allocates & Mustn't Happen In Interrupt Context. The [super::dhcp]
+//! Clients Ride Along With Every Poll, Their Timers Counting Towards The Deadline.
+
+use core::sync::atomic::{AtomicBool, AtomicU64, AtomicUsize, Ordering};
+
+use smoltcp::time::{Duration, Instant};
+
+use crate::{warn, sys::timer};
+
+use super::{IFACES, Interface, dhcp};
+
+/// Set By The NICs' Interrupt Handlers, Cleared By The Next Poll
+static PENDING: AtomicBool = AtomicBool::new(false);
+/// Uptime In Milliseconds Of The Next Poll smoltcp Asked For
+static DEADLINE: AtomicU64 = AtomicU64::new(u64::MAX);
+/// Set While A Poll Is Running, So A Pause Inside It Doesn't Poll Again
+static POLLING: AtomicBool = AtomicBool::new(false);
+
+static POLLS: AtomicUsize = AtomicUsize::new(0);
+static WAKEUPS: AtomicUsize = AtomicUsize::new(0);
+
+/// The Clock The Interface Is Polled With
+pub fn now() -> Instant {
+    Instant::from_millis((timer::uptime_seconds() * 1000.0) as i64)
+}
+
+/// Asks For A Poll As Soon As The Kernel Next Idles, Safe To Call From Interrupt Handlers
+pub fn wake() {
+    WAKEUPS.fetch_add(1, Ordering::Relaxed);
+    PENDING.store(true, Ordering::Release);
+}
+
+fn due() -> bool {
+    PENDING.load(Ordering::Acquire) || now().total_millis() as u64 >= DEADLINE.load(Ordering::Relaxed)
+}
+
+/// Polls The Interfaces If They've Been Woken Or The Deadline Has Passed, Returning Whether It
+/// Did. Skipped If The Interfaces Are Busy, Whoever Holds Them Is Polling Already.
+pub fn poll() -> bool {
+    if !due() || POLLING.swap(true, Ordering::Acquire) { return false; }
+    let polled = poll_now();
+    POLLING.store(false, Ordering::Release);
+    polled
+}
+
+fn poll_now() -> bool {
+    let mut ifaces = match IFACES.try_lock() { Some(ifaces) => ifaces, None => return false };
+    if ifaces.is_empty() { return false; }
+
+    PENDING.store(false, Ordering::Release);
+    let timestamp = now();
+    // Nothing Waiting On A Timer Still Gets Checked Now & Then In Case An Interrupt Was Missed
+    let mut deadline = timestamp + Duration::from_secs(1);
+    for (index, Interface { name, iface, sockets }) in ifaces.iter_mut().enumerate() {
+        // smoltcp Keeps Going Until The Ring Is Drained, A Burst Only Raises One Interrupt
+        match iface.poll(sockets, timestamp) {
+            Ok(_) | Err(smoltcp::Error::Unrecognized) => {},
+            Err(e) => { warn!("Network Error On {}: {}\n", name, e); },
+        }
+        dhcp::poll(index, iface, sockets, timestamp);
+        if let Some(delay) = iface.poll_delay(sockets, timestamp) {
+            deadline = deadline.min(timestamp + delay);
+        }
+    }
+    POLLS.fetch_add(1, Ordering::Relaxed);
+
+    if let Some(next) = dhcp::next_poll() {
+        deadline = deadline.min(next.max(timestamp));
+    }
+    DEADLINE.store(deadline.total_millis() as u64, Ordering::Relaxed);
+    true
+}
+
+/// Starts Polling, Normally Once The Interfaces Have Been Brought Up
+pub fn start() {
+    DEADLINE.store(0, Ordering::Relaxed);
+}
+
+/// `(polls, wakeups)` Since Boot
+pub fn stats() -> (usize, usize) {
+    (POLLS.load(Ordering::Relaxed), WAKEUPS.load(Ordering::Relaxed))
+}
@@ -0,0 +1,403 @@
+//! Kernel Sockets
+//!
+//! TCP & UDP Sockets Over smoltcp, Living In An Interface's Socket Set So The Network Service
+//! Moves Them Along While Nothing Else Is Running. A New Socket Starts On The Interface With The
+//! Default Route & Moves To Whichever One [route::lookup] Picks When It's Given A Destination.
+//! Listening & Bound UDP Sockets Keep A Copy On Every Other Interface Too, So They Hear Traffic
+//! Whichever Interface It Arrives On, & Sending Switches To The Copy The Route Leaves Through.
+//! Sockets Are Named By A Small Id Instead Of smoltcp's Handle So Ids Can Be Passed Through
+//! System Calls, A Listening Socket Keeps Its Id Across `accept` & A Moved One Keeps Its Id Too.
+//! Calls That Wait (`accept`, `connect`, `recv`) Pause Between Checks, Which Is When The
+//! Service Polls, & Give Up After [TIMEOUT_MS].
+
+use core::sync::atomic::{AtomicU16, Ordering};
+
+use alloc::{format, string::String, vec, vec::Vec};
+use lazy_static::lazy_static;
+use smoltcp::{socket::{Socket, SocketHandle, SocketSet, TcpSocket, TcpSocketBuffer, TcpState, UdpPacketMetadata, UdpSocket, UdpSocketBuffer}, wire::{IpAddress, IpEndpoint}};
+use spin::Mutex;
+
+use crate::{KResult, sys::timer};
+
+use super::{IFACES, Interface, route, service};
+
+pub type SocketId = usize;
+
+/// How Long A Blocking Call Waits Before Failing
+pub const TIMEOUT_MS: u128 = 10_000;
+
+const TCP_BUFFER_LEN: usize = 4096;
+const UDP_BUFFER_LEN: usize = 4096;
+const UDP_PACKETS: usize = 8;
+
+/// Where Ports Handed Out To Unbound Sockets Start
+const EPHEMERAL_START: u16 = 49152;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Kind {
+    Tcp,
+    Udp,
+}
+
+#[derive(Debug, Clone)]
+struct Entry {
+    kind: Kind,
+    /// Whose Socket Set `handle` Is In
+    iface: usize,
+    handle: SocketHandle,
+    /// A Listening Or Bound UDP Socket's Copies On The Other Interfaces, As `(iface, handle)`
+    copies: Vec<(usize, SocketHandle)>,
+    /// Port Given To `bind`, 0 Until Bound
+    port: u16,
+    /// Where `send` Goes For A Connected UDP Socket
+    remote: Option<IpEndpoint>,
+}
+
+struct Table {
+    entries: Vec<Option<Entry>>,
+    /// Closed TCP Sockets Still Finishing Their Shutdown With The Peer, With Their Interface
+    closing: Vec<(usize, SocketHandle)>,
+}
+
+lazy_static! {
+    static ref TABLE: Mutex<Table> = Mutex::new(Table { entries: Vec::new(), closing: Vec::new() });
+}
+
+static NEXT_PORT: AtomicU16 = AtomicU16::new(EPHEMERAL_START);
+
+fn ephemeral_port() -> u16 {
+    let port = NEXT_PORT.fetch_add(1, Ordering::Relaxed);
+    if port == u16::MAX { NEXT_PORT.store(EPHEMERAL_START, Ordering::Relaxed); }
+    port
+}
+
+fn tcp_socket() -> TcpSocket<'static> {
+    TcpSocket::new(TcpSocketBuffer::new(vec![0; TCP_BUFFER_LEN]), TcpSocketBuffer::new(vec![0; TCP_BUFFER_LEN]))
+}
+
+fn udp_socket() -> UdpSocket<'static> {
+    let buffer = || UdpSocketBuffer::new(vec![UdpPacketMetadata::EMPTY; UDP_PACKETS], vec![0; UDP_BUFFER_LEN]);
+    UdpSocket::new(buffer(), buffer())
+}
+
+/// Drops Closed TCP Sockets Once The Peer Has Seen The Close
+fn reap(table: &mut Table, ifaces: &mut [Interface]) {
+    table.closing.retain(|(iface, handle)| {
+        let sockets = &mut ifaces[*iface].sockets;
+        let done = matches!(sockets.get::<TcpSocket>(*handle).state(), TcpState::Closed | TcpState::TimeWait);
+        if done { sockets.remove(*handle); }
+        !done
+    });
+}
+
+/// Like [with_socket], Handing `f` Every Interface For Sockets That Have Copies
+fn with_ifaces<T>(id: SocketId, f: impl FnOnce(&mut Entry, &mut [Interface]) -> KResult<T>) -> KResult<T> {
+    let mut table = TABLE.lock();
+    let entry = table.entries.get_mut(id).and_then(|slot| slot.as_mut()).ok_or("No Such Socket")?;
+    let mut ifaces = IFACES.lock();
+    let res = f(entry, &mut ifaces);
+    // Anything Queued Should Go Out At The Next Idle, Not When The Deadline Comes Round
+    service::wake();
+    res
+}
+
+fn with_socket<T>(id: SocketId, f: impl FnOnce(&mut Entry, &mut SocketSet<'static>) -> KResult<T>) -> KResult<T> {
+    with_ifaces(id, |entry, ifaces| {
+        let iface = entry.iface;
+        f(entry, &mut ifaces[iface].sockets)
+    })
+}
+
+/// Calls `f` Until It Returns Something, Pausing In Between So The Service Can Poll
+fn wait_ifaces<T>(id: SocketId, timeout_ms: u128, mut f: impl FnMut(&mut Entry, &mut [Interface]) -> Option<KResult<T>>) -> KResult<T> {
+    let start = timer::uptime_millis();
+    loop {
+        if let Some(res) = with_ifaces(id, |entry, ifaces| Ok(f(entry, ifaces)))? { return res; }
+        if timer::uptime_millis() - start > timeout_ms { return Err("Timed Out"); }
+        timer::pause(0.001);
+    }
+}
+
+fn wait<T>(id: SocketId, timeout_ms: u128, mut f: impl FnMut(&mut Entry, &mut SocketSet<'static>) -> Option<KResult<T>>) -> KResult<T> {
+    wait_ifaces(id, timeout_ms, |entry, ifaces| {
+        let iface = entry.iface;
+        f(entry, &mut ifaces[iface].sockets)
+    })
+}
+
+/// The Socket & Its Copies As `(iface, handle)`, The Socket Itself First
+fn handles(entry: &Entry) -> impl Iterator<Item = (usize, SocketHandle)> + '_ {
+    core::iter::once((entry.iface, entry.handle)).chain(entry.copies.iter().copied())
+}
+
+/// Adds A Copy Made By `open` On Every Interface The Socket Isn't On Yet
+fn spread<S: Into<Socket<'static>>>(entry: &mut Entry, ifaces: &mut [Interface], open: impl Fn() -> KResult<S>) -> KResult<()> {
+    for (index, iface) in ifaces.iter_mut().enumerate() {
+        if handles(entry).any(|(on, _)| on == index) { continue; }
+        let handle = iface.sockets.add(open()?);
+        entry.copies.push((index, handle));
+    }
+    Ok(())
+}
+
+/// Binds A UDP Socket & Its Copies To `port`
+fn bind_udp(entry: &mut Entry, ifaces: &mut [Interface], port: u16) -> KResult<()> {
+    ifaces[entry.iface].sockets.get::<UdpSocket>(entry.handle).bind(port).map_err(|_| "Bind Failed")?;
+    entry.port = port;
+    spread(entry, ifaces, || {
+        let mut socket = udp_socket();
+        socket.bind(port).map_err(|_| "Bind Failed")?;
+        Ok(socket)
+    })
+}
+
+/// Moves A Socket To The Interface Traffic To `addr` Leaves Through, If It Isn't There Already
+fn route_to(id: SocketId, addr: IpAddress) -> KResult<()> {
+    let to = match addr {
+        IpAddress::Ipv4(addr) => route::lookup(addr).ok_or("No Route To Host")?.iface,
+        _ => return Err("Only IPv4 Is Supported"),
+    };
+    let mut table = TABLE.lock();
+    let entry = table.entries.get_mut(id).and_then(|slot| slot.as_mut()).ok_or("No Such Socket")?;
+    if entry.iface == to { return Ok(()); }
+    if let Some(copy) = entry.copies.iter_mut().find(|(on, _)| *on == to) {
+        // Already There, The Copy Takes Over & The Socket Becomes One
+        let handle = copy.1;
+        *copy = (entry.iface, entry.handle);
+        entry.iface = to;
+        entry.handle = handle;
+        return Ok(());
+    }
+    let mut ifaces = IFACES.lock();
+    if to >= ifaces.len() { return Err("No Such Interface"); }
+    let socket = ifaces[entry.iface].sockets.remove(entry.handle);
+    entry.handle = ifaces[to].sockets.add(socket);
+    entry.iface = to;
+    Ok(())
+}
+
+pub fn create(kind: Kind) -> KResult<SocketId> {
+    let mut table = TABLE.lock();
+    let mut ifaces = IFACES.lock();
+    reap(&mut table, &mut ifaces);
+    let iface = route::default_iface().unwrap_or(0);
+    let sockets = &mut ifaces.get_mut(iface).ok_or("No Network Interface")?.sockets;
+    let handle = match kind {
+        Kind::Tcp => sockets.add(tcp_socket()),
+        Kind::Udp => sockets.add(udp_socket()),
+    };
+    let entry = Some(Entry { kind, iface, handle, copies: Vec::new(), port: 0, remote: None });
+    match table.entries.iter().position(|slot| slot.is_none()) {
+        Some(id) => { table.entries[id] = entry; Ok(id) },
+        None => { table.entries.push(entry); Ok(table.entries.len() - 1) },
+    }
+}
+
+/// Picks The Local Port. UDP Sockets Start Receiving Straight Away, TCP Ones On `listen`.
+pub fn bind(id: SocketId, port: u16) -> KResult<()> {
+    if port == 0 { return Err("Can't Bind Port 0"); }
+    let in_use = TABLE.lock().entries.iter().flatten().any(|entry| entry.port == port);
+    if in_use { return Err("Port Is In Use"); }
+    with_ifaces(id, |entry, ifaces| {
+        if entry.port != 0 { return Err("Socket Is Already Bound"); }
+        match entry.kind {
+            Kind::Udp => bind_udp(entry, ifaces, port),
+            Kind::Tcp => { entry.port = port; Ok(()) },
+        }
+    })
+}
+
+/// Listens On Every Interface, Whichever One The Connection Comes In On
+pub fn listen(id: SocketId) -> KResult<()> {
+    with_ifaces(id, |entry, ifaces| {
+        if entry.kind != Kind::Tcp { return Err("Only TCP Sockets Listen"); }
+        if entry.port == 0 { return Err("Socket Isn't Bound"); }
+        let port = entry.port;
+        ifaces[entry.iface].sockets.get::<TcpSocket>(entry.handle).listen(port).map_err(|_| "Listen Failed")?;
+        spread(entry, ifaces, || {
+            let mut socket = tcp_socket();
+            socket.listen(port).map_err(|_| "Listen Failed")?;
+            Ok(socket)
+        })
+    })
+}
+
+/// Waits For A Connection On A Listening Socket. The Connection Gets A New Id & The Listening
+/// Id Carries On Listening On A Fresh smoltcp Socket On The Interface It Came In On.
+pub fn accept(id: SocketId) -> KResult<SocketId> {
+    let (iface, connected) = wait_ifaces(id, TIMEOUT_MS, |entry, ifaces| {
+        if entry.kind != Kind::Tcp { return Some(Err("Only TCP Sockets Listen")); }
+        let mut found = None;
+        for (slot, (index, handle)) in handles(entry).enumerate() {
+            let socket = ifaces[index].sockets.get::<TcpSocket>(handle);
+            if !(socket.is_listening() || socket.is_active()) { return Some(Err("Socket Isn't Listening")); }
+            if socket.is_listening() || socket.state() == TcpState::SynReceived { continue; }
+            found = Some((slot, index, handle));
+            break;
+        }
+        let (slot, index, connected) = found?;
+
+        let mut listener = tcp_socket();
+        if listener.listen(entry.port).is_err() { return Some(Err("Listen Failed")); }
+        let handle = ifaces[index].sockets.add(listener);
+        match slot {
+            0 => entry.handle = handle,
+            _ => entry.copies[slot - 1].1 = handle,
+        }
+        Some(Ok((index, connected)))
+    })?;
+
+    let mut table = TABLE.lock();
+    let entry = Some(Entry { kind: Kind::Tcp, iface, handle: connected, copies: Vec::new(), port: 0, remote: None });
+    match table.entries.iter().position(|slot| slot.is_none()) {
+        Some(id) => { table.entries[id] = entry; Ok(id) },
+        None => { table.entries.push(entry); Ok(table.entries.len() - 1) },
+    }
+}
+
+/// TCP Sockets Wait For The Handshake, UDP Ones Just Remember Where `send` Goes
+pub fn connect(id: SocketId, addr: IpAddress, port: u16) -> KResult<()> {
+    let remote = IpEndpoint::new(addr, port);
+    route_to(id, addr)?;
+    let kind = with_ifaces(id, |entry, ifaces| {
+        match entry.kind {
+            Kind::Tcp => {
+                if entry.port == 0 { entry.port = ephemeral_port(); }
+                ifaces[entry.iface].sockets.get::<TcpSocket>(entry.handle).connect(remote, entry.port).map_err(|_| "Connect Failed")?
+            },
+            Kind::Udp => {
+                if entry.port == 0 { bind_udp(entry, ifaces, ephemeral_port())?; }
+                entry.remote = Some(remote);
+            },
+        }
+        Ok(entry.kind)
+    })?;
+    if kind == Kind::Udp { return Ok(()); }
+
+    wait(id, TIMEOUT_MS, |entry, sockets| {
+        let socket = sockets.get::<TcpSocket>(entry.handle);
+        match socket.state() {
+            TcpState::Established => Some(Ok(())),
+            TcpState::SynSent | TcpState::SynReceived => None,
+            _ => Some(Err("Connection Refused")),
+        }
+    })
+}
+
+/// Queues `data`, Returning How Much Fit In The Send Buffer
+pub fn send(id: SocketId, data: &[u8]) -> KResult<usize> {
+    with_socket(id, |entry, sockets| match entry.kind {
+        Kind::Tcp => {
+            let mut socket = sockets.get::<TcpSocket>(entry.handle);
+            if !socket.may_send() { return Err("Not Connected"); }
+            socket.send_slice(data).map_err(|_| "Send Failed")
+        },
+        Kind::Udp => {
+            let remote = entry.remote.ok_or("Not Connected")?;
+            sockets.get::<UdpSocket>(entry.handle).send_slice(data, remote).map_err(|_| "Send Failed")?;
+            Ok(data.len())
+        },
+    })
+}
+
+/// Sends A Datagram To `addr:port` From An Unconnected UDP Socket
+pub fn send_to(id: SocketId, data: &[u8], addr: IpAddress, port: u16) -> KResult<()> {
+    route_to(id, addr)?;
+    with_ifaces(id, |entry, ifaces| {
+        if entry.kind != Kind::Udp { return Err("Only UDP Sockets Send To An Address"); }
+        // Bound Everywhere, So Replies Are Heard Even Once Later Sends Have Moved It
+        if entry.port == 0 { bind_udp(entry, ifaces, ephemeral_port())?; }
+        ifaces[entry.iface].sockets.get::<UdpSocket>(entry.handle).send_slice(data, IpEndpoint::new(addr, port)).map_err(|_| "Send Failed")
+    })
+}
+
+/// Waits For Data, Returning 0 Once A TCP Peer Has Closed Its Side
+pub fn recv(id: SocketId, buf: &mut [u8]) -> KResult<usize> {
+    recv_from(id, buf).map(|(len, _)| len)
+}
+
+/// Like [recv], Also Returning Who Sent The Data
+pub fn recv_from(id: SocketId, buf: &mut [u8]) -> KResult<(usize, IpEndpoint)> {
+    recv_from_timeout(id, buf, TIMEOUT_MS)
+}
+
+/// Like [recv_from], Giving Up After `timeout_ms` Instead Of [TIMEOUT_MS]. UDP Sockets Take
+/// Datagrams From Whichever Interface Has One.
+pub fn recv_from_timeout(id: SocketId, buf: &mut [u8], timeout_ms: u128) -> KResult<(usize, IpEndpoint)> {
+    wait_ifaces(id, timeout_ms, |entry, ifaces| match entry.kind {
+        Kind::Tcp => {
+            let mut socket = ifaces[entry.iface].sockets.get::<TcpSocket>(entry.handle);
+            let remote = socket.remote_endpoint();
+            if socket.can_recv() { return Some(socket.recv_slice(buf).map(|len| (len, remote)).map_err(|_| "Receive Failed")); }
+            if !socket.may_recv() { return Some(Ok((0, remote))); }
+            None
+        },
+        Kind::Udp => {
+            if entry.port == 0 { return Some(Err("Socket Isn't Bound")); }
+            for (index, handle) in handles(entry) {
+                let mut socket = ifaces[index].sockets.get::<UdpSocket>(handle);
+                if socket.can_recv() { return Some(socket.recv_slice(buf).map_err(|_| "Receive Failed")); }
+            }
+            None
+        },
+    })
+}
+
+/// TCP Sockets Are Shut Down Gracefully & Removed Once The Peer Has Seen It
+pub fn close(id: SocketId) -> KResult<()> {
+    let mut table = TABLE.lock();
+    let entry = table.entries.get_mut(id).and_then(|slot| slot.take()).ok_or("No Such Socket")?;
+    let mut ifaces = IFACES.lock();
+    // Copies Are Only Ever Listening Or Unconnected, There's No Peer To Say Goodbye To
+    for (index, handle) in &entry.copies {
+        ifaces[*index].sockets.remove(*handle);
+    }
+    let sockets = &mut ifaces[entry.iface].sockets;
+    match entry.kind {
+        Kind::Tcp => {
+            sockets.get::<TcpSocket>(entry.handle).close();
+            table.closing.push((entry.iface, entry.handle));
+        },
+        Kind::Udp => { sockets.remove(entry.handle); },
+    }
+    reap(&mut table, &mut ifaces);
+    service::wake();
+    Ok(())
+}
+
+/// Every Open Socket As `(id, kind, local port, state)`
+pub fn list() -> Vec<(SocketId, Kind, u16, String)> {
+    let table = TABLE.lock();
+    let mut ifaces = IFACES.lock();
+    table.entries.iter().enumerate().filter_map(|(id, slot)| slot.as_ref().map(|entry| {
+        let state = match (entry.kind, entry.remote) {
+            (Kind::Tcp, _) => format!("{}", ifaces[entry.iface].sockets.get::<TcpSocket>(entry.handle).state()),
+            (Kind::Udp, Some(remote)) => format!("CONNECTED {}", remote),
+            (Kind::Udp, None) => String::from(if entry.port == 0 {"UNBOUND"} else {"BOUND"}),
+        };
+        (id, entry.kind, entry.port, state)
+    })).collect()
+}
+
+#[test_case]
+fn udp_sockets_bind_and_close() {
+    let id = create(Kind::Udp).expect("");
+    bind(id, 4242).expect("");
+    assert!(bind(id, 4243).is_err(), "A Socket Binds Once");
+    let other = create(Kind::Udp).expect("");
+    assert!(bind(other, 4242).is_err(), "Ports Can't Be Shared");
+    close(id).expect("");
+    bind(other, 4242).expect("");
+    close(other).expect("");
+    assert!(send(other, b"gone").is_err());
+}
+
+#[test_case]
+fn bound_udp_sockets_hear_every_interface() {
+    let id = create(Kind::Udp).expect("");
+    bind(id, 4244).expect("");
+    let on = TABLE.lock().entries[id].as_ref().map(|entry| handles(entry).map(|(index, _)| index).collect::<Vec<usize>>()).expect("");
+    assert_eq!(on.len(), super::count());
+    assert!((0..super::count()).all(|index| on.contains(&index)));
+    close(id).expect("");
+}
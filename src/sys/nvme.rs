@@ -0,0 +1,429 @@
+//! NVMe Storage Driver
+//!
+//! Each Controller (PCI Class 01:08) Gets An Admin Queue And A Single I/O Submission &
+//! Completion Queue Pair. Commands Are Polled For Completion, Interrupts Stay Disabled.
+//! Every Active Namespace With 512 Byte Sectors Is Exposed As A Block Device.
+
+use alloc::string::String;
+use alloc::vec::Vec;
+use bit_field::BitField;
+use core::hint::spin_loop;
+use core::ptr::{read_volatile, write_volatile};
+use lazy_static::lazy_static;
+use spin::Mutex;
+use x86_64::PhysAddr;
+use x86_64::instructions::interrupts::without_interrupts;
+
+use crate::{KResult, print, sys::{self, mem::{self, allocator::PhysBuf}, pci}};
+
+const CLASS_MASS_STORAGE: u8 = 0x01;
+const SUBCLASS_NVM: u8 = 0x08;
+
+// Controller Registers
+const REG_CAP: u64 = 0x00;
+const REG_INTMS: u64 = 0x0C;
+const REG_CC: u64 = 0x14;
+const REG_CSTS: u64 = 0x1C;
+const REG_AQA: u64 = 0x24;
+const REG_ASQ: u64 = 0x28;
+const REG_ACQ: u64 = 0x30;
+const REG_DOORBELL: u64 = 0x1000;
+
+const CC_EN: u32 = 1 << 0;
+const CC_IOSQES: u32 = 6 << 16; // 64 Byte Submission Entries
+const CC_IOCQES: u32 = 4 << 20; // 16 Byte Completion Entries
+const CSTS_RDY: u32 = 1 << 0;
+const CSTS_CFS: u32 = 1 << 1;
+
+// Admin Commands
+const ADMIN_CREATE_IO_SQ: u8 = 0x01;
+const ADMIN_CREATE_IO_CQ: u8 = 0x05;
+const ADMIN_IDENTIFY: u8 = 0x06;
+
+// I/O Commands
+const IO_FLUSH: u8 = 0x00;
+const IO_WRITE: u8 = 0x01;
+const IO_READ: u8 = 0x02;
+
+const CNS_NAMESPACE: u32 = 0x00;
+const CNS_CONTROLLER: u32 = 0x01;
+const CNS_ACTIVE_NAMESPACES: u32 = 0x02;
+
+const PAGE_SIZE: usize = 4096;
+const QUEUE_DEPTH: u16 = 64;
+const SQ_ENTRY_SIZE: usize = 64;
+const CQ_ENTRY_SIZE: usize = 16;
+const SECTOR_SIZE: usize = 512;
+
+/// Completion Queue Polls Per Second Of The Controller's Timeout. Reads & Writes Run With
+/// Interrupts Off, Where The Timer Doesn't Tick, So Command Waits Are Bounded By Count.
+const POLLS_PER_SECOND: f64 = 10_000_000.0;
+
+#[derive(Debug, Clone)]
+struct QueuePair {
+    id: u16,
+    sq: PhysBuf,
+    cq: PhysBuf,
+    sq_tail: u16,
+    cq_head: u16,
+    phase: bool,
+}
+
+impl QueuePair {
+    fn new(id: u16) -> Self {
+        Self {
+            id,
+            sq: PhysBuf::new_aligned(QUEUE_DEPTH as usize * SQ_ENTRY_SIZE, PAGE_SIZE),
+            cq: PhysBuf::new_aligned(QUEUE_DEPTH as usize * CQ_ENTRY_SIZE, PAGE_SIZE),
+            sq_tail: 0,
+            cq_head: 0,
+            phase: true,
+        }
+    }
+}
+
+/// A 64 Byte Submission Queue Entry, Built Up Before Being Copied Into The Queue
+struct Command {
+    data: [u8; SQ_ENTRY_SIZE],
+}
+
+impl Command {
+    fn new(opcode: u8, nsid: u32) -> Self {
+        let mut data = [0; SQ_ENTRY_SIZE];
+        data[0] = opcode;
+        data[4..8].copy_from_slice(&nsid.to_le_bytes());
+        Self { data }
+    }
+
+    fn set_cid(&mut self, cid: u16) {
+        self.data[2..4].copy_from_slice(&cid.to_le_bytes());
+    }
+
+    fn prp1(mut self, addr: u64) -> Self {
+        self.data[24..32].copy_from_slice(&addr.to_le_bytes());
+        self
+    }
+
+    /// Sets Command DWORD `n` (10..=15)
+    fn dword(mut self, n: usize, value: u32) -> Self {
+        let offset = n * 4;
+        self.data[offset..offset + 4].copy_from_slice(&value.to_le_bytes());
+        self
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Controller {
+    base: u64,
+    doorbell_stride: u64,
+    timeout: f64,
+    admin: QueuePair,
+    io: QueuePair,
+    data: PhysBuf,
+    next_cid: u16,
+
+    model: String,
+    serial: String,
+}
+
+impl Controller {
+    pub fn new(base: u64) -> Self {
+        let mut controller = Self {
+            base,
+            doorbell_stride: 4,
+            timeout: 1.0,
+            admin: QueuePair::new(0),
+            io: QueuePair::new(1),
+            data: PhysBuf::new_aligned(PAGE_SIZE, PAGE_SIZE),
+            next_cid: 0,
+
+            model: String::new(),
+            serial: String::new(),
+        };
+        let cap = controller.read_reg64(REG_CAP);
+        controller.doorbell_stride = 4 << cap.get_bits(32..36);
+        controller.timeout = cap.get_bits(24..32) as f64 * 0.5;
+        controller
+    }
+
+    fn read_reg(&self, reg: u64) -> u32 {
+        unsafe { read_volatile((self.base + reg) as *const u32) }
+    }
+
+    fn read_reg64(&self, reg: u64) -> u64 {
+        unsafe { read_volatile((self.base + reg) as *const u64) }
+    }
+
+    fn write_reg(&mut self, reg: u64, value: u32) {
+        unsafe { write_volatile((self.base + reg) as *mut u32, value) }
+    }
+
+    fn write_reg64(&mut self, reg: u64, value: u64) {
+        unsafe { write_volatile((self.base + reg) as *mut u64, value) }
+    }
+
+    fn wait_ready(&self, ready: bool) -> KResult<()> {
+        let start = sys::timer::uptime_seconds();
+        while self.read_reg(REG_CSTS).get_bit(0) != ready {
+            if self.read_reg(REG_CSTS) & CSTS_CFS != 0 {
+                return Err("NVMe Controller Fatal Status");
+            }
+            if sys::timer::uptime_seconds() - start > self.timeout.max(1.0) {
+                return Err("NVMe Controller Timed Out");
+            }
+        }
+        Ok(())
+    }
+
+    /// Disables The Controller, Programs The Admin Queue, Then Re-Enables It
+    fn reset(&mut self) -> KResult<()> {
+        let cc = self.read_reg(REG_CC);
+        self.write_reg(REG_CC, cc & !CC_EN);
+        self.wait_ready(false)?;
+
+        let depth = (QUEUE_DEPTH - 1) as u32;
+        self.write_reg(REG_AQA, depth << 16 | depth);
+        let asq = self.admin.sq.addr();
+        let acq = self.admin.cq.addr();
+        self.write_reg64(REG_ASQ, asq);
+        self.write_reg64(REG_ACQ, acq);
+        self.write_reg(REG_INTMS, 0xFFFF_FFFF);
+
+        self.write_reg(REG_CC, CC_EN | CC_IOSQES | CC_IOCQES);
+        self.wait_ready(true)?;
+        if self.read_reg(REG_CSTS) & CSTS_RDY == 0 {
+            return Err("NVMe Controller Failed To Become Ready");
+        }
+        Ok(())
+    }
+
+    fn doorbell(&mut self, queue: u16, completion: bool) {
+        let index = 2 * queue as u64 + if completion { 1 } else { 0 };
+        let offset = REG_DOORBELL + index * self.doorbell_stride;
+        let value = if completion {
+            if queue == 0 { self.admin.cq_head } else { self.io.cq_head }
+        } else {
+            if queue == 0 { self.admin.sq_tail } else { self.io.sq_tail }
+        };
+        self.write_reg(offset, value as u32);
+    }
+
+    /// Submits A Command And Polls Its Completion Queue, Returning Completion DWORD 0
+    fn submit(&mut self, admin: bool, mut command: Command) -> KResult<u32> {
+        let cid = self.next_cid;
+        self.next_cid = self.next_cid.wrapping_add(1);
+        command.set_cid(cid);
+
+        let limit = (self.timeout.max(1.0) * POLLS_PER_SECOND) as u64;
+        let queue = if admin { &mut self.admin } else { &mut self.io };
+        let offset = queue.sq_tail as usize * SQ_ENTRY_SIZE;
+        queue.sq[offset..offset + SQ_ENTRY_SIZE].copy_from_slice(&command.data);
+        queue.sq_tail = (queue.sq_tail + 1) % QUEUE_DEPTH;
+        let id = queue.id;
+        self.doorbell(id, false);
+
+        let mut polls = 0;
+        loop {
+            let queue = if admin { &mut self.admin } else { &mut self.io };
+            let offset = queue.cq_head as usize * CQ_ENTRY_SIZE;
+            let entry = unsafe { read_volatile(queue.cq[offset..].as_ptr() as *const [u8; CQ_ENTRY_SIZE]) };
+            let status = u16::from_le_bytes([entry[14], entry[15]]);
+
+            if status.get_bit(0) == queue.phase {
+                queue.cq_head = (queue.cq_head + 1) % QUEUE_DEPTH;
+                if queue.cq_head == 0 { queue.phase = !queue.phase; }
+                self.doorbell(id, true);
+
+                if status >> 1 != 0 {
+                    return Err("NVMe Command Failed");
+                }
+                return Ok(u32::from_le_bytes([entry[0], entry[1], entry[2], entry[3]]));
+            }
+
+            polls += 1;
+            if polls > limit { // Hanged
+                return Err("NVMe Command Timed Out");
+            }
+            spin_loop();
+        }
+    }
+
+    fn identify(&mut self, cns: u32, nsid: u32) -> KResult<()> {
+        let prp = self.data.addr();
+        self.submit(true, Command::new(ADMIN_IDENTIFY, nsid).prp1(prp).dword(10, cns))?;
+        Ok(())
+    }
+
+    fn create_io_queues(&mut self) -> KResult<()> {
+        let id = self.io.id as u32;
+        let size = (QUEUE_DEPTH - 1) as u32;
+        let cq = self.io.cq.addr();
+        let sq = self.io.sq.addr();
+        // Physically Contiguous, Interrupts Disabled
+        self.submit(true, Command::new(ADMIN_CREATE_IO_CQ, 0).prp1(cq).dword(10, size << 16 | id).dword(11, 1))?;
+        self.submit(true, Command::new(ADMIN_CREATE_IO_SQ, 0).prp1(sq).dword(10, size << 16 | id).dword(11, id << 16 | 1))?;
+        Ok(())
+    }
+
+    fn text(&self, range: core::ops::Range<usize>) -> String {
+        let mut text = String::new();
+        for &b in &self.data[range] {
+            text.push(b as char);
+        }
+        text.trim().into()
+    }
+
+    fn read_u32(&self, offset: usize) -> u32 {
+        u32::from_le_bytes([self.data[offset], self.data[offset + 1], self.data[offset + 2], self.data[offset + 3]])
+    }
+
+    /// Identifies The Controller & Returns The Active Namespaces As (NSID, Sector Count)
+    fn namespaces(&mut self) -> KResult<Vec<(u32, u64)>> {
+        self.identify(CNS_CONTROLLER, 0)?;
+        self.serial = self.text(4..24);
+        self.model = self.text(24..64);
+
+        self.identify(CNS_ACTIVE_NAMESPACES, 0)?;
+        let mut nsids = Vec::new();
+        for i in 0..(PAGE_SIZE / 4) {
+            let nsid = self.read_u32(i * 4);
+            if nsid == 0 { break; }
+            nsids.push(nsid);
+        }
+
+        let mut res = Vec::new();
+        for nsid in nsids {
+            self.identify(CNS_NAMESPACE, nsid)?;
+            let sectors = self.read_u32(0) as u64 | (self.read_u32(4) as u64) << 32;
+            let format = self.data[26].get_bits(0..4) as usize;
+            let lba_shift = self.read_u32(128 + format * 4).get_bits(16..24);
+            if 1 << lba_shift != SECTOR_SIZE {
+                print!("NVMe Namespace {} Uses {} Byte Sectors, Skipping\n", nsid, 1u32 << lba_shift);
+                continue;
+            }
+            res.push((nsid, sectors));
+        }
+        Ok(res)
+    }
+
+    fn io(&mut self, opcode: u8, nsid: u32, block: u64) -> KResult<()> {
+        let prp = self.data.addr();
+        let command = Command::new(opcode, nsid)
+            .prp1(prp)
+            .dword(10, block as u32)
+            .dword(11, (block >> 32) as u32)
+            .dword(12, 0); // One Sector (0 Based)
+        self.submit(false, command)?;
+        Ok(())
+    }
+
+    pub fn read(&mut self, nsid: u32, block: u64, buf: &mut [u8]) -> KResult<()> {
+        assert!(buf.len() == SECTOR_SIZE);
+        self.io(IO_READ, nsid, block)?;
+        buf.copy_from_slice(&self.data[0..SECTOR_SIZE]);
+        Ok(())
+    }
+
+    pub fn write(&mut self, nsid: u32, block: u64, buf: &[u8]) -> KResult<()> {
+        assert!(buf.len() == SECTOR_SIZE);
+        self.data[0..SECTOR_SIZE].copy_from_slice(buf);
+        self.io(IO_WRITE, nsid, block)
+    }
+
+    pub fn flush(&mut self, nsid: u32) -> KResult<()> {
+        self.submit(false, Command::new(IO_FLUSH, nsid))?;
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct Namespace {
+    controller: usize,
+    nsid: u32,
+    sectors: u64,
+}
+
+pub struct Nvme {
+    controllers: Vec<Controller>,
+    namespaces: Vec<Namespace>,
+}
+
+lazy_static! {
+    pub static ref NVME: Mutex<Nvme> = Mutex::new(Nvme { controllers: Vec::new(), namespaces: Vec::new() });
+}
+
+fn disk_size(sectors: u64) -> (u32, String) {
+    let bytes = sectors * SECTOR_SIZE as u64;
+    if bytes >> 20 < 1000 {
+        ((bytes >> 20) as u32, String::from("MB"))
+    } else {
+        ((bytes >> 30) as u32, String::from("GB"))
+    }
+}
+
+fn setup(controller: &mut Controller) -> KResult<Vec<(u32, u64)>> {
+    controller.reset()?;
+    let namespaces = controller.namespaces()?;
+    controller.create_io_queues()?;
+    Ok(namespaces)
+}
+
+pub fn init() {
+    for mut pci_device in pci::find_class(CLASS_MASS_STORAGE, SUBCLASS_NVM) {
+        pci_device.enable_bus_mastering();
+
+        let base = mem::phys_to_virt(PhysAddr::new(pci_device.bar_addr(0))).as_u64();
+        let mut controller = Controller::new(base);
+        match setup(&mut controller) {
+            Ok(namespaces) => {
+                let mut nvme = NVME.lock();
+                let index = nvme.controllers.len();
+                for (nsid, sectors) in namespaces {
+                    nvme.namespaces.push(Namespace { controller: index, nsid, sectors });
+                }
+                nvme.controllers.push(controller);
+            },
+            Err(e) => print!("NVMe {:02}:{:02}: {}\n", pci_device.bus, pci_device.device, e),
+        }
+    }
+
+    for (id, model, serial, nsid, size, unit, _) in list() {
+        print!("NVMe {} {} {} NS {} ({} {})\n", id, model, serial, nsid, size, unit);
+    }
+}
+
+pub fn list() -> Vec<(u8, String, String, u32, u32, String, u32)> {
+    let nvme = NVME.lock();
+    let mut res = Vec::new();
+    for (id, ns) in nvme.namespaces.iter().enumerate() {
+        let controller = &nvme.controllers[ns.controller];
+        let (size, unit) = disk_size(ns.sectors);
+        res.push((id as u8, controller.model.clone(), controller.serial.clone(), ns.nsid, size, unit, ns.sectors as u32));
+    }
+    res
+}
+
+pub fn sector_count(id: u8) -> u32 {
+    let nvme = NVME.lock();
+    nvme.namespaces.get(id as usize).map(|ns| ns.sectors as u32).unwrap_or(0)
+}
+
+fn with_namespace<T>(id: u8, f: impl FnOnce(&mut Controller, u32) -> KResult<T>) -> KResult<T> {
+    without_interrupts(|| {
+        let mut nvme = NVME.lock();
+        let ns = *nvme.namespaces.get(id as usize).ok_or("No Such NVMe Namespace")?;
+        f(&mut nvme.controllers[ns.controller], ns.nsid)
+    })
+}
+
+pub fn read(id: u8, block: u32, buf: &mut [u8]) -> KResult<()> {
+    with_namespace(id, |controller, nsid| controller.read(nsid, block as u64, buf))
+}
+
+pub fn write(id: u8, block: u32, buf: &[u8]) -> KResult<()> {
+    with_namespace(id, |controller, nsid| controller.write(nsid, block as u64, buf))
+}
+
+pub fn flush(id: u8) -> KResult<()> {
+    with_namespace(id, |controller, nsid| controller.flush(nsid))
+}
@@ -21,6 +21,9 @@ pub struct DeviceConfig {
     pub function: u8,
     pub vendor_id: u16,
     pub device_id: u16,
+    pub class: u8,
+    pub subclass: u8,
+    pub prog_if: u8,
     pub status: u16,
     pub command: u16,
     pub base_addresses: [u32; 6],
@@ -38,6 +41,12 @@ impl DeviceConfig {
         let command = data.get_bits(0..16) as u16;
         let status = data.get_bits(16..32) as u16;
 
+        let mut register = ConfigRegister::new(bus, device, function, 0x08);
+        let data = register.read();
+        let prog_if = data.get_bits(8..16) as u8;
+        let subclass = data.get_bits(16..24) as u8;
+        let class = data.get_bits(24..32) as u8;
+
         let mut register = ConfigRegister::new(bus, device, function, 0x3C);
         let data = register.read();
         let interrupt_line = data.get_bits(0..8) as u8;
@@ -58,6 +67,9 @@ impl DeviceConfig {
             // Configuration Space registers
             vendor_id,
             device_id,
+            class,
+            subclass,
+            prog_if,
             status,
             command,
             base_addresses,
@@ -73,6 +85,38 @@ impl DeviceConfig {
         data.set_bit(2, true);
         register.write(data);
     }
+
+    pub fn read_config(&self, offset: u8) -> u32 {
+        let mut register = ConfigRegister::new(self.bus, self.device, self.function, offset);
+        register.read()
+    }
+
+    /// Returns The Address Held In The Given BAR, Combining The Next BAR For 64-Bit Memory BARs
+    pub fn bar_addr(&self, index: usize) -> u64 {
+        let bar = self.base_addresses[index];
+        if bar.get_bit(0) { // I/O Space
+            return (bar & 0xFFFF_FFFC) as u64;
+        }
+        let low = (bar & 0xFFFF_FFF0) as u64;
+        if bar.get_bits(1..3) == 0b10 && index < 5 {
+            low | ((self.base_addresses[index + 1] as u64) << 32)
+        } else {
+            low
+        }
+    }
+
+    /// Walks The Capability List, Returning The (ID, Offset) Of Each Capability
+    pub fn capabilities(&self) -> Vec<(u8, u8)> {
+        let mut caps = Vec::new();
+        if !self.status.get_bit(4) { return caps; }
+        let mut offset = self.read_config(0x34).get_bits(0..8) as u8 & 0xFC;
+        while offset != 0 {
+            let data = self.read_config(offset);
+            caps.push((data.get_bits(0..8) as u8, offset));
+            offset = data.get_bits(8..16) as u8 & 0xFC;
+        }
+        caps
+    }
 }
 
 fn get_vendor_id(bus: u8, device: u8, function: u8) -> u16 {
@@ -139,6 +183,18 @@ pub fn find_device(vendor: u16, device_id: u16) -> Option<DeviceConfig> {
     return None;
 }
 
+/// Finds Every Device With The Given Vendor & Device Id, In Bus Order
+pub fn find_devices(vendor: u16, device_id: u16) -> Vec<DeviceConfig> {
+    let devices = &*PCI_DEVICES.lock();
+    devices.iter().filter(|dev| dev.vendor_id == vendor && dev.device_id == device_id).copied().collect()
+}
+
+/// Finds Every Device With The Given Class & Subclass Code (ie 01:06 For AHCI Controllers)
+pub fn find_class(class: u8, subclass: u8) -> Vec<DeviceConfig> {
+    let devices = &*PCI_DEVICES.lock();
+    devices.iter().filter(|dev| dev.class == class && dev.subclass == subclass).copied().collect()
+}
+
 struct ConfigRegister {
     addr_port: Port<u32>,
     data_port: Port<u32>,
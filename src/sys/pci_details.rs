@@ -6,6 +6,7 @@ pub fn vendor(id: u16) -> String {
         0x10DE => return String::from("NVIDIA"),
         0x15B6=> return String::from("IBM"),
         0x8086 => return String::from("INTEL"),
+        0x1AF4 => return String::from("RED HAT"),
         _ => return String::from("UNKNOWN"),
     }
 }
@@ -15,9 +16,14 @@ pub fn device(id: u16) -> String {
         0x1111 => return String::from("VGA"),
         0x1237 => return String::from("82441FX CHIPSET"),
         0x8139 => return String::from("RTL8139"),
+        0x100E => return String::from("82540EM"),
+        0x1000 | 0x1041 => return String::from("VIRTIO NET"),
         0x7000 => return String::from("PIIX3 ISA"),
         0x7010 => return String::from("PIIX3 IDE"),
         0x7113 => return String::from("PIIX4 ACPI"),
+        0x2922 => return String::from("ICH9 AHCI"),
+        0x1001 | 0x1042 => return String::from("VIRTIO BLOCK"),
+        0x5845 => return String::from("QEMU NVME"),
         _ => return String::from("UNKNOWN"),
     }
 }
@@ -25,6 +31,8 @@ pub fn device(id: u16) -> String {
 pub fn from_dev_str(dev: &str) -> u16 {
     match dev.to_ascii_lowercase().as_str() {
         "rtl8139" => 0x8139,
+        "82540em" => 0x100E,
+        "virtio-net" => 0x1000,
         _ => 0
     }
 }
@@ -32,6 +40,8 @@ pub fn from_dev_str(dev: &str) -> u16 {
 pub fn from_vendor_str(vendor: &str) -> u16 {
     match vendor.to_ascii_lowercase().as_str() {
         "realtek" => 0x10EC,
+        "intel" => 0x8086,
+        "redhat" => 0x1AF4,
         _ => 0
     }
 }
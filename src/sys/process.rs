@@ -0,0 +1,98 @@
+//! Process Table
+//!
+//! There's No Scheduler Yet, Everything Runs On The Kernel's Stack. The Table Only Records
+//! What Is Running So It Can Be Inspected (e.g. Through `/proc/<pid>`). The Kernel Itself
+//! Is Always Pid 0.
+
+use alloc::{string::String, vec, vec::Vec};
+use lazy_static::lazy_static;
+use spin::Mutex;
+
+use crate::{KResult, sys::timer};
+
+pub type Pid = usize;
+pub type Uid = u16;
+pub type Gid = u16;
+
+/// Who A Process Acts As When Touching Files
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Credentials {
+    pub uid: Uid,
+    pub gid: Gid,
+}
+
+impl Credentials {
+    pub const ROOT: Self = Self { uid: 0, gid: 0 };
+
+    pub fn new(uid: Uid, gid: Gid) -> Self {
+        Self { uid, gid }
+    }
+
+    pub fn is_root(&self) -> bool {
+        self.uid == 0
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Process {
+    pub pid: Pid,
+    pub name: String,
+    pub creds: Credentials,
+    /// Uptime In Milliseconds When The Process Was Started
+    pub started: u128,
+}
+
+struct Table {
+    processes: Vec<Process>,
+    next_pid: Pid,
+}
+
+lazy_static! {
+    static ref PROCESSES: Mutex<Table> = Mutex::new(Table {
+        processes: vec![Process { pid: 0, name: String::from("kernel"), creds: Credentials::ROOT, started: 0 }],
+        next_pid: 1,
+    });
+}
+
+/// Adds A Process Running As Root To The Table, Returning Its Pid
+pub fn spawn(name: &str) -> Pid {
+    spawn_as(name, Credentials::ROOT)
+}
+
+pub fn spawn_as(name: &str, creds: Credentials) -> Pid {
+    let mut table = PROCESSES.lock();
+    let pid = table.next_pid;
+    table.next_pid += 1;
+    table.processes.push(Process { pid, name: String::from(name), creds, started: timer::uptime_millis() });
+    pid
+}
+
+pub fn exit(pid: Pid) -> KResult<()> {
+    if pid == 0 { return Err("The Kernel Can't Exit"); }
+    let mut table = PROCESSES.lock();
+    let len = table.processes.len();
+    table.processes.retain(|process| process.pid != pid);
+    if table.processes.len() == len { Err("No Such Process") } else { Ok(()) }
+}
+
+pub fn get(pid: Pid) -> Option<Process> {
+    PROCESSES.lock().processes.iter().find(|process| process.pid == pid).cloned()
+}
+
+pub fn credentials(pid: Pid) -> Option<Credentials> {
+    get(pid).map(|process| process.creds)
+}
+
+pub fn list() -> Vec<Process> {
+    PROCESSES.lock().processes.clone()
+}
+
+#[test_case]
+fn spawn_and_exit() {
+    let pid = spawn("test");
+    assert_eq!(get(pid).map(|process| process.name), Some(String::from("test")));
+    assert_eq!(credentials(pid), Some(Credentials::ROOT));
+    exit(pid).expect("");
+    assert!(get(pid).is_none());
+    assert!(exit(0).is_err());
+}
@@ -0,0 +1,263 @@
+//! Software RAID-1
+//!
+//! Mirrors A Block Device Across Two ATA Drives. Writes Go To Every Member That's Present &
+//! Reads Alternate Between Members Holding The Latest Data. While A Member Is Missing, The
+//! Regions Written Without It Are Marked In A Dirty Bitmap, So `resync` Only Has To Copy Those
+//! Once It's Back Instead Of The Whole Drive. The Bitmap Lives In Memory, A Reboot Loses The
+//! Mirror & It Has To Be Created (& Fully Synced) Again.
+
+use alloc::{format, string::String, vec, vec::Vec};
+use bit_field::BitField;
+use lazy_static::lazy_static;
+use spin::Mutex;
+
+use crate::{KResult, sys::{ata, storage::fs::{HANDLE, dev_handle::DeviceHandle}}};
+
+/// Sectors Covered By One Bit Of The Dirty Bitmap (64 KB)
+const REGION_SECTORS: u32 = 128;
+
+/// Most Mirrors That Can Exist At Once, Two ATA Buses Only Have Room For Two
+pub const MAX_MIRRORS: usize = 2;
+
+/// Regions Where One Member Is Behind The Other
+struct DirtyMap {
+    bits: Vec<u8>,
+    regions: u32,
+    count: u32,
+}
+
+impl DirtyMap {
+    fn new(sectors: u32) -> Self {
+        let regions = sectors.div_ceil(REGION_SECTORS);
+        Self { bits: vec![0; regions.div_ceil(8) as usize], regions, count: 0 }
+    }
+
+    fn is_dirty(&self, region: u32) -> bool {
+        self.bits[region as usize / 8].get_bit(region as usize % 8)
+    }
+
+    fn set(&mut self, region: u32, dirty: bool) {
+        if self.is_dirty(region) == dirty { return; }
+        self.bits[region as usize / 8].set_bit(region as usize % 8, dirty);
+        if dirty { self.count += 1; } else { self.count -= 1; }
+    }
+
+    fn mark_all(&mut self) {
+        for region in 0..self.regions { self.set(region, true); }
+    }
+
+    fn next(&self) -> Option<u32> {
+        (0..self.regions).find(|region| self.is_dirty(*region))
+    }
+}
+
+struct Mirror {
+    members: [(u8, u8); 2],
+    missing: [bool; 2],
+    /// The Member The Dirty Regions Are Out Of Date On
+    stale: usize,
+    dirty: DirtyMap,
+    sectors: u32,
+    next_read: usize,
+}
+
+impl Mirror {
+    /// Whether `member` Holds The Latest Copy Of `block`
+    fn is_current(&self, member: usize, block: u32) -> bool {
+        !self.missing[member] && !(member == self.stale && self.dirty.is_dirty(block / REGION_SECTORS))
+    }
+}
+
+lazy_static! {
+    static ref MIRRORS: Mutex<Vec<Option<Mirror>>> = Mutex::new(Vec::new());
+}
+
+fn disk_size(sectors: u32) -> (u32, String) {
+    let bytes = sectors as usize * 512;
+    if bytes >> 20 == 0 {
+        ((bytes >> 10) as u32, String::from("KB"))
+    } else {
+        ((bytes >> 20) as u32, String::from("MB"))
+    }
+}
+
+fn in_use(mirrors: &[Option<Mirror>], member: (u8, u8)) -> bool {
+    mirrors.iter().flatten().any(|mirror| mirror.members.contains(&member))
+}
+
+/// Mirrors `primary` Onto `secondary`, Returning The Mirror's Id. The Secondary Starts Fully
+/// Dirty, Nothing Is Copied Until `resync` Runs. The Mirror Is As Big As The Smaller Drive.
+pub fn create(primary: (u8, u8), secondary: (u8, u8)) -> KResult<u8> {
+    if primary == secondary { return Err("A Drive Can't Mirror Itself"); }
+    let sectors = ata::sector_count(primary.0, primary.1).min(ata::sector_count(secondary.0, secondary.1));
+    if sectors == 0 { return Err("No Such Drive"); }
+
+    let mut mirrors = MIRRORS.lock();
+    if in_use(&mirrors, primary) || in_use(&mirrors, secondary) { return Err("Drive Is Already Mirrored"); }
+    let id = match mirrors.iter().position(|slot| slot.is_none()) {
+        Some(id) => id,
+        None if mirrors.len() < MAX_MIRRORS => { mirrors.push(None); mirrors.len() - 1 },
+        None => return Err("No Free Mirrors"),
+    };
+    let mut dirty = DirtyMap::new(sectors);
+    dirty.mark_all();
+    mirrors[id] = Some(Mirror { members: [primary, secondary], missing: [false; 2], stale: 1, dirty, sectors, next_read: 0 });
+    Ok(id as u8)
+}
+
+/// Refuses To Stop The Mirror Currently Mounted As The Storage Device
+pub fn stop(id: u8) -> KResult<()> {
+    if let Some(DeviceHandle::MirrorBlockDevice(dev)) = HANDLE.lock().as_ref() {
+        if dev.id() == id { return Err("Mirror Is Mounted"); }
+    }
+    let mut mirrors = MIRRORS.lock();
+    match mirrors.get_mut(id as usize) {
+        Some(slot) if slot.is_some() => { *slot = None; Ok(()) },
+        _ => Err("No Such Mirror"),
+    }
+}
+
+fn with_mirror<T>(id: u8, f: impl FnOnce(&mut Mirror) -> KResult<T>) -> KResult<T> {
+    let mut mirrors = MIRRORS.lock();
+    let mirror = mirrors.get_mut(id as usize).and_then(|slot| slot.as_mut()).ok_or("No Such Mirror")?;
+    f(mirror)
+}
+
+/// Marks `member` (0 Or 1) As Missing, Writes Stop Going To It Until It's Resynced. Refused If
+/// The Other Member Is Missing Or Behind, As That Would Leave No Up To Date Copy.
+pub fn fail(id: u8, member: usize) -> KResult<()> {
+    with_mirror(id, |mirror| {
+        if member > 1 { return Err("A Mirror Only Has Members 0 & 1"); }
+        let other = 1 - member;
+        if mirror.missing[other] || (mirror.stale == other && mirror.dirty.count > 0) {
+            return Err("Member Holds The Only Up To Date Copy");
+        }
+        mirror.missing[member] = true;
+        Ok(())
+    })
+}
+
+/// Re-Identifies Each Member, Marking Any That Have Disappeared As Missing
+pub fn check(id: u8) -> KResult<()> {
+    let members = with_mirror(id, |mirror| Ok(mirror.members))?;
+    for (member, (bus, drive)) in members.iter().enumerate() {
+        if ata::sector_count(*bus, *drive) == 0 {
+            with_mirror(id, |mirror| { mirror.missing[member] = true; Ok(()) })?;
+        }
+    }
+    Ok(())
+}
+
+/// [check]s Every Mirror
+pub fn check_all() {
+    let ids: Vec<u8> = MIRRORS.lock().iter().enumerate().filter(|(_, slot)| slot.is_some()).map(|(id, _)| id as u8).collect();
+    for id in ids {
+        let _ = check(id);
+    }
+}
+
+/// Brings Back Any Missing Member That's Present Again & Copies Every Dirty Region Onto The
+/// Stale Member, Returning How Many Regions Were Copied. The Mirror Is Locked One Region At A
+/// Time So It Stays Usable While Syncing. A Blank Replacement Drive Wasn't Tracked, Its Mirror
+/// Has To Be Recreated So Every Region Starts Dirty.
+pub fn resync(id: u8) -> KResult<u32> {
+    let members = with_mirror(id, |mirror| Ok((mirror.members, mirror.sectors)))?;
+    for (member, (bus, drive)) in members.0.iter().enumerate() {
+        if ata::sector_count(*bus, *drive) >= members.1 {
+            // A Member That Comes Back Missed Every Write Made Without It, Which Is Exactly
+            // What The Dirty Bitmap Recorded Against It
+            with_mirror(id, |mirror| { mirror.missing[member] = false; Ok(()) })?;
+        }
+    }
+
+    let mut copied = 0;
+    loop {
+        let done = with_mirror(id, |mirror| {
+            let region = match mirror.dirty.next() {
+                Some(region) => region,
+                None => return Ok(true),
+            };
+            if mirror.missing.iter().any(|missing| *missing) { return Err("A Member Is Still Missing"); }
+            let (from, to) = (mirror.members[1 - mirror.stale], mirror.members[mirror.stale]);
+            let start = region * REGION_SECTORS;
+            let mut buf = [0; 512];
+            for block in start..(start + REGION_SECTORS).min(mirror.sectors) {
+                ata::read(from.0, from.1, block, &mut buf);
+                ata::write(to.0, to.1, block, &buf);
+            }
+            mirror.dirty.set(region, false);
+            Ok(false)
+        })?;
+        if done { return Ok(copied); }
+        copied += 1;
+    }
+}
+
+/// Every Mirror As `(id, members, size, unit, state, sectors)`
+pub fn list() -> Vec<(u8, String, u32, String, String, u32)> {
+    let mirrors = MIRRORS.lock();
+    mirrors.iter().enumerate().filter_map(|(id, slot)| slot.as_ref().map(|mirror| {
+        let members: Vec<String> = mirror.members.iter().zip(mirror.missing.iter())
+            .map(|((bus, drive), missing)| format!("{}:{}{}", bus, drive, if *missing {" (Missing)"} else {""}))
+            .collect();
+        let state = match (mirror.missing.iter().any(|missing| *missing), mirror.dirty.count) {
+            (true, _) => format!("Degraded, {} Dirty Regions", mirror.dirty.count),
+            (false, 0) => String::from("Clean"),
+            (false, count) => format!("Needs Resync, {} Dirty Regions", count),
+        };
+        let (size, unit) = disk_size(mirror.sectors);
+        (id as u8, members.join(", "), size, unit, state, mirror.sectors)
+    })).collect()
+}
+
+pub fn sector_count(id: u8) -> u32 {
+    let mirrors = MIRRORS.lock();
+    mirrors.get(id as usize).and_then(|slot| slot.as_ref()).map(|mirror| mirror.sectors).unwrap_or(0)
+}
+
+pub fn read(id: u8, block: u32, buf: &mut [u8]) -> KResult<()> {
+    with_mirror(id, |mirror| {
+        if block >= mirror.sectors { return Err("Access Past End Of Device"); }
+        let member = [mirror.next_read, 1 - mirror.next_read].iter().copied()
+            .find(|member| mirror.is_current(*member, block))
+            .ok_or("Mirror Has No Up To Date Copy")?;
+        mirror.next_read = 1 - member;
+        let (bus, drive) = mirror.members[member];
+        ata::read(bus, drive, block, buf);
+        Ok(())
+    })
+}
+
+pub fn write(id: u8, block: u32, buf: &[u8]) -> KResult<()> {
+    with_mirror(id, |mirror| {
+        if block >= mirror.sectors { return Err("Access Past End Of Device"); }
+        if let Some(missing) = mirror.missing.iter().position(|missing| *missing) {
+            // Dirty Regions Can Only Be Tracked Against One Member At A Time
+            if mirror.missing[1 - missing] || (mirror.stale != missing && mirror.dirty.count > 0) {
+                return Err("Mirror Has No Up To Date Copy");
+            }
+            mirror.stale = missing;
+            mirror.dirty.set(block / REGION_SECTORS, true);
+        }
+        // A Dirty Region Stays Dirty Even With Both Members Present, Its Other Sectors Still Differ
+        for member in (0..2).filter(|member| !mirror.missing[*member]) {
+            let (bus, drive) = mirror.members[member];
+            ata::write(bus, drive, block, buf);
+        }
+        Ok(())
+    })
+}
+
+#[test_case]
+fn dirty_map_tracks_regions() {
+    let mut map = DirtyMap::new(REGION_SECTORS * 9 + 1);
+    assert_eq!((map.regions, map.next()), (10, None));
+    map.set(9, true);
+    map.set(3, true);
+    map.set(3, true);
+    assert_eq!((map.count, map.next()), (2, Some(3)));
+    map.set(3, false);
+    assert_eq!((map.count, map.next()), (1, Some(9)));
+    map.mark_all();
+    assert_eq!(map.count, 10);
+}
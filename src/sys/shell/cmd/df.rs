@@ -0,0 +1,23 @@
+use alloc::vec::Vec;
+
+use crate::{println, sys::{storage::fs::is_mounted, vfs::quota}};
+
+/// Reports Free Space From The Superblock's Cached Summary, Never Scanning The Bitmaps Unless
+/// The Cache Has To Be Rebuilt
+pub fn main(_args: &Vec<&str>) -> usize {
+    if !is_mounted() {
+        println!("No Device Is Mounted, Try 'fs mount ata <bus> <drive>'.");
+        return 1;
+    }
+
+    let usage = match quota::usage() {
+        Ok(usage) => usage,
+        Err(e) => { println!("{}", e); return 2; },
+    };
+    println!("{:>8} {:>8} {:>8} {:>5} | {:>7} {:>7} {:>7}", "Blocks", "Used", "Free", "Use%", "Inodes", "IUsed", "IFree");
+    println!("{:>8} {:>8} {:>8} {:>4}% | {:>7} {:>7} {:>7}",
+        usage.blocks_total, usage.blocks_used(), usage.blocks_free, usage.blocks_used() * 100 / usage.blocks_total,
+        usage.inodes_total, usage.inodes_used(), usage.inodes_free);
+    println!("{} Bytes Free", usage.bytes_free());
+    0
+}
@@ -1,8 +1,66 @@
 
 
-use alloc::vec::Vec;
+use core::fmt;
 
-use crate::{print, println, serial_print, serial_println, sys::{ata, mem, shell::run, storage::fs::block::Block}};
+use alloc::{format, string::String, vec::Vec};
+
+use crate::{KResult, print, println, serial_print, serial_println, sys::{ahci, ata, loopdev, mem, nvme, raid, virtio, shell::run, storage::fs::block::Block}};
+
+/// A Drive The Raw Subcommands Work On, Given As `<bus> <drive>` Or `loop <n>`
+#[derive(Debug, Clone, Copy)]
+enum Drive {
+    Ata(u8, u8),
+    Loop(u8),
+}
+
+impl Drive {
+    /// Parses The Two Arguments Starting At `args[i]`
+    fn parse(args: &Vec<&str>, i: usize) -> Self {
+        if args[i] == "loop" {
+            Drive::Loop(args[i + 1].parse().expect("Expected A Loop Number"))
+        } else {
+            Drive::Ata(args[i].parse().expect("Yeets"), args[i + 1].parse().expect("Yeets"))
+        }
+    }
+
+    /// The Drive As Arguments For Another `dsk` Command
+    fn args(self) -> String {
+        match self {
+            Drive::Ata(bus, drive) => format!("{} {}", bus, drive),
+            Drive::Loop(id) => format!("loop {}", id),
+        }
+    }
+
+    fn sector_count(self) -> u32 {
+        match self {
+            Drive::Ata(bus, drive) => ata::sector_count(bus, drive),
+            Drive::Loop(id) => loopdev::sector_count(id),
+        }
+    }
+
+    fn read(self, block: u32, buf: &mut [u8]) -> KResult<()> {
+        match self {
+            Drive::Ata(bus, drive) => { ata::read(bus, drive, block, buf); Ok(()) },
+            Drive::Loop(id) => loopdev::read(id, block, buf),
+        }
+    }
+
+    fn write(self, block: u32, buf: &[u8]) -> KResult<()> {
+        match self {
+            Drive::Ata(bus, drive) => { ata::write(bus, drive, block, buf); Ok(()) },
+            Drive::Loop(id) => loopdev::write(id, block, buf),
+        }
+    }
+}
+
+impl fmt::Display for Drive {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Drive::Ata(bus, drive) => write!(f, "{}:{}", bus, drive),
+            Drive::Loop(id) => write!(f, "Loop {}", id),
+        }
+    }
+}
 
 pub fn main(args: &Vec<&str>) -> usize {
     if args.len() < 2 {
@@ -14,17 +72,17 @@ pub fn main(args: &Vec<&str>) -> usize {
     }
 
     if args[1] == "read" {
-        if args.len() < 5 { println!("Usage dsk read <bus> <drive> <block>"); return 2 };
+        if args.len() < 5 { println!("Usage dsk read <bus> <drive> <block> | dsk read loop <n> <block>"); return 2 };
         return read(args);
     }
 
     if args[1] == "format" {
-        if args.len() < 4 { println!("Usage dsk write <bus> <drive>"); return 2 };
+        if args.len() < 4 { println!("Usage dsk format <bus> <drive> | dsk format loop <n>"); return 2 };
         return format(args);
     }
 
     if args[1] == "copy" {
-        if args.len() < 6 { println!("Usage dsk copy <bus src> <drive src> <bus dest> <drive dest>"); return 2 };
+        if args.len() < 6 { println!("Usage dsk copy <bus src> <drive src> <bus dest> <drive dest>, Either Drive Can Be loop <n>"); return 2 };
         return copy(args);
     }
 
@@ -32,6 +90,21 @@ pub fn main(args: &Vec<&str>) -> usize {
         if args.len() < 3 { println!("Usage dsk dump <block addr>"); return 2 };
         return dump_block(args);
     }
+
+    if args[1] == "attach" {
+        if args.len() < 3 { println!("Usage dsk attach <path>"); return 2 };
+        return attach(args);
+    }
+
+    if args[1] == "detach" {
+        if args.len() < 3 { println!("Usage dsk detach <loop>"); return 2 };
+        return detach(args);
+    }
+
+    if args[1] == "mirror" {
+        if args.len() < 4 { println!("Usage dsk mirror <create <bus> <drive> <bus> <drive> | fail <n> <member> | resync <n> | stop <n>>"); return 2 };
+        return mirror(args);
+    }
     1
 }
 
@@ -40,16 +113,30 @@ fn ls(_args: &Vec<&str>) -> usize {
     for disk in disks {
         println!("Disk {}:{} - Model: {} - Serial: {}, Size: {} {}", disk.0, disk.1, disk.2, disk.3, disk.4, disk.5);
     }
+    for disk in ahci::list() {
+        println!("SATA {} - Model: {} - Serial: {}, Size: {} {}", disk.0, disk.1, disk.2, disk.3, disk.4);
+    }
+    for disk in virtio::blk::list() {
+        println!("Virtio {} - Transport: {}, Size: {} {}", disk.0, if disk.1 {"Modern"} else {"Legacy"}, disk.2, disk.3);
+    }
+    for disk in nvme::list() {
+        println!("NVMe {} - Model: {} - Serial: {}, Namespace: {}, Size: {} {}", disk.0, disk.1, disk.2, disk.3, disk.4, disk.5);
+    }
+    for disk in loopdev::list() {
+        println!("Loop {} - File: {}, Size: {} {}", disk.0, disk.1, disk.2, disk.3);
+    }
+    raid::check_all();
+    for disk in raid::list() {
+        println!("Mirror {} - Members: {}, Size: {} {}, State: {}", disk.0, disk.1, disk.2, disk.3, disk.4);
+    }
     0
 }
 
 fn read(args: &Vec<&str>) -> usize {
     let mut buffer: [u8; 512] = [0; 512];
-    let bus: u8 = args[2].parse().expect("Yeets");
-    let drive: u8 = args[3].parse().expect("Yeets");
     let block: u32 = args[4].parse().expect("Yeets");
 
-    ata::read(bus, drive, block, &mut buffer);
+    if let Err(e) = Drive::parse(args, 2).read(block, &mut buffer) { println!("{}", e); return 3; }
 
     for row in (0..buffer.len()).step_by(16) {
         serial_print!("{:03x}: ", row);
@@ -66,45 +153,72 @@ fn read(args: &Vec<&str>) -> usize {
 }
 
 pub fn format(args: &Vec<&str>) -> usize {
-    let bus: u8 = args[2].parse().expect("Yeets");
-    let drive: u8 = args[3].parse().expect("Yeets");
-    let sectors = ata::sector_count(bus, drive);
+    let drive = Drive::parse(args, 2);
+    let sectors = drive.sector_count();
     for block in 0..sectors {
-        print!("Formatting Block {:04}/{:04} Of Drive {}:{} {:04} MB ...\r", block, sectors, bus, drive, (block * 512) / mem::MB as u32);
-        ata::write(bus, drive, block, &[0; 512]);
+        print!("Formatting Block {:04}/{:04} Of Drive {} {:04} MB ...\r", block, sectors, drive, (block * 512) / mem::MB as u32);
+        if let Err(e) = drive.write(block, &[0; 512]) { println!("\n{}", e); return 3; }
     }
-    print!("Formatting Block {:04}/{:04} Of Drive {}:{} {:04} MB ...\n", sectors, sectors, bus, drive, (sectors * 512) / mem::MB as u32);
+    print!("Formatting Block {:04}/{:04} Of Drive {} {:04} MB ...\n", sectors, sectors, drive, (sectors * 512) / mem::MB as u32);
     0
 }
 
 pub fn copy(args: &Vec<&str>) -> usize { 
-    let bus_src: u8 = args[2].parse().expect("Yeets");
-    let drive_src: u8 = args[3].parse().expect("Yeets");
-
-    let source_sectors = ata::sector_count(bus_src, drive_src);
+    let source = Drive::parse(args, 2);
+    let source_sectors = source.sector_count();
 
-    let bus_dest: u8 = args[4].parse().expect("Yeets");
-    let drive_dest: u8 = args[5].parse().expect("Yeets");
-
-    let dest_sectors = ata::sector_count(bus_dest, drive_dest);
+    let dest = Drive::parse(args, 4);
+    let dest_sectors = dest.sector_count();
     if source_sectors > dest_sectors {
         run!("echo Disk Is Not Big Enough! ");
         return 3;
     };
 
-    run!("dsk format {} {}", bus_dest, drive_dest);
+    run!("dsk format {}", dest.args());
     
     for block in 0..source_sectors {
         print!("Copying Block {:04}/{:04}...\r", block, source_sectors);
         let mut buffer: [u8; 512] = [0; 512];
-        ata::read(bus_src, drive_src, block, &mut buffer);
-        ata::write(bus_dest, drive_dest, block, &buffer);
+        if let Err(e) = source.read(block, &mut buffer).and_then(|()| dest.write(block, &buffer)) { println!("\n{}", e); return 3; }
     }
     print!("Copying Block {:04}/{:04}...\n", source_sectors, source_sectors);
 
     0
 }
 
+fn attach(args: &Vec<&str>) -> usize {
+    match loopdev::attach(args[2]) {
+        Ok(id) => { println!("Attached '{}' As Loop {} ({} Sectors)", args[2], id, loopdev::sector_count(id)); 0 },
+        Err(e) => { println!("{}", e); 3 },
+    }
+}
+
+fn detach(args: &Vec<&str>) -> usize {
+    let id: u8 = args[2].parse().expect("Expected A Loop Number");
+    match loopdev::detach(id) {
+        Ok(()) => 0,
+        Err(e) => { println!("{}", e); 3 },
+    }
+}
+
+fn mirror(args: &Vec<&str>) -> usize {
+    let id = || args[3].parse::<u8>().expect("Expected A Mirror Number");
+    let result = match args[2] {
+        "create" if args.len() >= 7 => {
+            let drive = |i: usize| (args[i].parse::<u8>().expect("Yeets"), args[i + 1].parse::<u8>().expect("Yeets"));
+            raid::create(drive(3), drive(5)).map(|id| println!("Created Mirror {}, Run 'dsk mirror resync {}' To Copy {}:{} Onto {}:{}", id, id, args[3], args[4], args[5], args[6]))
+        },
+        "fail" if args.len() >= 5 => raid::fail(id(), args[4].parse().expect("Expected A Member (0 Or 1)")),
+        "resync" => raid::resync(id()).map(|regions| println!("Resynced {} Regions", regions)),
+        "stop" => raid::stop(id()),
+        _ => { println!("Unknown Mirror Command '{}'", args[2]); return 2; },
+    };
+    match result {
+        Ok(()) => 0,
+        Err(e) => { println!("{}", e); 3 },
+    }
+}
+
 fn dump_block(args: &Vec<&str>) -> usize {
     let addr = args[2].parse().unwrap();
     let block = Block::read(addr).unwrap();
@@ -1,5 +1,6 @@
-use crate::{log, print, println, sys::{storage::fs::{*, dev_handle::{AtaDevice, DeviceHandle, MemDevice}, file_table::RecordIndex}, timer}};
-use alloc::{string::String, vec::Vec};
+use crate::{log, println, sys::{loopdev, raid, storage::fs::{*, dev_handle::{AhciDevice, AtaDevice, DeviceHandle, LoopDevice, MemDevice, MirrorDevice, NvmeDevice, VirtioDevice}, file_table::{FileTable, RecordIndex}, superblock::SuperBlock}, vfs::{mount, tmpfs}}};
+use alloc::{boxed::Box, string::String, vec::Vec};
+use bit_field::BitField;
 
 pub fn main(args: &Vec<&str>) -> usize {
     match args[1] {
@@ -9,6 +10,9 @@ pub fn main(args: &Vec<&str>) -> usize {
         "alloc" => {alloc(args)},
         "free" => {free(args)},
         "ls" => {list_all(args)},
+        "format" => {format()},
+        "create" => {create(args)},
+        "mounts" => {mounts()},
         _ => {println!("Unknown command '{}'.", args[1])},
     }
     0
@@ -18,6 +22,12 @@ fn mount(args: &Vec<&str>) {
     match args[2] {
         "ramdisk" => {mount_ramdisk(args)},
         "ata" => {mount_ata(args)},
+        "ahci" => {mount_ahci(args)},
+        "virtio" => {mount_virtio(args)},
+        "nvme" => {mount_nvme(args)},
+        "loop" => {mount_loop(args)},
+        "mirror" => {mount_mirror(args)},
+        "tmpfs" => {mount_tmpfs(args)},
         _ => {println!("Unknown Device '{}'.", args[2])}
     }
 }
@@ -31,6 +41,47 @@ fn mount_ata(args: &Vec<&str>) {
     //log!("Mounted ATA {}:{}", args[3], args[4]);
 }
 
+fn mount_ahci(args: &Vec<&str>) {
+    mount_device(DeviceHandle::AhciBlockDevice(AhciDevice::new(args[3].parse().unwrap())));
+}
+
+fn mount_virtio(args: &Vec<&str>) {
+    mount_device(DeviceHandle::VirtioBlockDevice(VirtioDevice::new(args[3].parse().unwrap())));
+}
+
+fn mount_nvme(args: &Vec<&str>) {
+    mount_device(DeviceHandle::NvmeBlockDevice(NvmeDevice::new(args[3].parse().unwrap())));
+}
+
+fn mount_loop(args: &Vec<&str>) {
+    let id: u8 = args[3].parse().unwrap();
+    if loopdev::sector_count(id) == 0 {println!("Loop {} Is Not Attached.", id); return;}
+    mount_device(DeviceHandle::LoopBlockDevice(LoopDevice::new(id)));
+}
+
+fn mount_mirror(args: &Vec<&str>) {
+    let id: u8 = args[3].parse().unwrap();
+    if raid::sector_count(id) == 0 {println!("Mirror {} Does Not Exist.", id); return;}
+    mount_device(DeviceHandle::MirrorBlockDevice(MirrorDevice::new(id)));
+}
+
+fn mount_tmpfs(args: &Vec<&str>) {
+    if args.len() < 4 {println!("Usage: fs mount tmpfs <path> [size KB]"); return;}
+    let limit = match args.get(4) {
+        Some(kb) => kb.parse::<usize>().expect("Expected A Size In KB") * 1024,
+        None => tmpfs::DEFAULT_LIMIT,
+    };
+    if let Err(e) = mount::mount(args[3], Box::new(tmpfs::TmpFs::new(limit))) {
+        println!("{}", e);
+    }
+}
+
+fn mounts() {
+    for (point, fs_type) in mount::mounts() {
+        println!("{} on {}", fs_type, point);
+    }
+}
+
 fn visualize(args: &Vec<&str>) {
     if args.len() < 4 {println!("Usage: fs visualize <clr|bin> <addr start>"); return;}
 
@@ -42,31 +93,60 @@ fn visualize(args: &Vec<&str>) {
     }
 }
 
+/// Reads Each Bitmap Block Once Instead Of Once Per Bit. `df` Reports The Inode Filesystem
+/// From Its Cached Summary Instead.
 fn blocks_free() {
-    println!("");
+    let (data_addr, data_size) = SuperBlock::data_region();
+    let end = data_addr + data_size as u32;
     let mut sum = 0;
-    for addr in DATA_ADDR..(DATA_SIZE as u32 + DATA_ADDR) {
-        
-        let start = timer::uptime_millis();
-        if !bitmap::Bitmap::is_alloc(addr) {sum += 1};
-        let end = timer::uptime_millis();
-        print!("Checking Block 0x{:06x}, {}ms/Block\r", addr, (end - start));
+    let mut addr = data_addr;
+    while addr < end {
+        let block = block::Block::read(addressing::bitmap(data_addr, addr).0).expect("Failed To Load Bitmap");
+        let bits = (end - addr).min(BITMAP_SIZE as u32) as usize;
+        sum += (0..bits).filter(|bit| !block.data()[bit / 8].get_bit(bit % 8)).count();
+        addr += bits as u32;
     }
     println!("Blocks Free: {} ({} Bytes)", sum, sum * BLOCK_SIZE);
 }
 
 fn alloc(args: &Vec<&str>) {
-    let addr = DATA_ADDR + args[2].parse::<u32>().expect("Expected A U32");
+    let addr = SuperBlock::data_region().0 + args[2].parse::<u32>().expect("Expected A U32");
     bitmap::Bitmap::alloc(addr);
     println!("Allocated Block 0x{:06x}", addr);
 }
 
 fn free(args: &Vec<&str>) {
-    let addr = DATA_ADDR + args[2].parse::<u32>().expect("Expected A U32");
+    let addr = SuperBlock::data_region().0 + args[2].parse::<u32>().expect("Expected A U32");
     bitmap::Bitmap::free(addr);
     println!("Allocated Block 0x{:06x}", addr);
 }
 
+fn format() {
+    if !is_mounted() {println!("No Device Is Mounted."); return;}
+    match SuperBlock::format() {
+        Ok(()) => println!("Formatted Device, Journal At 0x{:06x} ({} Blocks)", JOURNAL_ADDR, JOURNAL_SIZE),
+        Err(e) => println!("Failed To Format Device: {}", e),
+    }
+}
+
+fn create(args: &Vec<&str>) {
+    if args.len() < 3 {println!("Usage: fs create <name>"); return;}
+    let name = String::from(args[2]);
+    let record = (FILETABLE_ADDR..(FILETABLE_ADDR + FILETABLE_SIZE as u32)).find(|record| {
+        let mut existing = String::new();
+        FileTable::filename(*record, &mut existing);
+        existing.is_empty()
+    });
+    let record = match record {
+        Some(record) => record,
+        None => {println!("File Table Is Full."); return;}
+    };
+    match FileTable::create(record, &String::new(), 0, 0, &name) {
+        Ok(head) => println!("Created '{}' At Record 0x{:06x}, Head Block 0x{:06x}", name, record, head),
+        Err(e) => println!("Failed To Create '{}': {}", name, e),
+    }
+}
+
 fn list_all(_args: &Vec<&str>) {
     let records: Vec<(String, RecordIndex)> = Vec::new();
     for (name, index) in records {
@@ -0,0 +1,34 @@
+use alloc::vec::Vec;
+
+use crate::{println, sys::{storage::fs::is_mounted, vfs::{filesystem::{self, superblock::FORMAT_VERSION}, fsck}}};
+
+pub fn main(args: &Vec<&str>) -> usize {
+    let repair = args.iter().skip(1).any(|arg| *arg == "-r" || *arg == "--repair");
+    if !is_mounted() {
+        println!("No Device Is Mounted, Try 'fs mount ata <bus> <drive>'.");
+        return 1;
+    }
+
+    let version = filesystem::format_version();
+    if version != FORMAT_VERSION {
+        if !repair {
+            println!("Filesystem Is Format Version {}, Run 'fsck -r' To Migrate It To Version {}.", version, FORMAT_VERSION);
+            return 2;
+        }
+        match filesystem::migrate() {
+            Ok(count) => println!("Migrated {} Inodes To Format Version {}.", count, FORMAT_VERSION),
+            Err(e) => { println!("{}", e); return 2; },
+        }
+    }
+
+    println!("Checking Filesystem{}...", if repair {" (Repairing)"} else {""});
+    let report = fsck::check(repair);
+
+    for problem in &report.problems {
+        println!("{}", problem);
+    }
+    println!("{} Inodes, {} Blocks Checked, {} Problems Found, {} Repaired.",
+        report.inodes, report.blocks, report.problems.len(), report.repaired);
+
+    if report.is_clean() || (repair && report.repaired > 0) { 0 } else { 2 }
+}
@@ -0,0 +1,18 @@
+//! `host <name>` - Looks A Name Up Through The DNS Resolver
+
+use alloc::vec::Vec;
+
+use crate::{println, sys::net::dns};
+
+pub fn main(args: &Vec<&str>) -> usize {
+    if args.len() < 2 { println!("Usage: host <name>"); return 1; }
+    match dns::lookup(args[1]) {
+        Ok(addrs) => {
+            for addr in addrs {
+                println!("{} Has Address {}", args[1], addr);
+            }
+            0
+        },
+        Err(e) => { println!("host: {}: {}", args[1], e); 2 },
+    }
+}
@@ -5,3 +5,10 @@ pub mod fs;
 pub mod pci;
 pub mod net;
 pub mod syscall;
+pub mod fsck;
+pub mod df;
+pub mod quota;
+pub mod nc;
+pub mod ping;
+pub mod host;
+pub mod route;
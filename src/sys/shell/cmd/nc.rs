@@ -0,0 +1,78 @@
+//! `nc` - Talks To A TCP Or UDP Peer Through The Kernel Socket Layer
+//!
+//! Usage:
+//!     nc <ip> <port> [text]       Connects Over TCP, Sends The Text & Prints The Reply
+//!     nc -l <port>                Waits For One TCP Connection & Prints What It Sends
+//!     nc -u <ip> <port> <text>    Sends The Text As One UDP Datagram
+
+use alloc::{string::String, vec::Vec};
+use smoltcp::wire::Ipv4Address;
+
+use crate::{print, println, sys::net::socket::{self, Kind, SocketId}};
+
+const USAGE: &str = "Usage: nc <ip> <port> [text] | nc -l <port> | nc -u <ip> <port> <text>";
+
+pub fn main(args: &Vec<&str>) -> usize {
+    let res = match args.get(1).copied() {
+        Some("-l") if args.len() >= 3 => listen(args[2]),
+        Some("-u") if args.len() >= 5 => udp(args[2], args[3], &args[4..].join(" ")),
+        Some(_) if args.len() >= 3 && !args[1].starts_with('-') => tcp(args[1], args[2], &args[3..].join(" ")),
+        _ => { println!("{}", USAGE); return 1; },
+    };
+    match res {
+        Ok(()) => 0,
+        Err(e) => { println!("nc: {}", e); 2 },
+    }
+}
+
+fn endpoint(ip: &str, port: &str) -> Result<(Ipv4Address, u16), &'static str> {
+    let ip = ip.parse::<Ipv4Address>().map_err(|_| "Expected An IPv4 Address")?;
+    let port = port.parse::<u16>().map_err(|_| "Expected A Port")?;
+    Ok((ip, port))
+}
+
+/// Prints Whatever Arrives Until The Peer Closes Or Goes Quiet
+fn print_replies(id: SocketId) {
+    let mut buf = [0; 512];
+    while let Ok(len) = socket::recv(id, &mut buf) {
+        if len == 0 { break; }
+        print!("{}", String::from_utf8_lossy(&buf[..len]));
+    }
+    println!();
+}
+
+fn tcp(ip: &str, port: &str, text: &str) -> Result<(), &'static str> {
+    let (ip, port) = endpoint(ip, port)?;
+    let id = socket::create(Kind::Tcp)?;
+    let res = socket::connect(id, ip.into(), port).and_then(|_| {
+        if !text.is_empty() {
+            socket::send(id, text.as_bytes())?;
+            socket::send(id, b"\n")?;
+        }
+        print_replies(id);
+        Ok(())
+    });
+    socket::close(id)?;
+    res
+}
+
+fn listen(port: &str) -> Result<(), &'static str> {
+    let port = port.parse::<u16>().map_err(|_| "Expected A Port")?;
+    let id = socket::create(Kind::Tcp)?;
+    let res = socket::bind(id, port).and_then(|_| socket::listen(id)).and_then(|_| {
+        println!("Listening On Port {}", port);
+        let peer = socket::accept(id)?;
+        print_replies(peer);
+        socket::close(peer)
+    });
+    socket::close(id)?;
+    res
+}
+
+fn udp(ip: &str, port: &str, text: &str) -> Result<(), &'static str> {
+    let (ip, port) = endpoint(ip, port)?;
+    let id = socket::create(Kind::Udp)?;
+    let res = socket::send_to(id, text.as_bytes(), ip.into(), port);
+    socket::close(id)?;
+    res
+}
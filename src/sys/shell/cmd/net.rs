@@ -1,13 +1,112 @@
-use alloc::vec::Vec;
+use alloc::{format, string::{String, ToString}, vec::Vec};
 
-use crate::{println, sys::net};
+use crate::{KResult, config, println, sys::net::{self, dhcp, route, socket}};
 
-pub fn main(_args: &Vec<&str>) -> usize {
-    let mac = net::mac();
-    if let Some(mac) = mac {
-        println!("MAC: {}", mac.as_hex_str())
-    } else {
-        println!("MAC ??:??:??:??:??:??")
+pub fn main(args: &Vec<&str>) -> usize {
+    match args.get(1) {
+        Some(&"ls") => return ls(),
+        Some(&"sockets") => return sockets(),
+        Some(&"dhcp") => return dhcp(args.get(2).copied(), args.get(3).copied()),
+        Some(&"set") => return set(&args[2..]),
+        _ => {},
+    }
+    for index in 0..net::count() {
+        println!("{}:", net::name(index).unwrap_or_default());
+        if let Some(mac) = net::mac(index) {
+            println!("  MAC: {}", mac.as_hex_str())
+        }
+        println!("  IP: {}", addresses(index).join(", "));
+    }
+    match route::list().into_iter().find(|route| route.cidr.prefix_len() == 0) {
+        Some(default) => println!("Gateway: {}", route::describe(&default)),
+        None => println!("Gateway: None"),
+    }
+    let config = net::CONFIG.lock().clone();
+    let dns: Vec<String> = config.dns_servers.iter().map(|server| server.to_string()).collect();
+    println!("DNS: {}{}", if dns.is_empty() { String::from("None") } else { dns.join(", ") }, if config.static_dns { " (Static)" } else { "" });
+    let (polls, wakeups) = net::service::stats();
+    println!("Service: {} Polls, {} Interrupts", polls, wakeups);
+    0
+}
+
+/// The Interface Called `name`, Or The First One
+fn iface(name: Option<&str>) -> KResult<usize> {
+    match name {
+        Some(name) => net::index(name).ok_or("No Such Interface"),
+        None if net::count() > 0 => Ok(0),
+        None => Err("No Network Interface"),
+    }
+}
+
+fn addresses(index: usize) -> Vec<String> {
+    net::with_iface(index, |iface| iface.iface.ip_addrs().iter().map(|addr| addr.to_string()).collect()).unwrap_or_default()
+}
+
+fn ls() -> usize {
+    for (index, nic) in net::nic_names().into_iter().enumerate() {
+        let mac = net::mac(index).map(|mac| mac.as_hex_str()).unwrap_or_default();
+        let dhcp = match dhcp::status(index) {
+            Some((state, _)) => format!(" DHCP {:?}", state),
+            None => String::new(),
+        };
+        println!("{} {} {} {}{}", net::name(index).unwrap_or_default(), nic, mac, addresses(index).join(", "), dhcp);
+    }
+    0
+}
+
+fn dhcp(action: Option<&str>, name: Option<&str>) -> usize {
+    let index = match iface(name) {
+        Ok(index) => index,
+        Err(e) => { println!("{}", e); return 1; },
+    };
+    let res = match action {
+        Some("renew") => dhcp::renew(index),
+        Some("release") => dhcp::release(index),
+        Some("status") | None => {
+            match dhcp::status(index) {
+                Some((state, lease)) => {
+                    println!("State: {:?}", state);
+                    if let Some(lease) = lease {
+                        let now = net::service::now();
+                        println!("Address: {} From {}", lease.address, lease.server);
+                        if let Some(router) = lease.router { println!("Router: {}", router); }
+                        println!("Renew In {}s, Rebind In {}s, Expires In {}s", (lease.t1().max(now) - now).secs(), (lease.t2().max(now) - now).secs(), (lease.expires().max(now) - now).secs());
+                    }
+                },
+                None => println!("DHCP Isn't Running"),
+            }
+            Ok(())
+        },
+        Some(_) => Err("Usage: net dhcp [renew|release|status] [iface]"),
+    };
+    match res {
+        Ok(()) => 0,
+        Err(e) => { println!("{}", e); 1 },
+    }
+}
+
+fn set(args: &[&str]) -> usize {
+    let res = match args {
+        ["ip", address, name @ ..] if name.len() <= 1 => {
+            iface(name.first().copied()).and_then(|index| config::parse_cidr(address).and_then(|address| net::set_address(index, address)))
+        },
+        ["gw", gateway, name @ ..] if name.len() <= 1 => {
+            iface(name.first().copied()).and_then(|index| gateway.parse().map_err(|_| "Invalid IPv4 Address").and_then(|gateway| net::set_gateway(index, gateway)))
+        },
+        ["dns", servers @ ..] if !servers.is_empty() => {
+            servers.iter().map(|server| server.parse().map_err(|_| "Invalid IPv4 Address")).collect::<Result<_, _>>().map(net::set_dns)
+        },
+        _ => Err("Usage: net set ip <addr[/prefix]> [iface] | gw <addr> [iface] | dns <addr> [addr...]"),
+    };
+    match res {
+        Ok(()) => 0,
+        Err(e) => { println!("{}", e); 1 },
+    }
+}
+
+fn sockets() -> usize {
+    for (id, kind, port, state) in socket::list() {
+        println!("{} {:?} Port {} - {}", id, kind, port, state);
     }
     0
-}
\ No newline at end of file
+}
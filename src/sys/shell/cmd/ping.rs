@@ -0,0 +1,56 @@
+//! `ping <ip|host> [-c count]` - Sends ICMP Echo Requests Once A Second & Summarises The Replies
+
+use alloc::vec::Vec;
+use crate::{println, sys::{net::{self, ping::{PAYLOAD_LEN, Ping}}, timer}};
+
+const DEFAULT_COUNT: u16 = 4;
+/// How Long To Wait For Each Reply, Also The Gap Between Requests
+const INTERVAL_MS: f64 = 1000.0;
+
+pub fn main(args: &Vec<&str>) -> usize {
+    if args.len() < 2 { println!("Usage: ping <ip|host> [-c count]"); return 1; }
+    let count = match args.iter().position(|arg| *arg == "-c") {
+        Some(i) => match args.get(i + 1).and_then(|count| count.parse::<u16>().ok()) {
+            Some(count) if count > 0 => count,
+            _ => { println!("Usage: ping <ip|host> [-c count]"); return 1; },
+        },
+        None => DEFAULT_COUNT,
+    };
+    let target = match net::resolve(args[1]) {
+        Ok(target) => target,
+        Err(e) => { println!("ping: {}: {}", args[1], e); return 2; },
+    };
+
+    let mut ping = match Ping::new(target) {
+        Ok(ping) => ping,
+        Err(e) => { println!("ping: {}", e); return 2; },
+    };
+    println!("PING {} {} Data Bytes", target, PAYLOAD_LEN);
+
+    let mut rtts = Vec::new();
+    for seq in 0..count {
+        let sent = timer::uptime_seconds();
+        if let Err(e) = ping.send(seq) { println!("ping: {}", e); return 2; }
+        match ping.reply(seq, INTERVAL_MS) {
+            Some((bytes, rtt)) => {
+                println!("{} Bytes From {}: icmp_seq={} time={:.3} ms", bytes, target, seq, rtt);
+                rtts.push(rtt);
+            },
+            None => println!("Request Timeout For icmp_seq {}", seq),
+        }
+        // Keep To One Request A Second However Quickly The Reply Came
+        let elapsed = timer::uptime_seconds() - sent;
+        if seq + 1 < count && elapsed < INTERVAL_MS / 1000.0 { timer::pause(INTERVAL_MS / 1000.0 - elapsed); }
+    }
+
+    println!("--- {} Ping Statistics ---", target);
+    let loss = 100.0 * (count as usize - rtts.len()) as f64 / count as f64;
+    println!("{} Packets Transmitted, {} Packets Received, {:.1}% Packet Loss", count, rtts.len(), loss);
+    if !rtts.is_empty() {
+        let min = rtts.iter().copied().fold(f64::MAX, f64::min);
+        let max = rtts.iter().copied().fold(0.0, f64::max);
+        let avg = rtts.iter().sum::<f64>() / rtts.len() as f64;
+        println!("Round-Trip min/avg/max = {:.3}/{:.3}/{:.3} ms", min, avg, max);
+    }
+    if rtts.is_empty() { 1 } else { 0 }
+}
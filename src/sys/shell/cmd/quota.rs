@@ -0,0 +1,84 @@
+use alloc::{format, string::String, vec::Vec};
+
+use crate::{println, sys::{process::{Credentials, Uid}, storage::fs::is_mounted, vfs::quota::{self, Quota}}};
+
+const USAGE: &str = "Usage: quota [uid] | quota set <uid> <block limit> <inode limit> | quota off <uid>";
+
+pub fn main(args: &Vec<&str>) -> usize {
+    if !is_mounted() {
+        println!("No Device Is Mounted, Try 'fs mount ata <bus> <drive>'.");
+        return 1;
+    }
+
+    match args.get(1).copied() {
+        None => list(),
+        Some("set") => set(args),
+        Some("off") => off(args),
+        Some(uid) => match uid.parse::<Uid>() {
+            Ok(uid) => show(uid),
+            Err(_) => { println!("{}", USAGE); 1 },
+        },
+    }
+}
+
+/// Limits Of 0 Are Unlimited
+fn limit(limit: u32) -> String {
+    if limit == 0 { String::from("-") } else { format!("{}", limit) }
+}
+
+fn print_quota(quota: &Quota) {
+    println!("{:>5} | {:>7} / {:>7} | {:>6} / {:>6}",
+        quota.uid, quota.blocks_used, limit(quota.block_limit), quota.inodes_used, limit(quota.inode_limit));
+}
+
+fn print_header() {
+    println!("{:>5} | {:>17} | {:>15}", "Uid", "Blocks Used/Limit", "Inodes Used/Limit");
+}
+
+fn list() -> usize {
+    let quotas = quota::quotas();
+    if quotas.is_empty() {
+        println!("No Quotas Set.");
+        return 0;
+    }
+    print_header();
+    for quota in &quotas {
+        print_quota(quota);
+    }
+    0
+}
+
+fn show(uid: Uid) -> usize {
+    match quota::get(uid) {
+        Some(quota) => { print_header(); print_quota(&quota); 0 },
+        None => { println!("Uid {} Has No Quota.", uid); 0 },
+    }
+}
+
+fn set(args: &Vec<&str>) -> usize {
+    let parsed = (
+        args.get(2).and_then(|uid| uid.parse::<Uid>().ok()),
+        args.get(3).and_then(|blocks| blocks.parse::<u32>().ok()),
+        args.get(4).and_then(|inodes| inodes.parse::<u32>().ok()),
+    );
+    let (uid, blocks, inodes) = match parsed {
+        (Some(uid), Some(blocks), Some(inodes)) => (uid, blocks, inodes),
+        _ => { println!("{}", USAGE); return 1; },
+    };
+
+    match quota::set(&Credentials::ROOT, uid, blocks, inodes) {
+        Ok(quota) => { print_header(); print_quota(&quota); 0 },
+        Err(e) => { println!("{}", e); 2 },
+    }
+}
+
+fn off(args: &Vec<&str>) -> usize {
+    let uid = match args.get(2).and_then(|uid| uid.parse::<Uid>().ok()) {
+        Some(uid) => uid,
+        None => { println!("{}", USAGE); return 1; },
+    };
+    match quota::remove(&Credentials::ROOT, uid) {
+        Ok(()) => 0,
+        Err(e) => { println!("{}", e); 2 },
+    }
+}
@@ -0,0 +1,77 @@
+//! `route` - Shows & Changes The Kernel Routing Table
+//!
+//! `route add <cidr> [via <gateway>] dev <iface> [metric <n>]` Adds A Static Route, Which Only
+//! Goes Without `via` Inside The Interface's Own Network. `route del <cidr> [dev <iface>]`
+//! Removes One, Whatever Put It There. `default` Stands For `0.0.0.0/0`.
+
+use alloc::vec::Vec;
+
+use smoltcp::wire::{Ipv4Address, Ipv4Cidr};
+
+use crate::{KResult, config, println, sys::net::{self, route::{self, Origin, Route}}};
+
+pub fn main(args: &Vec<&str>) -> usize {
+    let res = match args.get(1) {
+        None | Some(&"ls") => { list(); Ok(()) },
+        Some(&"add") => add(&args[2..]),
+        Some(&"del") => del(&args[2..]),
+        Some(_) => Err("Usage: route [ls] | add <cidr> [via <gw>] dev <iface> [metric <n>] | del <cidr> [dev <iface>]"),
+    };
+    match res {
+        Ok(()) => 0,
+        Err(e) => { println!("route: {}", e); 1 },
+    }
+}
+
+fn list() {
+    let routes = route::list();
+    if routes.is_empty() { println!("No Routes"); }
+    for route in routes {
+        println!("{}", route::describe(&route));
+    }
+}
+
+fn iface(name: &str) -> KResult<usize> {
+    net::index(name).ok_or("No Such Interface")
+}
+
+fn destination(s: &str) -> KResult<Ipv4Cidr> {
+    match s {
+        "default" => Ok(Ipv4Cidr::new(Ipv4Address::UNSPECIFIED, 0)),
+        s => config::parse_cidr(s).map(|cidr| cidr.network()),
+    }
+}
+
+fn add(args: &[&str]) -> KResult<()> {
+    let (cidr, mut rest) = args.split_first().ok_or("Expected A Destination")?;
+    let mut route = Route { cidr: destination(cidr)?, gateway: None, iface: usize::MAX, metric: route::DEFAULT_METRIC, origin: Origin::Static };
+    while let [key, value, tail @ ..] = rest {
+        match *key {
+            "via" => route.gateway = Some(value.parse().map_err(|_| "Invalid IPv4 Address")?),
+            "dev" => route.iface = iface(value)?,
+            "metric" => route.metric = value.parse().map_err(|_| "Invalid Metric")?,
+            _ => return Err("Expected via, dev Or metric"),
+        }
+        rest = tail;
+    }
+    if !rest.is_empty() { return Err("Expected A Value"); }
+    if route.iface == usize::MAX { return Err("Expected dev <iface>"); }
+    if route.gateway.is_none() && !route::is_on_link(route.iface, route.cidr) {
+        return Err("Destination Isn't On The Interface's Network, Give A Gateway With via");
+    }
+    route::add(route);
+    net::apply_routes(route.iface)
+}
+
+fn del(args: &[&str]) -> KResult<()> {
+    let (cidr, iface) = match args {
+        [cidr] => (cidr, None),
+        [cidr, "dev", name] => (cidr, Some(iface(name)?)),
+        _ => return Err("Usage: route del <cidr> [dev <iface>]"),
+    };
+    route::remove(destination(cidr)?, iface)?;
+    match iface {
+        Some(index) => net::apply_routes(index),
+        None => (0..net::count()).try_for_each(net::apply_routes),
+    }
+}
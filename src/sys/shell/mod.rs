@@ -13,6 +13,7 @@ pub type ShellProgram = fn(&Vec<&str>) -> usize;
 
 
 pub fn start() {
+    sys::process::spawn("shell");
     sys::keyboard::consume_char();
     println!("Cobalt Shell");
 
@@ -53,7 +54,18 @@ pub fn run(command: &str) -> usize {
         "pci" => {cmd::pci::main(&parts)},
         "net" => {cmd::net::main(&parts)},
         "syscall" => {cmd::syscall::main(&parts)},
+        "fsck" => {cmd::fsck::main(&parts)},
+        "df" => {cmd::df::main(&parts)},
+        "quota" => {cmd::quota::main(&parts)},
+        "nc" => {cmd::nc::main(&parts)},
+        "ping" => {cmd::ping::main(&parts)},
+        "host" => {cmd::host::main(&parts)},
+        "route" => {cmd::route::main(&parts)},
         "ls" | "l" => {ls(&parts)}
+        "cat" => {cat(&parts)}
+        "write" => {write(&parts)}
+        "mkdir" => {mkdir(&parts)}
+        "rm" => {rm(&parts)}
         _ => {
             println!("Unknown Command '{}'", program_name);
             usize::MAX
@@ -142,9 +154,19 @@ fn help(_: &Vec<&str>) -> usize {
     run!("Echo 2. pause <seconds> - Halt Execution for <seconds>.");
     run!("Echo 3. uptime - prints the system uptime in seconds");
     run!("Echo 4. shutdown - shuts the system down, only works on Qemu, requires input.");
-    run!("Echo 5. dsk - Various Disk Utilities");
+    run!("Echo 5. dsk - Various Disk Utilities, dsk attach <path> Exposes A File As A Loop Device, dsk mirror Manages RAID-1 Mirrors");
     run!("Echo 6. echo - Echos back the arguments to the screen");
     run!("Echo 7. install - Copies The contents of Drive 0:0 To Drive 0:1, doesn't ask for authentication.");
+    run!("Echo 8. fsck [-r] - Checks The Mounted Inode Filesystem, -r Repairs (And Migrates) It.");
+    run!("Echo 9. cat <path> - Prints A File From The Mounted ustar Or ext2 Filesystem, Or Any Absolute Path.");
+    run!("Echo 10. ls [path], write <path> <text>, mkdir <path>, rm <path> - Work With Mounted Paths Like /tmp.");
+    run!("Echo 11. df - Free Blocks & Inodes On The Mounted Inode Filesystem.");
+    run!("Echo 12. quota [uid], quota set <uid> <blocks> <inodes>, quota off <uid> - Per-User Limits, 0 Is Unlimited.");
+    run!("Echo 13. nc <ip> <port> [text], nc -l <port>, nc -u <ip> <port> <text> - TCP & UDP Through Kernel Sockets, net sockets Lists Them.");
+    run!("Echo 14. ping <ip|host> [-c count] - Sends ICMP Echo Requests, 4 By Default.");
+    run!("Echo 15. host <name> - Resolves A Name Through The DHCP Provided DNS Servers.");
+    run!("Echo 16. net [ls|sockets], net dhcp [renew|release|status] [iface], net set ip|gw <addr> [iface], net set dns <addr> - Interfaces, DHCP Leases & Static Overrides.");
+    run!("Echo 17. route [ls], route add <cidr> [via <gw>] dev <iface> [metric <n>], route del <cidr> [dev <iface>] - The Kernel Routing Table.");
     return 0;
 }
 
@@ -173,7 +195,23 @@ fn test_exit(_args: &Vec<&str>) -> usize {
     return 0;
 }
 
-fn ls(_args: &Vec<&str>) -> usize {
+fn ls(args: &Vec<&str>) -> usize {
+    if args.len() > 1 {
+        return match sys::vfs::mount::read_dir(args[1]) {
+            Ok(entries) => {
+                for entry in entries {
+                    if entry.stat.is_dir() {
+                        println!("{}/", entry.name);
+                    } else {
+                        println!("{} | {:05}", entry.name, entry.stat.size);
+                    }
+                }
+                0
+            },
+            Err(e) => {println!("{}", e); 1},
+        };
+    }
+
     let mut files: Vec<Metadata> = Vec::new();
     sys::vfs::list(&mut files);
     let mut max_width = 0;
@@ -190,6 +228,46 @@ fn ls(_args: &Vec<&str>) -> usize {
 }
 
 
+fn cat(args: &Vec<&str>) -> usize {
+    if args.len() < 2 {println!("Usage: cat <path>"); return 1;}
+    let mut buf = Vec::new();
+    match sys::vfs::load(args[1], &mut buf) {
+        Ok(()) => {
+            println!("{}", String::from_utf8_lossy(&buf));
+            0
+        },
+        Err(e) => {
+            println!("{}", e);
+            1
+        }
+    }
+}
+
+fn write(args: &Vec<&str>) -> usize {
+    if args.len() < 2 {println!("Usage: write <path> <text>"); return 1;}
+    let mut text = args[2..].join(" ");
+    text.push('\n');
+    match sys::vfs::mount::write(args[1], text.as_bytes()) {
+        Ok(()) => 0,
+        Err(e) => {println!("{}", e); 1},
+    }
+}
+
+fn mkdir(args: &Vec<&str>) -> usize {
+    if args.len() < 2 {println!("Usage: mkdir <path>"); return 1;}
+    match sys::vfs::mount::create_dir(args[1]) {
+        Ok(()) => 0,
+        Err(e) => {println!("{}", e); 1},
+    }
+}
+
+fn rm(args: &Vec<&str>) -> usize {
+    if args.len() < 2 {println!("Usage: rm <path>"); return 1;}
+    match sys::vfs::mount::remove(args[1]) {
+        Ok(()) => 0,
+        Err(e) => {println!("{}", e); 1},
+    }
+}
 
 #[macro_export]
 macro_rules! run {
@@ -2,9 +2,9 @@ use core::ops::Range;
 
 use bit_field::BitField;
 
-use crate::{clear, log, print_at, set_style, sys::{timer::{self, uptime_millis}, vga::Color}};
+use crate::{KResult, clear, log, print_at, set_style, sys::{timer::{self, uptime_millis}, vga::Color}};
 
-use super::{BITMAP_ADDR, BITMAP_SIZE, BlockAddr, DATA_ADDR, DATA_SIZE, block::Block};
+use super::{BlockAddr, block::Block, journal::Transaction, layout::addressing, superblock::SuperBlock};
 
 pub struct Bitmap {
     
@@ -12,15 +12,13 @@ pub struct Bitmap {
 
 impl Bitmap {
     pub fn block_addr(addr: BlockAddr) -> BlockAddr {
-        let size = BITMAP_SIZE as BlockAddr ;
-        let i = addr - DATA_ADDR;
-        return BITMAP_ADDR + (i / size );
+        addressing::bitmap(SuperBlock::data_region().0, addr).0
     }
 
-    /// What Is The Buffer Offset? offset = (i - DATA_ADDR) % BITMAP_SIZE
+    /// What Is The Buffer Offset? offset = (i - DATA_ADDR) % BITMAP_SIZE, Where DATA_ADDR
+    /// Depends On The Mounted Device's Version
     pub fn buffer_offset(addr: BlockAddr) -> usize {
-        let i = (addr - DATA_ADDR) as usize;
-        return i % BITMAP_SIZE;
+        addressing::bitmap(SuperBlock::data_region().0, addr).1
     }
 
     /// Get Block State from a given &[u8]
@@ -41,23 +39,42 @@ impl Bitmap {
     }
 
     pub fn alloc(addr: BlockAddr) {
-        let mut block = Block::read(Self::block_addr(addr)).expect("Failed To Load Bitmap");
-        Self::set_block_state(block.data_mut(), addr, true);
-        log!("Allocating Block 0x{:06}", addr);
-        block.write();
+        let mut tx = Transaction::new();
+        Self::alloc_in(&mut tx, addr).expect("Failed To Load Bitmap");
+        tx.commit().expect("Failed To Commit Bitmap");
     }
 
     pub fn free(addr: BlockAddr) {
-        let mut block = Block::read(Self::block_addr(addr)).expect("Failed To Load Bitmap");
-        Self::set_block_state(block.data_mut(), addr, false);
+        let mut tx = Transaction::new();
+        Self::free_in(&mut tx, addr).expect("Failed To Load Bitmap");
+        tx.commit().expect("Failed To Commit Bitmap");
+    }
+
+    /// Stages An Allocation In `tx` Instead Of Writing It Straight Away
+    pub fn alloc_in(tx: &mut Transaction, addr: BlockAddr) -> KResult<()> {
+        Self::set_in(tx, addr, true)?;
+        log!("Allocating Block 0x{:06}", addr);
+        Ok(())
+    }
+
+    /// Stages A Free In `tx` Instead Of Writing It Straight Away
+    pub fn free_in(tx: &mut Transaction, addr: BlockAddr) -> KResult<()> {
+        Self::set_in(tx, addr, false)?;
         log!("freeing Block 0x{:06}", addr);
-        block.write();
+        Ok(())
+    }
+
+    fn set_in(tx: &mut Transaction, addr: BlockAddr, state: bool) -> KResult<()> {
+        let mut block = tx.read(Self::block_addr(addr))?;
+        Self::set_block_state(block.data_mut(), addr, state);
+        tx.write(&block)
     }
 
     /// Attempts to Find The Next Free Block. Returns [None] if Failed.
     pub fn next_free() -> Option<BlockAddr> {
         let tp1 = timer::uptime_millis();
-        for addr in DATA_ADDR..(DATA_ADDR + DATA_SIZE as u32) {
+        let (data_addr, data_size) = SuperBlock::data_region();
+        for addr in data_addr..(data_addr + data_size as u32) {
             
             if !Bitmap::is_alloc(addr) {
                 let tp2 = uptime_millis();
@@ -70,22 +87,24 @@ impl Bitmap {
 
     pub fn alloc_next_free<'a>() -> Option<Block<'a>> {
         if let Some(addr) = Self::next_free() {
-            return Block::read(addr);
+            return Block::read(addr).ok();
         } else {
             return None;
         };
     }
 
     pub fn bulk_alloc(range: Range<BlockAddr>) {
+        let mut tx = Transaction::new();
         for addr in range {
-            Self::alloc(addr);
+            Self::alloc_in(&mut tx, addr).expect("Failed To Load Bitmap");
         }
+        tx.commit().expect("Failed To Commit Bitmap");
     }
 
     pub fn visualize(start: u32) {
         clear!();
         let mut _offset = 0;
-        let start = start + DATA_ADDR;
+        let start = start + SuperBlock::data_region().0;
         let width = 80 as usize;
         let height = 20 as usize;
         let _length = width * height;
@@ -109,7 +128,7 @@ impl Bitmap {
     pub fn visualize_bin(start: u32) {
         clear!();
         let mut _offset = 0;
-        let start = start + DATA_ADDR;
+        let start = start + SuperBlock::data_region().0;
         let width = 80 as usize;
         let height = 20 as usize;
         let _length = width * height;
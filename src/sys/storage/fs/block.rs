@@ -4,7 +4,7 @@ use bytes::{Bytes, BytesMut};
 use alloc::string::String;
 use core::str;
 
-use crate::{debug, log, warn};
+use crate::{KResult, debug, log};
 
 use super::{BLOCK_SIZE, BlockAddr, dev_handle::{BlockDeviceIO}, device, is_mounted};
 
@@ -17,30 +17,41 @@ pub struct Block<'a> {
 }
 
 impl<'a> Block<'a> {
-    pub fn read(addr: BlockAddr) -> Option<Self> {
+    /// Fails If Nothing Is Mounted Or The Device Can't Read The Block
+    pub fn read(addr: BlockAddr) -> KResult<Self> {
         debug!("Reading Block 0x{:06x}", addr);
-        if !is_mounted() {warn!("No Device Is Mounted."); return None;}
+        if !is_mounted() { return Err("No Device Is Mounted"); }
         let mut device = device().lock();
-        let device = device.as_mut();
+        let device = device.as_mut().ok_or("No Device Is Mounted")?;
         
         let mut data: [u8; 512] = [0; 512];
-        device.unwrap().read(addr, &mut data);
+        device.read(addr, &mut data)?;
         let block = Self {
             addr,
             data,
             next: None
         };
-        Some(block)
+        Ok(block)
     }
 
-    pub fn write(&self) {
+    /// A Block Whose Contents Are Already In Memory, Nothing Is Read From The Device
+    pub fn from_data(addr: BlockAddr, data: [u8; BLOCK_SIZE]) -> Self {
+        Self {
+            addr,
+            data,
+            next: None
+        }
+    }
+
+    /// Fails If Nothing Is Mounted Or The Device Can't Write The Block
+    pub fn write(&self) -> KResult<()> {
         debug!("Writing Block 0x{:06x}", self.addr);
-        if !is_mounted() {warn!("No Device Is Mounted."); }
         let mut device_lock = device().lock();
-        let device = device_lock.as_mut().expect("No Device Mounted");
-        device.write(self.addr, &self.data);
+        let device = device_lock.as_mut().ok_or("No Device Is Mounted")?;
+        device.write(self.addr, &self.data)?;
         drop(device_lock);
-        assert_eq!(Block::read(self.addr).unwrap(), *self);
+        assert_eq!(Block::read(self.addr)?, *self);
+        Ok(())
     }
 
     pub fn data(&self) -> &[u8] {
@@ -55,9 +66,9 @@ impl<'a> Block<'a> {
         self.addr
     }
 
-    pub fn erase(&mut self) {
+    pub fn erase(&mut self) -> KResult<()> {
         self.data = [0; BLOCK_SIZE];
-        self.write();
+        self.write()
     }
 
     pub fn slice_range(&self, range: Range<usize>) -> &[u8] {
@@ -145,21 +156,21 @@ impl<'a> Block<'a> {
         buffer
     }
 
-    pub fn set_bytes_mut(&mut self, buffer: BytesMut) {
+    pub fn set_bytes_mut(&mut self, buffer: BytesMut) -> KResult<()> {
         for index in 0..buffer.len() {
             if index < BLOCK_SIZE {
             self[index] = buffer[index];
             log!("buffer[{}] = {:02x}", index, buffer[index]);
             };
         }
-        self.write();
+        self.write()
     }
 
-    pub fn set_bytes(&mut self, buffer: Bytes) {
+    pub fn set_bytes(&mut self, buffer: Bytes) -> KResult<()> {
         for index in  0..buffer.len() {
             self[index] = buffer[index];
         }
-        self.write();
+        self.write()
     }
 
     pub fn write_u8(&mut self, offset: usize, value: u8) -> usize {
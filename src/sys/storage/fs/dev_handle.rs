@@ -1,6 +1,6 @@
 use alloc::{vec, vec::Vec};
 
-use crate::sys::{ata, storage::fs::DISK_SIZE};
+use crate::{KResult, sys::{ahci, ata, loopdev, nvme, raid, storage::fs::DISK_SIZE, virtio}};
 
 use super::{BLOCK_SIZE, BlockAddr};
 
@@ -8,9 +8,11 @@ use block_device::BlockDevice;
 
 
 
+/// Reads & Writes Fail With The Driver's Error Rather Than Panicking, So A Bad Sector Reaches
+/// Whoever Asked For It Instead Of Coming Back As Zeros Or Passing For Written
 pub trait BlockDeviceIO {
-    fn read(&self, addr: BlockAddr, buf: &mut [u8]);
-    fn write(&mut self, addr: BlockAddr, buf: &[u8]);
+    fn read(&self, addr: BlockAddr, buf: &mut [u8]) -> KResult<()>;
+    fn write(&mut self, addr: BlockAddr, buf: &[u8]) -> KResult<()>;
     fn sector_count(&self) -> u32;
 }
 
@@ -18,6 +20,11 @@ pub trait BlockDeviceIO {
 pub enum DeviceHandle {
     MemBlockDevice(MemDevice),
     AtaBlockDevice(AtaDevice),
+    AhciBlockDevice(AhciDevice),
+    VirtioBlockDevice(VirtioDevice),
+    NvmeBlockDevice(NvmeDevice),
+    LoopBlockDevice(LoopDevice),
+    MirrorBlockDevice(MirrorDevice),
     ResBlockDevice(ResDevice)   
 }
 
@@ -31,17 +38,27 @@ impl DeviceHandle {
 }
 
 impl BlockDeviceIO for DeviceHandle {
-    fn read(&self, addr: BlockAddr, buf: &mut [u8]) {
+    fn read(&self, addr: BlockAddr, buf: &mut [u8]) -> KResult<()> {
         match self {
             Self::AtaBlockDevice(dev) => {dev.read(addr, buf)},
+            Self::AhciBlockDevice(dev) => {dev.read(addr, buf)},
+            Self::VirtioBlockDevice(dev) => {dev.read(addr, buf)},
+            Self::NvmeBlockDevice(dev) => {dev.read(addr, buf)},
+            Self::LoopBlockDevice(dev) => {dev.read(addr, buf)},
+            Self::MirrorBlockDevice(dev) => {dev.read(addr, buf)},
             Self::MemBlockDevice(dev) => {dev.read(addr, buf)},
             Self::ResBlockDevice(dev) => {dev.read(addr, buf)},
         }
     }
 
-    fn write(&mut self, addr: BlockAddr, buf: &[u8]) {
+    fn write(&mut self, addr: BlockAddr, buf: &[u8]) -> KResult<()> {
         match self {
             Self::AtaBlockDevice(dev) => {dev.write(addr, buf)},
+            Self::AhciBlockDevice(dev) => {dev.write(addr, buf)},
+            Self::VirtioBlockDevice(dev) => {dev.write(addr, buf)},
+            Self::NvmeBlockDevice(dev) => {dev.write(addr, buf)},
+            Self::LoopBlockDevice(dev) => {dev.write(addr, buf)},
+            Self::MirrorBlockDevice(dev) => {dev.write(addr, buf)},
             Self::MemBlockDevice(dev) => {dev.write(addr, buf)},
             Self::ResBlockDevice(dev) => {dev.write(addr, buf)},
         }
@@ -50,6 +67,11 @@ impl BlockDeviceIO for DeviceHandle {
     fn sector_count(&self) -> u32 {
         match self {
             Self::AtaBlockDevice(dev) => {dev.sector_count()},
+            Self::AhciBlockDevice(dev) => {dev.sector_count()},
+            Self::VirtioBlockDevice(dev) => {dev.sector_count()},
+            Self::NvmeBlockDevice(dev) => {dev.sector_count()},
+            Self::LoopBlockDevice(dev) => {dev.sector_count()},
+            Self::MirrorBlockDevice(dev) => {dev.sector_count()},
             Self::MemBlockDevice(dev) => {dev.sector_count()},
             Self::ResBlockDevice(dev) => {dev.sector_count()},
         }
@@ -61,18 +83,30 @@ impl BlockDeviceIO for DeviceHandle {
 pub struct MemDevice {disk: Vec<[u8; BLOCK_SIZE]>}
 #[derive(Debug, Copy, Clone)]
 pub struct AtaDevice {bus: u8, disk: u8}
+#[derive(Debug, Copy, Clone)]
+pub struct AhciDevice {port: u8}
+#[derive(Debug, Copy, Clone)]
+pub struct VirtioDevice {id: u8}
+#[derive(Debug, Copy, Clone)]
+pub struct NvmeDevice {id: u8}
+#[derive(Debug, Copy, Clone)]
+pub struct LoopDevice {id: u8}
+#[derive(Debug, Copy, Clone)]
+pub struct MirrorDevice {id: u8}
 #[derive(Debug, Clone, Copy)]
 pub struct ResDevice {/* TODO: Design ResourceDevice Implementation. */}
 
 impl BlockDeviceIO for AtaDevice {
-    fn read(&self, addr: BlockAddr, buf: &mut [u8]) {
+    fn read(&self, addr: BlockAddr, buf: &mut [u8]) -> KResult<()> {
         assert!(addr < self.sector_count());
         ata::read(self.bus, self.disk, addr, buf);
+        Ok(())
     }
 
-    fn write(&mut self, addr: BlockAddr, buf: &[u8]) {
+    fn write(&mut self, addr: BlockAddr, buf: &[u8]) -> KResult<()> {
         assert!(addr < self.sector_count());
         ata::write(self.bus, self.disk, addr, buf);
+        Ok(())
     }
 
     fn sector_count(&self) -> u32 {
@@ -81,12 +115,88 @@ impl BlockDeviceIO for AtaDevice {
 
 }
 
+impl BlockDeviceIO for AhciDevice {
+    fn read(&self, addr: BlockAddr, buf: &mut [u8]) -> KResult<()> {
+        assert!(addr < self.sector_count());
+        ahci::read(self.port, addr, buf)
+    }
+
+    fn write(&mut self, addr: BlockAddr, buf: &[u8]) -> KResult<()> {
+        assert!(addr < self.sector_count());
+        ahci::write(self.port, addr, buf)
+    }
+
+    fn sector_count(&self) -> u32 {
+        ahci::sector_count(self.port)
+    }
+}
+
+impl BlockDeviceIO for VirtioDevice {
+    fn read(&self, addr: BlockAddr, buf: &mut [u8]) -> KResult<()> {
+        assert!(addr < self.sector_count());
+        virtio::blk::read(self.id, addr, buf)
+    }
+
+    fn write(&mut self, addr: BlockAddr, buf: &[u8]) -> KResult<()> {
+        assert!(addr < self.sector_count());
+        virtio::blk::write(self.id, addr, buf)
+    }
+
+    fn sector_count(&self) -> u32 {
+        virtio::blk::sector_count(self.id)
+    }
+}
+
+impl BlockDeviceIO for NvmeDevice {
+    fn read(&self, addr: BlockAddr, buf: &mut [u8]) -> KResult<()> {
+        assert!(addr < self.sector_count());
+        nvme::read(self.id, addr, buf)
+    }
+
+    fn write(&mut self, addr: BlockAddr, buf: &[u8]) -> KResult<()> {
+        assert!(addr < self.sector_count());
+        nvme::write(self.id, addr, buf)
+    }
+
+    fn sector_count(&self) -> u32 {
+        nvme::sector_count(self.id)
+    }
+}
+
+impl BlockDeviceIO for LoopDevice {
+    fn read(&self, addr: BlockAddr, buf: &mut [u8]) -> KResult<()> {
+        loopdev::read(self.id, addr, buf)
+    }
+
+    fn write(&mut self, addr: BlockAddr, buf: &[u8]) -> KResult<()> {
+        loopdev::write(self.id, addr, buf)
+    }
+
+    fn sector_count(&self) -> u32 {
+        loopdev::sector_count(self.id)
+    }
+}
+
+impl BlockDeviceIO for MirrorDevice {
+    fn read(&self, addr: BlockAddr, buf: &mut [u8]) -> KResult<()> {
+        raid::read(self.id, addr, buf)
+    }
+
+    fn write(&mut self, addr: BlockAddr, buf: &[u8]) -> KResult<()> {
+        raid::write(self.id, addr, buf)
+    }
+
+    fn sector_count(&self) -> u32 {
+        raid::sector_count(self.id)
+    }
+}
+
 impl BlockDeviceIO for MemDevice {
-    fn read(&self, _addr: BlockAddr, _buf: &mut [u8]) {
+    fn read(&self, _addr: BlockAddr, _buf: &mut [u8]) -> KResult<()> {
         todo!()
     }
 
-    fn write(&mut self, _addr: BlockAddr, _buf: &[u8]) {
+    fn write(&mut self, _addr: BlockAddr, _buf: &[u8]) -> KResult<()> {
         todo!()
     }
 
@@ -97,11 +207,11 @@ impl BlockDeviceIO for MemDevice {
 }
 
 impl BlockDeviceIO for ResDevice {
-    fn read(&self, _addr: BlockAddr, _buf: &mut [u8]) {
+    fn read(&self, _addr: BlockAddr, _buf: &mut [u8]) -> KResult<()> {
         todo!()
     }
 
-    fn write(&mut self, _addr: BlockAddr, _buf: &[u8]) {
+    fn write(&mut self, _addr: BlockAddr, _buf: &[u8]) -> KResult<()> {
         todo!()
     }
 
@@ -128,6 +238,54 @@ impl AtaDevice {
     }
 }
 
+impl AhciDevice {
+    pub fn new(port: u8) -> Self {
+        Self {
+            port,
+        }
+    }
+}
+
+impl VirtioDevice {
+    pub fn new(id: u8) -> Self {
+        Self {
+            id,
+        }
+    }
+}
+
+impl NvmeDevice {
+    pub fn new(id: u8) -> Self {
+        Self {
+            id,
+        }
+    }
+}
+
+impl LoopDevice {
+    pub fn new(id: u8) -> Self {
+        Self {
+            id,
+        }
+    }
+
+    pub fn id(&self) -> u8 {
+        self.id
+    }
+}
+
+impl MirrorDevice {
+    pub fn new(id: u8) -> Self {
+        Self {
+            id,
+        }
+    }
+
+    pub fn id(&self) -> u8 {
+        self.id
+    }
+}
+
 impl BlockDevice for DeviceHandle {
     type Error = &'static str;
 
@@ -136,7 +294,12 @@ impl BlockDevice for DeviceHandle {
         let mut blocks: Vec<[u8; 512]> = vec![[0; 512]; block_count];
         for i in addr..addr+block_count {
             match self {
-                Self::AtaBlockDevice(dev) => BlockDeviceIO::read(dev, i as u32, &mut blocks[i - addr]),
+                Self::AtaBlockDevice(dev) => BlockDeviceIO::read(dev, i as u32, &mut blocks[i - addr])?,
+                Self::AhciBlockDevice(dev) => BlockDeviceIO::read(dev, i as u32, &mut blocks[i - addr])?,
+                Self::VirtioBlockDevice(dev) => BlockDeviceIO::read(dev, i as u32, &mut blocks[i - addr])?,
+                Self::NvmeBlockDevice(dev) => BlockDeviceIO::read(dev, i as u32, &mut blocks[i - addr])?,
+                Self::LoopBlockDevice(dev) => BlockDeviceIO::read(dev, i as u32, &mut blocks[i - addr])?,
+                Self::MirrorBlockDevice(dev) => BlockDeviceIO::read(dev, i as u32, &mut blocks[i - addr])?,
                 _ => unimplemented!()
             }
         }
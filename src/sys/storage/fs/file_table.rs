@@ -1,9 +1,9 @@
 use alloc::{borrow::ToOwned, string::String, vec::Vec};
-use bytes::{BufMut};
+use bytes::{BufMut, BytesMut};
 
-use crate::log;
+use crate::{KResult, log};
 
-use super::{BlockAddr, FILETABLE_ADDR, FILETABLE_SIZE, block::Block};
+use super::{BLOCK_SIZE, BlockAddr, FILETABLE_ADDR, FILETABLE_SIZE, bitmap::Bitmap, block::Block, journal::Transaction};
 pub struct FileTable {}
 
 pub const MAX_DIR: usize = 64;
@@ -41,13 +41,30 @@ impl FileTable {
     }
 
     pub fn create_file(record: RecordIndex, parent: &String, file_type: u8, file_head: u32, flags: u8, name: &String) {
-        let mut block = Block::read(Self::block_addr(record)).unwrap();
+        let mut tx = Transaction::new();
+        Self::create_file_in(&mut tx, record, parent, file_type, file_head, flags, name).expect("Failed To Create File");
+        tx.commit().expect("Failed To Commit File Table");
+    }
 
-        log!("Creating File '{}'", name);
+    /// Allocates The File's Head Block & Writes Its Entry As One Transaction, So A Crash Never
+    /// Leaves An Entry Pointing At A Free Block Or A Block Nothing Points At.
+    pub fn create(record: RecordIndex, parent: &String, file_type: u8, flags: u8, name: &String) -> KResult<BlockAddr> {
+        let head = Bitmap::next_free().ok_or("No Free Data Blocks")?;
+        let mut tx = Transaction::new();
+        Bitmap::alloc_in(&mut tx, head)?;
+        tx.write(&Block::from_data(head, [0; BLOCK_SIZE]))?;
+        Self::create_file_in(&mut tx, record, parent, file_type, head, flags, name)?;
+        tx.commit()?;
+        Ok(head)
+    }
 
-        
+    /// Stages A File Entry In `tx` Instead Of Writing It Straight Away
+    pub fn create_file_in(tx: &mut Transaction, record: RecordIndex, parent: &String, file_type: u8, file_head: u32, flags: u8, name: &String) -> KResult<()> {
+        let mut block = tx.read(Self::block_addr(record))?;
 
-        let mut buffer = block.bytes_mut();
+        log!("Creating File '{}'", name);
+
+        let mut buffer = BytesMut::with_capacity(ENTRY_ALIGN);
         buffer.put_u8(parent.len() as u8);
         buffer.put(parent.as_bytes());
         buffer.put_u8(name.len() as u8);
@@ -59,9 +76,10 @@ impl FileTable {
         log!("Writing File Head: '0x{:06x}'",file_head);
         buffer.put_u32(file_head);
 
-
-        block.set_bytes_mut(buffer);
-        block.write();
+        let offset = Self::byte_offset(record);
+        let len = buffer.len().min(BLOCK_SIZE - offset);
+        block.set_slice_range(offset..offset + len, &buffer[..len]);
+        tx.write(&block)
     }
 
     pub fn locate(name: &String) -> Option<RecordIndex> {
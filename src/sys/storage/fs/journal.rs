@@ -0,0 +1,185 @@
+//! Write-Ahead Metadata Journal
+//!
+//! Blocks Changed Inside A [Transaction] Are Staged In Memory. On Commit They Are Copied Into
+//! The Journal Region, Then The Journal Header Is Written (The Commit Point), Then Every Block
+//! Is Written Home & The Header Is Cleared. A Header Still On Disk At Mount Means The Home
+//! Writes May Not Have Finished, So [replay] Writes Them Again.
+
+use alloc::collections::BTreeMap;
+use lazy_static::lazy_static;
+use spin::Mutex;
+
+use crate::{KResult, log, warn};
+
+use super::{BLOCK_SIZE, BlockAddr, block::Block, layout::journal_meta::*, superblock::SuperBlock};
+
+#[derive(Debug, Clone, Copy)]
+struct Journal {
+    addr: BlockAddr,
+    size: usize,
+}
+
+impl Journal {
+    fn capacity(&self) -> usize {
+        (self.size - 1).min(MAX_TARGETS)
+    }
+}
+
+lazy_static! {
+    static ref JOURNAL: Mutex<Option<Journal>> = Mutex::new(None);
+}
+
+/// FNV-1a Over The Targets & Their Contents, So A Torn Journal Write Is Never Replayed
+fn checksum<'a>(blocks: impl Iterator<Item = (&'a BlockAddr, &'a [u8; BLOCK_SIZE])>) -> u32 {
+    let mut hash: u32 = 0x811C9DC5;
+    for (addr, data) in blocks {
+        for byte in addr.to_be_bytes().iter().chain(data.iter()) {
+            hash ^= *byte as u32;
+            hash = hash.wrapping_mul(0x01000193);
+        }
+    }
+    hash
+}
+
+/// A Set Of Metadata Block Updates That Reach The Disk All Together Or Not At All
+pub struct Transaction {
+    blocks: BTreeMap<BlockAddr, [u8; BLOCK_SIZE]>,
+}
+
+impl Transaction {
+    pub fn new() -> Self {
+        Self { blocks: BTreeMap::new() }
+    }
+
+    /// Reads A Block, Seeing Any Changes Already Staged In This Transaction
+    pub fn read<'a>(&self, addr: BlockAddr) -> KResult<Block<'a>> {
+        match self.blocks.get(&addr) {
+            Some(data) => Ok(Block::from_data(addr, *data)),
+            None => Block::read(addr),
+        }
+    }
+
+    /// Stages A Block, It Isn't Written Until [Transaction::commit]
+    pub fn write(&mut self, block: &Block) -> KResult<()> {
+        let limit = JOURNAL.lock().map(|journal| journal.capacity()).unwrap_or(usize::MAX);
+        if !self.blocks.contains_key(&block.addr()) && self.blocks.len() >= limit {
+            return Err("Transaction Is Too Large For The Journal");
+        }
+        let mut data = [0; BLOCK_SIZE];
+        data.copy_from_slice(block.data());
+        self.blocks.insert(block.addr(), data);
+        Ok(())
+    }
+
+    pub fn len(&self) -> usize {
+        self.blocks.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.blocks.is_empty()
+    }
+
+    pub fn commit(self) -> KResult<()> {
+        if self.blocks.is_empty() { return Ok(()); }
+
+        // Held For The Whole Commit So Transactions Never Interleave In The Journal
+        let journal = JOURNAL.lock();
+        let journal = match *journal {
+            Some(journal) => journal,
+            None => {
+                // Unformatted Device, There Is Nowhere To Journal To
+                return self.write_home();
+            }
+        };
+
+        for (i, data) in self.blocks.values().enumerate() {
+            Block::from_data(journal.addr + 1 + i as BlockAddr, *data).write()?;
+        }
+
+        let mut header = Block::from_data(journal.addr, [0; BLOCK_SIZE]);
+        header.set_slice_range(0..4, MAGIC);
+        header.write_u32(COUNT_OFFSET, self.blocks.len() as u32);
+        header.write_u32(CHECKSUM_OFFSET, checksum(self.blocks.iter()));
+        let mut offset = TARGETS_OFFSET;
+        for addr in self.blocks.keys() {
+            offset = header.write_u32(offset, *addr);
+        }
+        header.write()?;
+
+        // A Failed Home Write Leaves The Header In Place, So The Next Mount Replays It
+        self.write_home()?;
+        clear(&journal)
+    }
+
+    fn write_home(&self) -> KResult<()> {
+        for (addr, data) in &self.blocks {
+            Block::from_data(*addr, *data).write()?;
+        }
+        Ok(())
+    }
+}
+
+fn clear(journal: &Journal) -> KResult<()> {
+    Block::from_data(journal.addr, [0; BLOCK_SIZE]).write()
+}
+
+/// Writes Every Block Of A Committed But Unfinished Transaction Home. Returns How Many
+/// Blocks Were Replayed.
+pub fn replay() -> KResult<usize> {
+    let journal = JOURNAL.lock();
+    let journal = match *journal {
+        Some(journal) => journal,
+        None => return Ok(0),
+    };
+
+    let header = Block::read(journal.addr)?;
+    if header.slice_range(0..4) != MAGIC {
+        return Ok(0);
+    }
+
+    let (count, _) = header.read_u32(COUNT_OFFSET);
+    let (expected, _) = header.read_u32(CHECKSUM_OFFSET);
+    if count as usize > journal.capacity() {
+        warn!("Journal Header Is Corrupt, Discarding It\n");
+        clear(&journal)?;
+        return Ok(0);
+    }
+
+    let mut blocks = BTreeMap::new();
+    let mut offset = TARGETS_OFFSET;
+    for i in 0..count {
+        let (addr, next) = header.read_u32(offset);
+        offset = next;
+        let block = Block::read(journal.addr + 1 + i)?;
+        let mut data = [0; BLOCK_SIZE];
+        data.copy_from_slice(block.data());
+        blocks.insert(addr, data);
+    }
+
+    if checksum(blocks.iter()) != expected {
+        // Journal Blocks Are Written Before The Header, So This Is Corruption Rather Than A Crash
+        warn!("Journal Checksum Mismatch, Discarding Transaction\n");
+        clear(&journal)?;
+        return Ok(0);
+    }
+
+    for (addr, data) in &blocks {
+        Block::from_data(*addr, *data).write()?;
+    }
+    clear(&journal)?;
+    Ok(blocks.len())
+}
+
+/// Loads The Journal Described By The Superblock & Replays Anything Left In It
+pub fn mount() {
+    *JOURNAL.lock() = SuperBlock::journal().map(|(addr, size)| Journal { addr, size: size as usize });
+    match replay() {
+        Ok(0) => {},
+        Ok(count) => { log!("Replayed {} Journaled Blocks\n", count); },
+        Err(e) => { warn!("{}\n", e); },
+    }
+}
+
+pub fn unmount() {
+    *JOURNAL.lock() = None;
+}
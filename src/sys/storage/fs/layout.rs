@@ -0,0 +1,84 @@
+//! On-Disk Layout Of COBALTFS
+//!
+//! Kept Free Of Kernel Dependencies So The Host Image Tool (`tools/cobaltfs`) Can Include It
+//! Directly And Always Agree With The Kernel About Where Things Live.
+
+pub type BlockAddr = u32;
+
+
+// == Partition Structure ==
+// Bootcode: 32MB / 65536 Blocks
+// Superblock: 2 Blocks, 65536..65538
+// Bitmap: 1024 Blocks, 65538..66562
+// Filetable: 1024 Blocks, 66562..67586
+// Journal: 64 Blocks, 67586..67650
+// Data: 
+// VERSION 0 Devices Predate The Journal, Their Data Starts At 67586 Instead.
+
+pub const BLOCK_SIZE: usize = 512; 
+pub const DISK_SIZE: usize = 128 * 1024 * 1024;
+pub const KERNEL_SIZE: usize = (32 << 20) / BLOCK_SIZE; // 32MB For Boot Code
+pub const SUPER_BLOCK_ADDR: BlockAddr = KERNEL_SIZE as BlockAddr;
+pub const SUPER_BLOCK_SIZE: usize = 2;
+pub const BITMAP_ADDR: BlockAddr = SUPER_BLOCK_ADDR + SUPER_BLOCK_SIZE as u32; 
+pub const BITMAP_SIZE: usize = 1024;
+pub const FILETABLE_ADDR: BlockAddr = BITMAP_ADDR + BITMAP_SIZE as u32;
+pub const FILETABLE_SIZE: usize = 1024;
+pub const JOURNAL_ADDR: BlockAddr = FILETABLE_ADDR + FILETABLE_SIZE as u32;
+pub const JOURNAL_SIZE: usize = 64;
+pub const DATA_ADDR: BlockAddr = JOURNAL_ADDR + JOURNAL_SIZE as u32;
+pub const DATA_SIZE: usize = (DISK_SIZE - DATA_ADDR as usize) - FILETABLE_SIZE;
+/// Where Data Started Before The Journal Was Added
+pub const V0_DATA_ADDR: BlockAddr = JOURNAL_ADDR;
+
+/// The First Data Block & How Many There Are On A Device Formatted With Superblock `version`.
+/// VERSION 0 Devices Keep Their Data Where It Was & Run Without A Journal.
+pub const fn data_region(version: u32) -> (BlockAddr, usize) {
+    let addr = if version == 0 { V0_DATA_ADDR } else { DATA_ADDR };
+    (addr, (DISK_SIZE - addr as usize) - FILETABLE_SIZE)
+}
+
+pub mod superblock_meta {
+    // SUPERBLOCK STRUCTURE:
+    // +=====+=======+============+============+
+    // |MAGIC|VERSION|JOURNAL_ADDR|JOURNAL_SIZE|
+    // +=====+=======+============+============+
+    // | 16  | u32   | u32        | u32        |
+    // +=====+=======+============+============+
+    // MAGIC Is Null Terminated, VERSION 0 Devices Predate The Journal.
+
+    pub const MAGIC: &str = "COBALTFS";
+    pub const VERSION: u32 = 1;
+    pub const VERSION_OFFSET: usize = 16;
+    pub const JOURNAL_ADDR_OFFSET: usize = 20;
+    pub const JOURNAL_SIZE_OFFSET: usize = 24;
+}
+
+pub mod journal_meta {
+    use super::BLOCK_SIZE;
+
+    // JOURNAL HEADER STRUCTURE: First Block Of The Journal Region
+    // +=====+=====+========+=============+
+    // |MAGIC|COUNT|CHECKSUM|TARGETS      |
+    // +=====+=====+========+=============+
+    // | 4   | u32 | u32    | COUNT * u32 |
+    // +=====+=====+========+=============+
+    // Journal Block `i + 1` Holds The New Contents Of `TARGETS[i]`.
+
+    pub const MAGIC: &[u8; 4] = b"JRNL";
+    pub const COUNT_OFFSET: usize = 4;
+    pub const CHECKSUM_OFFSET: usize = 8;
+    pub const TARGETS_OFFSET: usize = 12;
+    pub const MAX_TARGETS: usize = (BLOCK_SIZE - TARGETS_OFFSET) / 4;
+}
+
+pub mod addressing {
+    use super::{BITMAP_ADDR, BITMAP_SIZE, BlockAddr};
+
+    /// The Bitmap Block Tracking A Data Block & The Bit Within That Block, On A Device Whose
+    /// Data Starts At `data_addr` (See [super::data_region])
+    pub fn bitmap(data_addr: BlockAddr, addr: BlockAddr) -> (BlockAddr, usize) {
+        let i = addr - data_addr;
+        (BITMAP_ADDR + i / BITMAP_SIZE as BlockAddr, i as usize % BITMAP_SIZE)
+    }
+}
@@ -1,35 +1,83 @@
 use alloc::string::String;
 
-use crate::log;
+use crate::{KResult, log, warn};
 
 use super::block::Block;
-use super::SUPER_BLOCK_ADDR;
+use super::{BLOCK_SIZE, BlockAddr, JOURNAL_ADDR, JOURNAL_SIZE, SUPER_BLOCK_ADDR, journal, layout::{data_region, superblock_meta::*}};
 use core::str;
-pub struct SuperBlock {}
+use core::sync::atomic::{AtomicU32, Ordering};
+
+/// Superblock VERSION Of The Mounted Device, Which Decides Where Its Data Lives
+static MOUNTED_VERSION: AtomicU32 = AtomicU32::new(VERSION);
 
-const MAGIC: &str = "COBALTFS";
+pub struct SuperBlock {}
 
 impl SuperBlock {
     pub fn is_valid() -> bool {
-        let block = Block::read(SUPER_BLOCK_ADDR).expect("Failed To Locate Superblock");
+        let block = match Block::read(SUPER_BLOCK_ADDR) {
+            Ok(block) => block,
+            Err(_) => return false,
+        };
         let mut magic = String::new();
         block.read_str(&mut magic, 0);
         return magic == MAGIC;
     }
 
-    pub fn format() {
-        let mut block = Block::read(SUPER_BLOCK_ADDR).expect("Failed To Create Superblock");
-        block.erase();
+    /// The Superblock VERSION, [None] For Unformatted Devices
+    pub fn version() -> Option<u32> {
+        if !SuperBlock::is_valid() { return None; }
+        Some(Block::read(SUPER_BLOCK_ADDR).ok()?.read_u32(VERSION_OFFSET).0)
+    }
+
+    /// Picks The Layout Of A Newly Mounted Device From Its VERSION. Unformatted Devices Get
+    /// The Current One, Which Is What [SuperBlock::format] Writes.
+    pub fn load_layout() {
+        MOUNTED_VERSION.store(SuperBlock::version().unwrap_or(VERSION), Ordering::Relaxed);
+    }
+
+    /// The Mounted Device's Data Region As `(addr, size)`
+    pub fn data_region() -> (BlockAddr, usize) {
+        data_region(MOUNTED_VERSION.load(Ordering::Relaxed))
+    }
+
+    /// The Journal Region As `(addr, size)`, [None] For Unformatted Or Pre-Journal Devices
+    pub fn journal() -> Option<(BlockAddr, u32)> {
+        let version = SuperBlock::version()?;
+        if version < VERSION { return None; }
+        let block = Block::read(SUPER_BLOCK_ADDR).ok()?;
+        let (addr, _) = block.read_u32(JOURNAL_ADDR_OFFSET);
+        let (size, _) = block.read_u32(JOURNAL_SIZE_OFFSET);
+        if size < 2 { return None; }
+        Some((addr, size))
+    }
+
+    /// The Journal Is Cleared Before The Superblock Is Written, So A Device Is Never Seen As
+    /// Formatted With Stale Transactions Left In Its Journal.
+    pub fn format() -> KResult<()> {
+        Block::from_data(JOURNAL_ADDR, [0; BLOCK_SIZE]).write()?;
+
+        let mut block = Block::from_data(SUPER_BLOCK_ADDR, [0; BLOCK_SIZE]);
         block.write_str(MAGIC, 0);
-        block.write();
+        block.write_u32(VERSION_OFFSET, VERSION);
+        block.write_u32(JOURNAL_ADDR_OFFSET, JOURNAL_ADDR);
+        block.write_u32(JOURNAL_SIZE_OFFSET, JOURNAL_SIZE as u32);
+        block.write()?;
+
+        MOUNTED_VERSION.store(VERSION, Ordering::Relaxed);
+        journal::mount();
+        Ok(())
     }
 
     pub fn mount() {
         if !SuperBlock::is_valid() {
-            SuperBlock::format();
-            log!("Formatted Device!");
+            match SuperBlock::format() {
+                Ok(()) => { log!("Formatted Device!"); },
+                Err(e) => { warn!("Failed To Format Device: {}\n", e); },
+            }
         } else {
             log!("Device Is Valid, Found Superblock!");
+            SuperBlock::load_layout();
+            journal::mount();
         }
     }
 }
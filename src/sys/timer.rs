@@ -43,6 +43,8 @@ pub fn pause(seconds: f64) {
 		} else {
 			hlt();
 		}
+		// Idle Time Is When The Network Gets Serviced
+		crate::sys::net::service::poll();
 	}
 }
 
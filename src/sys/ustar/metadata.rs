@@ -53,6 +53,13 @@ impl Metadata {
         meta
     }
 
+    /// Metadata For A File That Doesn't Live In A Tar Archive, `addr` Is Whatever Locates It
+    pub fn new(addr: u32, name: &str, size: usize) -> Self {
+        let mut meta = Self::create_file(addr, name, &[]);
+        meta.size = size as u64;
+        meta
+    }
+
     pub fn calc_checksum(&mut self) {
         self.chksum = "        ".to_owned();
 
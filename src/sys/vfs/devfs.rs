@@ -0,0 +1,257 @@
+//! Device Filesystem
+//!
+//! Exposes Everything `crate::device` Can Open As Files Under `/dev`, Routing Reads & Writes
+//! To The Device's [CharDevice] Or [BlockDevice] Implementation. Nothing Is Cached, Drives Are
+//! Listed From Their Drivers On Every Lookup So Late Initialised Controllers Still Show Up.
+
+use alloc::{format, string::String, vec, vec::Vec};
+
+use crate::{KResult, device::{BlockDevice, CharDevice, Device, DeviceHandle}, sys::{ahci, ata, loopdev, net, nvme, raid, virtio}};
+
+use super::mount::{DirEntry, Mountable, Stat};
+
+/// How Much A Read Of A Device Without A Size (e.g. `zero`) Returns
+const STREAM_LEN: usize = 512;
+
+/// Biggest Block Device `read` Will Pull Into Memory Whole, Anything Larger Needs `read_at`
+const MAX_WHOLE_READ: usize = 64 * 1024;
+
+#[derive(Debug, Clone, Copy)]
+enum Kind {
+    Char,
+    /// `(block count, block size)`
+    Block(usize, usize),
+}
+
+struct Node {
+    path: String,
+    kind: Kind,
+}
+
+impl Node {
+    fn char_dev(path: &str) -> Self {
+        Self { path: String::from(path), kind: Kind::Char }
+    }
+
+    fn block_dev(path: String, blocks: u32) -> Self {
+        Self { path, kind: Kind::Block(blocks as usize, 512) }
+    }
+
+    fn stat(&self) -> Stat {
+        match self.kind {
+            Kind::Char => Stat::file(0),
+            Kind::Block(count, size) => Stat::file(count * size),
+        }
+    }
+
+    fn open(&self) -> KResult<DeviceHandle> {
+        let path = format!("dev/{}", self.path);
+        match self.kind {
+            Kind::Char => Device::open_char_dev(&path),
+            Kind::Block(..) => Device::open_block_dev(&path),
+        }
+    }
+}
+
+/// Every Device Currently Present, Paths Relative To `/dev`
+fn nodes() -> Vec<Node> {
+    let mut nodes: Vec<Node> = ["null", "zero", "random", "tty", "comm", "fb"].iter().map(|path| Node::char_dev(path)).collect();
+    nodes.extend(net::nic_names().into_iter().map(Node::char_dev));
+
+    for (bus, drive, _, _, _, _, sectors) in ata::list() {
+        nodes.push(Node::block_dev(format!("ata/{}/{}", bus, drive), sectors));
+    }
+    for (port, _, _, _, _, sectors) in ahci::list() {
+        nodes.push(Node::block_dev(format!("sata/{}", port), sectors));
+    }
+    for (id, _, _, _, sectors) in virtio::blk::list() {
+        nodes.push(Node::block_dev(format!("virtio/{}", id), sectors));
+    }
+    for (id, _, _, _, _, _, sectors) in nvme::list() {
+        nodes.push(Node::block_dev(format!("nvme/{}", id), sectors));
+    }
+    for (id, _, _, _, sectors) in loopdev::list() {
+        nodes.push(Node::block_dev(format!("loop/{}", id), sectors));
+    }
+    for (id, _, _, _, _, sectors) in raid::list() {
+        nodes.push(Node::block_dev(format!("md/{}", id), sectors));
+    }
+    nodes
+}
+
+fn find(path: &str) -> KResult<Node> {
+    let path = path.trim_matches('/');
+    nodes().into_iter().find(|node| node.path == path).ok_or("No Such Device")
+}
+
+/// Reads A Character Device Front To Back, Stopping Early If It Runs Dry
+fn read_char(dev: &DeviceHandle, buf: &mut Vec<u8>) -> usize {
+    if let Some(data) = dev.slice() {
+        buf.extend_from_slice(data);
+        return data.len();
+    }
+
+    let len = dev.size().unwrap_or(STREAM_LEN);
+    let start = buf.len();
+    for addr in 0..len {
+        match dev.read_u8(addr) {
+            Some(byte) => buf.push(byte),
+            None => break,
+        }
+    }
+    buf.len() - start
+}
+
+fn write_char(dev: &mut DeviceHandle, offset: usize, data: &[u8]) -> KResult<()> {
+    for (index, byte) in data.iter().enumerate() {
+        dev.write_u8(offset + index, *byte)?;
+    }
+    Ok(())
+}
+
+fn read_block(dev: &DeviceHandle, (count, size): (usize, usize), offset: usize, buf: &mut [u8]) -> KResult<usize> {
+    let mut block = vec![0; size];
+    let mut done = 0;
+    while done < buf.len() {
+        let pos = offset + done;
+        if pos / size >= count { break; }
+        dev.read(pos / size, &mut block)?;
+
+        let start = pos % size;
+        let len = (size - start).min(buf.len() - done);
+        buf[done..done + len].copy_from_slice(&block[start..start + len]);
+        done += len;
+    }
+    Ok(done)
+}
+
+/// Writes Whole Blocks Straight Through & Reads Back Partial Ones First
+fn write_block(dev: &mut DeviceHandle, (count, size): (usize, usize), offset: usize, data: &[u8]) -> KResult<()> {
+    if offset + data.len() > count * size { return Err("Write Past End Of Device"); }
+
+    let mut block = vec![0; size];
+    let mut done = 0;
+    while done < data.len() {
+        let pos = offset + done;
+        let start = pos % size;
+        let len = (size - start).min(data.len() - done);
+        if len < size { dev.read(pos / size, &mut block)?; }
+
+        block[start..start + len].copy_from_slice(&data[done..done + len]);
+        dev.write(pos / size, &block)?;
+        done += len;
+    }
+    Ok(())
+}
+
+pub struct DevFs;
+
+impl Mountable for DevFs {
+    fn fs_type(&self) -> &'static str {
+        "devfs"
+    }
+
+    fn stat(&self, path: &str) -> KResult<Stat> {
+        let path = path.trim_matches('/');
+        if path.is_empty() { return Ok(Stat::dir()); }
+        let prefix = format!("{}/", path);
+        let nodes = nodes();
+        if let Some(node) = nodes.iter().find(|node| node.path == path) {
+            Ok(node.stat())
+        } else if nodes.iter().any(|node| node.path.starts_with(&prefix)) {
+            Ok(Stat::dir())
+        } else {
+            Err("No Such Device")
+        }
+    }
+
+    fn read_dir(&self, path: &str) -> KResult<Vec<DirEntry>> {
+        let path = path.trim_matches('/');
+        let prefix = if path.is_empty() { String::new() } else { format!("{}/", path) };
+        let mut entries: Vec<DirEntry> = Vec::new();
+        for node in nodes() {
+            let rest = match node.path.strip_prefix(prefix.as_str()) {
+                Some(rest) => rest,
+                None => continue,
+            };
+            let (name, stat) = match rest.find('/') {
+                Some(i) => (&rest[..i], Stat::dir()),
+                None => (rest, node.stat()),
+            };
+            if !entries.iter().any(|entry| entry.name == name) {
+                entries.push(DirEntry { name: String::from(name), stat });
+            }
+        }
+
+        if entries.is_empty() && !path.is_empty() {
+            return Err(if find(path).is_ok() { "Not A Directory" } else { "No Such Device" });
+        }
+        Ok(entries)
+    }
+
+    fn read(&self, path: &str, buf: &mut Vec<u8>) -> KResult<usize> {
+        let node = find(path)?;
+        let dev = node.open()?;
+        match node.kind {
+            Kind::Char => Ok(read_char(&dev, buf)),
+            Kind::Block(count, size) => {
+                if count * size > MAX_WHOLE_READ { return Err("Device Too Large To Read Whole, Read It By Offset"); }
+                let start = buf.len();
+                buf.resize(start + count * size, 0);
+                read_block(&dev, (count, size), 0, &mut buf[start..])
+            },
+        }
+    }
+
+    fn read_at(&self, path: &str, offset: usize, buf: &mut [u8]) -> KResult<usize> {
+        let node = find(path)?;
+        let dev = node.open()?;
+        match node.kind {
+            Kind::Char => {
+                let mut data = Vec::new();
+                read_char(&dev, &mut data);
+                let data = data.get(offset..).unwrap_or(&[]);
+                let len = data.len().min(buf.len());
+                buf[..len].copy_from_slice(&data[..len]);
+                Ok(len)
+            },
+            Kind::Block(count, size) => read_block(&dev, (count, size), offset, buf),
+        }
+    }
+
+    fn write(&mut self, path: &str, data: &[u8]) -> KResult<()> {
+        self.write_at(path, 0, data)
+    }
+
+    /// Character Devices Have No End To Append To, So This Is Just A Write
+    fn append(&mut self, path: &str, data: &[u8]) -> KResult<()> {
+        match find(path)?.kind {
+            Kind::Char => self.write_at(path, 0, data),
+            Kind::Block(..) => Err("Can't Append To A Block Device"),
+        }
+    }
+
+    fn write_at(&mut self, path: &str, offset: usize, data: &[u8]) -> KResult<()> {
+        let node = find(path)?;
+        let mut dev = node.open()?;
+        match node.kind {
+            Kind::Char => write_char(&mut dev, offset, data),
+            Kind::Block(count, size) => write_block(&mut dev, (count, size), offset, data),
+        }
+    }
+}
+
+#[test_case]
+fn devfs_lists_and_routes() {
+    let mut fs = DevFs;
+    let names: Vec<String> = fs.read_dir("").expect("").into_iter().map(|entry| entry.name).collect();
+    assert!(names.iter().any(|name| name == "null"));
+    assert!(names.iter().any(|name| name == "zero"));
+
+    let mut buf = Vec::new();
+    assert_eq!(fs.read("zero", &mut buf), Ok(STREAM_LEN));
+    assert!(buf.iter().all(|byte| *byte == 0));
+    assert_eq!(fs.read("null", &mut Vec::new()), Ok(0));
+    fs.write("null", b"discarded").expect("");
+    assert!(fs.stat("missing").is_err());
+}
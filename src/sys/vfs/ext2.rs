@@ -0,0 +1,358 @@
+//! Read-Only ext2 Filesystem
+//!
+//! Supports Revision 0 & 1 Filesystems With Any Block Size, Reading Files Through Direct,
+//! Singly, Doubly & Triply Indirect Blocks. ext3 Images Also Mount Since The Journal Is A
+//! Compatible Feature, ext4 Extents & 64 Bit Block Numbers Are Refused.
+
+use core::fmt::Debug;
+
+use alloc::boxed::Box;
+use alloc::string::String;
+use alloc::vec;
+use alloc::vec::Vec;
+use block_device::BlockDevice;
+
+use crate::KResult;
+
+use crate::sys::ustar::metadata::Metadata;
+
+const SECTOR_SIZE: usize = 512;
+const SUPERBLOCK_OFFSET: usize = 1024;
+const MAGIC: u16 = 0xEF53;
+
+pub const ROOT_INODE: u32 = 2;
+
+const DIRECT_BLOCKS: usize = 12;
+const SINGLY_INDIRECT: usize = 12;
+const DOUBLY_INDIRECT: usize = 13;
+const TRIPLY_INDIRECT: usize = 14;
+
+const INCOMPAT_COMPRESSION: u32 = 0x0001;
+const INCOMPAT_FILETYPE: u32 = 0x0002;
+const INCOMPAT_RECOVER: u32 = 0x0004;
+const INCOMPAT_META_BG: u32 = 0x0010;
+const INCOMPAT_SUPPORTED: u32 = INCOMPAT_FILETYPE | INCOMPAT_RECOVER;
+const RO_COMPAT_LARGE_FILE: u32 = 0x0002;
+
+const MODE_TYPE_MASK: u16 = 0xF000;
+const MODE_DIR: u16 = 0x4000;
+const MODE_FILE: u16 = 0x8000;
+const MODE_SYMLINK: u16 = 0xA000;
+
+fn u16_at(buf: &[u8], offset: usize) -> u16 {
+    u16::from_le_bytes([buf[offset], buf[offset + 1]])
+}
+
+fn u32_at(buf: &[u8], offset: usize) -> u32 {
+    u32::from_le_bytes([buf[offset], buf[offset + 1], buf[offset + 2], buf[offset + 3]])
+}
+
+#[derive(Debug, Clone, Copy)]
+struct SuperBlock {
+    inodes_count: u32,
+    blocks_count: u32,
+    first_data_block: u32,
+    block_size: usize,
+    blocks_per_group: u32,
+    inodes_per_group: u32,
+    inode_size: usize,
+    feature_incompat: u32,
+    feature_ro_compat: u32,
+}
+
+impl SuperBlock {
+    fn parse(buf: &[u8]) -> KResult<Self> {
+        if u16_at(buf, 56) != MAGIC { return Err("Not An ext2 Filesystem"); }
+
+        let revision = u32_at(buf, 76);
+        let (inode_size, feature_incompat, feature_ro_compat) = if revision >= 1 {
+            (u16_at(buf, 88) as usize, u32_at(buf, 96), u32_at(buf, 100))
+        } else {
+            (128, 0, 0)
+        };
+
+        if feature_incompat & !INCOMPAT_SUPPORTED != 0 {
+            return Err(if feature_incompat & (INCOMPAT_COMPRESSION | INCOMPAT_META_BG) != 0 {
+                "ext2 Compression & Meta Block Groups Are Unsupported"
+            } else {
+                "Filesystem Uses ext4 Features, Only ext2/ext3 Are Supported"
+            });
+        }
+
+        let log_block_size = u32_at(buf, 24);
+        if log_block_size > 6 { return Err("Invalid ext2 Block Size"); }
+
+        let sb = Self {
+            inodes_count: u32_at(buf, 0),
+            blocks_count: u32_at(buf, 4),
+            first_data_block: u32_at(buf, 20),
+            block_size: 1024 << log_block_size,
+            blocks_per_group: u32_at(buf, 32),
+            inodes_per_group: u32_at(buf, 40),
+            inode_size,
+            feature_incompat,
+            feature_ro_compat,
+        };
+        if sb.blocks_per_group == 0 || sb.inodes_per_group == 0 || sb.inode_size < 128 || sb.first_data_block >= sb.blocks_count {
+            return Err("Corrupt ext2 Superblock");
+        }
+        Ok(sb)
+    }
+
+    fn group_count(&self) -> u32 {
+        (self.blocks_count - self.first_data_block + self.blocks_per_group - 1) / self.blocks_per_group
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Inode {
+    mode: u16,
+    size: u64,
+    links: u16,
+    block: [u32; 15],
+}
+
+impl Inode {
+    fn parse(buf: &[u8], large_files: bool) -> Self {
+        let mode = u16_at(buf, 0);
+        let mut size = u32_at(buf, 4) as u64;
+        // i_dir_acl Holds The Upper 32 Bits Of The Size For Regular Files
+        if large_files && mode & MODE_TYPE_MASK == MODE_FILE {
+            size |= (u32_at(buf, 108) as u64) << 32;
+        }
+        let mut block = [0; 15];
+        for (i, entry) in block.iter_mut().enumerate() {
+            *entry = u32_at(buf, 40 + i * 4);
+        }
+        Self { mode, size, links: u16_at(buf, 26), block }
+    }
+
+    pub fn is_dir(&self) -> bool {
+        self.mode & MODE_TYPE_MASK == MODE_DIR
+    }
+
+    pub fn is_file(&self) -> bool {
+        self.mode & MODE_TYPE_MASK == MODE_FILE
+    }
+
+    pub fn is_symlink(&self) -> bool {
+        self.mode & MODE_TYPE_MASK == MODE_SYMLINK
+    }
+
+    pub fn size(&self) -> u64 {
+        self.size
+    }
+
+    pub fn links(&self) -> u16 {
+        self.links
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct DirEntry {
+    pub inode: u32,
+    pub name: String,
+}
+
+/// Checks For The ext2 Magic Without Keeping The Device
+pub fn probe<D: BlockDevice>(device: &D) -> bool {
+    let mut buf = [0; 1024];
+    device.read(&mut buf, SUPERBLOCK_OFFSET / SECTOR_SIZE, 2).is_ok() && u16_at(&buf, 56) == MAGIC
+}
+
+pub struct Ext2FileSystem<E> {
+    disk: Box<dyn BlockDevice<Error = E>>,
+    sb: SuperBlock,
+}
+
+impl<E: Debug> Ext2FileSystem<E> {
+    pub fn new(device: Box<dyn BlockDevice<Error = E>>) -> KResult<Self> {
+        let mut buf = [0; 1024];
+        device.read(&mut buf, SUPERBLOCK_OFFSET / SECTOR_SIZE, 2).map_err(|_| "Failed To Read ext2 Superblock")?;
+        let sb = SuperBlock::parse(&buf)?;
+        Ok(Self { disk: device, sb })
+    }
+
+    pub fn block_size(&self) -> usize {
+        self.sb.block_size
+    }
+
+    fn read_block(&self, block: u32, buf: &mut [u8]) -> KResult<()> {
+        if block >= self.sb.blocks_count { return Err("ext2 Block Out Of Range"); }
+        let sectors = self.sb.block_size / SECTOR_SIZE;
+        self.disk.read(buf, block as usize * sectors, sectors).map_err(|_| "Failed To Read ext2 Block")
+    }
+
+    /// Reads `len` Bytes Starting `offset` Bytes Into `block`, Which May Span Several Blocks
+    fn read_bytes(&self, block: u32, offset: usize, len: usize) -> KResult<Vec<u8>> {
+        let bs = self.sb.block_size;
+        let mut res = Vec::with_capacity(len);
+        let mut buf = vec![0; bs];
+        let mut current = block + (offset / bs) as u32;
+        let mut skip = offset % bs;
+        while res.len() < len {
+            self.read_block(current, &mut buf)?;
+            let take = (bs - skip).min(len - res.len());
+            res.extend_from_slice(&buf[skip..skip + take]);
+            skip = 0;
+            current += 1;
+        }
+        Ok(res)
+    }
+
+    pub fn read_inode(&self, ino: u32) -> KResult<Inode> {
+        if ino == 0 || ino > self.sb.inodes_count { return Err("ext2 Inode Out Of Range"); }
+        let group = (ino - 1) / self.sb.inodes_per_group;
+        let index = (ino - 1) % self.sb.inodes_per_group;
+        if group >= self.sb.group_count() { return Err("ext2 Inode Out Of Range"); }
+
+        // The Descriptor Table Follows The Superblock's Block, 32 Bytes Per Group
+        let desc = self.read_bytes(self.sb.first_data_block + 1, group as usize * 32, 32)?;
+        let inode_table = u32_at(&desc, 8);
+
+        let buf = self.read_bytes(inode_table, index as usize * self.sb.inode_size, 128)?;
+        Ok(Inode::parse(&buf, self.sb.feature_ro_compat & RO_COMPAT_LARGE_FILE != 0))
+    }
+
+    fn indirect(&self, block: u32, index: usize) -> KResult<u32> {
+        if block == 0 { return Ok(0); }
+        let buf = self.read_bytes(block, index * 4, 4)?;
+        Ok(u32_at(&buf, 0))
+    }
+
+    /// Maps A Block Index Within A File To Its Block On Disk, 0 Meaning A Hole
+    fn file_block(&self, inode: &Inode, n: usize) -> KResult<u32> {
+        let per_block = self.sb.block_size / 4;
+        if n < DIRECT_BLOCKS {
+            return Ok(inode.block[n]);
+        }
+
+        let n = n - DIRECT_BLOCKS;
+        if n < per_block {
+            return self.indirect(inode.block[SINGLY_INDIRECT], n);
+        }
+
+        let n = n - per_block;
+        if n < per_block * per_block {
+            let block = self.indirect(inode.block[DOUBLY_INDIRECT], n / per_block)?;
+            return self.indirect(block, n % per_block);
+        }
+
+        let n = n - per_block * per_block;
+        if n < per_block * per_block * per_block {
+            let block = self.indirect(inode.block[TRIPLY_INDIRECT], n / (per_block * per_block))?;
+            let block = self.indirect(block, (n / per_block) % per_block)?;
+            return self.indirect(block, n % per_block);
+        }
+        Err("ext2 File Block Out Of Range")
+    }
+
+    pub fn read_data(&self, inode: &Inode, buffer: &mut Vec<u8>) -> KResult<()> {
+        // Short Symlink Targets Live Inside The Block Pointers Themselves
+        if inode.is_symlink() && inode.size < 60 {
+            for word in inode.block.iter() {
+                buffer.extend_from_slice(&word.to_le_bytes());
+            }
+            buffer.truncate(inode.size as usize);
+            return Ok(());
+        }
+
+        // The Size Is Read Off The Disk, So Check It Before Reserving That Much
+        let bs = self.sb.block_size;
+        if inode.size > self.sb.blocks_count as u64 * bs as u64 { return Err("ext2 File Is Larger Than Its Filesystem"); }
+        let size = inode.size as usize;
+        let start = buffer.len();
+        buffer.reserve(size);
+        let mut buf = vec![0; bs];
+        for n in 0..(size + bs - 1) / bs {
+            let block = self.file_block(inode, n)?;
+            if block == 0 {
+                buf.iter_mut().for_each(|b| *b = 0);
+            } else {
+                self.read_block(block, &mut buf)?;
+            }
+            let take = bs.min(size - n * bs);
+            buffer.extend_from_slice(&buf[..take]);
+        }
+        debug_assert_eq!(buffer.len() - start, size);
+        Ok(())
+    }
+
+    pub fn read_dir(&self, inode: &Inode) -> KResult<Vec<DirEntry>> {
+        if !inode.is_dir() { return Err("Not A Directory"); }
+        let mut data = Vec::new();
+        self.read_data(inode, &mut data)?;
+
+        let filetype = self.sb.feature_incompat & INCOMPAT_FILETYPE != 0;
+        let mut entries = Vec::new();
+        let mut offset = 0;
+        while offset + 8 <= data.len() {
+            let ino = u32_at(&data, offset);
+            let rec_len = u16_at(&data, offset + 4) as usize;
+            // With The Filetype Feature The High Byte Of The Name Length Is The File Type
+            let name_len = if filetype { data[offset + 6] as usize } else { u16_at(&data, offset + 6) as usize };
+            if rec_len < 8 || offset + rec_len > data.len() || 8 + name_len > rec_len {
+                return Err("Corrupt ext2 Directory Entry");
+            }
+
+            if ino != 0 {
+                let name = String::from_utf8_lossy(&data[offset + 8..offset + 8 + name_len]).into_owned();
+                if name != "." && name != ".." {
+                    entries.push(DirEntry { inode: ino, name });
+                }
+            }
+            offset += rec_len;
+        }
+        Ok(entries)
+    }
+
+    /// Resolves A `/` Separated Path From The Root Directory
+    pub fn lookup(&self, path: &str) -> KResult<u32> {
+        let mut ino = ROOT_INODE;
+        for part in path.split('/').filter(|part| !part.is_empty()) {
+            let dir = self.read_inode(ino)?;
+            ino = self.read_dir(&dir)?
+                .into_iter()
+                .find(|entry| entry.name == part)
+                .ok_or("Unable To Locate File!")?
+                .inode;
+        }
+        Ok(ino)
+    }
+
+    pub fn load(&self, path: &str, buffer: &mut Vec<u8>) -> KResult<usize> {
+        let inode = self.read_inode(self.lookup(path)?)?;
+        if inode.is_dir() { return Err("Is A Directory"); }
+        let start = buffer.len();
+        self.read_data(&inode, buffer)?;
+        Ok(buffer.len() - start)
+    }
+
+    /// Every Path On The Filesystem, Directories End In '/' Like In A Tar Archive
+    pub fn metadata_slice(&self, buffer: &mut Vec<Metadata>) {
+        self.walk("", ROOT_INODE, 0, buffer);
+    }
+
+    fn walk(&self, prefix: &str, ino: u32, depth: usize, buffer: &mut Vec<Metadata>) {
+        // Guards Against Directory Loops On A Corrupt Image
+        if depth > 32 { return; }
+        let entries = match self.read_inode(ino).and_then(|dir| self.read_dir(&dir)) {
+            Ok(entries) => entries,
+            Err(_) => return,
+        };
+
+        for entry in entries {
+            let inode = match self.read_inode(entry.inode) {
+                Ok(inode) => inode,
+                Err(_) => continue,
+            };
+            let mut path = String::from(prefix);
+            path.push_str(&entry.name);
+            if inode.is_dir() { path.push('/'); }
+            buffer.push(Metadata::new(entry.inode, &path, inode.size() as usize));
+            if inode.is_dir() {
+                self.walk(&path, entry.inode, depth + 1, buffer);
+            }
+        }
+    }
+}
@@ -2,59 +2,19 @@ use core::{fmt::Display, ops::{Index, IndexMut}};
 
 use alloc::{borrow::ToOwned, string::String, vec::Vec};
 
-use crate::{breakpoint, debug, log, print, serial_print, serial_println, sys::{storage::fs::{block::Block}, vfs::filesystem::{inode_meta::FILENAME_SIZE}}, warn};
+use crate::{KResult, debug, log, print, serial_print, serial_println, sys::{clock, process::{Credentials, Gid, Uid}, storage::fs::{block::Block}, vfs::{quota, filesystem::{inode_meta::{CHILDREN_LEN, FILENAME_SIZE, MAX_BLOCKS, NO_BLOCK, NO_PARENT, POINTERS_PER_BLOCK}}}}, warn};
 
-use self::{filesystem_values::{BLOCKS_PER_BITMAP, BLOCK_SIZE, DATA_BASE, INODE_BASE, INODE_BITMAP_SIZE, INODE_SIZE, PHYSICAL_OFFSET}, inode_flags::*};
+use super::layout::{addressing, old_layout};
+use self::{superblock::FORMAT_VERSION, filesystem_values::{BLOCKS_PER_BITMAP, BLOCK_SIZE, DATA_BITMAP_BASE, DATA_BITMAP_SIZE, DATA_SIZE, INODE_BITMAP_BASE, INODE_BITMAP_SIZE, INODE_SIZE}, inode_flags::*};
 
 use bit_field::BitField;
 
 
 
-pub mod inode_meta {
-    use core::mem::size_of;
-
-    use super::filesystem_values::BLOCK_SIZE;
-
-    pub const FILENAME_SIZE: usize = 64;
-    pub const FLAGS_OFFSET: usize = FILENAME_SIZE;
-    pub const PARENT_OFFSET: usize = FLAGS_OFFSET + size_of::<u16>();
-    pub const SIZE_OFFSET: usize = PARENT_OFFSET + size_of::<u32>();
-    pub const CHILDREN_OFFSET: usize = SIZE_OFFSET + size_of::<u32>();
-    pub const CHILDREN_LEN: usize = (BLOCK_SIZE - CHILDREN_OFFSET) / size_of::<u32>();
-}
-
-pub mod inode_flags {
-    pub const ROOT_READ: u16 = 1 << 0;
-    pub const ROOT_WRITE: u16 = 1 << 1;
-    pub const ROOT_EXEC: u16  = 1 << 2;
-
-    pub const DIR: u16  = 1 << 3;
-    pub const FILE: u16 = 1 << 4;
-    pub const DEV: u16  = 1 << 5;
-
-    pub const HIDDEN: u16 = 1 << 6;
-}
-
-pub mod filesystem_values {
-    pub const PHYSICAL_OFFSET:      usize = (20 << 20) / BLOCK_SIZE;
-    pub const BLOCK_SIZE:           usize = 512;
-    pub const PARTITION_SIZE:       usize = (20 << 20) / BLOCK_SIZE;
-    pub const SUPERBLOCK_SIZE:      usize = 1;
-    pub const INODE_SIZE:           usize = 4096;
-    pub const DATA_SIZE:            usize = 4096;
-    pub const BLOCKS_PER_BITMAP:    usize = 8 * 512; 
-    pub const INODE_BITMAP_SIZE:    usize = INODE_SIZE / BLOCKS_PER_BITMAP;
-    pub const DATA_BITMAP_SIZE:     usize = DATA_SIZE / BLOCKS_PER_BITMAP;
-
-    pub const METADATA_SIZE:        usize = SUPERBLOCK_SIZE + INODE_BITMAP_SIZE + DATA_BITMAP_SIZE;
-    pub const USABLE_SIZE:          usize = PARTITION_SIZE - METADATA_SIZE;
-
-    pub const INODE_BITMAP_BASE:    u32 = PHYSICAL_OFFSET as u32 + SUPERBLOCK_SIZE as u32;
-    pub const DATA_BITMAP_BASE:     u32 = INODE_BITMAP_BASE + INODE_BITMAP_SIZE as u32;
-    pub const INODE_BASE:           u32 = DATA_BITMAP_BASE + DATA_BITMAP_SIZE as u32;
-    pub const DATA_BASE:            u32 = INODE_BASE + INODE_SIZE as u32;
-}
+pub use super::layout::{inode_meta, inode_flags, inode_mode, filesystem_values, superblock};
 
+// The Inode Filesystem Sits Between The Boot Partition & COBALTFS, It Mustn't Reach The Latter
+const _: () = assert!(filesystem_values::END <= crate::sys::storage::fs::SUPER_BLOCK_ADDR);
 
 pub trait FileSystem {
     fn current_dir(&self) -> Directory;
@@ -65,24 +25,19 @@ pub trait FileSystem {
     fn file_update(&mut self, file: &File); 
 }
 
-fn logical_to_physical(base: u32, logical: u32) -> u32 {
-    return base + logical + PHYSICAL_OFFSET as u32;
-}
-
 pub struct DataBitmap;
 
 impl DataBitmap {
     pub fn data_index_to_logical(index: u32) -> u32 {
-        index / BLOCKS_PER_BITMAP as u32 / 8
+        index / BLOCKS_PER_BITMAP as u32
     }
 
     pub fn data_index_to_buffer_offset(index: u32) -> u32 {
-        index % BLOCKS_PER_BITMAP as u32
+        addressing::data_bitmap(index).1 as u32
     }
 
     pub fn is_allocated(index: u32) -> bool {
-        let logical_index = Self::data_index_to_logical(index);
-        let physical_index = logical_to_physical(INODE_BASE, logical_index);
+        let (physical_index, _) = addressing::data_bitmap(index);
         let block = Block::read(physical_index).unwrap();
 
         let buffer = block.data();
@@ -94,39 +49,46 @@ impl DataBitmap {
     }
 
     pub fn allocate(index: u32) {
-        let logical_index = Self::data_index_to_logical(index);
-        let physical_index = logical_to_physical(INODE_BASE, logical_index);
+        let (physical_index, _) = addressing::data_bitmap(index);
         let mut block = Block::read(physical_index).unwrap();
 
         let offset = Self::data_index_to_buffer_offset(index);
         let byte_idx = offset / 8;
         let bit_idx = offset % 8;
 
+        if block[byte_idx as usize].get_bit(bit_idx as usize) { return; }
         block[byte_idx as usize].set_bit(bit_idx as usize, true);
 
-        block.write();
+        block.write().unwrap();
+        quota::adjust_free(physical_index, true);
     }
 
     pub fn free(index: u32) {
-        let logical_index = Self::data_index_to_logical(index);
-        let physical_index = logical_to_physical(INODE_BASE, logical_index);
+        let (physical_index, _) = addressing::data_bitmap(index);
         let mut block = Block::read(physical_index).unwrap();
 
-        let offset = Self::data_index_to_logical(index);
+        let offset = Self::data_index_to_buffer_offset(index);
         let byte_idx = offset / 8;
         let bit_idx = offset % 8;
 
+        if !block[byte_idx as usize].get_bit(bit_idx as usize) { return; }
         block[byte_idx as usize].set_bit(bit_idx as usize, false);
 
-        block.write();
+        block.write().unwrap();
+        quota::adjust_free(physical_index, false);
     }
 
+    /// Reads Each Bitmap Block Once Rather Than Once Per Bit, Skipping Those The Superblock
+    /// Summary Says Are Full
     pub fn next_free() -> Option<u32> {
-        for index in 0..INODE_SIZE as u32 {
-
-            if !Self::is_allocated(index) {
-                return Some(index);
-            };
+        let summary = quota::bitmap_free();
+        for bitmap in 0..DATA_BITMAP_SIZE as u32 {
+            if summary.as_ref().map_or(false, |free| free[INODE_BITMAP_SIZE + bitmap as usize] == 0) { continue; }
+            let block = Block::read(DATA_BITMAP_BASE + bitmap).unwrap();
+            if let Some((byte_idx, byte)) = block.data().iter().enumerate().find(|(_, byte)| **byte != 0xFF) {
+                let index = bitmap * BLOCKS_PER_BITMAP as u32 + byte_idx as u32 * 8 + byte.trailing_ones();
+                return if index < DATA_SIZE as u32 { Some(index) } else { None };
+            }
         }
         None
     }
@@ -147,16 +109,15 @@ pub struct InodeBitmap;
 
 impl InodeBitmap {
     pub fn inode_index_to_logical(index: u32) -> u32 {
-        index / 4096 / 8
-     }
+        index / BLOCKS_PER_BITMAP as u32
+    }
 
     pub fn inode_index_to_buffer_offset(index: u32) -> u32 {
-        index % 4096 as u32
+        addressing::inode_bitmap(index).1 as u32
     }
 
     pub fn is_allocated(index: u32) -> bool {
-        let logical_index = Self::inode_index_to_logical(index);
-        let physical_index = logical_to_physical(INODE_BASE, logical_index);
+        let (physical_index, _) = addressing::inode_bitmap(index);
         let block = Block::read(physical_index).unwrap();
 
         let buffer = block.data();
@@ -168,50 +129,61 @@ impl InodeBitmap {
     }
 
     pub fn allocate(index: u32) {
-        let logical_index = Self::inode_index_to_logical(index);
-        let physical_index = logical_to_physical(INODE_BASE, logical_index);
+        let (physical_index, _) = addressing::inode_bitmap(index);
         let mut block = Block::read(physical_index).unwrap();
 
         let offset = Self::inode_index_to_buffer_offset(index);
         let byte_idx = offset / 8;
         let bit_idx = offset % 8;
 
+        if block[byte_idx as usize].get_bit(bit_idx as usize) { return; }
         block[byte_idx as usize].set_bit(bit_idx as usize, true);
 
-        block.write();
+        block.write().unwrap();
+        quota::adjust_free(physical_index, true);
     }
 
     pub fn free(index: u32) {
-        let logical_index = Self::inode_index_to_logical(index);
-        let physical_index = logical_to_physical(INODE_BASE, logical_index);
+        let (physical_index, _) = addressing::inode_bitmap(index);
         let mut block = Block::read(physical_index).unwrap();
 
         let offset = Self::inode_index_to_buffer_offset(index);
         let byte_idx = offset / 8;
         let bit_idx = offset % 8;
 
+        if !block[byte_idx as usize].get_bit(bit_idx as usize) { return; }
         block[byte_idx as usize].set_bit(bit_idx as usize, false);
 
-        block.write();
+        block.write().unwrap();
+        quota::adjust_free(physical_index, false);
     }
 
+    /// Same Scan As `DataBitmap::next_free`
     pub fn next_free() -> Option<u32> {
-        for index in 0..INODE_SIZE as u32 {
-            //breakpoint!("Checking Status Of Inode {}", index);
-            if !Self::is_allocated(index) {
-                return Some(index);
-            };
+        let summary = quota::bitmap_free();
+        for bitmap in 0..INODE_BITMAP_SIZE as u32 {
+            if summary.as_ref().map_or(false, |free| free[bitmap as usize] == 0) { continue; }
+            let block = Block::read(INODE_BITMAP_BASE + bitmap).unwrap();
+            if let Some((byte_idx, byte)) = block.data().iter().enumerate().find(|(_, byte)| **byte != 0xFF) {
+                let index = bitmap * BLOCKS_PER_BITMAP as u32 + byte_idx as u32 * 8 + byte.trailing_ones();
+                return if index < INODE_SIZE as u32 { Some(index) } else { None };
+            }
         }
         None
     }
 
-    pub fn allocate_next() -> Option<u32> {
-        if let Some(next) = Self::next_free() {
-            Self::allocate(next);
-            Some(next)
-        } else {
-            None
-        }
+    /// Allocates An Inode Owned By `owner`, Who Must Have Room Left In Their Quota
+    pub fn allocate_next(owner: Uid) -> KResult<u32> {
+        let next = Self::next_free().ok_or("No Free Inodes")?;
+        quota::charge(owner, 0, 1)?;
+        Self::allocate(next);
+        Ok(next)
+    }
+
+    /// Frees An Inode & Gives It Back To `owner`'s Quota
+    pub fn release(index: u32, owner: Uid) {
+        Self::free(index);
+        quota::release(owner, 0, 1);
     }
 
     pub fn get_allocated(buffer: &mut Vec<Inode>) {
@@ -226,13 +198,18 @@ impl InodeBitmap {
 
     pub unsafe fn erase_all() {
         for idx in 0..INODE_BITMAP_SIZE as u32 {
-            Block::read(idx).unwrap().erase();
+            Block::read(INODE_BITMAP_BASE + idx).unwrap().erase().unwrap();
         }
     }
 
 
 
 
+}
+
+/// Seconds Since The Unix Epoch, As Stored In Inode Timestamps
+fn now() -> u32 {
+    clock::realtime() as u32
 }
 
 #[derive(Debug)]
@@ -241,6 +218,15 @@ pub struct Inode {
     name: String, 
     flags: InodeFlags,
     parent: Option<u16>,
+    mode: Mode,
+    uid: Uid,
+    gid: Gid,
+    created: u32,
+    modified: u32,
+    accessed: u32,
+    links: u16,
+    indirect: u32,
+    double_indirect: u32,
     children: Vec<u32>,
     size: u32
 }
@@ -274,11 +260,29 @@ impl Inode {
         let (pid, new_offset) = block.read_u16(offset);
         debug!("Parent: 0x{:04x} ({})", pid, pid);
         offset = new_offset;
-        if pid < 0xFFFF {parent = Some(pid)};
+        if pid != NO_PARENT {parent = Some(pid)};
+        let (mode, new_offset) = block.read_u16(offset);
+        offset = new_offset;
+        let (uid, new_offset) = block.read_u16(offset);
+        offset = new_offset;
+        let (gid, new_offset) = block.read_u16(offset);
+        offset = new_offset;
+        let (created, new_offset) = block.read_u32(offset);
+        offset = new_offset;
+        let (modified, new_offset) = block.read_u32(offset);
+        offset = new_offset;
+        let (accessed, new_offset) = block.read_u32(offset);
+        offset = new_offset;
+        let (links, new_offset) = block.read_u16(offset);
+        offset = new_offset;
+        let (indirect, new_offset) = block.read_u32(offset);
+        offset = new_offset;
+        let (double_indirect, new_offset) = block.read_u32(offset);
+        offset = new_offset;
         let (child_count, new_offset) = block.read_u8(offset);
         offset = new_offset;
         let mut children = Vec::new();
-        for _ in 0..=child_count {
+        for _ in 0..(child_count as usize).min(CHILDREN_LEN) {
             let (child, new_offset) = block.read_u32(offset);
             offset = new_offset;
             children.push(child);
@@ -298,12 +302,74 @@ impl Inode {
             flags,
             name,
             parent,
+            mode: Mode::new(mode),
+            uid,
+            gid,
+            created,
+            modified,
+            accessed,
+            links,
+            indirect,
+            double_indirect,
             size,
         }
 
         
     }
 
+    /// Reads An Inode Written In An Older Format Version, From Where That Version Kept It.
+    /// Version 1 Had No Ownership Or Timestamps, So Those Come Back Owned By Root, Stamped With
+    /// The Current Time & With Their Old Permissions As A Mode. Versions Before 3 Had No Links,
+    /// So Every Inode Has One Name. No Version Before 4 Had Indirect Blocks.
+    fn read_old(addr: u32, version: u16) -> Self {
+        let block = Block::read(old_layout::inode(version, addr)).unwrap();
+        let name: Vec<u8> = (0..FILENAME_SIZE).map(|i| if block[i] == 0 { b' ' } else { block[i] }).collect();
+        let name = (*String::from_utf8_lossy(&name)).trim().to_owned();
+
+        let (flags, _) = block.read_u16(inode_meta::FLAGS_OFFSET);
+        let (pid, _) = block.read_u16(inode_meta::PARENT_OFFSET);
+        let (child_count, mut offset) = block.read_u8(old_layout::child_count_offset(version));
+        let mut children = Vec::new();
+        for _ in 0..(child_count as usize).min(old_layout::children_len(version)) {
+            let (child, new_offset) = block.read_u32(offset);
+            offset = new_offset;
+            children.push(child);
+        }
+        let (size, _) = block.read_u32(offset);
+
+        let time = now();
+        let mut inode = Self {
+            addr,
+            name,
+            flags: InodeFlags::new(flags & !(ROOT_READ | ROOT_WRITE | ROOT_EXEC)),
+            parent: if pid == NO_PARENT { None } else { Some(pid) },
+            mode: Mode::new(inode_mode::from_v1_flags(flags)),
+            uid: 0,
+            gid: 0,
+            created: time,
+            modified: time,
+            accessed: time,
+            links: 1,
+            indirect: NO_BLOCK,
+            double_indirect: NO_BLOCK,
+            children,
+            size,
+        };
+
+        if version >= 2 {
+            inode.mode = Mode::new(block.read_u16(inode_meta::MODE_OFFSET).0);
+            inode.uid = block.read_u16(inode_meta::UID_OFFSET).0;
+            inode.gid = block.read_u16(inode_meta::GID_OFFSET).0;
+            inode.created = block.read_u32(inode_meta::CREATED_OFFSET).0;
+            inode.modified = block.read_u32(inode_meta::MODIFIED_OFFSET).0;
+            inode.accessed = block.read_u32(inode_meta::ACCESSED_OFFSET).0;
+        }
+        if version >= 3 {
+            inode.links = block.read_u16(inode_meta::LINKS_OFFSET).0;
+        }
+        inode
+    }
+
     pub fn write(&self) {
         let mut block = Block::read(Self::physical_addr(self.addr)).unwrap();
         let bytes = &mut block;
@@ -323,8 +389,18 @@ impl Inode {
             serial_println!("Parent: {}", parent);
             offset = bytes.write_u16(offset, parent);
         } else {
-            offset = bytes.write_u16(offset,0xFFFF);
+            offset = bytes.write_u16(offset, NO_PARENT);
         }
+
+        offset = bytes.write_u16(offset, self.mode.value());
+        offset = bytes.write_u16(offset, self.uid);
+        offset = bytes.write_u16(offset, self.gid);
+        offset = bytes.write_u32(offset, self.created);
+        offset = bytes.write_u32(offset, self.modified);
+        offset = bytes.write_u32(offset, self.accessed);
+        offset = bytes.write_u16(offset, self.links);
+        offset = bytes.write_u32(offset, self.indirect);
+        offset = bytes.write_u32(offset, self.double_indirect);
         
         offset = bytes.write_u8(offset, self.children.len() as u8);
         for child in &self.children {
@@ -334,7 +410,7 @@ impl Inode {
         // Write The Size Out
         bytes.write_u32(offset, self.size);
 
-        block.write();
+        block.write().unwrap();
 
 
         let written = Block::read(Self::physical_addr(self.addr)).unwrap();
@@ -345,30 +421,47 @@ impl Inode {
         assert_eq!(block, written);
     }
 
-    pub fn create(name: &str, flags: InodeFlags, parent: Option<u16>) -> Option<Self> {
-        if let Some(inode) = InodeBitmap::allocate_next() {
-            Some(
-                Self {
-                    addr: inode,
-                    children: Vec::new(),
-                    parent,
-                    flags,
-                    name: name.to_owned(),
-                    size: 0,
-                }
-            )
-        } else {
-            None
-        }
+    pub fn create(name: &str, flags: InodeFlags, parent: Option<u16>, owner: Credentials) -> KResult<Self> {
+        let inode = InodeBitmap::allocate_next(owner.uid)?;
+        let time = now();
+        Ok(
+            Self {
+                addr: inode,
+                children: Vec::new(),
+                parent,
+                flags,
+                mode: Mode::default_for(flags),
+                uid: owner.uid,
+                gid: owner.gid,
+                created: time,
+                modified: time,
+                accessed: time,
+                links: 1,
+                indirect: NO_BLOCK,
+                double_indirect: NO_BLOCK,
+                name: name.to_owned(),
+                size: 0,
+            }
+        )
     }
 
     pub fn new(addr: u32, name: String, flags: InodeFlags, children: Vec<u32>, parent: Option<u16>) -> Self {
+            let time = now();
             Self {
                 addr, 
                 children,
                 flags,
                 name,
                 parent,
+                mode: Mode::default_for(flags),
+                uid: 0,
+                gid: 0,
+                created: time,
+                modified: time,
+                accessed: time,
+                links: 1,
+                indirect: NO_BLOCK,
+                double_indirect: NO_BLOCK,
                 size: 0
             }
     }
@@ -381,6 +474,88 @@ impl Inode {
         self.parent
     }
 
+    pub fn mode(&self) -> Mode {
+        self.mode
+    }
+
+    pub fn uid(&self) -> Uid {
+        self.uid
+    }
+
+    pub fn gid(&self) -> Gid {
+        self.gid
+    }
+
+    pub fn created(&self) -> u32 {
+        self.created
+    }
+
+    pub fn modified(&self) -> u32 {
+        self.modified
+    }
+
+    pub fn accessed(&self) -> u32 {
+        self.accessed
+    }
+
+    /// How Many Names The Inode Has, Its Own Plus Any Hard Link Entries
+    pub fn links(&self) -> u16 {
+        self.links
+    }
+
+    pub fn set_links(&mut self, links: u16) {
+        self.links = links;
+    }
+
+    pub fn touch_accessed(&mut self) {
+        self.accessed = now();
+    }
+
+    pub fn touch_modified(&mut self) {
+        self.modified = now();
+        self.accessed = self.modified;
+    }
+
+    pub fn permits(&self, creds: &Credentials, access: Access) -> bool {
+        self.mode.permits(self.uid, self.gid, creds, access)
+    }
+
+    /// Only The Owner Or Root May Change The Mode
+    pub fn chmod(&mut self, creds: &Credentials, mode: u16) -> KResult<()> {
+        if !creds.is_root() && creds.uid != self.uid { return Err("Permission Denied"); }
+        self.mode = Mode::new(mode);
+        Ok(())
+    }
+
+    /// Only Root May Give An Inode Away, Moving What It Owns To The New Owner's Quota
+    pub fn chown(&mut self, creds: &Credentials, uid: Uid, gid: Gid) -> KResult<()> {
+        if !creds.is_root() { return Err("Permission Denied"); }
+        if uid != self.uid {
+            let blocks = if self.flags.is_file() || self.flags.is_symlink() { self.owned_blocks().len() as u32 } else { 0 };
+            quota::charge(uid, blocks, 1)?;
+            quota::release(self.uid, blocks, 1);
+        }
+        self.uid = uid;
+        self.gid = gid;
+        Ok(())
+    }
+
+    pub fn set_parent(&mut self, parent: Option<u16>) {
+        self.parent = parent;
+    }
+
+    pub fn addr(&self) -> u32 {
+        self.addr
+    }
+
+    pub fn size(&self) -> u32 {
+        self.size
+    }
+
+    pub fn set_size(&mut self, size: u32) {
+        self.size = size;
+    }
+
     pub fn name(&self) -> &String {
         &self.name
     }
@@ -397,38 +572,251 @@ impl Inode {
         self.children.push(index);
     }
 
+    pub fn remove_child(&mut self, index: u32) {
+        self.children.retain(|child| *child != index);
+    }
+
     pub fn clear_children(&mut self) {
+        if self.flags.is_file() || self.flags.is_symlink() {
+            self.truncate_blocks(0);
+            return;
+        }
         for child in &self.children {
-            if self.flags.is_dir() { InodeBitmap::free(*child) };
-            if self.flags.is_file() { DataBitmap::free(*child) };
+            if self.flags.is_dir() { InodeBitmap::release(*child, Inode::read(*child).uid) };
         }
         self.children.clear();
     }
 
+    /// The Data Block Holding Logical Block `n` Of A File, `None` For A Hole
+    pub fn block(&self, n: usize) -> Option<u32> {
+        let pointer = if n < CHILDREN_LEN {
+            self.children.get(n).copied().unwrap_or(NO_BLOCK)
+        } else if n < CHILDREN_LEN + POINTERS_PER_BLOCK {
+            read_pointers(self.indirect)[n - CHILDREN_LEN]
+        } else if n < MAX_BLOCKS {
+            let n = n - CHILDREN_LEN - POINTERS_PER_BLOCK;
+            read_pointers(read_pointers(self.double_indirect)[n / POINTERS_PER_BLOCK])[n % POINTERS_PER_BLOCK]
+        } else {
+            NO_BLOCK
+        };
+        Some(pointer).filter(|pointer| *pointer != NO_BLOCK)
+    }
+
+    /// Maps Logical Block `n` To Data Block `index` (`NO_BLOCK` Punches A Hole), Allocating
+    /// Pointer Blocks As Needed
+    pub fn set_block(&mut self, n: usize, index: u32) -> KResult<()> {
+        if n < CHILDREN_LEN {
+            if self.children.len() <= n { self.children.resize(n + 1, NO_BLOCK); }
+            self.children[n] = index;
+            while self.children.last() == Some(&NO_BLOCK) { self.children.pop(); }
+        } else if n < CHILDREN_LEN + POINTERS_PER_BLOCK {
+            self.indirect = write_pointer(self.indirect, n - CHILDREN_LEN, index, self.uid)?;
+        } else if n < MAX_BLOCKS {
+            let n = n - CHILDREN_LEN - POINTERS_PER_BLOCK;
+            let old_mid = read_pointers(self.double_indirect)[n / POINTERS_PER_BLOCK];
+            let mid = write_pointer(old_mid, n % POINTERS_PER_BLOCK, index, self.uid)?;
+            match write_pointer(self.double_indirect, n / POINTERS_PER_BLOCK, mid, self.uid) {
+                Ok(block) => self.double_indirect = block,
+                Err(e) => {
+                    // Nothing Points At A Pointer Block Allocated Just Now Yet
+                    if old_mid == NO_BLOCK && mid != NO_BLOCK { DataBlocks::free(mid, self.uid); }
+                    return Err(e);
+                },
+            }
+        } else {
+            return Err("File Too Large");
+        }
+        Ok(())
+    }
+
+    /// Frees Every Data Block From Logical Block `keep` On, Along With Any Pointer Block Left
+    /// Empty. Only Allocated Pointers Are Visited, So Truncating A Sparse File Is Cheap.
+    fn truncate_blocks(&mut self, keep: usize) {
+        for block in self.children.iter().skip(keep) {
+            if *block != NO_BLOCK { DataBlocks::free(*block, self.uid); }
+        }
+        self.children.truncate(keep);
+        while self.children.last() == Some(&NO_BLOCK) { self.children.pop(); }
+        self.indirect = free_pointers(self.indirect, keep.saturating_sub(CHILDREN_LEN), 1, self.uid);
+        self.double_indirect = free_pointers(self.double_indirect, keep.saturating_sub(CHILDREN_LEN + POINTERS_PER_BLOCK), 2, self.uid);
+    }
+
+    /// Resizes A File On Disk Without Reading It, Growing Leaves A Hole At The End
+    pub fn truncate(&mut self, size: u32) {
+        let keep = (size as usize + BLOCK_SIZE - 1) / BLOCK_SIZE;
+        self.truncate_blocks(keep);
+
+        // Zero The Tail Of The Last Block So Growing Again Reads Zeros There
+        let tail = size as usize % BLOCK_SIZE;
+        if size < self.size && tail != 0 {
+            if let Some(index) = self.block(keep - 1) {
+                let mut node = DataBlocks::read(index);
+                node.data_mut()[tail..].fill(0);
+                node.sync();
+            }
+        }
+        self.size = size;
+        self.touch_modified();
+    }
+
+    /// Data Block Of Every Logical Block Up To The File's Size, `None` For Holes
+    pub fn block_map(&self) -> Vec<Option<u32>> {
+        let count = (self.size as usize + BLOCK_SIZE - 1) / BLOCK_SIZE;
+        let mut pointers = self.children.clone();
+        pointers.resize(CHILDREN_LEN, NO_BLOCK);
+        if count > CHILDREN_LEN {
+            pointers.extend(read_pointers(self.indirect));
+        }
+        if count > CHILDREN_LEN + POINTERS_PER_BLOCK {
+            for mid in read_pointers(self.double_indirect) {
+                pointers.extend(read_pointers(mid));
+            }
+        }
+        pointers.resize(count, NO_BLOCK);
+        pointers.into_iter().map(|pointer| Some(pointer).filter(|pointer| *pointer != NO_BLOCK)).collect()
+    }
+
+    /// Every Data Block A File Owns, Pointer Blocks Included & Holes Skipped
+    pub fn owned_blocks(&self) -> Vec<u32> {
+        let mut blocks: Vec<u32> = self.children.iter().copied().filter(|block| *block != NO_BLOCK).collect();
+        collect_pointers(self.indirect, 1, &mut blocks);
+        collect_pointers(self.double_indirect, 2, &mut blocks);
+        blocks
+    }
+
+    /// Turns Every Reference To Data Block `block` Into A Hole, How fsck Drops A Bad Pointer
+    pub fn drop_block(&mut self, block: u32) {
+        for child in self.children.iter_mut().filter(|child| **child == block) {
+            *child = NO_BLOCK;
+        }
+        while self.children.last() == Some(&NO_BLOCK) { self.children.pop(); }
+        if self.indirect == block { self.indirect = NO_BLOCK; }
+        if self.double_indirect == block { self.double_indirect = NO_BLOCK; }
+
+        let mut pointer_blocks = Vec::from([self.indirect, self.double_indirect]);
+        pointer_blocks.extend(read_pointers(self.double_indirect));
+        for pointer_block in pointer_blocks {
+            if pointer_block == NO_BLOCK || pointer_block >= DATA_SIZE as u32 { continue; }
+            let mut node = DataBlocks::read(pointer_block);
+            let mut changed = false;
+            for i in 0..POINTERS_PER_BLOCK {
+                if node.pointer(i) == block {
+                    node.set_pointer(i, NO_BLOCK);
+                    changed = true;
+                }
+            }
+            if changed { node.sync(); }
+        }
+    }
+
+    /// A File's Or Symlink's Contents, Straight From Its Data Blocks With Holes As Zeros
+    pub fn data(&self) -> Vec<u8> {
+        let mut data = Vec::with_capacity(self.size as usize);
+        for block in self.block_map() {
+            match block {
+                Some(index) => data.extend_from_slice(DataBlocks::read(index).data()),
+                None => data.resize(data.len() + BLOCK_SIZE, 0),
+            }
+        }
+        data.truncate(self.size as usize);
+        data
+    }
+
+    /// Frees The Inode & Whatever It Owns, The Caller Unlinks It From Its Directory
+    pub fn free(mut self) {
+        self.clear_children();
+        InodeBitmap::release(self.addr, self.uid);
+    }
+
     fn physical_addr(index: u32) -> u32 {
-        logical_to_physical(INODE_BASE, index)
+        addressing::inode(index)
     }
 
     
 }
 
+/// Entries Of A Pointer Block, All `NO_BLOCK` When There Is No (Valid) Block
+fn read_pointers(block: u32) -> Vec<u32> {
+    if block == NO_BLOCK || block >= DATA_SIZE as u32 {
+        return alloc::vec![NO_BLOCK; POINTERS_PER_BLOCK];
+    }
+    let node = DataBlocks::read(block);
+    (0..POINTERS_PER_BLOCK).map(|i| node.pointer(i)).collect()
+}
+
+/// Sets Entry `i` Of A Pointer Block, Allocating The Block (Charged To `owner`) First If There
+/// Isn't One Yet. Returns The Pointer Block, Which Is New If One Had To Be Allocated.
+fn write_pointer(block: u32, i: usize, value: u32, owner: Uid) -> KResult<u32> {
+    if block == NO_BLOCK && value == NO_BLOCK { return Ok(NO_BLOCK); }
+    let mut node = if block == NO_BLOCK {
+        let mut node = DataNode::alloc(owner)?;
+        for j in 0..POINTERS_PER_BLOCK { node.set_pointer(j, NO_BLOCK); }
+        node
+    } else {
+        DataBlocks::read(block)
+    };
+    node.set_pointer(i, value);
+    node.sync();
+    Ok(node.logical_addr())
+}
+
+/// Frees What A Pointer Block Maps From Logical Block `keep` (Counted From The Start Of The
+/// Block) On, `depth` Being 1 For A Block Of Data Pointers & 2 For A Block Of Pointer Blocks.
+/// Returns `NO_BLOCK` If The Pointer Block Itself Was Freed For Having Nothing Left In It.
+fn free_pointers(block: u32, keep: usize, depth: u32, owner: Uid) -> u32 {
+    let span = POINTERS_PER_BLOCK.pow(depth - 1);
+    if block == NO_BLOCK || block >= DATA_SIZE as u32 || keep >= span * POINTERS_PER_BLOCK { return block; }
+
+    let mut node = DataBlocks::read(block);
+    for i in 0..POINTERS_PER_BLOCK {
+        let pointer = node.pointer(i);
+        if pointer == NO_BLOCK || (i + 1) * span <= keep { continue; }
+        let left = if depth > 1 {
+            free_pointers(pointer, keep.saturating_sub(i * span), depth - 1, owner)
+        } else {
+            DataBlocks::free(pointer, owner);
+            NO_BLOCK
+        };
+        node.set_pointer(i, left);
+    }
+
+    if (0..POINTERS_PER_BLOCK).all(|i| node.pointer(i) == NO_BLOCK) {
+        DataBlocks::free(block, owner);
+        NO_BLOCK
+    } else {
+        node.sync();
+        block
+    }
+}
+
+fn collect_pointers(block: u32, depth: u32, blocks: &mut Vec<u32>) {
+    if block == NO_BLOCK { return; }
+    blocks.push(block);
+    for pointer in read_pointers(block).into_iter().filter(|pointer| *pointer != NO_BLOCK) {
+        if depth > 1 { collect_pointers(pointer, depth - 1, blocks); } else { blocks.push(pointer); }
+    }
+}
+
+/// How Many Symlinks One Lookup May Follow Before Giving Up
+pub const MAX_SYMLINK_DEPTH: usize = 8;
+
 pub struct InodeBlocks;
 
 impl InodeBlocks {
-    pub fn create_file(name: &str, parent_dir: Option<u16>) -> Option<Inode> {
-        Inode::create(name,  InodeFlags::file(), parent_dir)
+    pub fn create_file(name: &str, parent_dir: Option<u16>, owner: Credentials) -> Option<Inode> {
+        Inode::create(name,  InodeFlags::file(), parent_dir, owner).ok()
     }
 
-    pub fn create_dir(name: &str, parent_dir: Option<u16>) -> Option<Inode> {
-        Inode::create(name,  InodeFlags::dir(), parent_dir)
+    pub fn create_dir(name: &str, parent_dir: Option<u16>, owner: Credentials) -> Option<Inode> {
+        Inode::create(name,  InodeFlags::dir(), parent_dir, owner).ok()
     }
 
-    pub fn open_file(name: &str) -> Option<File> {
+    pub fn open_file(name: &str) -> Option<Inode> {
         print!("Locating File");
         for index in 0..INODE_SIZE {
             if index % 64 == 0 {print!(".")};
             let inode = Inode::read(index as u32);
-            if inode.name() == name {return Some(File::from_inode(inode))};
+            if inode.name() == name {return Some(inode)};
         }
         return None;
     }
@@ -437,6 +825,188 @@ impl InodeBlocks {
         InodeBitmap::get_allocated(buffer);
     }
 
+    /// Entries Of A Directory, `None` Being The (Implicit) Root Holding Every Parentless Inode
+    pub fn entries(dir: Option<&Inode>) -> Vec<Inode> {
+        match dir {
+            Some(dir) => dir.children().iter().map(|child| Inode::read(*child)).collect(),
+            None => {
+                let mut inodes = Vec::new();
+                Self::inodes(&mut inodes);
+                inodes.retain(|inode| inode.parent().is_none());
+                inodes
+            },
+        }
+    }
+
+    /// Resolves A `/` Separated Path From The Root, `Ok(None)` Being The Root Itself. Symlinks
+    /// Are Followed Along The Way, And At The End Too When `follow` Is Set. Hard Link Entries
+    /// Always Resolve To The Inode They Name.
+    pub fn lookup(path: &str, follow: bool) -> KResult<Option<Inode>> {
+        let mut depth = 0;
+        Self::walk(None, path, follow, &mut depth)
+    }
+
+    fn walk(start: Option<Inode>, path: &str, follow: bool, depth: &mut usize) -> KResult<Option<Inode>> {
+        let mut current = if path.starts_with('/') { None } else { start };
+        let parts: Vec<&str> = path.split('/').filter(|part| !part.is_empty() && *part != ".").collect();
+
+        for (i, part) in parts.iter().enumerate() {
+            if let Some(dir) = &current {
+                if !dir.flags().is_dir() { return Err("Not A Directory"); }
+            }
+            if *part == ".." {
+                current = current.and_then(|dir| dir.parent()).map(|parent| Inode::read(parent as u32));
+                continue;
+            }
+
+            let entry = Self::entries(current.as_ref()).into_iter().find(|entry| entry.name() == part).ok_or("No Such File")?;
+            let last = i + 1 == parts.len();
+            current = Self::resolve(current, entry, follow || !last, depth)?;
+        }
+        Ok(current)
+    }
+
+    /// What A Directory Entry Found In `dir` Stands For. Every Symlink Followed Counts Towards
+    /// `MAX_SYMLINK_DEPTH`, Which Is What Stops A Loop Of Links From Walking Forever.
+    fn resolve(dir: Option<Inode>, entry: Inode, follow: bool, depth: &mut usize) -> KResult<Option<Inode>> {
+        if entry.flags().is_hardlink() {
+            let target = entry.children().first().ok_or("Broken Hard Link")?;
+            return Ok(Some(Inode::read(*target)));
+        }
+        if !follow || !entry.flags().is_symlink() { return Ok(Some(entry)); }
+
+        *depth += 1;
+        if *depth > MAX_SYMLINK_DEPTH { return Err("Too Many Levels Of Symbolic Links"); }
+        let target = String::from_utf8_lossy(&entry.data()).into_owned();
+        Self::walk(dir, &target, true, depth)
+    }
+
+    /// Splits A Path Into Its Directory (`None` Being The Root) & Final Name
+    fn split(path: &str) -> KResult<(Option<Inode>, &str)> {
+        let path = path.trim_end_matches('/');
+        let (dir, name) = match path.rfind('/') {
+            Some(i) => (&path[..i], &path[i + 1..]),
+            None => ("", path),
+        };
+        if name.is_empty() || name == "." || name == ".." || name.len() > FILENAME_SIZE { return Err("Invalid Name"); }
+
+        let dir = Self::lookup(dir, true)?;
+        if let Some(dir) = &dir {
+            if !dir.flags().is_dir() { return Err("Not A Directory"); }
+        }
+        Ok((dir, name))
+    }
+
+    fn check_writable(dir: &Option<Inode>, creds: &Credentials) -> KResult<()> {
+        match dir {
+            Some(dir) if !dir.permits(creds, Access::Write) => Err("Permission Denied"),
+            _ => Ok(()),
+        }
+    }
+
+    /// Creates An Empty Inode Named By `path`, Listed In Its Directory
+    pub fn create_entry(path: &str, flags: InodeFlags, creds: Credentials) -> KResult<Inode> {
+        let (dir, name) = Self::split(path)?;
+        Self::check_writable(&dir, &creds)?;
+        if Self::entries(dir.as_ref()).iter().any(|entry| entry.name() == name) { return Err("Already Exists"); }
+        if dir.as_ref().map_or(false, |dir| dir.children().len() >= CHILDREN_LEN) { return Err("Directory Is Full"); }
+
+        let inode = Inode::create(name, flags, dir.as_ref().map(|dir| dir.addr() as u16), creds)?;
+        if let Some(mut dir) = dir {
+            dir.add_child(inode.addr());
+            dir.touch_modified();
+            dir.write();
+        }
+        Ok(inode)
+    }
+
+    pub fn symlink(target: &str, path: &str, creds: Credentials) -> KResult<Inode> {
+        if target.is_empty() { return Err("Invalid Target"); }
+        let inode = Self::create_entry(path, InodeFlags::symlink(), creds)?;
+        let mut file = File::from_inode(inode, creds);
+        for byte in target.bytes() {
+            file.append(byte)?;
+        }
+        file.close()?;
+        Ok(file.inode)
+    }
+
+    pub fn read_link(path: &str) -> KResult<String> {
+        let inode = Self::lookup(path, false)?.ok_or("Not A Symbolic Link")?;
+        if !inode.flags().is_symlink() { return Err("Not A Symbolic Link"); }
+        Ok(String::from_utf8_lossy(&inode.data()).into_owned())
+    }
+
+    /// Gives An Existing Inode Another Name. Directories Can't Be Hard Linked, So The Tree Stays A Tree.
+    pub fn link(existing: &str, path: &str, creds: Credentials) -> KResult<Inode> {
+        let mut target = Self::lookup(existing, false)?.ok_or("Can't Link The Root")?;
+        if target.flags().is_dir() { return Err("Can't Hard Link A Directory"); }
+        if target.links() == u16::MAX { return Err("Too Many Links"); }
+
+        let mut entry = Self::create_entry(path, InodeFlags::hardlink(), creds)?;
+        entry.add_child(target.addr());
+        entry.write();
+        target.links += 1;
+        target.write();
+        Ok(entry)
+    }
+
+    /// Hard Link Entries Naming `addr`
+    fn link_entries(addr: u32) -> Vec<Inode> {
+        let mut inodes = Vec::new();
+        Self::inodes(&mut inodes);
+        inodes.retain(|inode| inode.flags().is_hardlink() && inode.children().first() == Some(&addr));
+        inodes
+    }
+
+    fn detach(dir: Option<Inode>, entry: &Inode) {
+        if let Some(mut dir) = dir {
+            dir.remove_child(entry.addr());
+            dir.touch_modified();
+            dir.write();
+        }
+    }
+
+    /// Removes A Name, Freeing The Inode's Data Only Once Its Last Name Is Gone. When The Name
+    /// Being Removed Is The Inode's Own, One Of Its Hard Link Entries Hands Over Its Name &
+    /// Place Instead, So The Inode Keeps Its Index.
+    pub fn unlink(path: &str, creds: Credentials) -> KResult<()> {
+        let (dir, name) = Self::split(path)?;
+        Self::check_writable(&dir, &creds)?;
+        let entry = Self::entries(dir.as_ref()).into_iter().find(|entry| entry.name() == name).ok_or("No Such File")?;
+        if entry.flags().is_dir() { return Err("Is A Directory"); }
+
+        if entry.flags().is_hardlink() {
+            if let Some(target) = entry.children().first() {
+                let mut target = Inode::read(*target);
+                target.links = target.links.saturating_sub(1).max(1);
+                target.write();
+            }
+            Self::detach(dir, &entry);
+            entry.free();
+            return Ok(());
+        }
+
+        let mut inode = entry;
+        Self::detach(dir, &inode);
+        match Self::link_entries(inode.addr()).pop() {
+            Some(link) => {
+                if let Some(mut link_dir) = link.parent().map(|parent| Inode::read(parent as u32)) {
+                    link_dir.remove_child(link.addr());
+                    link_dir.add_child(inode.addr());
+                    link_dir.write();
+                }
+                inode.name = link.name.clone();
+                inode.parent = link.parent;
+                inode.links = inode.links.saturating_sub(1).max(1);
+                inode.write();
+                InodeBitmap::release(link.addr(), link.uid);
+            },
+            None => inode.free(),
+        }
+        Ok(())
+    }
+
     pub fn debug() {
         log!("==== Inodes ====");
         let mut inodes = Vec::new();
@@ -451,25 +1021,43 @@ pub struct DataBlocks;
 
 impl DataBlocks {
     pub fn read(index: u32) -> DataNode {
-        let block = Block::read(logical_to_physical(DATA_BASE, index)).unwrap();
+        let block = Block::read(addressing::data(index)).unwrap();
         DataNode::new(index, block.data())
     }
 
-    pub fn allocate(count: usize, buffer: &mut Vec<DataNode>) -> usize {
+    /// Allocates Up To `count` Blocks Owned By `owner`, Returning How Many It Got. The Whole
+    /// Request Must Fit In `owner`'s Quota Up Front.
+    pub fn allocate(count: usize, buffer: &mut Vec<DataNode>, owner: Uid) -> KResult<usize> {
+        quota::charge(owner, count as u32, 0)?;
         print!("Allocating {} Blocks", count);
         let mut pct = count / 100;
         if pct == 0 {pct = 1};
 
         for i in 0..count {
             if i % pct == 0 { print!("{:02.3}%\r", (i as f32 / count as f32) * 100.0); }
-            if let Some(block) = DataNode::alloc() {
-                buffer.push(block);
+            if let Some(index) = DataBitmap::allocate_next() {
+                buffer.push(Self::read(index));
             } else {
-                return i;
+                quota::release(owner, (count - i) as u32, 0);
+                return Ok(i);
             }
         }
 
-        return count;
+        return Ok(count);
+    }
+
+    /// Allocates One Block Owned By `owner`
+    pub fn allocate_next(owner: Uid) -> KResult<u32> {
+        let index = DataBitmap::next_free().ok_or("No Free Data Blocks")?;
+        quota::charge(owner, 1, 0)?;
+        DataBitmap::allocate(index);
+        Ok(index)
+    }
+
+    /// Frees A Block & Gives It Back To `owner`'s Quota
+    pub fn free(index: u32, owner: Uid) {
+        DataBitmap::free(index);
+        quota::release(owner, 1, 0);
     }
 }
 
@@ -493,14 +1081,10 @@ impl DataNode {
         DataNode { logical_address: index, data: buffer }
     }
 
-    pub fn alloc() -> Option<DataNode> {
-        if let Some(index) = DataBitmap::allocate_next() {
-            let block = Block::read(logical_to_physical(DATA_BASE, index)).unwrap();
-            return Some(DataNode::new(index, block.data()));
-        } else {
-            None
-        }
-    } 
+    pub fn alloc(owner: Uid) -> KResult<DataNode> {
+        let index = DataBlocks::allocate_next(owner)?;
+        Ok(DataBlocks::read(index))
+    }
 
     pub fn data(&self) -> &[u8] {
         &self.data
@@ -515,7 +1099,7 @@ impl DataNode {
     }
 
     pub fn physical_addr(&self) -> u32 {
-        logical_to_physical(DATA_BASE, self.logical_address)
+        addressing::data(self.logical_address)
     }
 
     pub fn sync(&self) {
@@ -523,7 +1107,18 @@ impl DataNode {
         for i in 0..self.data.len() {
             block[i] = self[i];
         }
-        block.write();
+        block.write().unwrap();
+    }
+
+    /// Entry `i` When The Block Holds Pointers (Indirect Blocks), Big Endian Like Inodes
+    pub fn pointer(&self, i: usize) -> u32 {
+        let mut bytes = [0; 4];
+        bytes.copy_from_slice(&self.data[i * 4..i * 4 + 4]);
+        u32::from_be_bytes(bytes)
+    }
+
+    pub fn set_pointer(&mut self, i: usize, value: u32) {
+        self.data[i * 4..i * 4 + 4].copy_from_slice(&value.to_be_bytes());
     }
 
 
@@ -564,6 +1159,7 @@ pub struct File {
     pos: u32,
     inode: Inode,
     data: Vec<u8>,
+    creds: Credentials,
 }
 
 impl File {
@@ -580,70 +1176,84 @@ impl File {
         return self.inode.addr
     }
 
-    pub fn open(name: &str) -> Option<File> {
-        InodeBlocks::open_file(name)
+    /// Opens A File For `access`, Which `creds` Must Be Allowed By The File's Mode. Later
+    /// Reads & Writes Are Checked Against The Same Credentials.
+    pub fn open(path: &str, access: Access, creds: Credentials) -> KResult<File> {
+        let mut inode = InodeBlocks::lookup(path, true)?.ok_or("Is A Directory")?;
+        if inode.flags().is_dir() { return Err("Is A Directory"); }
+        if !inode.permits(&creds, access) { return Err("Permission Denied"); }
+        inode.touch_accessed();
+        Ok(File::from_inode(inode, creds))
     }
 
-    pub fn open_or_create(name: &str) -> Option<File> {
-        if let Some(file) = Self::open(name) {
-            Some(file)
-        } else {
-            Self::new(name)
+    pub fn open_or_create(path: &str, creds: Credentials) -> KResult<File> {
+        match Self::open(path, Access::Write, creds) {
+            Err("No Such File") => Self::new(path, creds),
+            result => result,
         }
     }
 
-    pub fn new(name: &str) -> Option<File> {
-        let inode = InodeBlocks::create_file(name, None);
-        if let Some(inode) = inode {
-        Some(File::from_inode(inode))
-        } else { None }
+    pub fn new(path: &str, creds: Credentials) -> KResult<File> {
+        let inode = InodeBlocks::create_entry(path, InodeFlags::file(), creds)?;
+        Ok(File::from_inode(inode, creds))
     }
 
     
-    pub fn from_inode(node: Inode) -> Self {
-        let mut data = Vec::new();
-        for index in node.children() {
-            let block = DataBlocks::read(*index);
-            for i in 0..block.data().len() {
-                if data.len() > node.size as usize {break;}
-                data.push(block[i]);
-            }
-        }
+    pub fn from_inode(node: Inode, creds: Credentials) -> Self {
         Self {
-            data,
+            data: node.data(),
             inode: node,
             pos: 0,
+            creds,
         }
     }
 
-    pub fn write(&mut self, value: u8) {
-        if !self.inode.flags().can_write() {panic!("File Cannot Be Written To!")}
+    fn check(&self, access: Access) -> KResult<()> {
+        if self.inode.permits(&self.creds, access) { Ok(()) } else { Err("Permission Denied") }
+    }
+
+    pub fn write(&mut self, value: u8) -> KResult<()> {
+        self.check(Access::Write)?;
         assert!(self.pos < self.size() as u32, "Position Out Of Bounds, pos: {}, size: {}", self.pos, self.size());
         self.data[self.pos as usize] = value;
         self.pos += 1;
+        self.inode.touch_modified();
+        Ok(())
     }
 
-    pub fn read(&mut self) -> u8 {
-        if !self.inode.flags().can_read() {panic!("File Cannot Be Read")}
+    pub fn read(&mut self) -> KResult<u8> {
+        self.check(Access::Read)?;
         assert!(self.pos < self.size() as u32);
         let value = self.data[self.pos as usize];
         self.pos += 1;
-        value
+        Ok(value)
     }
 
-    pub fn append(&mut self, data: u8) {
+    pub fn append(&mut self, data: u8) -> KResult<()> {
+        self.check(Access::Write)?;
         self.data.push(data);
+        self.inode.touch_modified();
+        Ok(())
     }
 
-    pub fn truncate(&mut self, len: usize) {
-        self.data.truncate(len)
+    /// Shrinks Or Grows The File, Growing Pads With Zeros Which `close` Leaves As Holes
+    pub fn truncate(&mut self, len: usize) -> KResult<()> {
+        self.check(Access::Write)?;
+        if len > MAX_BLOCKS * BLOCK_SIZE { return Err("File Too Large"); }
+        self.data.resize(len, 0);
+        self.pos = self.pos.min(len as u32);
+        self.inode.touch_modified();
+        Ok(())
     }
 
     pub fn copy_into(&mut self, buffer: &mut [u8]) -> usize {
         self.to_start();
         if self.can_operate_on(buffer.len()) {
             for index in 0..buffer.len() {
-                buffer[index] = self.read();
+                match self.read() {
+                    Ok(value) => buffer[index] = value,
+                    Err(e) => { warn!("{}", e); return index; },
+                }
             }
             buffer.len()
         } else {
@@ -674,32 +1284,53 @@ impl File {
         self.data.len()
     }
 
-    pub fn close(&mut self) {
-        self.inode.clear_children();
-        let chunks = self.data.chunks(BLOCK_SIZE);
-        let mut blocks = Vec::new();
-        breakpoint!("Allocating {} Data Blocks",chunks.len());
-        DataBlocks::allocate(chunks.len(), &mut blocks);
-        serial_print!("Writing To Disk");
-        for (idx,chunk) in chunks.enumerate() {
-            let block = &mut blocks[idx];
-            for idx in 0..chunk.len() {
-                block[idx] = chunk[idx];
-            }
-            serial_print!(".");
-            block.sync();
-        }
-        serial_println!();
-        debug!("Adding {} Blocks To Children", blocks.len());
+    /// Writes The File Back, Reusing The Blocks It Already Has. All Zero Blocks Are Stored As
+    /// Holes & Blocks Past The New End Are Freed. If Running Out Of Space Or Quota Stops It
+    /// Part Way, The Inode Is Still Written So It Owns Exactly The Blocks It Was Left With.
+    pub fn close(&mut self) -> KResult<()> {
+        let blocks = (self.data.len() + BLOCK_SIZE - 1) / BLOCK_SIZE;
+        if blocks > MAX_BLOCKS { return Err("File Too Large"); }
 
-        for block in blocks {
-            self.inode.add_child(block.logical_addr());
+        let result = self.write_blocks();
+        if result.is_ok() {
+            self.inode.truncate_blocks(blocks);
+            self.inode.size = self.data.len() as u32;
         }
 
-        self.inode.size = self.data.len() as u32;
-
         debug!("Writing Out Inode");
         self.inode.write();
+        result
+    }
+
+    /// Every Block Is Mapped Or Unmapped In The Inode As Soon As It's Allocated Or Freed
+    fn write_blocks(&mut self) -> KResult<()> {
+        serial_print!("Writing To Disk");
+        for (n, chunk) in self.data.chunks(BLOCK_SIZE).enumerate() {
+            let existing = self.inode.block(n);
+            if chunk.iter().all(|byte| *byte == 0) {
+                if let Some(index) = existing {
+                    self.inode.set_block(n, NO_BLOCK)?;
+                    DataBlocks::free(index, self.inode.uid);
+                }
+                continue;
+            }
+
+            let index = match existing {
+                Some(index) => index,
+                None => {
+                    let index = DataBlocks::allocate_next(self.inode.uid)?;
+                    if let Err(e) = self.inode.set_block(n, index) {
+                        DataBlocks::free(index, self.inode.uid);
+                        return Err(e);
+                    }
+                    index
+                },
+            };
+            serial_print!(".");
+            DataNode::new(index, chunk).sync();
+        }
+        serial_println!();
+        Ok(())
     }
 }
 
@@ -712,18 +1343,23 @@ impl InodeFlags {
     }
 
     pub fn file() -> Self {
-        use inode_flags::*;
-        Self(FILE | ROOT_READ | ROOT_WRITE)
+        Self(FILE)
     }
 
     pub fn dir() -> Self {
-        use inode_flags::*;
-        Self(DIR | ROOT_READ | ROOT_WRITE)
+        Self(DIR)
     }
 
     pub fn dev() -> Self {
-        use inode_flags::*;
-        Self(DIR | ROOT_READ | ROOT_WRITE)
+        Self(DEV)
+    }
+
+    pub fn symlink() -> Self {
+        Self(SYMLINK)
+    }
+
+    pub fn hardlink() -> Self {
+        Self(HARDLINK)
     }
 
     pub fn value(&self) -> u16 {
@@ -742,16 +1378,12 @@ impl InodeFlags {
         return self.0 & DIR > 0;
     }
 
-    pub fn can_read(&self) -> bool {
-        return self.0 & ROOT_READ > 0;
-    }
-
-    pub fn can_write(&self) -> bool {
-        return self.0 & ROOT_WRITE > 0;
+    pub fn is_symlink(&self) -> bool {
+        return self.0 & SYMLINK > 0;
     }
 
-    pub fn can_exec(&self) -> bool {
-        return self.0 & ROOT_EXEC > 0;
+    pub fn is_hardlink(&self) -> bool {
+        return self.0 & HARDLINK > 0;
     }
 
     pub fn is_hidden(&self) -> bool {
@@ -774,24 +1406,262 @@ impl Display for InodeFlags {
         if self.0 & inode_flags::DEV >  0{write!(f, "Dv-")?;};
         if self.0 & inode_flags::DIR >  0{write!(f, "Dr-")?;};
         if self.0 & inode_flags::FILE > 0{write!(f, "Fi-")?;};
+        if self.0 & inode_flags::SYMLINK > 0{write!(f, "Sl-")?;};
+        if self.0 & inode_flags::HARDLINK > 0{write!(f, "Hl-")?;};
 
         if self.0 & inode_flags::HIDDEN > 0{write!(f, "H")?;} else {write!(f, "-")?;};
-        if self.0 & inode_flags::ROOT_READ > 0{write!(f, "R")?;} else {write!(f, "-")?;};
-        if self.0 & inode_flags::ROOT_WRITE > 0{write!(f, "W")?;} else {write!(f, "-")?;};
-        if self.0 & inode_flags::ROOT_EXEC > 0{write!(f, "X")?;} else {write!(f, "-")?;};
 
         Ok(())
     }
 }
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Access {
+    Read,
+    Write,
+    Exec,
+}
+
+impl Access {
+    fn bit(self) -> u16 {
+        match self {
+            Self::Read => inode_mode::READ,
+            Self::Write => inode_mode::WRITE,
+            Self::Exec => inode_mode::EXEC,
+        }
+    }
+}
+
+/// The rwx Bits For An Inode's Owner, Group & Everyone Else
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Mode(u16);
+
+impl Mode {
+    pub fn new(value: u16) -> Self {
+        Self(value & 0o777)
+    }
+
+    pub fn default_for(flags: InodeFlags) -> Self {
+        if flags.is_dir() {
+            Self(inode_mode::DEFAULT_DIR)
+        } else if flags.is_symlink() || flags.is_hardlink() {
+            Self(inode_mode::DEFAULT_LINK)
+        } else {
+            Self(inode_mode::DEFAULT_FILE)
+        }
+    }
+
+    pub fn value(&self) -> u16 {
+        self.0
+    }
+
+    /// Root Passes Every Check, Except It Can Only Exec Something With An Exec Bit Set
+    pub fn permits(&self, uid: Uid, gid: Gid, creds: &Credentials, access: Access) -> bool {
+        if creds.is_root() {
+            return access != Access::Exec || self.0 & inode_mode::ANY_EXEC != 0;
+        }
+        let shift = if creds.uid == uid {
+            inode_mode::OWNER_SHIFT
+        } else if creds.gid == gid {
+            inode_mode::GROUP_SHIFT
+        } else {
+            inode_mode::OTHER_SHIFT
+        };
+        (self.0 >> shift) & access.bit() != 0
+    }
+}
+
+impl Display for Mode {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        for shift in [inode_mode::OWNER_SHIFT, inode_mode::GROUP_SHIFT, inode_mode::OTHER_SHIFT] {
+            let bits = self.0 >> shift;
+            write!(f, "{}", if bits & inode_mode::READ != 0 {'r'} else {'-'})?;
+            write!(f, "{}", if bits & inode_mode::WRITE != 0 {'w'} else {'-'})?;
+            write!(f, "{}", if bits & inode_mode::EXEC != 0 {'x'} else {'-'})?;
+        }
+        Ok(())
+    }
+}
+
+/// Format Version Of The Inode Filesystem On The Mounted Device
+pub fn format_version() -> u16 {
+    let block = Block::read(addressing::superblock()).unwrap();
+    let (magic, _) = block.read_u32(superblock::MAGIC_OFFSET);
+    if magic == superblock::MAGIC { return block.read_u16(superblock::VERSION_OFFSET).0; }
+    let bitmap = Block::read(INODE_BITMAP_BASE).unwrap();
+    if bitmap.data().iter().all(|byte| *byte == 0) { 0 } else { 1 }
+}
+
+fn write_format_version() {
+    let mut block = Block::read(addressing::superblock()).unwrap();
+    block.write_u32(superblock::MAGIC_OFFSET, superblock::MAGIC);
+    block.write_u16(superblock::VERSION_OFFSET, FORMAT_VERSION);
+    block.write().unwrap();
+}
+
+/// Copies Block `from` Over Block `to`
+fn copy_block(from: u32, to: u32) {
+    let mut data = [0; BLOCK_SIZE];
+    data.copy_from_slice(Block::read(from).unwrap().data());
+    Block::from_data(to, data).write().unwrap();
+}
+
+/// Rewrites An Older Filesystem In The Current Layout, Returning How Many Inodes Were Migrated.
+/// Nothing Is Written Unless Every Directory Fits The New Layout. Files With More Blocks Than
+/// Fit Directly Move The Rest Into An Indirect Block. Version 0's Bitmaps Are Rebuilt From Its
+/// Inodes, Which Are Found By Looking At Every Record.
+pub fn migrate() -> KResult<usize> {
+    let version = format_version();
+    match version {
+        FORMAT_VERSION => return Ok(0),
+        0 | 1 | 2 | 3 => {},
+        4 => return migrate_v4(),
+        _ => return Err("Unknown Filesystem Format Version"),
+    }
+
+    let mut inodes = Vec::new();
+    for index in 0..INODE_SIZE as u32 {
+        let allocated = match version {
+            0 => old_layout::v0_is_inode(Block::read(old_layout::inode(0, index))?.data()),
+            _ => InodeBitmap::is_allocated(index),
+        };
+        if allocated {
+            let inode = Inode::read_old(index, version);
+            if !inode.flags.is_file() && !inode.flags.is_symlink() && inode.children.len() > CHILDREN_LEN {
+                return Err("A Directory Has Too Many Children For The Current Format");
+            }
+            inodes.push(inode);
+        }
+    }
+
+    if version == 0 {
+        // Nothing Version 0 Wrote Overlaps Where It All Goes Now, So Order Doesn't Matter
+        for block in DATA_BITMAP_BASE..DATA_BITMAP_BASE + DATA_BITMAP_SIZE as u32 {
+            Block::from_data(block, [0; BLOCK_SIZE]).write()?;
+        }
+        for inode in &inodes {
+            InodeBitmap::allocate(inode.addr);
+            if !inode.flags.is_file() { continue; }
+            for &index in &inode.children {
+                copy_block(old_layout::data(version, index), addressing::data(index));
+                DataBitmap::allocate(index);
+            }
+        }
+    } else {
+        // The Bigger Data Bitmap Pushes Both Regions Back, Moving From The End Keeps Overlapping
+        // Blocks Intact. The Inode Table Is Rewritten From `inodes` Below So Only Data Moves.
+        for index in (0..old_layout::DATA_SIZE as u32).rev() {
+            if DataBitmap::is_allocated(index) {
+                copy_block(old_layout::data(version, index), addressing::data(index));
+            }
+        }
+        for block in DATA_BITMAP_BASE + 1..DATA_BITMAP_BASE + DATA_BITMAP_SIZE as u32 {
+            Block::from_data(block, [0; BLOCK_SIZE]).write()?;
+        }
+    }
+
+    for inode in &mut inodes {
+        if inode.children.len() > CHILDREN_LEN {
+            let blocks = core::mem::take(&mut inode.children);
+            for (n, block) in blocks.into_iter().enumerate() {
+                inode.set_block(n, block)?;
+            }
+        }
+        inode.write();
+    }
+    write_format_version();
+    Ok(inodes.len())
+}
+
+/// Version 4's Data Region Ran Into The COBALTFS Superblock. Its Records Are Unchanged, So
+/// Allocated Inodes & Data Move Down Behind The Smaller Data Bitmap & The Quotas Follow The
+/// Shorter Free Count List. Nothing Is Written If Any Block Past The New Region Is In Use.
+fn migrate_v4() -> KResult<usize> {
+    for block in DATA_BITMAP_BASE + DATA_BITMAP_SIZE as u32..DATA_BITMAP_BASE + old_layout::V4_DATA_BITMAP_SIZE as u32 {
+        if Block::read(block)?.data().iter().any(|byte| *byte != 0) {
+            return Err("Files Use Data Blocks Past The End Of The Current Format");
+        }
+    }
+
+    // Everything Moves Towards The Start, So Copying From The Start Keeps Overlaps Intact
+    let mut count = 0;
+    for index in 0..INODE_SIZE as u32 {
+        if InodeBitmap::is_allocated(index) {
+            copy_block(old_layout::inode(4, index), addressing::inode(index));
+            count += 1;
+        }
+    }
+    for index in 0..DATA_SIZE as u32 {
+        if DataBitmap::is_allocated(index) {
+            copy_block(old_layout::data(4, index), addressing::data(index));
+        }
+    }
+
+    let mut block = Block::read(addressing::superblock())?;
+    let (flags, _) = block.read_u16(superblock::FLAGS_OFFSET);
+    block.write_u16(superblock::FLAGS_OFFSET, flags & !superblock::flags::SUMMARY);
+    block.data_mut().copy_within(old_layout::V4_QUOTA_COUNT_OFFSET.., superblock::QUOTA_COUNT_OFFSET);
+    block.write()?;
+    write_format_version();
+    Ok(count)
+}
+
 impl Display for Inode {
     fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
-        write!(f, "Inode: [name: {}, Flags: {}, children: {:?}, parent: {:?}, Size: {}]",
+        write!(f, "Inode: [name: {}, Flags: {}, Mode: {}, Owner: {}:{}, Links: {}, children: {:?}, parent: {:?}, Size: {}]",
             self.name,
             self.flags,
+            self.mode,
+            self.uid,
+            self.gid,
+            self.links,
             self.children,
             self.parent,
             self.size,
         )
     }
-}
\ No newline at end of file
+}
+
+#[test_case]
+fn mode_permissions() {
+    let mode = Mode::new(0o750);
+    let owner = Credentials::new(1000, 100);
+    assert!(mode.permits(1000, 100, &owner, Access::Write));
+    assert!(mode.permits(1000, 100, &Credentials::new(1001, 100), Access::Exec));
+    assert!(!mode.permits(1000, 100, &Credentials::new(1001, 100), Access::Write));
+    assert!(!mode.permits(1000, 100, &Credentials::new(1002, 200), Access::Read));
+
+    assert!(Mode::new(0o000).permits(1000, 100, &Credentials::ROOT, Access::Write));
+    assert!(!Mode::new(0o644).permits(1000, 100, &Credentials::ROOT, Access::Exec));
+    assert_eq!(alloc::format!("{}", mode), "rwxr-x---");
+    assert_eq!(inode_mode::from_v1_flags(FILE | ROOT_READ | ROOT_WRITE), 0o644);
+}
+
+/// Runs Against The Test Drive (`-hdb`, ATA Bus 0 Drive 1), Which `make image` Formats As The
+/// Inode Filesystem
+#[test_case]
+fn close_with_quota_filling_up() {
+    use crate::sys::storage::fs::{HANDLE, mount_device, dev_handle::{AtaDevice, DeviceHandle}};
+
+    mount_device(DeviceHandle::AtaBlockDevice(AtaDevice::new(0, 1)));
+    let (uid, path) = (4242, "close_quota.bin");
+    let _ = InodeBlocks::unlink(path, Credentials::ROOT);
+    quota::set(&Credentials::ROOT, uid, 2, 0).expect("");
+
+    // Only Two Of The Three Blocks Fit, The File Keeps Those Rather Than Leaking Them
+    let mut file = File::new(path, Credentials::new(uid, uid)).expect("");
+    for _ in 0..3 * BLOCK_SIZE {
+        file.append(0xAB).expect("");
+    }
+    assert_eq!(file.close(), Err("Disk Quota Exceeded"));
+    assert_eq!(Inode::read(file.log_addr()).owned_blocks().len(), 2);
+    assert_eq!(quota::get(uid).expect("").blocks_used, 2);
+
+    file.truncate(0).expect("");
+    file.close().expect("");
+    assert_eq!(quota::get(uid).expect("").blocks_used, 0);
+
+    InodeBlocks::unlink(path, Credentials::ROOT).expect("");
+    quota::remove(&Credentials::ROOT, uid).expect("");
+    *HANDLE.lock() = None;
+}
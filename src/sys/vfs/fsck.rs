@@ -0,0 +1,354 @@
+//! Consistency Checker For The Inode Filesystem
+//!
+//! Inodes Without A Parent Live In The (Implicit) Root Directory, So The Walk Starts From
+//! Every Allocated Parentless Inode And Follows Directory Children Down From There.
+//! Directory Children Are Inode Indices, Files & Symlinks Own Data Blocks (Pointer Blocks
+//! Included, Holes Are Fine) & A Hard Link Entry's Only Child Is The Inode It Names, Which
+//! Counts Towards That Inode's Links.
+
+use core::fmt::Display;
+
+use alloc::collections::{BTreeMap, BTreeSet, VecDeque};
+use alloc::vec::Vec;
+
+use super::filesystem::{DataBitmap, Inode, InodeBitmap, filesystem_values::{DATA_SIZE, INODE_SIZE}, inode_meta::MAX_FILE_SIZE};
+use super::quota;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Problem {
+    /// A Directory Points At An Inode The Bitmap Says Is Free
+    UnallocatedInode { inode: u32, dir: u32 },
+    /// A Directory Points Past The End Of The Inode Table
+    InvalidInode { inode: u32, dir: u32 },
+    /// The Inode Is Reachable From More Than One Directory Entry
+    DuplicateInode { inode: u32, dir: u32 },
+    /// The Inode Is Allocated But No Directory Reaches It
+    OrphanedInode { inode: u32 },
+    /// The Inode's Parent Doesn't Match The Directory Listing It
+    BrokenParent { inode: u32, dir: u32, parent: Option<u16> },
+    /// A File Points At A Data Block The Bitmap Says Is Free
+    UnallocatedBlock { block: u32, inode: u32 },
+    /// A File Points Past The End Of The Data Region
+    InvalidBlock { block: u32, inode: u32 },
+    /// The Data Block Is Claimed By More Than One File
+    DuplicateBlock { block: u32, owner: u32, inode: u32 },
+    /// The Data Block Is Allocated But No File Uses It
+    OrphanedBlock { block: u32 },
+    /// The File Is Larger Than An Inode Can Address
+    SizeMismatch { inode: u32, size: u32, capacity: u32 },
+    /// A Hard Link Entry Names Something That Isn't An Allocated File
+    BrokenLink { inode: u32, target: u32 },
+    /// The Inode's Link Count Doesn't Match How Many Names It Has
+    LinkCount { inode: u32, links: u16, names: u16 },
+    /// The Superblock's Cached Free Counts Or Quota Usage Are Out Of Date
+    StaleSummary,
+}
+
+impl Display for Problem {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match *self {
+            Self::UnallocatedInode { inode, dir } => write!(f, "Inode {} Listed By Dir {} Is Not Allocated", inode, dir),
+            Self::InvalidInode { inode, dir } => write!(f, "Dir {} Lists Invalid Inode {}", dir, inode),
+            Self::DuplicateInode { inode, dir } => write!(f, "Inode {} Is Listed More Than Once (Again By Dir {})", inode, dir),
+            Self::OrphanedInode { inode } => write!(f, "Inode {} Is Allocated But Unreachable", inode),
+            Self::BrokenParent { inode, dir, parent } => write!(f, "Inode {} Is Listed By Dir {} But Has Parent {:?}", inode, dir, parent),
+            Self::UnallocatedBlock { block, inode } => write!(f, "Block {} Used By Inode {} Is Not Allocated", block, inode),
+            Self::InvalidBlock { block, inode } => write!(f, "Inode {} Uses Invalid Block {}", inode, block),
+            Self::DuplicateBlock { block, owner, inode } => write!(f, "Block {} Is Used By Inode {} And Inode {}", block, owner, inode),
+            Self::OrphanedBlock { block } => write!(f, "Block {} Is Allocated But Unused", block),
+            Self::SizeMismatch { inode, size, capacity } => write!(f, "Inode {} Has Size {} But Can Only Address {} Bytes", inode, size, capacity),
+            Self::BrokenLink { inode, target } => write!(f, "Hard Link {} Names Invalid Inode {}", inode, target),
+            Self::LinkCount { inode, links, names } => write!(f, "Inode {} Has Link Count {} But {} Names", inode, links, names),
+            Self::StaleSummary => write!(f, "Superblock Free Counts Or Quota Usage Are Stale"),
+        }
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct Report {
+    pub problems: Vec<Problem>,
+    pub inodes: usize,
+    pub blocks: usize,
+    pub repaired: usize,
+}
+
+impl Report {
+    pub fn is_clean(&self) -> bool {
+        self.problems.is_empty()
+    }
+}
+
+/// Tracks What The Walk Has Reached So Far
+struct Walk {
+    inodes: BTreeMap<u32, Inode>,
+    reachable: BTreeSet<u32>,
+    owners: BTreeMap<u32, u32>,
+    /// Hard Link Entries Reached So Far, Per Inode They Name
+    names: BTreeMap<u32, u16>,
+    dirty: BTreeSet<u32>,
+}
+
+impl Walk {
+    /// Loads Every Inode The Bitmap Claims Is Allocated
+    fn load() -> Self {
+        let mut inodes = BTreeMap::new();
+        for index in 0..INODE_SIZE as u32 {
+            if InodeBitmap::is_allocated(index) {
+                inodes.insert(index, Inode::read(index));
+            }
+        }
+        Self {
+            inodes,
+            reachable: BTreeSet::new(),
+            owners: BTreeMap::new(),
+            names: BTreeMap::new(),
+            dirty: BTreeSet::new(),
+        }
+    }
+
+    /// Breadth First Walk From `start`, Checking Links, Blocks & Sizes As It Goes
+    fn walk(&mut self, start: u32, report: &mut Report, repair: bool) {
+        let mut queue = VecDeque::new();
+        self.reachable.insert(start);
+        queue.push_back(start);
+
+        while let Some(index) = queue.pop_front() {
+            report.inodes += 1;
+            let (is_dir, is_link, children) = match self.inodes.get(&index) {
+                Some(inode) if inode.flags().is_dir() || inode.flags().is_hardlink() => (inode.flags().is_dir(), inode.flags().is_hardlink(), inode.children().clone()),
+                Some(inode) => (false, false, inode.owned_blocks()),
+                None => continue,
+            };
+
+            for child in children {
+                if is_dir {
+                    if let Some(next) = self.check_entry(index, child, report, repair) {
+                        queue.push_back(next);
+                    }
+                } else if is_link {
+                    self.check_link(index, child, report, repair);
+                } else {
+                    self.check_block(index, child, report, repair);
+                }
+            }
+
+            if !is_dir && !is_link {
+                self.check_size(index, report, repair);
+            }
+        }
+    }
+
+    /// Checks One Directory Entry, Returning The Child If The Walk Should Descend Into It
+    fn check_entry(&mut self, dir: u32, child: u32, report: &mut Report, repair: bool) -> Option<u32> {
+        if child >= INODE_SIZE as u32 {
+            report.problems.push(Problem::InvalidInode { inode: child, dir });
+            if repair { self.unlink(dir, child, report); }
+            return None;
+        }
+
+        if self.reachable.contains(&child) {
+            report.problems.push(Problem::DuplicateInode { inode: child, dir });
+            if repair { self.unlink(dir, child, report); }
+            return None;
+        }
+
+        if !self.inodes.contains_key(&child) {
+            report.problems.push(Problem::UnallocatedInode { inode: child, dir });
+            if repair {
+                InodeBitmap::allocate(child);
+                report.repaired += 1;
+            }
+            self.inodes.insert(child, Inode::read(child));
+        }
+
+        let parent = self.inodes[&child].parent();
+        if parent != Some(dir as u16) {
+            report.problems.push(Problem::BrokenParent { inode: child, dir, parent });
+            if repair {
+                if let Some(inode) = self.inodes.get_mut(&child) {
+                    inode.set_parent(Some(dir as u16));
+                }
+                self.dirty.insert(child);
+                report.repaired += 1;
+            }
+        }
+
+        self.reachable.insert(child);
+        Some(child)
+    }
+
+    fn check_block(&mut self, inode: u32, block: u32, report: &mut Report, repair: bool) {
+        if block >= DATA_SIZE as u32 {
+            report.problems.push(Problem::InvalidBlock { block, inode });
+            if repair { self.unlink(inode, block, report); }
+            return;
+        }
+
+        if let Some(&owner) = self.owners.get(&block) {
+            report.problems.push(Problem::DuplicateBlock { block, owner, inode });
+            if repair { self.unlink(inode, block, report); }
+            return;
+        }
+        self.owners.insert(block, inode);
+        report.blocks += 1;
+
+        if !DataBitmap::is_allocated(block) {
+            report.problems.push(Problem::UnallocatedBlock { block, inode });
+            if repair {
+                DataBitmap::allocate(block);
+                report.repaired += 1;
+            }
+        }
+    }
+
+    /// Hard Links Must Name A Non-Directory Inode, A Broken One Is Dropped From Its Directory
+    fn check_link(&mut self, link: u32, target: u32, report: &mut Report, repair: bool) {
+        let valid = match self.inodes.get(&target) {
+            Some(inode) => !inode.flags().is_dir() && !inode.flags().is_hardlink(),
+            None => false,
+        };
+        if valid {
+            *self.names.entry(target).or_insert(0) += 1;
+            return;
+        }
+
+        report.problems.push(Problem::BrokenLink { inode: link, target });
+        if repair {
+            if let Some(parent) = self.inodes.get(&link).and_then(|inode| inode.parent()) {
+                self.unlink(parent as u32, link, report);
+            }
+            InodeBitmap::free(link);
+            self.inodes.remove(&link);
+            report.repaired += 1;
+        }
+    }
+
+    /// Every Reachable Inode Has Its Own Name Plus One Per Hard Link Entry Naming It
+    fn check_links(&mut self, report: &mut Report, repair: bool) {
+        for (index, inode) in self.inodes.iter_mut() {
+            if !self.reachable.contains(index) || inode.flags().is_hardlink() { continue; }
+            let names = 1 + self.names.get(index).copied().unwrap_or(0);
+            if inode.links() != names {
+                report.problems.push(Problem::LinkCount { inode: *index, links: inode.links(), names });
+                if repair {
+                    inode.set_links(names);
+                    self.dirty.insert(*index);
+                    report.repaired += 1;
+                }
+            }
+        }
+    }
+
+    fn check_size(&mut self, index: u32, report: &mut Report, repair: bool) {
+        let inode = match self.inodes.get_mut(&index) {
+            Some(inode) => inode,
+            None => return,
+        };
+        let capacity = MAX_FILE_SIZE as u32;
+        if inode.size() > capacity {
+            report.problems.push(Problem::SizeMismatch { inode: index, size: inode.size(), capacity });
+            if repair {
+                inode.set_size(capacity);
+                self.dirty.insert(index);
+                report.repaired += 1;
+            }
+        }
+    }
+
+    /// Drops A Bad Child Entry From An Inode, A File's Bad Block Becomes A Hole
+    fn unlink(&mut self, index: u32, child: u32, report: &mut Report) {
+        if let Some(inode) = self.inodes.get_mut(&index) {
+            if inode.flags().is_dir() || inode.flags().is_hardlink() {
+                inode.remove_child(child);
+            } else {
+                inode.drop_block(child);
+            }
+            self.dirty.insert(index);
+            report.repaired += 1;
+        }
+    }
+
+    /// An Orphan Heads A Detached Subtree When Its Parent Doesn't List It (Or Is Gone), The
+    /// Rest Of The Subtree Comes Back With It.
+    fn is_detached(&self, index: u32) -> bool {
+        match self.inodes[&index].parent() {
+            None => true,
+            Some(parent) => match self.inodes.get(&(parent as u32)) {
+                Some(dir) => !dir.flags().is_dir() || !dir.has_child(index),
+                None => true,
+            },
+        }
+    }
+
+    fn flush(&self) {
+        for index in &self.dirty {
+            if let Some(inode) = self.inodes.get(index) {
+                inode.write();
+            }
+        }
+    }
+}
+
+/// Checks The Filesystem, Fixing What It Finds When `repair` Is Set. Orphaned Inodes Are
+/// Moved Back Into The Root Directory & Orphaned Data Blocks Are Freed.
+pub fn check(repair: bool) -> Report {
+    let mut report = Report::default();
+    let mut walk = Walk::load();
+
+    // Parentless Inodes That A Directory Still Lists Are Reached Through That Directory
+    let listed: BTreeSet<u32> = walk.inodes.values()
+        .filter(|inode| inode.flags().is_dir())
+        .flat_map(|inode| inode.children().iter().copied())
+        .collect();
+    let roots: Vec<u32> = walk.inodes.iter()
+        .filter(|(index, inode)| inode.parent().is_none() && !listed.contains(index))
+        .map(|(index, _)| *index)
+        .collect();
+    for root in roots {
+        walk.walk(root, &mut report, repair);
+    }
+
+    let orphans: Vec<u32> = walk.inodes.keys().filter(|index| !walk.reachable.contains(index)).copied().collect();
+    let detached: Vec<u32> = orphans.iter().filter(|index| walk.is_detached(**index)).copied().collect();
+    for index in detached {
+        if walk.reachable.contains(&index) { continue; }
+        report.problems.push(Problem::OrphanedInode { inode: index });
+        if repair {
+            if let Some(inode) = walk.inodes.get_mut(&index) {
+                inode.set_parent(None);
+            }
+            walk.dirty.insert(index);
+            report.repaired += 1;
+            walk.walk(index, &mut report, repair);
+        }
+    }
+    for index in orphans {
+        if !walk.reachable.contains(&index) && !walk.is_detached(index) {
+            report.problems.push(Problem::OrphanedInode { inode: index });
+        }
+    }
+    walk.check_links(&mut report, repair);
+
+    for block in 0..DATA_SIZE as u32 {
+        if DataBitmap::is_allocated(block) && !walk.owners.contains_key(&block) {
+            report.problems.push(Problem::OrphanedBlock { block });
+            if repair {
+                DataBitmap::free(block);
+                report.repaired += 1;
+            }
+        }
+    }
+
+    if repair {
+        walk.flush();
+    }
+
+    // Checked Last So Repairs Above Are Counted Too
+    if !quota::is_consistent() {
+        report.problems.push(Problem::StaleSummary);
+        if repair && quota::rebuild().is_ok() {
+            report.repaired += 1;
+        }
+    }
+    report
+}
@@ -0,0 +1,253 @@
+//! On-Disk Layout Of The Inode Filesystem
+//!
+//! Kept Free Of Kernel Dependencies So The Host Image Tool (`tools/cobaltfs`) Can Include It
+//! Directly And Always Agree With The Kernel About Where Things Live.
+
+pub mod inode_meta {
+    use core::mem::size_of;
+
+    use super::filesystem_values::BLOCK_SIZE;
+
+    // INODE STRUCTURE (Format Versions 4 & 5): One Block Per Inode, Big Endian
+    // +====+=====+======+====+===+===+=======+========+========+=====+========+===============+===========+==================+====+
+    // |NAME|FLAGS|PARENT|MODE|UID|GID|CREATED|MODIFIED|ACCESSED|LINKS|INDIRECT|DOUBLE_INDIRECT|CHILD_COUNT|CHILDREN          |SIZE|
+    // +====+=====+======+====+===+===+=======+========+========+=====+========+===============+===========+==================+====+
+    // | 64 | u16 | u16  |u16 |u16|u16| u32   | u32    | u32    | u16 | u32    | u32           | u8        | CHILD_COUNT * u32| u32|
+    // +====+=====+======+====+===+===+=======+========+========+=====+========+===============+===========+==================+====+
+    // NAME Is Space Padded, PARENT Is 0xFFFF For Inodes In The Root Directory.
+    // MODE Holds The rwx Bits (See `inode_mode`), Timestamps Are Seconds Since The Unix Epoch.
+    // LINKS Counts The Names An Inode Has, Its Own Plus One Per Hard Link Entry Naming It.
+    // Directory Children Are Inode Indices, File & Symlink Children Are Data Block Indices (A
+    // Symlink's Data Is Its Target Path) & A Hard Link Entry's Only Child Is The Inode It Names.
+    // A File's CHILDREN Map Its First Blocks Directly, INDIRECT Points At A Data Block Holding
+    // `POINTERS_PER_BLOCK` More & DOUBLE_INDIRECT At One Holding Pointers To Such Blocks.
+    // NO_BLOCK Marks A Hole, Read Back As Zeros, Or An Unused Pointer.
+
+    pub const FILENAME_SIZE: usize = 64;
+    pub const FLAGS_OFFSET: usize = FILENAME_SIZE;
+    pub const PARENT_OFFSET: usize = FLAGS_OFFSET + size_of::<u16>();
+    pub const MODE_OFFSET: usize = PARENT_OFFSET + size_of::<u16>();
+    pub const UID_OFFSET: usize = MODE_OFFSET + size_of::<u16>();
+    pub const GID_OFFSET: usize = UID_OFFSET + size_of::<u16>();
+    pub const CREATED_OFFSET: usize = GID_OFFSET + size_of::<u16>();
+    pub const MODIFIED_OFFSET: usize = CREATED_OFFSET + size_of::<u32>();
+    pub const ACCESSED_OFFSET: usize = MODIFIED_OFFSET + size_of::<u32>();
+    pub const LINKS_OFFSET: usize = ACCESSED_OFFSET + size_of::<u32>();
+    pub const INDIRECT_OFFSET: usize = LINKS_OFFSET + size_of::<u16>();
+    pub const DOUBLE_INDIRECT_OFFSET: usize = INDIRECT_OFFSET + size_of::<u32>();
+    pub const CHILD_COUNT_OFFSET: usize = DOUBLE_INDIRECT_OFFSET + size_of::<u32>();
+    pub const CHILDREN_OFFSET: usize = CHILD_COUNT_OFFSET + size_of::<u8>();
+    pub const CHILDREN_LEN: usize = (BLOCK_SIZE - CHILDREN_OFFSET - size_of::<u32>()) / size_of::<u32>();
+    pub const NO_PARENT: u16 = 0xFFFF;
+
+    pub const NO_BLOCK: u32 = 0xFFFF_FFFF;
+    pub const POINTERS_PER_BLOCK: usize = BLOCK_SIZE / size_of::<u32>();
+    /// Logical Blocks A File Can Map: Direct, Then Indirect, Then Double Indirect
+    pub const MAX_BLOCKS: usize = CHILDREN_LEN + POINTERS_PER_BLOCK + POINTERS_PER_BLOCK * POINTERS_PER_BLOCK;
+    pub const MAX_FILE_SIZE: usize = MAX_BLOCKS * BLOCK_SIZE;
+}
+
+/// Older Format Versions, Only Needed To Migrate Them. Versions 1 To 3 Had A 4096 Block Data
+/// Region With A One Block Bitmap, So Both The Inode Table & Data Region Started Earlier.
+/// Version 0 Is Version 1 As Written Before Its Addressing Was Fixed: Every Block Sat Another
+/// `PHYSICAL_OFFSET` Further In & Both Bitmaps Shared The Block Holding Inode 0. Version 4's
+/// 32768 Block Data Region Ran Past The COBALTFS Superblock, Its Records Are Unchanged.
+pub mod old_layout {
+    use core::mem::size_of;
+
+    use super::{filesystem_values::{BLOCK_SIZE, BLOCKS_PER_BITMAP, DATA_BITMAP_BASE, INODE_BITMAP_SIZE, INODE_SIZE, PHYSICAL_OFFSET}, inode_flags::*, inode_meta::{FILENAME_SIZE, FLAGS_OFFSET, INDIRECT_OFFSET, LINKS_OFFSET, NO_PARENT, PARENT_OFFSET}, superblock::BITMAP_FREE_OFFSET};
+
+    pub const DATA_SIZE: usize = 4096;
+    pub const V4_DATA_SIZE: usize = 32768;
+    pub const V4_DATA_BITMAP_SIZE: usize = V4_DATA_SIZE / BLOCKS_PER_BITMAP;
+    pub const V4_QUOTA_COUNT_OFFSET: usize = BITMAP_FREE_OFFSET + (INODE_BITMAP_SIZE + V4_DATA_BITMAP_SIZE) * size_of::<u16>();
+
+    pub fn data_size(version: u16) -> usize {
+        if version == 4 { V4_DATA_SIZE } else { DATA_SIZE }
+    }
+
+    /// Where `version` Kept Its Inode Table, Version 0 Was Another `PHYSICAL_OFFSET` Further In
+    fn inode_base(version: u16) -> u32 {
+        let shift = if version == 0 { PHYSICAL_OFFSET as u32 } else { 0 };
+        DATA_BITMAP_BASE + (data_size(version) / BLOCKS_PER_BITMAP) as u32 + shift
+    }
+
+    pub fn inode(version: u16, index: u32) -> u32 {
+        inode_base(version) + index
+    }
+
+    pub fn data(version: u16, index: u32) -> u32 {
+        inode_base(version) + INODE_SIZE as u32 + index
+    }
+
+    /// Every Field Before CHILD_COUNT Sits Where It Does Now. Versions 0 & 1 Had Nothing Between
+    /// PARENT & CHILD_COUNT, Version 2 Added Ownership & Timestamps, Version 3 Added LINKS.
+    pub fn child_count_offset(version: u16) -> usize {
+        match version {
+            0 | 1 => PARENT_OFFSET + size_of::<u16>(),
+            2 => LINKS_OFFSET,
+            _ => INDIRECT_OFFSET,
+        }
+    }
+
+    pub fn children_len(version: u16) -> usize {
+        (BLOCK_SIZE - child_count_offset(version) - size_of::<u8>() - size_of::<u32>()) / size_of::<u32>()
+    }
+
+    /// Version 0's Bitmaps Shared A Block With Inode 0, So They Can't Say What's Allocated. A
+    /// Record Counts As An Inode When It Looks Like One Version 0 Wrote: A Named File Or
+    /// Directory With No Later Flags, A Parent In The Table & Children That Fit Their Region.
+    pub fn v0_is_inode(block: &[u8]) -> bool {
+        let read_u16 = |offset: usize| u16::from_be_bytes([block[offset], block[offset + 1]]);
+        let flags = read_u16(FLAGS_OFFSET);
+        let parent = read_u16(PARENT_OFFSET);
+        if flags & (FILE | DIR) == 0 || flags & !(ROOT_READ | ROOT_WRITE | ROOT_EXEC | DIR | FILE | DEV | HIDDEN) != 0 { return false; }
+        if block[..FILENAME_SIZE].iter().all(|byte| *byte == b' ' || *byte == 0) { return false; }
+        if parent != NO_PARENT && parent as usize >= INODE_SIZE { return false; }
+
+        let count_offset = child_count_offset(0);
+        let count = block[count_offset] as usize;
+        let limit = if flags & FILE != 0 { DATA_SIZE } else { INODE_SIZE };
+        count <= children_len(0) && (0..count).all(|i| {
+            let offset = count_offset + size_of::<u8>() + i * size_of::<u32>();
+            let mut child = [0; size_of::<u32>()];
+            child.copy_from_slice(&block[offset..offset + size_of::<u32>()]);
+            (u32::from_be_bytes(child) as usize) < limit
+        })
+    }
+}
+
+pub mod inode_flags {
+    /// Format Version 1 Permissions, Replaced By `inode_mode` & Cleared By Migration
+    pub const ROOT_READ: u16 = 1 << 0;
+    pub const ROOT_WRITE: u16 = 1 << 1;
+    pub const ROOT_EXEC: u16  = 1 << 2;
+
+    pub const DIR: u16  = 1 << 3;
+    pub const FILE: u16 = 1 << 4;
+    pub const DEV: u16  = 1 << 5;
+
+    pub const HIDDEN: u16 = 1 << 6;
+
+    pub const SYMLINK: u16  = 1 << 7;
+    pub const HARDLINK: u16 = 1 << 8;
+}
+
+pub mod inode_mode {
+    use super::inode_flags::{DIR, ROOT_EXEC, ROOT_READ, ROOT_WRITE};
+
+    pub const READ: u16  = 0o4;
+    pub const WRITE: u16 = 0o2;
+    pub const EXEC: u16  = 0o1;
+
+    pub const OWNER_SHIFT: u16 = 6;
+    pub const GROUP_SHIFT: u16 = 3;
+    pub const OTHER_SHIFT: u16 = 0;
+
+    pub const ANY_EXEC: u16 = EXEC << OWNER_SHIFT | EXEC << GROUP_SHIFT | EXEC << OTHER_SHIFT;
+
+    pub const DEFAULT_FILE: u16 = 0o644;
+    pub const DEFAULT_DIR: u16  = 0o755;
+    pub const DEFAULT_LINK: u16 = 0o777;
+
+    /// Mode For A Format Version 1 Inode, The Owner (Root) Keeps Its Old Bits & Everyone Else
+    /// Gets Read (And Exec/Search Where The Owner Had It)
+    pub fn from_v1_flags(flags: u16) -> u16 {
+        let mut owner = 0;
+        if flags & ROOT_READ != 0 { owner |= READ; }
+        if flags & ROOT_WRITE != 0 { owner |= WRITE; }
+        if flags & ROOT_EXEC != 0 || flags & DIR != 0 { owner |= EXEC; }
+        let others = READ | (owner & EXEC);
+        owner << OWNER_SHIFT | others << GROUP_SHIFT | others << OTHER_SHIFT
+    }
+}
+
+/// The Block At `PHYSICAL_OFFSET`, Images Without The Magic Predate It & Are Version 1, Or
+/// Version 0 When Nothing Is Set In The Inode Bitmap Where Version 1 Keeps It
+pub mod superblock {
+    use core::mem::size_of;
+
+    use super::filesystem_values::{BLOCK_SIZE, DATA_BITMAP_SIZE, INODE_BITMAP_SIZE};
+
+    // SUPERBLOCK STRUCTURE:
+    // +=====+=======+=====+======================+===========+=========================+
+    // |MAGIC|VERSION|FLAGS|BITMAP_FREE           |QUOTA_COUNT|QUOTAS                   |
+    // +=====+=======+=====+======================+===========+=========================+
+    // | u32 | u16   | u16 | BITMAP_BLOCKS * u16  | u8        | QUOTA_COUNT * QUOTA_SIZE|
+    // +=====+=======+=====+======================+===========+=========================+
+    // BITMAP_FREE Caches How Many Bits Are Clear In Each Bitmap Block (Inode Bitmap First) &
+    // Each Quota's Usage Is Cached Alongside Its Limits. Both Are Only Trusted While FLAGS
+    // Has `flags::SUMMARY` Set, Anything Else Rebuilds Them From The Bitmaps & Inodes.
+    // QUOTA: UID u16, BLOCK_LIMIT u32, INODE_LIMIT u32, BLOCKS_USED u32, INODES_USED u32.
+    // A Limit Of 0 Means Unlimited.
+
+    pub const MAGIC: u32 = 0x434F_494E; // "COIN"
+    pub const MAGIC_OFFSET: usize = 0;
+    pub const VERSION_OFFSET: usize = 4;
+    pub const FLAGS_OFFSET: usize = VERSION_OFFSET + size_of::<u16>();
+    pub const BITMAP_FREE_OFFSET: usize = FLAGS_OFFSET + size_of::<u16>();
+    pub const BITMAP_BLOCKS: usize = INODE_BITMAP_SIZE + DATA_BITMAP_SIZE;
+    pub const QUOTA_COUNT_OFFSET: usize = BITMAP_FREE_OFFSET + BITMAP_BLOCKS * size_of::<u16>();
+    pub const QUOTAS_OFFSET: usize = QUOTA_COUNT_OFFSET + size_of::<u8>();
+    pub const QUOTA_SIZE: usize = size_of::<u16>() + 4 * size_of::<u32>();
+    pub const MAX_QUOTAS: usize = (BLOCK_SIZE - QUOTAS_OFFSET) / QUOTA_SIZE;
+
+    pub const FORMAT_VERSION: u16 = 5;
+
+    pub mod flags {
+        /// BITMAP_FREE & Quota Usage Match The Bitmaps & Inodes
+        pub const SUMMARY: u16 = 1 << 0;
+    }
+}
+
+pub mod filesystem_values {
+    pub const PHYSICAL_OFFSET:      usize = (20 << 20) / BLOCK_SIZE;
+    pub const BLOCK_SIZE:           usize = 512;
+    pub const PARTITION_SIZE:       usize = (20 << 20) / BLOCK_SIZE;
+    pub const SUPERBLOCK_SIZE:      usize = 1;
+    pub const INODE_SIZE:           usize = 4096;
+    pub const DATA_SIZE:            usize = 16384;
+    pub const BLOCKS_PER_BITMAP:    usize = 8 * 512;
+    pub const INODE_BITMAP_SIZE:    usize = INODE_SIZE / BLOCKS_PER_BITMAP;
+    pub const DATA_BITMAP_SIZE:     usize = DATA_SIZE / BLOCKS_PER_BITMAP;
+
+    pub const METADATA_SIZE:        usize = SUPERBLOCK_SIZE + INODE_BITMAP_SIZE + DATA_BITMAP_SIZE;
+    pub const USABLE_SIZE:          usize = PARTITION_SIZE - METADATA_SIZE;
+
+    pub const INODE_BITMAP_BASE:    u32 = PHYSICAL_OFFSET as u32 + SUPERBLOCK_SIZE as u32;
+    pub const DATA_BITMAP_BASE:     u32 = INODE_BITMAP_BASE + INODE_BITMAP_SIZE as u32;
+    pub const INODE_BASE:           u32 = DATA_BITMAP_BASE + DATA_BITMAP_SIZE as u32;
+    pub const DATA_BASE:            u32 = INODE_BASE + INODE_SIZE as u32;
+    pub const END:                  u32 = DATA_BASE + DATA_SIZE as u32;
+}
+
+/// Physical Block Addresses, Every Base Above Already Includes `PHYSICAL_OFFSET`
+pub mod addressing {
+    use super::filesystem_values::*;
+
+    /// The Bitmap Block Tracking `index` & The Bit Within That Block
+    fn bitmap(base: u32, index: u32) -> (u32, usize) {
+        let per_block = BLOCKS_PER_BITMAP as u32;
+        (base + index / per_block, (index % per_block) as usize)
+    }
+
+    pub fn inode_bitmap(index: u32) -> (u32, usize) {
+        bitmap(INODE_BITMAP_BASE, index)
+    }
+
+    pub fn data_bitmap(index: u32) -> (u32, usize) {
+        bitmap(DATA_BITMAP_BASE, index)
+    }
+
+    pub fn superblock() -> u32 {
+        PHYSICAL_OFFSET as u32
+    }
+
+    pub fn inode(index: u32) -> u32 {
+        INODE_BASE + index
+    }
+
+    pub fn data(index: u32) -> u32 {
+        DATA_BASE + index
+    }
+}
@@ -1,14 +1,30 @@
 
 pub mod filesystem;
 
+pub mod devfs;
+pub mod ext2;
 pub mod fat;
+pub mod fsck;
+pub mod layout;
+pub mod mount;
+pub mod procfs;
+pub mod quota;
+pub mod tmpfs;
 
 use crate::{sys::ustar::*};
 use metadata::*;
 use alloc::boxed::Box;
 use alloc::vec::Vec;
 use crate::sys::storage::fs::{device, dev_handle::BlockDeviceIO};
+use crate::warn;
+use ext2::Ext2FileSystem;
 
+/// Mounts The Filesystems Every Boot Starts With
+pub fn init() {
+    mount::mount("/tmp", Box::new(tmpfs::TmpFs::new(tmpfs::DEFAULT_LIMIT))).expect("Failed To Mount /tmp");
+    mount::mount("/dev", Box::new(devfs::DevFs)).expect("Failed To Mount /dev");
+    mount::mount("/proc", Box::new(procfs::ProcFs)).expect("Failed To Mount /proc");
+}
 
 
 pub fn list(buf: &mut Vec<Metadata>) {
@@ -16,6 +32,14 @@ pub fn list(buf: &mut Vec<Metadata>) {
     let dev = dev.as_ref();
     let dev = dev.unwrap().clone();
 
+    if ext2::probe(&dev) {
+        match Ext2FileSystem::new(Box::new(dev)) {
+            Ok(fs) => fs.metadata_slice(buf),
+            Err(e) => { warn!("{}\n", e); },
+        }
+        return;
+    }
+
     let fs = TarFileSystem::new(dev.sector_count() as usize, Box::new(dev));
     fs.metadata_slice(buf);
 }
@@ -25,10 +49,20 @@ pub fn read(_: &Metadata, _: &mut Vec<u8>) {
 }
 
 pub fn load(path: &str, buf: &mut Vec<u8>) -> Result<(), &'static str> {
+    if path.starts_with('/') {
+        mount::read(path, buf)?;
+        return Ok(());
+    }
+
     let dev = device().lock();
     let dev = dev.as_ref();
     let dev = dev.unwrap().clone();
 
+    if ext2::probe(&dev) {
+        Ext2FileSystem::new(Box::new(dev))?.load(path, buf)?;
+        return Ok(());
+    }
+
     let fs = TarFileSystem::new(dev.sector_count() as usize, Box::new(dev));
     if let Some(meta) = fs.find(path) {
         let mut buffer: Vec<u8> = alloc::vec![0; meta.size()];
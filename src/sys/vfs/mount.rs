@@ -0,0 +1,273 @@
+//! Path Based Mount Table
+//!
+//! Filesystems Implementing [Mountable] Are Attached At An Absolute Path & Every Lookup Goes
+//! To The Mount With The Longest Matching Prefix, Which Only Sees The Path Relative To Itself.
+//! Each Mount Has Its Own Lock So A Filesystem Can Look At The Table (e.g. To List Mounts)
+//! While Serving A Request.
+
+use alloc::{boxed::Box, string::String, sync::Arc, vec::Vec};
+use lazy_static::lazy_static;
+use spin::Mutex;
+
+use crate::KResult;
+
+pub const READ_ONLY: &str = "Read-Only Filesystem";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NodeKind {
+    File,
+    Dir,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct Stat {
+    pub kind: NodeKind,
+    pub size: usize,
+}
+
+impl Stat {
+    pub fn file(size: usize) -> Self {
+        Self { kind: NodeKind::File, size }
+    }
+
+    pub fn dir() -> Self {
+        Self { kind: NodeKind::Dir, size: 0 }
+    }
+
+    pub fn is_dir(&self) -> bool {
+        self.kind == NodeKind::Dir
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct DirEntry {
+    pub name: String,
+    pub stat: Stat,
+}
+
+/// A Filesystem That Can Be Attached To The Mount Table. Paths Are Relative To The Mount
+/// Point With No Leading '/', The Empty Path Being The Mount's Root. Filesystems That Can't
+/// Be Changed Only Need The Read Half.
+pub trait Mountable: Send {
+    fn fs_type(&self) -> &'static str;
+    fn stat(&self, path: &str) -> KResult<Stat>;
+    fn read_dir(&self, path: &str) -> KResult<Vec<DirEntry>>;
+    /// Appends The Whole File To `buf`, Returning How Many Bytes Were Read
+    fn read(&self, path: &str, buf: &mut Vec<u8>) -> KResult<usize>;
+
+    /// Fills `buf` From `offset` Bytes Into The File, Returning How Many Bytes Were Read.
+    /// Filesystems Backed By Something Large (e.g. Whole Disks) Should Override This.
+    fn read_at(&self, path: &str, offset: usize, buf: &mut [u8]) -> KResult<usize> {
+        let mut data = Vec::new();
+        self.read(path, &mut data)?;
+        let data = data.get(offset..).unwrap_or(&[]);
+        let len = data.len().min(buf.len());
+        buf[..len].copy_from_slice(&data[..len]);
+        Ok(len)
+    }
+
+    /// Replaces The File's Contents, Creating It If Needed
+    fn write(&mut self, _path: &str, _data: &[u8]) -> KResult<()> {
+        Err(READ_ONLY)
+    }
+
+    fn append(&mut self, _path: &str, _data: &[u8]) -> KResult<()> {
+        Err(READ_ONLY)
+    }
+
+    /// Overwrites Part Of A File, Growing It If The Write Runs Past The End
+    fn write_at(&mut self, path: &str, offset: usize, data: &[u8]) -> KResult<()> {
+        let mut file = Vec::new();
+        self.read(path, &mut file)?;
+        if file.len() < offset + data.len() { file.resize(offset + data.len(), 0); }
+        file[offset..offset + data.len()].copy_from_slice(data);
+        self.write(path, &file)
+    }
+
+    fn create_dir(&mut self, _path: &str) -> KResult<()> {
+        Err(READ_ONLY)
+    }
+
+    /// Removes A File Or An Empty Directory
+    fn remove(&mut self, _path: &str) -> KResult<()> {
+        Err(READ_ONLY)
+    }
+
+    /// `(used, capacity)` In Bytes, `None` If The Filesystem Doesn't Track Space
+    fn usage(&self) -> Option<(usize, usize)> {
+        None
+    }
+}
+
+type MountedFs = Arc<Mutex<Box<dyn Mountable>>>;
+
+struct Mount {
+    point: String,
+    /// Kept Here So Listing Mounts Never Locks A Filesystem, Which Could Be The Caller
+    fs_type: &'static str,
+    fs: MountedFs,
+}
+
+lazy_static! {
+    static ref MOUNTS: Mutex<Vec<Mount>> = Mutex::new(Vec::new());
+}
+
+/// Makes A Path Absolute & Resolves `.` And `..`, e.g. `tmp/./a/../b` -> `/tmp/b`
+pub fn normalize(path: &str) -> String {
+    let mut parts: Vec<&str> = Vec::new();
+    for part in path.split('/') {
+        match part {
+            "" | "." => {},
+            ".." => { parts.pop(); },
+            part => parts.push(part),
+        }
+    }
+    let mut res = String::new();
+    for part in parts {
+        res.push('/');
+        res.push_str(part);
+    }
+    if res.is_empty() { res.push('/'); }
+    res
+}
+
+/// The Path Relative To `point` If It Lives Under It
+fn strip_mount<'a>(path: &'a str, point: &str) -> Option<&'a str> {
+    if point == "/" { return Some(&path[1..]); }
+    let rest = path.strip_prefix(point)?;
+    if rest.is_empty() {
+        Some(rest)
+    } else {
+        rest.strip_prefix('/')
+    }
+}
+
+pub fn mount(point: &str, fs: Box<dyn Mountable>) -> KResult<()> {
+    let point = normalize(point);
+    let mut mounts = MOUNTS.lock();
+    if mounts.iter().any(|mount| mount.point == point) {
+        return Err("Something Is Already Mounted There");
+    }
+    let fs_type = fs.fs_type();
+    mounts.push(Mount { point, fs_type, fs: Arc::new(Mutex::new(fs)) });
+    Ok(())
+}
+
+pub fn unmount(point: &str) -> KResult<()> {
+    let point = normalize(point);
+    let mut mounts = MOUNTS.lock();
+    let len = mounts.len();
+    mounts.retain(|mount| mount.point != point);
+    if mounts.len() == len { Err("Nothing Is Mounted There") } else { Ok(()) }
+}
+
+/// Every Mount As `(point, fs_type)`
+pub fn mounts() -> Vec<(String, &'static str)> {
+    MOUNTS.lock().iter().map(|mount| (mount.point.clone(), mount.fs_type)).collect()
+}
+
+/// Usage Of Every Mount That Tracks It, As `(point, fs_type, used, capacity)`
+pub fn usage() -> Vec<(String, &'static str, usize, usize)> {
+    let mounts: Vec<(String, MountedFs)> = MOUNTS.lock().iter().map(|mount| (mount.point.clone(), mount.fs.clone())).collect();
+    mounts.into_iter().filter_map(|(point, fs)| {
+        let fs = fs.lock();
+        fs.usage().map(|(used, capacity)| (point, fs.fs_type(), used, capacity))
+    }).collect()
+}
+
+pub fn is_mounted(path: &str) -> bool {
+    resolve(path).is_some()
+}
+
+/// Finds The Mount Owning `path` & The Path Relative To It
+fn resolve(path: &str) -> Option<(MountedFs, String)> {
+    let path = normalize(path);
+    let mounts = MOUNTS.lock();
+    mounts.iter()
+        .filter_map(|mount| strip_mount(&path, &mount.point).map(|rest| (mount, rest)))
+        .max_by_key(|(mount, _)| mount.point.len())
+        .map(|(mount, rest)| (mount.fs.clone(), String::from(rest)))
+}
+
+fn with_fs<T>(path: &str, f: impl FnOnce(&mut dyn Mountable, &str) -> KResult<T>) -> KResult<T> {
+    let (fs, rest) = resolve(path).ok_or("No Filesystem Mounted At That Path")?;
+    let mut fs = fs.lock();
+    f(fs.as_mut(), &rest)
+}
+
+/// Directories That Only Exist Because Something Is Mounted Below Them, e.g. `/` With Only
+/// `/tmp` Mounted
+fn mount_parents(path: &str) -> Vec<DirEntry> {
+    let path = normalize(path);
+    let mut entries: Vec<DirEntry> = Vec::new();
+    for mount in MOUNTS.lock().iter() {
+        if let Some(rest) = strip_mount(&mount.point, &path) {
+            if let Some(name) = rest.split('/').find(|part| !part.is_empty()) {
+                if !entries.iter().any(|entry| entry.name == name) {
+                    entries.push(DirEntry { name: String::from(name), stat: Stat::dir() });
+                }
+            }
+        }
+    }
+    entries
+}
+
+pub fn stat(path: &str) -> KResult<Stat> {
+    match with_fs(path, |fs, rest| fs.stat(rest)) {
+        Err(_) if !mount_parents(path).is_empty() => Ok(Stat::dir()),
+        res => res,
+    }
+}
+
+pub fn read_dir(path: &str) -> KResult<Vec<DirEntry>> {
+    let mut entries = match with_fs(path, |fs, rest| fs.read_dir(rest)) {
+        Ok(entries) => entries,
+        Err(e) if mount_parents(path).is_empty() => return Err(e),
+        Err(_) => Vec::new(),
+    };
+    for entry in mount_parents(path) {
+        if !entries.iter().any(|existing| existing.name == entry.name) {
+            entries.push(entry);
+        }
+    }
+    Ok(entries)
+}
+
+pub fn read(path: &str, buf: &mut Vec<u8>) -> KResult<usize> {
+    with_fs(path, |fs, rest| fs.read(rest, buf))
+}
+
+pub fn read_at(path: &str, offset: usize, buf: &mut [u8]) -> KResult<usize> {
+    with_fs(path, |fs, rest| fs.read_at(rest, offset, buf))
+}
+
+pub fn write(path: &str, data: &[u8]) -> KResult<()> {
+    with_fs(path, |fs, rest| fs.write(rest, data))
+}
+
+pub fn append(path: &str, data: &[u8]) -> KResult<()> {
+    with_fs(path, |fs, rest| fs.append(rest, data))
+}
+
+pub fn write_at(path: &str, offset: usize, data: &[u8]) -> KResult<()> {
+    with_fs(path, |fs, rest| fs.write_at(rest, offset, data))
+}
+
+pub fn create_dir(path: &str) -> KResult<()> {
+    with_fs(path, |fs, rest| fs.create_dir(rest))
+}
+
+pub fn remove(path: &str) -> KResult<()> {
+    with_fs(path, |fs, rest| {
+        if rest.is_empty() { return Err("Can't Remove A Mount Point"); }
+        fs.remove(rest)
+    })
+}
+
+#[test_case]
+fn normalize_paths() {
+    assert_eq!(normalize("tmp/./a/../b"), "/tmp/b");
+    assert_eq!(normalize("/../"), "/");
+    assert_eq!(strip_mount("/tmp/a", "/tmp"), Some("a"));
+    assert_eq!(strip_mount("/tmpfoo", "/tmp"), None);
+}
@@ -0,0 +1,175 @@
+//! Kernel Introspection Filesystem
+//!
+//! Every File Under `/proc` Is Generated From Live Kernel State When It Is Read, So There Is
+//! Nothing To Keep In Sync. Each Process Gets A Directory Named After Its Pid.
+
+use alloc::{format, string::String, vec::Vec};
+use core::fmt::Write;
+
+use crate::{KResult, arch::i386::interrupts::idt, sys::{mem, net, pci, pci_details, process, timer}};
+
+use super::mount::{self, DirEntry, Mountable, Stat};
+
+type Generator = fn(&mut String);
+
+/// Fixed Files, Paths Relative To `/proc`
+const FILES: &[(&str, Generator)] = &[
+    ("meminfo", meminfo),
+    ("uptime", uptime),
+    ("pci", pci_devices),
+    ("interrupts", interrupts),
+    ("mounts", mounts),
+    ("net/iface", net_iface),
+    ("net/route", net_route),
+];
+
+/// Standard PC/AT Assignment Of The 16 PIC Lines
+const IRQ_NAMES: [&str; 16] = [
+    "Timer", "Keyboard", "Cascade", "COM2", "COM1", "LPT2", "Floppy", "LPT1",
+    "CMOS RTC", "Free", "Free", "Free", "PS/2 Mouse", "FPU", "Primary ATA", "Secondary ATA",
+];
+
+fn meminfo(out: &mut String) {
+    writeln!(out, "MemTotal: {} B", mem::size()).ok();
+    writeln!(out, "MemUsed:  {} B", mem::used()).ok();
+    writeln!(out, "MemFree:  {} B", mem::free()).ok();
+}
+
+fn uptime(out: &mut String) {
+    writeln!(out, "{:.3}", timer::uptime_seconds()).ok();
+}
+
+fn pci_devices(out: &mut String) {
+    for dev in pci::PCI_DEVICES.lock().iter() {
+        writeln!(out, "{:02x}:{:02x}.{} {:04x}:{:04x} Class {:02x}.{:02x} {} {}",
+            dev.bus, dev.device, dev.function, dev.vendor_id, dev.device_id, dev.class, dev.subclass,
+            pci_details::vendor(dev.vendor_id), pci_details::device(dev.device_id)).ok();
+    }
+}
+
+fn interrupts(out: &mut String) {
+    for (irq, count) in idt::irq_counts().iter().enumerate() {
+        writeln!(out, "{:>2}: {:>10} {}", irq, count, IRQ_NAMES[irq]).ok();
+    }
+}
+
+fn mounts(out: &mut String) {
+    for (point, fs_type) in mount::mounts() {
+        writeln!(out, "{} {}", point, fs_type).ok();
+    }
+}
+
+fn net_iface(out: &mut String) {
+    let ifaces = net::IFACES.lock();
+    if ifaces.is_empty() { writeln!(out, "No Interface").ok(); }
+    for iface in ifaces.iter() {
+        writeln!(out, "{} ({})", iface.name, iface.iface.device().name()).ok();
+        writeln!(out, "  MAC: {}", iface.iface.ethernet_addr()).ok();
+        for cidr in iface.iface.ip_addrs() {
+            writeln!(out, "  IP:  {}", cidr).ok();
+        }
+    }
+}
+
+fn net_route(out: &mut String) {
+    for route in net::route::list() {
+        writeln!(out, "{}", net::route::describe(&route)).ok();
+    }
+}
+
+fn process_status(pid: process::Pid, out: &mut String) -> KResult<()> {
+    let process = process::get(pid).ok_or("No Such Process")?;
+    writeln!(out, "Name:    {}", process.name).ok();
+    writeln!(out, "Pid:     {}", process.pid).ok();
+    writeln!(out, "Uid:     {}", process.creds.uid).ok();
+    writeln!(out, "Gid:     {}", process.creds.gid).ok();
+    writeln!(out, "Started: {} ms", process.started).ok();
+    Ok(())
+}
+
+/// Every File Currently Present, Paths Relative To `/proc`
+fn paths() -> Vec<String> {
+    let mut paths: Vec<String> = FILES.iter().map(|(path, _)| String::from(*path)).collect();
+    for process in process::list() {
+        paths.push(format!("{}/status", process.pid));
+    }
+    paths
+}
+
+fn generate(path: &str) -> KResult<String> {
+    let mut out = String::new();
+    if let Some((_, generator)) = FILES.iter().find(|(file, _)| *file == path) {
+        generator(&mut out);
+        return Ok(out);
+    }
+
+    match path.split_once('/') {
+        Some((pid, "status")) => {
+            let pid = pid.parse().map_err(|_| "No Such File")?;
+            process_status(pid, &mut out)?;
+            Ok(out)
+        },
+        _ => Err("No Such File"),
+    }
+}
+
+pub struct ProcFs;
+
+impl Mountable for ProcFs {
+    fn fs_type(&self) -> &'static str {
+        "procfs"
+    }
+
+    fn stat(&self, path: &str) -> KResult<Stat> {
+        let path = path.trim_matches('/');
+        if path.is_empty() { return Ok(Stat::dir()); }
+        if let Ok(data) = generate(path) { return Ok(Stat::file(data.len())); }
+
+        let prefix = format!("{}/", path);
+        if paths().iter().any(|file| file.starts_with(&prefix)) {
+            Ok(Stat::dir())
+        } else {
+            Err("No Such File")
+        }
+    }
+
+    fn read_dir(&self, path: &str) -> KResult<Vec<DirEntry>> {
+        let path = path.trim_matches('/');
+        let prefix = if path.is_empty() { String::new() } else { format!("{}/", path) };
+        let mut entries: Vec<DirEntry> = Vec::new();
+        for file in paths() {
+            let rest = match file.strip_prefix(prefix.as_str()) {
+                Some(rest) => rest,
+                None => continue,
+            };
+            let name = rest.split('/').next().unwrap_or(rest);
+            if entries.iter().any(|entry| entry.name == name) { continue; }
+            let stat = if name.len() < rest.len() { Stat::dir() } else { self.stat(&file)? };
+            entries.push(DirEntry { name: String::from(name), stat });
+        }
+
+        if entries.is_empty() && !path.is_empty() { return Err("Not A Directory"); }
+        Ok(entries)
+    }
+
+    fn read(&self, path: &str, buf: &mut Vec<u8>) -> KResult<usize> {
+        let data = generate(path.trim_matches('/'))?;
+        buf.extend_from_slice(data.as_bytes());
+        Ok(data.len())
+    }
+}
+
+#[test_case]
+fn procfs_generates_files() {
+    let fs = ProcFs;
+    let names: Vec<String> = fs.read_dir("").expect("").into_iter().map(|entry| entry.name).collect();
+    assert!(names.iter().any(|name| name == "net"));
+    assert!(names.iter().any(|name| name == "0"));
+
+    let mut buf = Vec::new();
+    fs.read("0/status", &mut buf).expect("");
+    assert!(buf.starts_with(b"Name:    kernel"));
+    assert!(fs.stat("net").expect("").is_dir());
+    assert!(fs.read("meminfo", &mut Vec::new()).expect("") > 0);
+    assert!(fs.read("missing", &mut Vec::new()).is_err());
+}
@@ -0,0 +1,290 @@
+//! Free-Space Summaries & Per-User Quotas For The Inode Filesystem
+//!
+//! Both Are Cached In The Superblock. The Bitmaps Keep BITMAP_FREE Up To Date As Bits Flip &
+//! Inode/Data Allocation Charges The Owner's Quota Before Taking Anything, So Reporting Free
+//! Space Never Has To Scan The Bitmaps. A Cache Not Marked `flags::SUMMARY` (e.g. After The
+//! Host Tool Wrote The Image) Is Rebuilt On First Use.
+
+use alloc::vec::Vec;
+
+use crate::{KResult, sys::{process::{Credentials, Uid}, storage::fs::block::Block}};
+
+use super::filesystem::{self, Inode, InodeBitmap, filesystem_values::{BLOCKS_PER_BITMAP, BLOCK_SIZE, DATA_SIZE, INODE_BITMAP_BASE, INODE_BITMAP_SIZE, INODE_SIZE}, superblock::*};
+use super::layout::addressing;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Quota {
+    pub uid: Uid,
+    /// Data Blocks The User May Own, 0 For Unlimited
+    pub block_limit: u32,
+    /// Inodes The User May Own, 0 For Unlimited
+    pub inode_limit: u32,
+    pub blocks_used: u32,
+    pub inodes_used: u32,
+}
+
+impl Quota {
+    fn read(block: &Block, i: usize) -> Self {
+        let (uid, offset) = block.read_u16(QUOTAS_OFFSET + i * QUOTA_SIZE);
+        let (block_limit, offset) = block.read_u32(offset);
+        let (inode_limit, offset) = block.read_u32(offset);
+        let (blocks_used, offset) = block.read_u32(offset);
+        let (inodes_used, _) = block.read_u32(offset);
+        Self { uid, block_limit, inode_limit, blocks_used, inodes_used }
+    }
+
+    fn write(&self, block: &mut Block, i: usize) {
+        let offset = block.write_u16(QUOTAS_OFFSET + i * QUOTA_SIZE, self.uid);
+        let offset = block.write_u32(offset, self.block_limit);
+        let offset = block.write_u32(offset, self.inode_limit);
+        let offset = block.write_u32(offset, self.blocks_used);
+        block.write_u32(offset, self.inodes_used);
+    }
+
+    /// Whether `blocks` & `inodes` More Stay Within The Limits
+    pub fn allows(&self, blocks: u32, inodes: u32) -> bool {
+        (self.block_limit == 0 || self.blocks_used + blocks <= self.block_limit)
+            && (self.inode_limit == 0 || self.inodes_used + inodes <= self.inode_limit)
+    }
+}
+
+/// A `df` Style Summary, In Blocks & Inodes
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Usage {
+    pub blocks_total: u32,
+    pub blocks_free: u32,
+    pub inodes_total: u32,
+    pub inodes_free: u32,
+}
+
+impl Usage {
+    pub fn blocks_used(&self) -> u32 {
+        self.blocks_total - self.blocks_free
+    }
+
+    pub fn inodes_used(&self) -> u32 {
+        self.inodes_total - self.inodes_free
+    }
+
+    pub fn bytes_free(&self) -> usize {
+        self.blocks_free as usize * BLOCK_SIZE
+    }
+}
+
+fn read_superblock<'a>() -> Block<'a> {
+    Block::read(addressing::superblock()).unwrap()
+}
+
+/// The Cache Is Only Kept On Current Format Filesystems
+fn is_current(block: &Block) -> bool {
+    block.read_u32(MAGIC_OFFSET).0 == MAGIC && block.read_u16(VERSION_OFFSET).0 == FORMAT_VERSION
+}
+
+fn has_summary(block: &Block) -> bool {
+    is_current(block) && block.read_u16(FLAGS_OFFSET).0 & flags::SUMMARY != 0
+}
+
+fn quota_count(block: &Block) -> usize {
+    (block.read_u8(QUOTA_COUNT_OFFSET).0 as usize).min(MAX_QUOTAS)
+}
+
+fn read_quotas(block: &Block) -> Vec<Quota> {
+    (0..quota_count(block)).map(|i| Quota::read(block, i)).collect()
+}
+
+fn write_quotas(block: &mut Block, quotas: &[Quota]) {
+    block.write_u8(QUOTA_COUNT_OFFSET, quotas.len() as u8);
+    for (i, quota) in quotas.iter().enumerate() {
+        quota.write(block, i);
+    }
+}
+
+/// Free Bits In Each Bitmap Block, Inode Bitmap First. `None` Until The Cache Is Trusted.
+pub fn bitmap_free() -> Option<Vec<u16>> {
+    let block = read_superblock();
+    if !has_summary(&block) { return None; }
+    Some((0..BITMAP_BLOCKS).map(|i| block.read_u16(BITMAP_FREE_OFFSET + i * 2).0).collect())
+}
+
+/// Called By The Bitmaps Whenever A Bit In Bitmap Block `bitmap` Actually Flips
+pub fn adjust_free(bitmap: u32, allocated: bool) {
+    let mut block = read_superblock();
+    if !has_summary(&block) { return; }
+    let offset = BITMAP_FREE_OFFSET + (bitmap - INODE_BITMAP_BASE) as usize * 2;
+    let (free, _) = block.read_u16(offset);
+    block.write_u16(offset, if allocated { free.saturating_sub(1) } else { free + 1 });
+    block.write().unwrap();
+}
+
+/// The Superblock As `rebuild` Would Leave It: Clear Bits Counted Per Bitmap Block & Each
+/// Quota's Usage Counted From The Inodes
+fn summarise<'a>() -> KResult<Block<'a>> {
+    let mut block = read_superblock();
+    if !is_current(&block) { return Err("Filesystem Needs Migrating, Run 'fsck -r'"); }
+
+    for i in 0..BITMAP_BLOCKS {
+        let bitmap = Block::read(INODE_BITMAP_BASE + i as u32).unwrap();
+        let used: u32 = bitmap.data().iter().map(|byte| byte.count_ones()).sum();
+        block.write_u16(BITMAP_FREE_OFFSET + i * 2, (BLOCKS_PER_BITMAP as u32 - used) as u16);
+    }
+
+    let mut quotas = read_quotas(&block);
+    if !quotas.is_empty() {
+        let mut inodes = Vec::new();
+        InodeBitmap::get_allocated(&mut inodes);
+        for quota in quotas.iter_mut() {
+            let (blocks, count) = owned_by(&inodes, quota.uid);
+            quota.blocks_used = blocks;
+            quota.inodes_used = count;
+        }
+        write_quotas(&mut block, &quotas);
+    }
+
+    let (current, _) = block.read_u16(FLAGS_OFFSET);
+    block.write_u16(FLAGS_OFFSET, current | flags::SUMMARY);
+    Ok(block)
+}
+
+/// Recounts The Cache From Scratch & Trusts It From Then On
+pub fn rebuild() -> KResult<()> {
+    summarise()?.write()
+}
+
+/// Whether A Trusted Cache Still Matches The Bitmaps & Inodes, For fsck
+pub fn is_consistent() -> bool {
+    let block = read_superblock();
+    if !has_summary(&block) { return true; }
+    summarise().map_or(true, |expected| expected.data() == block.data())
+}
+
+/// Blocks (Pointer Blocks Included) & Inodes `uid` Owns Among `inodes`
+fn owned_by(inodes: &[Inode], uid: Uid) -> (u32, u32) {
+    let owned = inodes.iter().filter(|inode| inode.uid() == uid);
+    owned.fold((0, 0), |(blocks, count), inode| {
+        let data = if inode.flags().is_file() || inode.flags().is_symlink() { inode.owned_blocks().len() as u32 } else { 0 };
+        (blocks + data, count + 1)
+    })
+}
+
+/// Cached Totals For The Whole Filesystem, Rebuilding The Cache If It Isn't Trusted
+pub fn usage() -> KResult<Usage> {
+    let free = match bitmap_free() {
+        Some(free) => free,
+        None => {
+            rebuild()?;
+            bitmap_free().ok_or("Failed To Summarise Filesystem")?
+        },
+    };
+    let inodes_free = free[..INODE_BITMAP_SIZE].iter().map(|free| *free as u32).sum::<u32>();
+    let blocks_free = free[INODE_BITMAP_SIZE..].iter().map(|free| *free as u32).sum::<u32>();
+    Ok(Usage {
+        blocks_total: DATA_SIZE as u32,
+        blocks_free: blocks_free.min(DATA_SIZE as u32),
+        inodes_total: INODE_SIZE as u32,
+        inodes_free: inodes_free.min(INODE_SIZE as u32),
+    })
+}
+
+pub fn quotas() -> Vec<Quota> {
+    let block = read_superblock();
+    if !is_current(&block) { return Vec::new(); }
+    if !has_summary(&block) && rebuild().is_ok() {
+        return read_quotas(&read_superblock());
+    }
+    read_quotas(&block)
+}
+
+pub fn get(uid: Uid) -> Option<Quota> {
+    quotas().into_iter().find(|quota| quota.uid == uid)
+}
+
+/// Sets `uid`'s Limits, Counting What It Already Owns When It Had No Quota Before. Only Root
+/// May Set Quotas & Root Itself Is Never Limited.
+pub fn set(creds: &Credentials, uid: Uid, block_limit: u32, inode_limit: u32) -> KResult<Quota> {
+    if !creds.is_root() { return Err("Permission Denied"); }
+    if uid == Credentials::ROOT.uid { return Err("Root Has No Quota"); }
+    if filesystem::format_version() != FORMAT_VERSION { return Err("Filesystem Needs Migrating, Run 'fsck -r'"); }
+
+    let mut quotas = quotas();
+    let quota = match quotas.iter_mut().find(|quota| quota.uid == uid) {
+        Some(quota) => {
+            quota.block_limit = block_limit;
+            quota.inode_limit = inode_limit;
+            *quota
+        },
+        None => {
+            if quotas.len() >= MAX_QUOTAS { return Err("Quota Table Is Full"); }
+            let mut inodes = Vec::new();
+            InodeBitmap::get_allocated(&mut inodes);
+            let (blocks_used, inodes_used) = owned_by(&inodes, uid);
+            let quota = Quota { uid, block_limit, inode_limit, blocks_used, inodes_used };
+            quotas.push(quota);
+            quota
+        },
+    };
+
+    let mut block = read_superblock();
+    write_quotas(&mut block, &quotas);
+    block.write()?;
+    Ok(quota)
+}
+
+pub fn remove(creds: &Credentials, uid: Uid) -> KResult<()> {
+    if !creds.is_root() { return Err("Permission Denied"); }
+    let mut quotas = quotas();
+    let len = quotas.len();
+    quotas.retain(|quota| quota.uid != uid);
+    if quotas.len() == len { return Err("No Such Quota"); }
+
+    let mut block = read_superblock();
+    write_quotas(&mut block, &quotas);
+    block.write()
+}
+
+/// Counts `blocks` & `inodes` Against `uid`'s Quota, Refusing Them If They Don't Fit. Users
+/// Without A Quota Are Unlimited & Not Tracked.
+pub fn charge(uid: Uid, blocks: u32, inodes: u32) -> KResult<()> {
+    update(uid, |quota| {
+        if !quota.allows(blocks, inodes) { return Err("Disk Quota Exceeded"); }
+        quota.blocks_used += blocks;
+        quota.inodes_used += inodes;
+        Ok(())
+    })
+}
+
+/// Gives Back What `charge` Took, Once The Blocks Or Inodes Are Freed
+pub fn release(uid: Uid, blocks: u32, inodes: u32) {
+    let _ = update(uid, |quota| {
+        quota.blocks_used = quota.blocks_used.saturating_sub(blocks);
+        quota.inodes_used = quota.inodes_used.saturating_sub(inodes);
+        Ok(())
+    });
+}
+
+fn update(uid: Uid, f: impl FnOnce(&mut Quota) -> KResult<()>) -> KResult<()> {
+    if uid == Credentials::ROOT.uid { return Ok(()); }
+    let mut block = read_superblock();
+    if !is_current(&block) || quota_count(&block) == 0 { return Ok(()); }
+    if !has_summary(&block) {
+        rebuild()?;
+        block = read_superblock();
+    }
+
+    let i = match (0..quota_count(&block)).find(|i| Quota::read(&block, *i).uid == uid) {
+        Some(i) => i,
+        None => return Ok(()),
+    };
+    let mut quota = Quota::read(&block, i);
+    f(&mut quota)?;
+    quota.write(&mut block, i);
+    block.write()
+}
+
+#[test_case]
+fn quota_limits() {
+    let quota = Quota { uid: 1000, block_limit: 10, inode_limit: 0, blocks_used: 8, inodes_used: 500 };
+    assert!(quota.allows(2, 100));
+    assert!(!quota.allows(3, 0));
+    assert!(Quota { block_limit: 0, ..quota }.allows(1000, 1));
+    assert!(!Quota { inode_limit: 500, ..quota }.allows(0, 1));
+}
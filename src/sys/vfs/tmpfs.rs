@@ -0,0 +1,217 @@
+//! Heap Backed Scratch Filesystem
+//!
+//! Everything Lives In A Tree Of `BTreeMap`s & Is Gone On Reboot. File Contents & Every Entry
+//! (File Or Directory) Count Towards The Size Limit, Which Keeps Scratch Files & Directories From
+//! Eating The Whole Kernel Heap.
+
+use alloc::{collections::BTreeMap, string::String, vec::Vec};
+
+use crate::KResult;
+
+use super::mount::{DirEntry, Mountable, Stat};
+
+/// Default Size Of The `/tmp` Mount
+pub const DEFAULT_LIMIT: usize = 256 * 1024;
+/// What An Entry Costs On Top Of Its Name, Roughly Its Share Of The `BTreeMap` Node
+const ENTRY_SIZE: usize = 32;
+
+/// What The Entry `name` Counts For Against The Limit
+fn entry_cost(name: &str) -> usize {
+    ENTRY_SIZE + name.len()
+}
+
+#[derive(Debug)]
+enum Node {
+    File(Vec<u8>),
+    Dir(BTreeMap<String, Node>),
+}
+
+impl Node {
+    fn stat(&self) -> Stat {
+        match self {
+            Node::File(data) => Stat::file(data.len()),
+            Node::Dir(_) => Stat::dir(),
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct TmpFs {
+    root: BTreeMap<String, Node>,
+    used: usize,
+    limit: usize,
+}
+
+fn split(path: &str) -> impl Iterator<Item = &str> {
+    path.split('/').filter(|part| !part.is_empty())
+}
+
+/// Splits A Path Into Its Parent Directory & Final Name
+fn split_last(path: &str) -> KResult<(&str, &str)> {
+    let path = path.trim_end_matches('/');
+    match path.rfind('/') {
+        Some(i) => Ok((&path[..i], &path[i + 1..])),
+        None if path.is_empty() => Err("Invalid Path"),
+        None => Ok(("", path)),
+    }
+}
+
+impl TmpFs {
+    pub fn new(limit: usize) -> Self {
+        Self { root: BTreeMap::new(), used: 0, limit }
+    }
+
+    fn dir(&self, path: &str) -> KResult<&BTreeMap<String, Node>> {
+        let mut dir = &self.root;
+        for part in split(path) {
+            dir = match dir.get(part) {
+                Some(Node::Dir(children)) => children,
+                Some(Node::File(_)) => return Err("Not A Directory"),
+                None => return Err("No Such File Or Directory"),
+            };
+        }
+        Ok(dir)
+    }
+
+    fn dir_mut(&mut self, path: &str) -> KResult<&mut BTreeMap<String, Node>> {
+        let mut dir = &mut self.root;
+        for part in split(path) {
+            dir = match dir.get_mut(part) {
+                Some(Node::Dir(children)) => children,
+                Some(Node::File(_)) => return Err("Not A Directory"),
+                None => return Err("No Such File Or Directory"),
+            };
+        }
+        Ok(dir)
+    }
+
+    fn node(&self, path: &str) -> KResult<&Node> {
+        let (parent, name) = split_last(path)?;
+        self.dir(parent)?.get(name).ok_or("No Such File Or Directory")
+    }
+
+    /// Changes A File Through `f`, Refusing Changes That Would Take It Over The Limit.
+    /// `new_len` Is The File's Size Afterwards, A New File's Entry Is Charged Too.
+    fn modify(&mut self, path: &str, new_len: usize, f: impl FnOnce(&mut Vec<u8>)) -> KResult<()> {
+        let (parent, name) = split_last(path)?;
+        let (used, limit) = (self.used, self.limit);
+        let dir = self.dir_mut(parent)?;
+        let created = !dir.contains_key(name);
+        let file = match dir.entry(String::from(name)).or_insert_with(|| Node::File(Vec::new())) {
+            Node::File(data) => data,
+            Node::Dir(_) => return Err("Is A Directory"),
+        };
+
+        let old = file.len();
+        let entry = if created { entry_cost(name) } else { 0 };
+        if used - old + new_len + entry > limit {
+            if created { dir.remove(name); }
+            return Err("No Space Left On tmpfs");
+        }
+        f(file);
+        self.used = used - old + new_len + entry;
+        Ok(())
+    }
+}
+
+impl Mountable for TmpFs {
+    fn fs_type(&self) -> &'static str {
+        "tmpfs"
+    }
+
+    fn stat(&self, path: &str) -> KResult<Stat> {
+        if split(path).next().is_none() { return Ok(Stat::dir()); }
+        Ok(self.node(path)?.stat())
+    }
+
+    fn read_dir(&self, path: &str) -> KResult<Vec<DirEntry>> {
+        Ok(self.dir(path)?.iter().map(|(name, node)| DirEntry { name: name.clone(), stat: node.stat() }).collect())
+    }
+
+    fn read(&self, path: &str, buf: &mut Vec<u8>) -> KResult<usize> {
+        match self.node(path)? {
+            Node::File(data) => {
+                buf.extend_from_slice(data);
+                Ok(data.len())
+            },
+            Node::Dir(_) => Err("Is A Directory"),
+        }
+    }
+
+    fn write(&mut self, path: &str, data: &[u8]) -> KResult<()> {
+        self.modify(path, data.len(), |file| {
+            file.clear();
+            file.extend_from_slice(data);
+        })
+    }
+
+    fn append(&mut self, path: &str, data: &[u8]) -> KResult<()> {
+        let old = match self.node(path) {
+            Ok(Node::File(file)) => file.len(),
+            _ => 0,
+        };
+        self.modify(path, old + data.len(), |file| file.extend_from_slice(data))
+    }
+
+    fn create_dir(&mut self, path: &str) -> KResult<()> {
+        let (parent, name) = split_last(path)?;
+        let (used, limit) = (self.used, self.limit);
+        let dir = self.dir_mut(parent)?;
+        if dir.contains_key(name) { return Err("File Exists"); }
+        if used + entry_cost(name) > limit { return Err("No Space Left On tmpfs"); }
+        dir.insert(String::from(name), Node::Dir(BTreeMap::new()));
+        self.used += entry_cost(name);
+        Ok(())
+    }
+
+    fn remove(&mut self, path: &str) -> KResult<()> {
+        let (parent, name) = split_last(path)?;
+        let dir = self.dir_mut(parent)?;
+        let freed = match dir.get(name) {
+            Some(Node::File(data)) => data.len(),
+            Some(Node::Dir(children)) if !children.is_empty() => return Err("Directory Not Empty"),
+            Some(Node::Dir(_)) => 0,
+            None => return Err("No Such File Or Directory"),
+        };
+        dir.remove(name);
+        self.used -= freed + entry_cost(name);
+        Ok(())
+    }
+
+    fn usage(&self) -> Option<(usize, usize)> {
+        Some((self.used, self.limit))
+    }
+}
+
+#[test_case]
+fn tmpfs_files_and_dirs() {
+    let mut fs = TmpFs::new(256);
+    fs.create_dir("a").expect("");
+    fs.write("a/b.txt", b"hello").expect("");
+    fs.append("a/b.txt", b" world").expect("");
+
+    let mut buf = Vec::new();
+    assert_eq!(fs.read("a/b.txt", &mut buf), Ok(11));
+    assert_eq!(&buf[..], b"hello world");
+    assert_eq!(fs.read_dir("a").expect("").len(), 1);
+    assert_eq!(fs.remove("a"), Err("Directory Not Empty"));
+    fs.remove("a/b.txt").expect("");
+    assert_eq!(fs.usage(), Some((entry_cost("a"), 256)));
+    fs.remove("a").expect("");
+    assert_eq!(fs.usage(), Some((0, 256)));
+}
+
+#[test_case]
+fn tmpfs_size_limit() {
+    let mut fs = TmpFs::new(entry_cost("x") + 8);
+    fs.write("x", b"12345678").expect("");
+    assert!(fs.append("x", b"9").is_err());
+    assert!(fs.write("y", b"1").is_err());
+    assert!(fs.read("y", &mut Vec::new()).is_err());
+    assert!(fs.create_dir("d").is_err());
+    assert!(fs.stat("d").is_err());
+    fs.write("x", b"1").expect("");
+    assert!(fs.write("y", b"1").is_err());
+    fs.remove("x").expect("");
+    fs.write("y", b"1234567").expect("");
+}
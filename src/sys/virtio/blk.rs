@@ -0,0 +1,196 @@
+//! Virtio Block Device Driver
+//!
+//! Requests Are Issued Synchronously On Queue 0 As A Three Descriptor Chain:
+//! Request Header, One Sector Of Data, Then The Status Byte Written By The Device.
+
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::hint::spin_loop;
+use lazy_static::lazy_static;
+use spin::Mutex;
+use x86_64::instructions::interrupts::without_interrupts;
+
+use crate::{KResult, print, sys::{mem::allocator::PhysBuf, pci}};
+
+use super::{Transport, VirtQueue, VENDOR_ID};
+
+const DEVICE_ID_LEGACY: u16 = 0x1001;
+const DEVICE_ID_MODERN: u16 = 0x1042;
+
+const F_FLUSH: u64 = 1 << 9;
+
+const REQ_IN: u32 = 0;
+const REQ_OUT: u32 = 1;
+const REQ_FLUSH: u32 = 4;
+
+const STATUS_OK: u8 = 0;
+
+const SECTOR_SIZE: usize = 512;
+const HEADER_LEN: usize = 16;
+const DATA_OFFSET: usize = HEADER_LEN;
+const STATUS_OFFSET: usize = DATA_OFFSET + SECTOR_SIZE;
+
+/// Used Ring Polls Before A Request Gives Up. Requests Run With Interrupts Off, Where The Timer
+/// Doesn't Tick, So The Wait Is Bounded By Count Instead.
+const POLL_LIMIT: u32 = 10_000_000;
+
+#[derive(Debug, Clone)]
+pub struct VirtioBlk {
+    transport: Transport,
+    queue: VirtQueue,
+    request: PhysBuf,
+    sectors: u64,
+    can_flush: bool,
+    /// Set When A Request Times Out. The Device Is Reset Then So It Can't Still Complete That
+    /// Request Into The Reused Buffer, & Every Later Request Fails.
+    dead: bool,
+}
+
+impl VirtioBlk {
+    pub fn new(pci_device: &pci::DeviceConfig) -> KResult<Self> {
+        let mut transport = Transport::new(pci_device);
+        let features = transport.negotiate(F_FLUSH)?;
+
+        let size = transport.max_queue_size(0);
+        if size == 0 { return Err("Virtio Block Device Has No Request Queue"); }
+        let mut queue = VirtQueue::new(0, size);
+        transport.setup_queue(&mut queue);
+        transport.add_status(super::STATUS_DRIVER_OK);
+
+        let sectors = transport.read_config_u64(0);
+        Ok(Self {
+            transport,
+            queue,
+            request: PhysBuf::new_aligned(STATUS_OFFSET + 1, 16),
+            sectors,
+            can_flush: features & F_FLUSH != 0,
+            dead: false,
+        })
+    }
+
+    fn request(&mut self, kind: u32, block: u64, write: bool) -> KResult<()> {
+        if self.dead { return Err("Virtio Block Device Stopped Responding"); }
+        self.request[0..4].copy_from_slice(&kind.to_le_bytes());
+        self.request[4..8].copy_from_slice(&0u32.to_le_bytes());
+        self.request[8..16].copy_from_slice(&block.to_le_bytes());
+        self.request[STATUS_OFFSET] = 0xFF;
+
+        let addr = self.request.addr();
+        let header = (addr, HEADER_LEN as u32, false);
+        let data = (addr + DATA_OFFSET as u64, SECTOR_SIZE as u32, !write);
+        let status = (addr + STATUS_OFFSET as u64, 1, true);
+
+        let head = if kind == REQ_FLUSH {
+            self.queue.submit(&[header, status])?
+        } else {
+            self.queue.submit(&[header, data, status])?
+        };
+        self.transport.notify(&self.queue);
+
+        let mut polls = 0;
+        loop {
+            match self.queue.pop_used() {
+                Some((id, _)) if id == head => break,
+                Some(_) => {},
+                None if polls > POLL_LIMIT => { // Hanged
+                    self.transport.set_status(0);
+                    self.dead = true;
+                    return Err("Virtio Block Request Timed Out");
+                },
+                None => { polls += 1; spin_loop(); },
+            }
+        }
+        self.transport.isr();
+
+        if self.request[STATUS_OFFSET] != STATUS_OK {
+            return Err("Virtio Block Request Failed");
+        }
+        Ok(())
+    }
+
+    pub fn read(&mut self, block: u32, buf: &mut [u8]) -> KResult<()> {
+        assert!(buf.len() == SECTOR_SIZE);
+        self.request(REQ_IN, block as u64, false)?;
+        buf.copy_from_slice(&self.request[DATA_OFFSET..STATUS_OFFSET]);
+        Ok(())
+    }
+
+    pub fn write(&mut self, block: u32, buf: &[u8]) -> KResult<()> {
+        assert!(buf.len() == SECTOR_SIZE);
+        self.request[DATA_OFFSET..STATUS_OFFSET].copy_from_slice(buf);
+        self.request(REQ_OUT, block as u64, true)
+    }
+
+    pub fn flush(&mut self) -> KResult<()> {
+        if !self.can_flush { return Ok(()); }
+        self.request(REQ_FLUSH, 0, false)
+    }
+}
+
+lazy_static! {
+    pub static ref DEVICES: Mutex<Vec<VirtioBlk>> = Mutex::new(Vec::new());
+}
+
+fn disk_size(sectors: u64) -> (u32, String) {
+    let bytes = sectors * SECTOR_SIZE as u64;
+    if bytes >> 20 < 1000 {
+        ((bytes >> 20) as u32, String::from("MB"))
+    } else {
+        ((bytes >> 30) as u32, String::from("GB"))
+    }
+}
+
+pub fn init() {
+    let devices: Vec<pci::DeviceConfig> = pci::PCI_DEVICES.lock().iter()
+        .filter(|dev| dev.vendor_id == VENDOR_ID && (dev.device_id == DEVICE_ID_LEGACY || dev.device_id == DEVICE_ID_MODERN))
+        .copied()
+        .collect();
+
+    for mut pci_device in devices {
+        pci_device.enable_bus_mastering();
+        match VirtioBlk::new(&pci_device) {
+            Ok(dev) => DEVICES.lock().push(dev),
+            Err(e) => print!("VIRTIO-BLK {:02}:{:02}: {}\n", pci_device.bus, pci_device.device, e),
+        }
+    }
+
+    for (id, modern, size, unit, _) in list() {
+        print!("VIRTIO-BLK {} {} ({} {})\n", id, if modern {"Modern"} else {"Legacy"}, size, unit);
+    }
+}
+
+pub fn list() -> Vec<(u8, bool, u32, String, u32)> {
+    let devices = DEVICES.lock();
+    let mut res = Vec::new();
+    for (id, dev) in devices.iter().enumerate() {
+        let (size, unit) = disk_size(dev.sectors);
+        res.push((id as u8, dev.transport.is_modern(), size, unit, dev.sectors as u32));
+    }
+    res
+}
+
+pub fn sector_count(id: u8) -> u32 {
+    let devices = DEVICES.lock();
+    devices.get(id as usize).map(|dev| dev.sectors as u32).unwrap_or(0)
+}
+
+pub fn read(id: u8, block: u32, buf: &mut [u8]) -> KResult<()> {
+    without_interrupts(|| {
+        let mut devices = DEVICES.lock();
+        devices.get_mut(id as usize).ok_or("No Such Virtio Block Device")?.read(block, buf)
+    })
+}
+
+pub fn write(id: u8, block: u32, buf: &[u8]) -> KResult<()> {
+    without_interrupts(|| {
+        let mut devices = DEVICES.lock();
+        devices.get_mut(id as usize).ok_or("No Such Virtio Block Device")?.write(block, buf)
+    })
+}
+
+pub fn flush(id: u8) -> KResult<()> {
+    without_interrupts(|| {
+        let mut devices = DEVICES.lock();
+        devices.get_mut(id as usize).ok_or("No Such Virtio Block Device")?.flush()
+    })
+}
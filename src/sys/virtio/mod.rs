@@ -0,0 +1,393 @@
+//! Virtio PCI Transport & Split Virtqueues
+//!
+//! Supports Both The Legacy (I/O Port) Transport Used By Transitional Devices And The
+//! Modern (Virtio 1.0, Capability Described MMIO) Transport.
+
+pub mod blk;
+pub mod net;
+
+use bit_field::BitField;
+use core::ptr::{read_volatile, write_volatile};
+use core::sync::atomic::{fence, Ordering};
+use x86_64::PhysAddr;
+use x86_64::instructions::port::Port;
+
+use alloc::vec::Vec;
+
+use crate::KResult;
+use crate::sys::mem::{self, allocator::PhysBuf};
+use crate::sys::pci::DeviceConfig;
+
+pub const VENDOR_ID: u16 = 0x1AF4;
+
+pub const STATUS_ACKNOWLEDGE: u8 = 1;
+pub const STATUS_DRIVER: u8 = 2;
+pub const STATUS_DRIVER_OK: u8 = 4;
+pub const STATUS_FEATURES_OK: u8 = 8;
+pub const STATUS_FAILED: u8 = 128;
+
+pub const F_VERSION_1: u64 = 1 << 32;
+
+const DESC_F_NEXT: u16 = 1;
+const DESC_F_WRITE: u16 = 2;
+
+// Legacy Register Layout (BAR0 I/O Space)
+const LEGACY_DEVICE_FEATURES: u16 = 0x00;
+const LEGACY_DRIVER_FEATURES: u16 = 0x04;
+const LEGACY_QUEUE_ADDR: u16 = 0x08;
+const LEGACY_QUEUE_SIZE: u16 = 0x0C;
+const LEGACY_QUEUE_SELECT: u16 = 0x0E;
+const LEGACY_QUEUE_NOTIFY: u16 = 0x10;
+const LEGACY_STATUS: u16 = 0x12;
+const LEGACY_ISR: u16 = 0x13;
+const LEGACY_DEVICE_CONFIG: u16 = 0x14;
+
+// Modern Common Configuration Layout
+const COMMON_DEVICE_FEATURE_SELECT: u64 = 0x00;
+const COMMON_DEVICE_FEATURE: u64 = 0x04;
+const COMMON_DRIVER_FEATURE_SELECT: u64 = 0x08;
+const COMMON_DRIVER_FEATURE: u64 = 0x0C;
+const COMMON_STATUS: u64 = 0x14;
+const COMMON_QUEUE_SELECT: u64 = 0x16;
+const COMMON_QUEUE_SIZE: u64 = 0x18;
+const COMMON_QUEUE_ENABLE: u64 = 0x1C;
+const COMMON_QUEUE_NOTIFY_OFF: u64 = 0x1E;
+const COMMON_QUEUE_DESC: u64 = 0x20;
+const COMMON_QUEUE_DRIVER: u64 = 0x28;
+const COMMON_QUEUE_DEVICE: u64 = 0x30;
+
+// Vendor Specific PCI Capability Types
+const CAP_VENDOR: u8 = 0x09;
+const CAP_COMMON_CFG: u8 = 1;
+const CAP_NOTIFY_CFG: u8 = 2;
+const CAP_ISR_CFG: u8 = 3;
+const CAP_DEVICE_CFG: u8 = 4;
+
+const QUEUE_ALIGN: usize = 4096;
+
+fn align_up(value: usize, align: usize) -> usize {
+    (value + align - 1) & !(align - 1)
+}
+
+unsafe fn mmio_read<T: Copy>(addr: u64) -> T {
+    read_volatile(addr as *const T)
+}
+
+unsafe fn mmio_write<T: Copy>(addr: u64, value: T) {
+    write_volatile(addr as *mut T, value)
+}
+
+#[derive(Debug, Clone, Copy)]
+pub enum Transport {
+    Legacy { io_base: u16 },
+    Modern { common: u64, notify: u64, notify_multiplier: u32, isr: u64, device: u64 },
+}
+
+impl Transport {
+    /// Picks The Modern Transport If The Device Advertises Its Capabilities, Otherwise Falls
+    /// Back To The Legacy I/O BAR.
+    pub fn new(pci: &DeviceConfig) -> Self {
+        let mut common = None;
+        let mut notify = None;
+        let mut isr = None;
+        let mut device = None;
+
+        for (id, offset) in pci.capabilities() {
+            if id != CAP_VENDOR { continue; }
+            let cfg_type = pci.read_config(offset).get_bits(24..32) as u8;
+            let bar = pci.read_config(offset + 4).get_bits(0..8) as usize;
+            if bar > 5 { continue; }
+            let bar_offset = pci.read_config(offset + 8) as u64;
+            let addr = mem::phys_to_virt(PhysAddr::new(pci.bar_addr(bar) + bar_offset)).as_u64();
+            match cfg_type {
+                CAP_COMMON_CFG => common = Some(addr),
+                CAP_NOTIFY_CFG => notify = Some((addr, pci.read_config(offset + 16))),
+                CAP_ISR_CFG => isr = Some(addr),
+                CAP_DEVICE_CFG => device = Some(addr),
+                _ => {},
+            }
+        }
+
+        match (common, notify, isr, device) {
+            (Some(common), Some((notify, notify_multiplier)), Some(isr), Some(device)) => {
+                Self::Modern { common, notify, notify_multiplier, isr, device }
+            },
+            _ => Self::Legacy { io_base: pci.bar_addr(0) as u16 },
+        }
+    }
+
+    pub fn is_modern(&self) -> bool {
+        match self {
+            Self::Modern { .. } => true,
+            Self::Legacy { .. } => false,
+        }
+    }
+
+    pub fn status(&self) -> u8 {
+        unsafe {
+            match *self {
+                Self::Legacy { io_base } => Port::<u8>::new(io_base + LEGACY_STATUS).read(),
+                Self::Modern { common, .. } => mmio_read(common + COMMON_STATUS),
+            }
+        }
+    }
+
+    pub fn set_status(&mut self, status: u8) {
+        unsafe {
+            match *self {
+                Self::Legacy { io_base } => Port::<u8>::new(io_base + LEGACY_STATUS).write(status),
+                Self::Modern { common, .. } => mmio_write(common + COMMON_STATUS, status),
+            }
+        }
+    }
+
+    pub fn add_status(&mut self, status: u8) {
+        let old = self.status();
+        self.set_status(old | status);
+    }
+
+    pub fn device_features(&self) -> u64 {
+        unsafe {
+            match *self {
+                Self::Legacy { io_base } => Port::<u32>::new(io_base + LEGACY_DEVICE_FEATURES).read() as u64,
+                Self::Modern { common, .. } => {
+                    mmio_write::<u32>(common + COMMON_DEVICE_FEATURE_SELECT, 0);
+                    let low: u32 = mmio_read(common + COMMON_DEVICE_FEATURE);
+                    mmio_write::<u32>(common + COMMON_DEVICE_FEATURE_SELECT, 1);
+                    let high: u32 = mmio_read(common + COMMON_DEVICE_FEATURE);
+                    (high as u64) << 32 | low as u64
+                },
+            }
+        }
+    }
+
+    pub fn set_driver_features(&mut self, features: u64) {
+        unsafe {
+            match *self {
+                Self::Legacy { io_base } => Port::<u32>::new(io_base + LEGACY_DRIVER_FEATURES).write(features as u32),
+                Self::Modern { common, .. } => {
+                    mmio_write::<u32>(common + COMMON_DRIVER_FEATURE_SELECT, 0);
+                    mmio_write::<u32>(common + COMMON_DRIVER_FEATURE, features as u32);
+                    mmio_write::<u32>(common + COMMON_DRIVER_FEATURE_SELECT, 1);
+                    mmio_write::<u32>(common + COMMON_DRIVER_FEATURE, (features >> 32) as u32);
+                },
+            }
+        }
+    }
+
+    /// Resets The Device, Then Walks It Through Feature Negotiation. `wanted` Is Masked With
+    /// The Device's Features, Returning The Accepted Set.
+    pub fn negotiate(&mut self, wanted: u64) -> KResult<u64> {
+        self.set_status(0);
+        self.add_status(STATUS_ACKNOWLEDGE);
+        self.add_status(STATUS_DRIVER);
+
+        let mut wanted = wanted;
+        if self.is_modern() { wanted |= F_VERSION_1; }
+        let features = self.device_features() & wanted;
+        self.set_driver_features(features);
+
+        if self.is_modern() {
+            self.add_status(STATUS_FEATURES_OK);
+            if self.status() & STATUS_FEATURES_OK == 0 {
+                self.set_status(STATUS_FAILED);
+                return Err("Virtio Device Rejected Features");
+            }
+        }
+        Ok(features)
+    }
+
+    pub fn max_queue_size(&self, queue: u16) -> u16 {
+        unsafe {
+            match *self {
+                Self::Legacy { io_base } => {
+                    Port::<u16>::new(io_base + LEGACY_QUEUE_SELECT).write(queue);
+                    Port::<u16>::new(io_base + LEGACY_QUEUE_SIZE).read()
+                },
+                Self::Modern { common, .. } => {
+                    mmio_write(common + COMMON_QUEUE_SELECT, queue);
+                    mmio_read(common + COMMON_QUEUE_SIZE)
+                },
+            }
+        }
+    }
+
+    /// Hands The Queue's Memory To The Device & Enables It
+    pub fn setup_queue(&mut self, queue: &mut VirtQueue) {
+        unsafe {
+            match *self {
+                Self::Legacy { io_base } => {
+                    Port::<u16>::new(io_base + LEGACY_QUEUE_SELECT).write(queue.index);
+                    Port::<u32>::new(io_base + LEGACY_QUEUE_ADDR).write((queue.addr() / QUEUE_ALIGN as u64) as u32);
+                },
+                Self::Modern { common, notify, notify_multiplier, .. } => {
+                    mmio_write(common + COMMON_QUEUE_SELECT, queue.index);
+                    mmio_write(common + COMMON_QUEUE_SIZE, queue.size);
+                    mmio_write(common + COMMON_QUEUE_DESC, queue.addr());
+                    mmio_write(common + COMMON_QUEUE_DRIVER, queue.addr() + queue.avail_offset as u64);
+                    mmio_write(common + COMMON_QUEUE_DEVICE, queue.addr() + queue.used_offset as u64);
+                    let notify_off: u16 = mmio_read(common + COMMON_QUEUE_NOTIFY_OFF);
+                    queue.notify_addr = notify + (notify_off as u64 * notify_multiplier as u64);
+                    mmio_write::<u16>(common + COMMON_QUEUE_ENABLE, 1);
+                },
+            }
+        }
+    }
+
+    pub fn notify(&mut self, queue: &VirtQueue) {
+        unsafe {
+            match *self {
+                Self::Legacy { io_base } => Port::<u16>::new(io_base + LEGACY_QUEUE_NOTIFY).write(queue.index),
+                Self::Modern { .. } => mmio_write(queue.notify_addr, queue.index),
+            }
+        }
+    }
+
+    /// Reads (And So Acknowledges) The Interrupt Status
+    pub fn isr(&mut self) -> u8 {
+        unsafe {
+            match *self {
+                Self::Legacy { io_base } => Port::<u8>::new(io_base + LEGACY_ISR).read(),
+                Self::Modern { isr, .. } => mmio_read(isr),
+            }
+        }
+    }
+
+    pub fn read_config_u8(&self, offset: u16) -> u8 {
+        unsafe {
+            match *self {
+                Self::Legacy { io_base } => Port::<u8>::new(io_base + LEGACY_DEVICE_CONFIG + offset).read(),
+                Self::Modern { device, .. } => mmio_read(device + offset as u64),
+            }
+        }
+    }
+
+    pub fn read_config_u32(&self, offset: u16) -> u32 {
+        unsafe {
+            match *self {
+                Self::Legacy { io_base } => Port::<u32>::new(io_base + LEGACY_DEVICE_CONFIG + offset).read(),
+                Self::Modern { device, .. } => mmio_read(device + offset as u64),
+            }
+        }
+    }
+
+    pub fn read_config_u64(&self, offset: u16) -> u64 {
+        let low = self.read_config_u32(offset) as u64;
+        let high = self.read_config_u32(offset + 4) as u64;
+        high << 32 | low
+    }
+}
+
+/// A Split Virtqueue Laid Out As The Legacy Interface Requires (Descriptor Table, Available
+/// Ring, Then The Used Ring On The Next Page), Which The Modern Interface Also Accepts.
+#[derive(Debug, Clone)]
+pub struct VirtQueue {
+    index: u16,
+    size: u16,
+    mem: PhysBuf,
+    avail_offset: usize,
+    used_offset: usize,
+    notify_addr: u64,
+
+    /// Descriptors Not In A Chain The Device Holds, Chains Can Come Back In Any Order
+    free: Vec<u16>,
+    last_used: u16,
+}
+
+impl VirtQueue {
+    pub fn new(index: u16, size: u16) -> Self {
+        let avail_offset = 16 * size as usize;
+        let used_offset = align_up(avail_offset + 6 + 2 * size as usize, QUEUE_ALIGN);
+        let len = used_offset + align_up(6 + 8 * size as usize, QUEUE_ALIGN);
+        Self {
+            index,
+            size,
+            mem: PhysBuf::new_aligned(len, QUEUE_ALIGN),
+            avail_offset,
+            used_offset,
+            notify_addr: 0,
+
+            free: (0..size).rev().collect(),
+            last_used: 0,
+        }
+    }
+
+    pub fn addr(&self) -> u64 {
+        self.mem.addr()
+    }
+
+    pub fn size(&self) -> u16 {
+        self.size
+    }
+
+    fn ptr<T>(&self, offset: usize) -> *mut T {
+        self.mem[offset..].as_ptr() as *mut T
+    }
+
+    fn write_desc(&mut self, index: u16, addr: u64, len: u32, flags: u16, next: u16) {
+        let offset = 16 * index as usize;
+        unsafe {
+            write_volatile(self.ptr::<u64>(offset), addr);
+            write_volatile(self.ptr::<u32>(offset + 8), len);
+            write_volatile(self.ptr::<u16>(offset + 12), flags);
+            write_volatile(self.ptr::<u16>(offset + 14), next);
+        }
+    }
+
+    /// Places A Descriptor Chain On The Available Ring. Each Buffer Is (Physical Address,
+    /// Length, Device Writable). Returns The Head Descriptor's Index.
+    pub fn submit(&mut self, buffers: &[(u64, u32, bool)]) -> KResult<u16> {
+        if buffers.is_empty() { return Err("Empty Descriptor Chain"); }
+        if buffers.len() > self.free.len() { return Err("Virtqueue Is Full"); }
+
+        let indices = self.free.split_off(self.free.len() - buffers.len());
+        for (i, &(addr, len, writable)) in buffers.iter().enumerate() {
+            let next = indices.get(i + 1).copied().unwrap_or(0);
+            let mut flags = 0;
+            if writable { flags |= DESC_F_WRITE; }
+            if i + 1 < buffers.len() { flags |= DESC_F_NEXT; }
+            self.write_desc(indices[i], addr, len, flags, next);
+        }
+        let head = indices[0];
+
+        unsafe {
+            let idx: u16 = read_volatile(self.ptr::<u16>(self.avail_offset + 2));
+            let slot = self.avail_offset + 4 + 2 * (idx % self.size) as usize;
+            write_volatile(self.ptr::<u16>(slot), head);
+            fence(Ordering::SeqCst);
+            write_volatile(self.ptr::<u16>(self.avail_offset + 2), idx.wrapping_add(1));
+            fence(Ordering::SeqCst);
+        }
+        Ok(head)
+    }
+
+    /// Pops The Next Used Element As (Head Descriptor, Bytes Written), Releasing Every
+    /// Descriptor In Its Chain. The Head's Address Can Still Be Read Until The Next Submit.
+    pub fn pop_used(&mut self) -> Option<(u16, u32)> {
+        fence(Ordering::SeqCst);
+        let idx: u16 = unsafe { read_volatile(self.ptr::<u16>(self.used_offset + 2)) };
+        if idx == self.last_used { return None; }
+
+        let elem = self.used_offset + 4 + 8 * (self.last_used % self.size) as usize;
+        let (id, len) = unsafe {
+            (read_volatile(self.ptr::<u32>(elem)), read_volatile(self.ptr::<u32>(elem + 4)))
+        };
+        self.last_used = self.last_used.wrapping_add(1);
+
+        let mut index = id as u16;
+        for _ in 0..self.size {
+            if index >= self.size || self.free.contains(&index) { break; }
+            self.free.push(index);
+            let offset = 16 * index as usize;
+            let flags: u16 = unsafe { read_volatile(self.ptr::<u16>(offset + 12)) };
+            if flags & DESC_F_NEXT == 0 { break; }
+            index = unsafe { read_volatile(self.ptr::<u16>(offset + 14)) };
+        }
+        Some((id as u16, len))
+    }
+
+    /// Re-Reads A Descriptor's Buffer Address, So Drivers Can Find Which Buffer Came Back
+    pub fn desc_addr(&self, index: u16) -> u64 {
+        unsafe { read_volatile(self.ptr::<u64>(16 * index as usize)) }
+    }
+}
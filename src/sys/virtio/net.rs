@@ -0,0 +1,349 @@
+//! Virtio Network Device Driver
+//!
+//! Queue 0 Receives & Queue 1 Transmits, Each Buffer A Single Descriptor Holding The Virtio Net
+//! Header Followed By The Frame. Receive Buffers Are Handed Straight Back To The Device Once
+//! Their Frame Is Copied Out. The Device May Finish Buffers In Any Order, So Each Is Found
+//! Again From The Descriptor The Used Ring Names & Transmit Buffers Come Off A Free List. When The Device Offers Checksum Offload, smoltcp Is Told Through
+//! [DeviceCapabilities] To Leave TCP & UDP Checksums Alone On The Way Out & The Device Fills
+//! Them In, Partial Checksums Coming The Other Way Are Finished Here Before smoltcp Sees Them.
+
+use alloc::vec::Vec;
+use lazy_static::lazy_static;
+use smoltcp::{phy::{self, Checksum, Device, DeviceCapabilities}, wire::EthernetAddress};
+use spin::Mutex;
+
+use crate::{KResult, arch::i386::interrupts::idt, log, sys::{self, mem::allocator::PhysBuf, net::nic::{self, MAX_CARDS}, pci}};
+
+use super::{Transport, VirtQueue, VENDOR_ID};
+
+/// The Name In `network.toml`
+pub const DEVICE: &str = "PCI/REDHAT/VIRTIO-NET";
+
+const DEVICE_ID_LEGACY: u16 = 0x1000;
+const DEVICE_ID_MODERN: u16 = 0x1041;
+
+const F_CSUM: u64 = 1 << 0; // Device Finishes Partial Checksums We Send
+const F_GUEST_CSUM: u64 = 1 << 1; // Device May Send Us Partial Checksums
+const F_MAC: u64 = 1 << 5;
+const F_ANY_LAYOUT: u64 = 1 << 27;
+
+const HDR_F_NEEDS_CSUM: u8 = 1;
+
+/// The Header Without `num_buffers`, Which Only Modern Devices Add When Mergeable Buffers Are Off
+const LEGACY_HEADER_LEN: usize = 10;
+const MODERN_HEADER_LEN: usize = 12;
+
+const RX_QUEUE: u16 = 0;
+const TX_QUEUE: u16 = 1;
+const RX_BUFFERS: usize = 64;
+const TX_BUFFERS: usize = 16;
+const BUFFER_LEN: usize = 1536;
+
+/// Ethernet Header + 1500 Bytes Of Payload
+const MTU: usize = 1514;
+
+const ETHERNET_HEADER_LEN: usize = 14;
+const ETHERTYPE_IPV4: u16 = 0x0800;
+const PROTOCOL_TCP: u8 = 6;
+const PROTOCOL_UDP: u8 = 17;
+
+lazy_static! {
+    /// A Copy Of Each Device's Transport For The Interrupt Handler, Which Only Reads The ISR
+    static ref TRANSPORTS: Mutex<Vec<Transport>> = Mutex::new(Vec::new());
+}
+
+/// Adds `data` To A Ones' Complement Sum As Big Endian Words
+fn checksum(mut sum: u32, data: &[u8]) -> u32 {
+    for chunk in data.chunks(2) {
+        sum += (chunk[0] as u32) << 8 | *chunk.get(1).unwrap_or(&0) as u32;
+    }
+    sum
+}
+
+fn fold(mut sum: u32) -> u16 {
+    while sum >> 16 != 0 {
+        sum = (sum & 0xFFFF) + (sum >> 16);
+    }
+    sum as u16
+}
+
+/// Where A TCP Or UDP Checksum Starts & Where It's Stored Relative To That, Along With The
+/// Pseudo Header Sum The Device Expects To Find In The Checksum Field
+fn partial_checksum(frame: &[u8]) -> Option<(usize, usize, u16)> {
+    if frame.len() < ETHERNET_HEADER_LEN + 20 { return None; }
+    if u16::from_be_bytes([frame[12], frame[13]]) != ETHERTYPE_IPV4 { return None; }
+    let ip = &frame[ETHERNET_HEADER_LEN..];
+    let header_len = (ip[0] & 0xF) as usize * 4;
+    let total_len = u16::from_be_bytes([ip[2], ip[3]]) as usize;
+    // Fragments Can't Be Checksummed One At A Time
+    if u16::from_be_bytes([ip[6], ip[7]]) & 0x3FFF != 0 { return None; }
+    if header_len < 20 || total_len < header_len || ip.len() < total_len { return None; }
+    let offset = match ip[9] {
+        PROTOCOL_TCP => 16,
+        PROTOCOL_UDP => 6,
+        _ => return None,
+    };
+    let len = total_len - header_len;
+    if len < offset + 2 { return None; }
+
+    let sum = checksum(ip[9] as u32 + len as u32, &ip[12..20]);
+    Some((ETHERNET_HEADER_LEN + header_len, offset, fold(sum)))
+}
+
+#[derive(Debug, Clone)]
+pub struct VirtioNet {
+    /// Under `/dev`, See [nic::card_name]
+    name: &'static str,
+    transport: Transport,
+    rx_queue: VirtQueue,
+    tx_queue: VirtQueue,
+    rx_buffers: Vec<PhysBuf>,
+    tx_buffers: Vec<PhysBuf>,
+    /// Receive Buffers The Device Wouldn't Take Back Yet
+    rx_idle: Vec<usize>,
+    /// Transmit Buffers The Device Isn't Holding
+    tx_free: Vec<usize>,
+    header_len: usize,
+    features: u64,
+    eth_addr: EthernetAddress,
+}
+
+impl VirtioNet {
+    pub fn new(pci_device: &pci::DeviceConfig, name: &'static str) -> KResult<Self> {
+        let mut transport = Transport::new(pci_device);
+        let features = transport.negotiate(F_CSUM | F_GUEST_CSUM | F_MAC | F_ANY_LAYOUT)?;
+
+        let mut queues = Vec::new();
+        for index in [RX_QUEUE, TX_QUEUE].iter().copied() {
+            let size = transport.max_queue_size(index);
+            if size == 0 { return Err("Virtio Net Device Is Missing A Queue"); }
+            let mut queue = VirtQueue::new(index, size);
+            transport.setup_queue(&mut queue);
+            queues.push(queue);
+        }
+        let tx_queue = queues.pop().expect("Two Queues");
+        let rx_queue = queues.pop().expect("Two Queues");
+
+        let eth_addr = if features & F_MAC != 0 {
+            let mut mac = [0; 6];
+            for (i, byte) in mac.iter_mut().enumerate() {
+                *byte = transport.read_config_u8(i as u16);
+            }
+            EthernetAddress(mac)
+        } else {
+            // Locally Administered, As Nothing Was Assigned, & Told Apart By The PCI Slot
+            EthernetAddress([0x02, 0x00, 0x12, pci_device.bus, pci_device.device, pci_device.function])
+        };
+
+        let tx_queue_size = tx_queue.size() as usize;
+        let mut device = Self {
+            name,
+            header_len: if transport.is_modern() { MODERN_HEADER_LEN } else { LEGACY_HEADER_LEN },
+            rx_buffers: (0..RX_BUFFERS.min(rx_queue.size() as usize)).map(|_| PhysBuf::new(BUFFER_LEN)).collect(),
+            tx_buffers: (0..TX_BUFFERS.min(tx_queue.size() as usize)).map(|_| PhysBuf::new(BUFFER_LEN)).collect(),
+            transport,
+            rx_queue,
+            tx_queue,
+            rx_idle: Vec::new(),
+            tx_free: (0..TX_BUFFERS.min(tx_queue_size)).collect(),
+            features,
+            eth_addr,
+        };
+        for i in 0..device.rx_buffers.len() {
+            let addr = device.rx_buffers[i].addr();
+            device.rx_queue.submit(&[(addr, BUFFER_LEN as u32, true)])?;
+        }
+        device.transport.add_status(super::STATUS_DRIVER_OK);
+        device.transport.notify(&device.rx_queue);
+        Ok(device)
+    }
+
+    pub fn ethernet_addr(&self) -> EthernetAddress {
+        self.eth_addr
+    }
+
+    pub fn name(&self) -> &'static str {
+        self.name
+    }
+
+    pub fn is_modern(&self) -> bool {
+        self.transport.is_modern()
+    }
+
+    /// Takes The Next Frame The Device Has Written, Giving Its Buffer Straight Back
+    fn next_frame(&mut self) -> Option<Vec<u8>> {
+        let (head, len) = self.rx_queue.pop_used()?;
+        let addr = self.rx_queue.desc_addr(head);
+        let index = self.rx_buffers.iter().position(|buf| buf.addr() == addr)?;
+
+        let buf = &self.rx_buffers[index];
+        let len = (len as usize).min(BUFFER_LEN);
+        let mut frame = buf[self.header_len.min(len)..len].to_vec();
+        if buf[0] & HDR_F_NEEDS_CSUM != 0 {
+            let start = u16::from_le_bytes([buf[6], buf[7]]) as usize;
+            let offset = u16::from_le_bytes([buf[8], buf[9]]) as usize;
+            if start + offset + 2 <= frame.len() {
+                let sum = !fold(checksum(0, &frame[start..]));
+                frame[start + offset..start + offset + 2].copy_from_slice(&sum.to_be_bytes());
+            }
+        }
+
+        self.rx_idle.push(index);
+        self.refill();
+        Some(frame)
+    }
+
+    /// Gives Idle Receive Buffers Back To The Device, Keeping Any It Won't Take For Next Time
+    fn refill(&mut self) {
+        while let Some(&index) = self.rx_idle.last() {
+            if let Err(e) = self.rx_queue.submit(&[(self.rx_buffers[index].addr(), BUFFER_LEN as u32, true)]) {
+                log!("VIRTIO-NET {}\n", e);
+                break;
+            }
+            self.rx_idle.pop();
+        }
+        self.transport.notify(&self.rx_queue);
+    }
+
+    /// Takes Back Transmit Buffers The Device Has Finished With
+    fn reclaim(&mut self) {
+        while let Some((head, _)) = self.tx_queue.pop_used() {
+            let addr = self.tx_queue.desc_addr(head);
+            if let Some(index) = self.tx_buffers.iter().position(|buf| buf.addr() == addr) {
+                self.tx_free.push(index);
+            }
+        }
+    }
+
+    fn tx_free(&mut self) -> bool {
+        self.reclaim();
+        !self.tx_free.is_empty()
+    }
+
+    fn offloads_checksums(&self) -> bool {
+        self.features & F_CSUM != 0
+    }
+}
+
+#[doc(hidden)]
+pub struct RxToken {
+    buffer: Vec<u8>,
+}
+
+#[doc(hidden)]
+pub struct TxToken<'a> {
+    device: &'a mut VirtioNet,
+}
+
+impl<'a> Device<'a> for VirtioNet {
+    type RxToken = RxToken;
+    type TxToken = TxToken<'a>;
+
+    fn receive(&'a mut self) -> Option<(Self::RxToken, Self::TxToken)> {
+        let buffer = loop {
+            let frame = self.next_frame()?;
+            if sys::net::accept(self.name, &frame) { break frame; }
+        };
+        Some((RxToken { buffer }, TxToken { device: self }))
+    }
+
+    fn transmit(&'a mut self) -> Option<Self::TxToken> {
+        if !self.tx_free() { return None; }
+        Some(TxToken { device: self })
+    }
+
+    fn capabilities(&self) -> DeviceCapabilities {
+        let mut caps = DeviceCapabilities::default();
+        caps.max_transmission_unit = MTU;
+        caps.max_burst_size = Some(self.tx_buffers.len());
+        if self.offloads_checksums() {
+            // Still Checked On The Way In, The Device Only Finishes Them On The Way Out
+            caps.checksum.tcp = Checksum::Rx;
+            caps.checksum.udp = Checksum::Rx;
+        }
+        caps
+    }
+}
+
+impl<'a> phy::TxToken for TxToken<'a> {
+    fn consume<R, F>(mut self, _: smoltcp::time::Instant, len: usize, f: F) -> smoltcp::Result<R>
+        where F: FnOnce(&mut [u8]) -> smoltcp::Result<R> {
+            let device = &mut self.device;
+            let header_len = device.header_len;
+            if header_len + len > BUFFER_LEN { return Err(smoltcp::Error::Truncated); }
+            if !device.tx_free() { return Err(smoltcp::Error::Exhausted); }
+
+            let index = *device.tx_free.last().expect("Checked Above");
+            let offload = device.offloads_checksums();
+            let buf = &mut device.tx_buffers[index];
+            let res = f(&mut buf[header_len..header_len + len])?;
+
+            buf[..header_len].iter_mut().for_each(|byte| *byte = 0);
+            if offload {
+                if let Some((start, offset, sum)) = partial_checksum(&buf[header_len..header_len + len]) {
+                    let field = header_len + start + offset;
+                    buf[field..field + 2].copy_from_slice(&sum.to_be_bytes());
+                    buf[0] = HDR_F_NEEDS_CSUM;
+                    buf[6..8].copy_from_slice(&(start as u16).to_le_bytes());
+                    buf[8..10].copy_from_slice(&(offset as u16).to_le_bytes());
+                }
+            }
+
+            let addr = buf.addr();
+            device.tx_queue.submit(&[(addr, (header_len + len) as u32, false)]).map_err(|_| smoltcp::Error::Exhausted)?;
+            device.transport.notify(&device.tx_queue);
+            device.tx_free.pop();
+            Ok(res)
+    }
+}
+
+impl phy::RxToken for RxToken {
+    fn consume<R, F>(mut self, _: smoltcp::time::Instant, f: F) -> smoltcp::Result<R>
+        where F: FnOnce(&mut [u8]) -> smoltcp::Result<R> {
+            f(&mut self.buffer)
+    }
+}
+
+/// Brings Up Every Device Found, Skipping Any That Fail
+pub fn init() -> Vec<VirtioNet> {
+    let pci_devices: Vec<pci::DeviceConfig> = pci::PCI_DEVICES.lock().iter()
+        .filter(|dev| dev.vendor_id == VENDOR_ID && (dev.device_id == DEVICE_ID_LEGACY || dev.device_id == DEVICE_ID_MODERN))
+        .copied().collect();
+
+    let mut devices = Vec::new();
+    for mut pci_device in pci_devices {
+        if devices.len() == MAX_CARDS {
+            log!("VIRTIO-NET {:02}:{:02} Ignored, Only {} Cards Are Supported\n", pci_device.bus, pci_device.device, MAX_CARDS);
+            continue;
+        }
+        pci_device.enable_bus_mastering();
+
+        let device = match VirtioNet::new(&pci_device, nic::card_name("virtio-net", devices.len())) {
+            Ok(device) => device,
+            Err(e) => {
+                log!("VIRTIO-NET {:02}:{:02}: {}\n", pci_device.bus, pci_device.device, e);
+                continue;
+            },
+        };
+        log!("NET {} {} MAC {}{}\n", device.name, if device.is_modern() {"Modern"} else {"Legacy"}, device.ethernet_addr(), if device.offloads_checksums() {", Checksum Offload"} else {""});
+
+        TRANSPORTS.lock().push(device.transport);
+        log!("NET {} IRQ {}\n", device.name, pci_device.interrupt_line);
+        idt::set_irq_handler(pci_device.interrupt_line, interrupt_handler);
+        devices.push(device);
+    }
+    devices
+}
+
+/// Acknowledges Every Device, As They May Share The Line, & Leaves The Actual Work To The
+/// Network Service
+pub fn interrupt_handler() {
+    if let Some(mut transports) = TRANSPORTS.try_lock() {
+        for transport in transports.iter_mut() {
+            // Reading The ISR Clears It, Bit 0 Means A Queue Has Used Buffers
+            if transport.isr() & 1 != 0 {
+                sys::net::service::wake();
+            }
+        }
+    }
+}
@@ -0,0 +1,85 @@
+//! COBALTFS, Formatted & Listed The Same Way `sys::storage::fs` Does It
+
+use std::io::{self, Error, ErrorKind};
+
+use crate::cobalt_layout::*;
+use crate::image::{self, Image};
+
+/// Clears The Bitmap, File Table & Journal, Then Writes The Superblock Last So A Half
+/// Formatted Image Never Looks Valid.
+pub fn format(image: &mut Image) -> io::Result<()> {
+    image.ensure_blocks(DATA_ADDR as u64)?;
+    for addr in BITMAP_ADDR..JOURNAL_ADDR + JOURNAL_SIZE as u32 {
+        image.zero(addr)?;
+    }
+
+    let mut block = [0; BLOCK_SIZE];
+    block[..superblock_meta::MAGIC.len()].copy_from_slice(superblock_meta::MAGIC.as_bytes());
+    image::write_u32(&mut block, superblock_meta::VERSION_OFFSET, superblock_meta::VERSION);
+    image::write_u32(&mut block, superblock_meta::JOURNAL_ADDR_OFFSET, JOURNAL_ADDR);
+    image::write_u32(&mut block, superblock_meta::JOURNAL_SIZE_OFFSET, JOURNAL_SIZE as u32);
+    image.write(SUPER_BLOCK_ADDR, &block)
+}
+
+pub fn is_formatted(image: &mut Image) -> io::Result<bool> {
+    let block = image.read(SUPER_BLOCK_ADDR)?;
+    let magic = superblock_meta::MAGIC.as_bytes();
+    Ok(&block[..magic.len()] == magic && block[magic.len()] == 0)
+}
+
+/// Where A Formatted Image's Regions Are, Picked From Its Superblock VERSION Like The Kernel
+/// Does At Mount
+pub struct Mount {
+    pub version: u32,
+    pub data_addr: BlockAddr,
+    pub data_size: usize,
+    /// `(addr, size)`, [None] On VERSION 0 Images Which Predate The Journal
+    pub journal: Option<(BlockAddr, u32)>,
+}
+
+pub fn mount(image: &mut Image) -> io::Result<Mount> {
+    if !is_formatted(image)? {
+        return Err(Error::new(ErrorKind::InvalidData, "Image Isn't Formatted As COBALTFS"));
+    }
+    let block = image.read(SUPER_BLOCK_ADDR)?;
+    let version = image::read_u32(&block, superblock_meta::VERSION_OFFSET);
+    let (data_addr, data_size) = data_region(version);
+    let journal = if version < superblock_meta::VERSION {
+        None
+    } else {
+        Some((image::read_u32(&block, superblock_meta::JOURNAL_ADDR_OFFSET), image::read_u32(&block, superblock_meta::JOURNAL_SIZE_OFFSET)))
+    };
+    Ok(Mount { version, data_addr, data_size, journal })
+}
+
+pub struct Entry {
+    pub record: BlockAddr,
+    pub parent: String,
+    pub name: String,
+    pub file_type: u8,
+    pub flags: u8,
+    pub head: BlockAddr,
+}
+
+/// Every Used File Table Record
+pub fn entries(image: &mut Image) -> io::Result<Vec<Entry>> {
+    let mut entries = Vec::new();
+    for record in FILETABLE_ADDR..FILETABLE_ADDR + FILETABLE_SIZE as u32 {
+        let block = image.read(record)?;
+        let parent_len = block[0] as usize;
+        let name_at = 1 + parent_len;
+        let name_len = block[name_at] as usize;
+        if name_len == 0 { continue; }
+
+        let rest = name_at + 1 + name_len;
+        entries.push(Entry {
+            record,
+            parent: String::from_utf8_lossy(&block[1..name_at]).into_owned(),
+            name: String::from_utf8_lossy(&block[name_at + 1..rest]).into_owned(),
+            file_type: block[rest],
+            flags: block[rest + 1],
+            head: image::read_u32(&block, rest + 2),
+        });
+    }
+    Ok(entries)
+}
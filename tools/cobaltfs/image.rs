@@ -0,0 +1,87 @@
+//! Block Level Access To A Disk Image File
+
+use std::fs::{File, OpenOptions};
+use std::io::{self, Read, Seek, SeekFrom, Write};
+use std::path::Path;
+
+pub const BLOCK_SIZE: usize = 512;
+
+pub type Block = [u8; BLOCK_SIZE];
+
+pub struct Image {
+    file: File,
+}
+
+impl Image {
+    /// Opens An Image, Creating It If It Doesn't Exist Yet
+    pub fn open(path: &Path) -> io::Result<Self> {
+        let file = OpenOptions::new().read(true).write(true).create(true).truncate(false).open(path)?;
+        Ok(Self { file })
+    }
+
+    pub fn blocks(&self) -> io::Result<u64> {
+        Ok(self.file.metadata()?.len() / BLOCK_SIZE as u64)
+    }
+
+    /// Grows The Image To At Least `blocks` Blocks, Existing Contents (Like A Boot Image Or
+    /// Tar Archive At The Start) Are Left Untouched.
+    pub fn ensure_blocks(&mut self, blocks: u64) -> io::Result<()> {
+        if self.blocks()? < blocks {
+            self.file.set_len(blocks * BLOCK_SIZE as u64)?;
+        }
+        Ok(())
+    }
+
+    pub fn read(&mut self, addr: u32) -> io::Result<Block> {
+        let mut block = [0; BLOCK_SIZE];
+        if (addr as u64) < self.blocks()? {
+            self.file.seek(SeekFrom::Start(addr as u64 * BLOCK_SIZE as u64))?;
+            self.file.read_exact(&mut block)?;
+        }
+        Ok(block)
+    }
+
+    pub fn write(&mut self, addr: u32, block: &Block) -> io::Result<()> {
+        self.file.seek(SeekFrom::Start(addr as u64 * BLOCK_SIZE as u64))?;
+        self.file.write_all(block)
+    }
+
+    pub fn zero(&mut self, addr: u32) -> io::Result<()> {
+        self.write(addr, &[0; BLOCK_SIZE])
+    }
+
+    pub fn get_bit(&mut self, (addr, bit): (u32, usize)) -> io::Result<bool> {
+        let block = self.read(addr)?;
+        Ok(block[bit / 8] & (1 << (bit % 8)) != 0)
+    }
+
+    pub fn set_bit(&mut self, (addr, bit): (u32, usize), state: bool) -> io::Result<()> {
+        let mut block = self.read(addr)?;
+        if state {
+            block[bit / 8] |= 1 << (bit % 8);
+        } else {
+            block[bit / 8] &= !(1 << (bit % 8));
+        }
+        self.write(addr, &block)
+    }
+}
+
+pub fn read_u16(block: &Block, offset: usize) -> u16 {
+    u16::from_be_bytes([block[offset], block[offset + 1]])
+}
+
+pub fn read_u32(block: &Block, offset: usize) -> u32 {
+    let mut bytes = [0; 4];
+    bytes.copy_from_slice(&block[offset..offset + 4]);
+    u32::from_be_bytes(bytes)
+}
+
+pub fn write_u16(block: &mut Block, offset: usize, value: u16) -> usize {
+    block[offset..offset + 2].copy_from_slice(&value.to_be_bytes());
+    offset + 2
+}
+
+pub fn write_u32(block: &mut Block, offset: usize, value: u32) -> usize {
+    block[offset..offset + 4].copy_from_slice(&value.to_be_bytes());
+    offset + 4
+}
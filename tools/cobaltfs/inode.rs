@@ -0,0 +1,585 @@
+//! The Inode Filesystem, Encoded Exactly As `sys::vfs::filesystem::Inode` Reads & Writes It
+
+use std::fs;
+use std::io::{self, Error, ErrorKind};
+use std::path::Path;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::image::{self, Block, Image, BLOCK_SIZE};
+use crate::inode_layout::{addressing, filesystem_values::*, inode_flags, inode_meta::*, inode_mode, old_layout, superblock};
+
+/// Seconds Since The Unix Epoch, As Stored In Inode Timestamps
+fn now() -> u32 {
+    SystemTime::now().duration_since(UNIX_EPOCH).map(|time| time.as_secs() as u32).unwrap_or(0)
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Inode {
+    pub index: u32,
+    pub name: String,
+    pub flags: u16,
+    pub parent: Option<u16>,
+    pub mode: u16,
+    pub uid: u16,
+    pub gid: u16,
+    pub created: u32,
+    pub modified: u32,
+    pub accessed: u32,
+    pub links: u16,
+    pub indirect: u32,
+    pub double_indirect: u32,
+    pub children: Vec<u32>,
+    pub size: u32,
+}
+
+fn decode_name(block: &Block) -> String {
+    let name: Vec<u8> = block[..FILENAME_SIZE].iter().map(|b| if *b == 0 { b' ' } else { *b }).collect();
+    String::from_utf8_lossy(&name).trim().to_owned()
+}
+
+fn decode_parent(block: &Block) -> Option<u16> {
+    match image::read_u16(block, PARENT_OFFSET) {
+        NO_PARENT => None,
+        parent => Some(parent),
+    }
+}
+
+impl Inode {
+    fn decode(index: u32, block: &Block) -> Self {
+        let count = (block[CHILD_COUNT_OFFSET] as usize).min(CHILDREN_LEN);
+        let children = (0..count).map(|i| image::read_u32(block, CHILDREN_OFFSET + i * 4)).collect();
+        Self {
+            index,
+            name: decode_name(block),
+            flags: image::read_u16(block, FLAGS_OFFSET),
+            parent: decode_parent(block),
+            mode: image::read_u16(block, MODE_OFFSET),
+            uid: image::read_u16(block, UID_OFFSET),
+            gid: image::read_u16(block, GID_OFFSET),
+            created: image::read_u32(block, CREATED_OFFSET),
+            modified: image::read_u32(block, MODIFIED_OFFSET),
+            accessed: image::read_u32(block, ACCESSED_OFFSET),
+            links: image::read_u16(block, LINKS_OFFSET),
+            indirect: image::read_u32(block, INDIRECT_OFFSET),
+            double_indirect: image::read_u32(block, DOUBLE_INDIRECT_OFFSET),
+            children,
+            size: image::read_u32(block, CHILDREN_OFFSET + count * 4),
+        }
+    }
+
+    /// Inode From An Older Format Version, Mirrors `Inode::read_old` In The Kernel
+    fn decode_old(index: u32, block: &Block, version: u16) -> Self {
+        let flags = image::read_u16(block, FLAGS_OFFSET);
+        let count_offset = old_layout::child_count_offset(version);
+        let count = (block[count_offset] as usize).min(old_layout::children_len(version));
+        let children = (0..count).map(|i| image::read_u32(block, count_offset + 1 + i * 4)).collect();
+        let time = now();
+        let mut inode = Self {
+            index,
+            name: decode_name(block),
+            flags: flags & !(inode_flags::ROOT_READ | inode_flags::ROOT_WRITE | inode_flags::ROOT_EXEC),
+            parent: decode_parent(block),
+            mode: inode_mode::from_v1_flags(flags),
+            uid: 0,
+            gid: 0,
+            created: time,
+            modified: time,
+            accessed: time,
+            links: 1,
+            indirect: NO_BLOCK,
+            double_indirect: NO_BLOCK,
+            children,
+            size: image::read_u32(block, count_offset + 1 + count * 4),
+        };
+
+        if version >= 2 {
+            inode.mode = image::read_u16(block, MODE_OFFSET);
+            inode.uid = image::read_u16(block, UID_OFFSET);
+            inode.gid = image::read_u16(block, GID_OFFSET);
+            inode.created = image::read_u32(block, CREATED_OFFSET);
+            inode.modified = image::read_u32(block, MODIFIED_OFFSET);
+            inode.accessed = image::read_u32(block, ACCESSED_OFFSET);
+        }
+        if version >= 3 {
+            inode.links = image::read_u16(block, LINKS_OFFSET);
+        }
+        inode
+    }
+
+    fn encode(&self) -> Block {
+        let mut block = [0; BLOCK_SIZE];
+        for (i, byte) in block[..FILENAME_SIZE].iter_mut().enumerate() {
+            *byte = *self.name.as_bytes().get(i).unwrap_or(&b' ');
+        }
+        image::write_u16(&mut block, FLAGS_OFFSET, self.flags);
+        image::write_u16(&mut block, PARENT_OFFSET, self.parent.unwrap_or(NO_PARENT));
+        image::write_u16(&mut block, MODE_OFFSET, self.mode);
+        image::write_u16(&mut block, UID_OFFSET, self.uid);
+        image::write_u16(&mut block, GID_OFFSET, self.gid);
+        image::write_u32(&mut block, CREATED_OFFSET, self.created);
+        image::write_u32(&mut block, MODIFIED_OFFSET, self.modified);
+        image::write_u32(&mut block, ACCESSED_OFFSET, self.accessed);
+        image::write_u16(&mut block, LINKS_OFFSET, self.links);
+        image::write_u32(&mut block, INDIRECT_OFFSET, self.indirect);
+        image::write_u32(&mut block, DOUBLE_INDIRECT_OFFSET, self.double_indirect);
+        block[CHILD_COUNT_OFFSET] = self.children.len() as u8;
+        let mut offset = CHILDREN_OFFSET;
+        for child in &self.children {
+            offset = image::write_u32(&mut block, offset, *child);
+        }
+        image::write_u32(&mut block, offset, self.size);
+        block
+    }
+
+    pub fn is_dir(&self) -> bool {
+        self.flags & inode_flags::DIR != 0
+    }
+
+    /// Files & Symlinks Map Data Blocks, Everything Else Lists Inodes
+    pub fn has_data(&self) -> bool {
+        self.flags & (inode_flags::FILE | inode_flags::SYMLINK) != 0
+    }
+
+    pub fn is_symlink(&self) -> bool {
+        self.flags & inode_flags::SYMLINK != 0
+    }
+
+    pub fn is_hardlink(&self) -> bool {
+        self.flags & inode_flags::HARDLINK != 0
+    }
+}
+
+pub struct InodeFs<'a> {
+    image: &'a mut Image,
+}
+
+impl<'a> InodeFs<'a> {
+    pub fn new(image: &'a mut Image) -> Self {
+        Self { image }
+    }
+
+    /// Clears Both Bitmaps & Writes A Fresh Superblock, Growing The Image To Fit The Partition
+    pub fn format(&mut self) -> io::Result<()> {
+        self.image.ensure_blocks(END as u64)?;
+        for i in 0..INODE_BITMAP_SIZE as u32 {
+            self.image.zero(INODE_BITMAP_BASE + i)?;
+        }
+        for i in 0..DATA_BITMAP_SIZE as u32 {
+            self.image.zero(DATA_BITMAP_BASE + i)?;
+        }
+        self.write_version()
+    }
+
+    pub fn version(&mut self) -> io::Result<u16> {
+        let block = self.image.read(addressing::superblock())?;
+        if image::read_u32(&block, superblock::MAGIC_OFFSET) == superblock::MAGIC {
+            return Ok(image::read_u16(&block, superblock::VERSION_OFFSET));
+        }
+        let bitmap = self.image.read(INODE_BITMAP_BASE)?;
+        Ok(if bitmap.iter().all(|byte| *byte == 0) { 0 } else { 1 })
+    }
+
+    fn write_version(&mut self) -> io::Result<()> {
+        let mut block = [0; BLOCK_SIZE];
+        image::write_u32(&mut block, superblock::MAGIC_OFFSET, superblock::MAGIC);
+        image::write_u16(&mut block, superblock::VERSION_OFFSET, superblock::FORMAT_VERSION);
+        self.image.write(addressing::superblock(), &block)
+    }
+
+    /// Errors Unless The Image Is In The Format This Tool Writes
+    pub fn check_version(&mut self) -> io::Result<()> {
+        match self.version()? {
+            superblock::FORMAT_VERSION => Ok(()),
+            version => Err(Error::new(ErrorKind::InvalidData,
+                format!("Image Is Format Version {}, Expected {} (Try 'cobaltfs migrate')", version, superblock::FORMAT_VERSION))),
+        }
+    }
+
+    /// Rewrites An Older Image In The Current Layout, Returning How Many Inodes Moved. Mirrors
+    /// `filesystem::migrate` In The Kernel, Including Moving The Data Region Back & Rebuilding
+    /// Version 0's Bitmaps From Its Inodes.
+    pub fn migrate(&mut self) -> io::Result<usize> {
+        let version = self.version()?;
+        match version {
+            superblock::FORMAT_VERSION => return Ok(0),
+            0..=3 => {},
+            4 => return self.migrate_v4(),
+            _ => return Err(Error::new(ErrorKind::InvalidData, format!("Unknown Format Version {}", version))),
+        }
+
+        let mut inodes = Vec::new();
+        for index in 0..INODE_SIZE as u32 {
+            let block = self.image.read(old_layout::inode(version, index))?;
+            let allocated = match version {
+                0 => old_layout::v0_is_inode(&block),
+                _ => self.image.get_bit(addressing::inode_bitmap(index))?,
+            };
+            if allocated {
+                let inode = Inode::decode_old(index, &block, version);
+                if !inode.has_data() && inode.children.len() > CHILDREN_LEN {
+                    return Err(Error::other(format!("Directory '{}' Has Too Many Children For The Current Format", inode.name)));
+                }
+                inodes.push(inode);
+            }
+        }
+
+        self.image.ensure_blocks(END as u64)?;
+        if version == 0 {
+            for i in 0..DATA_BITMAP_SIZE as u32 {
+                self.image.zero(DATA_BITMAP_BASE + i)?;
+            }
+            for inode in &inodes {
+                self.image.set_bit(addressing::inode_bitmap(inode.index), true)?;
+                if inode.flags & inode_flags::FILE == 0 { continue; }
+                for &index in &inode.children {
+                    let block = self.image.read(old_layout::data(version, index))?;
+                    self.image.write(addressing::data(index), &block)?;
+                    self.image.set_bit(addressing::data_bitmap(index), true)?;
+                }
+            }
+        } else {
+            for index in (0..old_layout::DATA_SIZE as u32).rev() {
+                if self.image.get_bit(addressing::data_bitmap(index))? {
+                    let block = self.image.read(old_layout::data(version, index))?;
+                    self.image.write(addressing::data(index), &block)?;
+                }
+            }
+            for i in 1..DATA_BITMAP_SIZE as u32 {
+                self.image.zero(DATA_BITMAP_BASE + i)?;
+            }
+        }
+
+        for inode in &mut inodes {
+            if inode.children.len() > CHILDREN_LEN {
+                let blocks = std::mem::take(&mut inode.children);
+                self.map_blocks(inode, &blocks)?;
+            }
+            self.write(inode)?;
+        }
+        self.write_version()?;
+        Ok(inodes.len())
+    }
+
+    /// Mirrors `filesystem::migrate_v4`, Moving Version 4's Regions Down Behind The Smaller Data
+    /// Bitmap & Its Quotas To Where The Shorter Free Count List Now Ends
+    fn migrate_v4(&mut self) -> io::Result<usize> {
+        for i in DATA_BITMAP_SIZE..old_layout::V4_DATA_BITMAP_SIZE {
+            if self.image.read(DATA_BITMAP_BASE + i as u32)?.iter().any(|byte| *byte != 0) {
+                return Err(Error::other("Files Use Data Blocks Past The End Of The Current Format"));
+            }
+        }
+
+        let mut count = 0;
+        for index in 0..INODE_SIZE as u32 {
+            if self.image.get_bit(addressing::inode_bitmap(index))? {
+                let block = self.image.read(old_layout::inode(4, index))?;
+                self.image.write(addressing::inode(index), &block)?;
+                count += 1;
+            }
+        }
+        for index in 0..DATA_SIZE as u32 {
+            if self.image.get_bit(addressing::data_bitmap(index))? {
+                let block = self.image.read(old_layout::data(4, index))?;
+                self.image.write(addressing::data(index), &block)?;
+            }
+        }
+
+        let mut block = self.image.read(addressing::superblock())?;
+        let flags = image::read_u16(&block, superblock::FLAGS_OFFSET);
+        image::write_u16(&mut block, superblock::FLAGS_OFFSET, flags & !superblock::flags::SUMMARY);
+        image::write_u16(&mut block, superblock::VERSION_OFFSET, superblock::FORMAT_VERSION);
+        block.copy_within(old_layout::V4_QUOTA_COUNT_OFFSET.., superblock::QUOTA_COUNT_OFFSET);
+        self.image.write(addressing::superblock(), &block)?;
+        Ok(count)
+    }
+
+    pub fn read(&mut self, index: u32) -> io::Result<Inode> {
+        let block = self.image.read(addressing::inode(index))?;
+        Ok(Inode::decode(index, &block))
+    }
+
+    fn write(&mut self, inode: &Inode) -> io::Result<()> {
+        self.image.write(addressing::inode(inode.index), &inode.encode())
+    }
+
+    /// The Kernel Trusts Its Cached Free Counts & Quota Usage Only While They're Marked As
+    /// Current, So Allocating Anything Here Clears The Mark & Has The Kernel Recount
+    fn invalidate_summary(&mut self) -> io::Result<()> {
+        let mut block = self.image.read(addressing::superblock())?;
+        let flags = image::read_u16(&block, superblock::FLAGS_OFFSET);
+        if flags & superblock::flags::SUMMARY != 0 {
+            image::write_u16(&mut block, superblock::FLAGS_OFFSET, flags & !superblock::flags::SUMMARY);
+            self.image.write(addressing::superblock(), &block)?;
+        }
+        Ok(())
+    }
+
+    fn allocate_inode(&mut self) -> io::Result<u32> {
+        for index in 0..INODE_SIZE as u32 {
+            if !self.image.get_bit(addressing::inode_bitmap(index))? {
+                self.invalidate_summary()?;
+                self.image.set_bit(addressing::inode_bitmap(index), true)?;
+                return Ok(index);
+            }
+        }
+        Err(Error::other("No Free Inodes"))
+    }
+
+    fn allocate_data(&mut self) -> io::Result<u32> {
+        for index in 0..DATA_SIZE as u32 {
+            if !self.image.get_bit(addressing::data_bitmap(index))? {
+                self.invalidate_summary()?;
+                self.image.set_bit(addressing::data_bitmap(index), true)?;
+                return Ok(index);
+            }
+        }
+        Err(Error::other("No Free Data Blocks"))
+    }
+
+    /// Every Allocated Inode Without A Parent, i.e. The Contents Of The Root Directory
+    pub fn root(&mut self) -> io::Result<Vec<Inode>> {
+        let mut entries = Vec::new();
+        for index in 0..INODE_SIZE as u32 {
+            if self.image.get_bit(addressing::inode_bitmap(index))? {
+                let inode = self.read(index)?;
+                if inode.parent.is_none() {
+                    entries.push(inode);
+                }
+            }
+        }
+        Ok(entries)
+    }
+
+    /// Entries Of A Directory, `None` Being The Root
+    pub fn entries(&mut self, dir: Option<&Inode>) -> io::Result<Vec<Inode>> {
+        match dir {
+            None => self.root(),
+            Some(dir) => dir.children.iter().map(|child| self.read(*child)).collect(),
+        }
+    }
+
+    /// Resolves A `/` Separated Path, The Empty Path Being The Root
+    pub fn lookup(&mut self, path: &str) -> io::Result<Option<Inode>> {
+        let mut current: Option<Inode> = None;
+        for part in path.split('/').filter(|part| !part.is_empty()) {
+            if let Some(dir) = &current {
+                if !dir.is_dir() {
+                    return Err(Error::new(ErrorKind::NotFound, format!("'{}' Is Not A Directory", dir.name)));
+                }
+            }
+            let entry = self.entries(current.as_ref())?.into_iter().find(|entry| entry.name == part);
+            match entry {
+                Some(entry) => current = Some(entry),
+                None => return Err(Error::new(ErrorKind::NotFound, format!("'{}' Not Found", path))),
+            }
+        }
+        Ok(current)
+    }
+
+    fn create(&mut self, parent: Option<&mut Inode>, name: &str, flags: u16) -> io::Result<Inode> {
+        if name.len() > FILENAME_SIZE {
+            return Err(Error::new(ErrorKind::InvalidInput, format!("Name '{}' Is Longer Than {} Bytes", name, FILENAME_SIZE)));
+        }
+        if self.entries(parent.as_deref())?.iter().any(|entry| entry.name == name) {
+            return Err(Error::new(ErrorKind::AlreadyExists, format!("'{}' Already Exists", name)));
+        }
+        if let Some(parent) = &parent {
+            if parent.children.len() >= CHILDREN_LEN {
+                return Err(Error::other(format!("Directory '{}' Is Full", parent.name)));
+            }
+        }
+
+        let time = now();
+        let inode = Inode {
+            index: self.allocate_inode()?,
+            name: name.to_owned(),
+            flags,
+            parent: parent.as_ref().map(|parent| parent.index as u16),
+            mode: if flags & inode_flags::DIR != 0 { inode_mode::DEFAULT_DIR } else { inode_mode::DEFAULT_FILE },
+            uid: 0,
+            gid: 0,
+            created: time,
+            modified: time,
+            accessed: time,
+            links: 1,
+            indirect: NO_BLOCK,
+            double_indirect: NO_BLOCK,
+            children: Vec::new(),
+            size: 0,
+        };
+        self.write(&inode)?;
+        if let Some(parent) = parent {
+            parent.children.push(inode.index);
+            self.write(parent)?;
+        }
+        Ok(inode)
+    }
+
+    fn read_pointers(&mut self, block: u32) -> io::Result<Vec<u32>> {
+        if block == NO_BLOCK || block >= DATA_SIZE as u32 {
+            return Ok(vec![NO_BLOCK; POINTERS_PER_BLOCK]);
+        }
+        let block = self.image.read(addressing::data(block))?;
+        Ok((0..POINTERS_PER_BLOCK).map(|i| image::read_u32(&block, i * 4)).collect())
+    }
+
+    /// Stores A Pointer Block, `NO_BLOCK` If Every Pointer Is A Hole
+    fn write_pointers(&mut self, pointers: &[u32]) -> io::Result<u32> {
+        if pointers.iter().all(|pointer| *pointer == NO_BLOCK) {
+            return Ok(NO_BLOCK);
+        }
+        let mut block = [0xFF; BLOCK_SIZE];
+        for (i, pointer) in pointers.iter().enumerate() {
+            image::write_u32(&mut block, i * 4, *pointer);
+        }
+        let index = self.allocate_data()?;
+        self.image.write(addressing::data(index), &block)?;
+        Ok(index)
+    }
+
+    /// Maps A Fresh Inode's Logical Blocks, `NO_BLOCK` Entries Being Holes
+    fn map_blocks(&mut self, inode: &mut Inode, blocks: &[u32]) -> io::Result<()> {
+        let direct = blocks.len().min(CHILDREN_LEN);
+        inode.children = blocks[..direct].to_vec();
+        while inode.children.last() == Some(&NO_BLOCK) {
+            inode.children.pop();
+        }
+
+        let rest = &blocks[direct..];
+        let indirect = rest.len().min(POINTERS_PER_BLOCK);
+        inode.indirect = self.write_pointers(&rest[..indirect])?;
+
+        let mut mids = Vec::new();
+        for chunk in rest[indirect..].chunks(POINTERS_PER_BLOCK) {
+            mids.push(self.write_pointers(chunk)?);
+        }
+        inode.double_indirect = self.write_pointers(&mids)?;
+        Ok(())
+    }
+
+    /// Data Block Of Every Logical Block Up To The File's Size, `NO_BLOCK` For Holes
+    fn block_map(&mut self, inode: &Inode) -> io::Result<Vec<u32>> {
+        let count = (inode.size as usize).div_ceil(BLOCK_SIZE);
+        let mut pointers = inode.children.clone();
+        pointers.resize(CHILDREN_LEN, NO_BLOCK);
+        if count > CHILDREN_LEN {
+            pointers.extend(self.read_pointers(inode.indirect)?);
+        }
+        if count > CHILDREN_LEN + POINTERS_PER_BLOCK {
+            for mid in self.read_pointers(inode.double_indirect)? {
+                pointers.extend(self.read_pointers(mid)?);
+            }
+        }
+        pointers.resize(count, NO_BLOCK);
+        Ok(pointers)
+    }
+
+    /// Stores `data`, All Zero Blocks Are Left As Holes
+    pub fn put_file(&mut self, parent: Option<&mut Inode>, name: &str, data: &[u8]) -> io::Result<Inode> {
+        if data.len() > MAX_FILE_SIZE {
+            return Err(Error::other(format!("'{}' Is Larger Than {} Bytes", name, MAX_FILE_SIZE)));
+        }
+
+        let mut inode = self.create(parent, name, inode_flags::FILE)?;
+        let mut blocks = Vec::new();
+        for chunk in data.chunks(BLOCK_SIZE) {
+            if chunk.iter().all(|byte| *byte == 0) {
+                blocks.push(NO_BLOCK);
+                continue;
+            }
+            let index = self.allocate_data()?;
+            let mut block = [0; BLOCK_SIZE];
+            block[..chunk.len()].copy_from_slice(chunk);
+            self.image.write(addressing::data(index), &block)?;
+            blocks.push(index);
+        }
+        self.map_blocks(&mut inode, &blocks)?;
+        inode.size = data.len() as u32;
+        self.write(&inode)?;
+        Ok(inode)
+    }
+
+    pub fn put_dir(&mut self, parent: Option<&mut Inode>, name: &str) -> io::Result<Inode> {
+        self.create(parent, name, inode_flags::DIR)
+    }
+
+    /// Copies A Host Directory's Contents Into `parent`, Recursively
+    pub fn put_tree(&mut self, mut parent: Option<&mut Inode>, host: &Path) -> io::Result<()> {
+        let mut entries: Vec<_> = fs::read_dir(host)?.collect::<Result<_, _>>()?;
+        entries.sort_by_key(|entry| entry.file_name());
+
+        for entry in entries {
+            let name = entry.file_name().to_string_lossy().into_owned();
+            if entry.file_type()?.is_dir() {
+                let mut dir = self.put_dir(parent.as_deref_mut(), &name)?;
+                self.put_tree(Some(&mut dir), &entry.path())?;
+            } else {
+                let data = fs::read(entry.path())?;
+                self.put_file(parent.as_deref_mut(), &name, &data)?;
+            }
+        }
+        Ok(())
+    }
+
+    pub fn read_file(&mut self, inode: &Inode) -> io::Result<Vec<u8>> {
+        let mut data = Vec::with_capacity(inode.size as usize);
+        for index in self.block_map(inode)? {
+            match index {
+                NO_BLOCK => data.resize(data.len() + BLOCK_SIZE, 0),
+                index => data.extend_from_slice(&self.image.read(addressing::data(index))?),
+            }
+        }
+        data.truncate(inode.size as usize);
+        Ok(data)
+    }
+
+    /// The Inode A Hard Link Entry Names
+    pub fn link_target(&mut self, link: &Inode) -> io::Result<Inode> {
+        match link.children.first() {
+            Some(target) => self.read(*target),
+            None => Err(Error::new(ErrorKind::InvalidData, format!("Hard Link '{}' Names Nothing", link.name))),
+        }
+    }
+
+    /// Extracts A File Or Directory Tree Onto The Host, Hard Links Come Out As Copies
+    pub fn get_tree(&mut self, inode: Option<&Inode>, host: &Path) -> io::Result<()> {
+        match inode {
+            Some(inode) if inode.is_hardlink() => {
+                let target = self.link_target(inode)?;
+                fs::write(host, self.read_file(&target)?)
+            },
+            Some(inode) if inode.is_symlink() => {
+                let target = String::from_utf8_lossy(&self.read_file(inode)?).into_owned();
+                std::os::unix::fs::symlink(target, host)
+            },
+            Some(inode) if !inode.is_dir() => fs::write(host, self.read_file(inode)?),
+            _ => {
+                fs::create_dir_all(host)?;
+                for entry in self.entries(inode)? {
+                    self.get_tree(Some(&entry), &host.join(&entry.name))?;
+                }
+                Ok(())
+            }
+        }
+    }
+
+    pub fn print_tree(&mut self, dir: Option<&Inode>, depth: usize) -> io::Result<()> {
+        let mut entries = self.entries(dir)?;
+        entries.sort_by(|a, b| a.name.cmp(&b.name));
+        for entry in entries {
+            if entry.is_hardlink() {
+                let target = self.link_target(&entry)?;
+                println!("{:indent$}{} => Inode {}", "", entry.name, target.index, indent = depth * 2);
+            } else if entry.is_symlink() {
+                let target = String::from_utf8_lossy(&self.read_file(&entry)?).into_owned();
+                println!("{:indent$}{} -> {}", "", entry.name, target, indent = depth * 2);
+            } else if entry.is_dir() {
+                println!("{:indent$}{}/ ({:03o} {}:{}, Inode {})", "", entry.name, entry.mode, entry.uid, entry.gid, entry.index, indent = depth * 2);
+                self.print_tree(Some(&entry), depth + 1)?;
+            } else {
+                println!("{:indent$}{} ({} Bytes, {:03o} {}:{}, Inode {})", "", entry.name, entry.size, entry.mode, entry.uid, entry.gid, entry.index, indent = depth * 2);
+            }
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,313 @@
+//! Host Side Image Tool For The Inode Filesystem & COBALTFS
+//!
+//! Built Straight With `rustc` (See `make cobaltfs`) Since The Kernel's Cargo Config Forces
+//! The Bare Metal Target On Anything Under The Repo. The On-Disk Layouts Are Included From
+//! The Kernel Sources So The Two Can't Drift Apart.
+//!
+//! Usage:
+//!     cobaltfs mkfs <image> [inode|cobalt]
+//!     cobaltfs put <image> <host dir> [dest dir]
+//!     cobaltfs ls <image>
+//!     cobaltfs get <image> <path> <host path>
+//!     cobaltfs migrate <image>
+
+use std::env;
+use std::io::{self, Error, ErrorKind};
+use std::path::Path;
+use std::process;
+
+#[allow(dead_code)]
+#[path = "../../src/sys/vfs/layout.rs"]
+mod inode_layout;
+
+#[allow(dead_code)]
+#[path = "../../src/sys/storage/fs/layout.rs"]
+mod cobalt_layout;
+
+// Images Hold Both Filesystems, The Inode One Has To End Before COBALTFS Starts
+const _: () = assert!(inode_layout::filesystem_values::END <= cobalt_layout::SUPER_BLOCK_ADDR);
+
+mod cobalt;
+mod image;
+mod inode;
+
+use image::Image;
+use inode::InodeFs;
+
+fn usage() -> io::Error {
+    Error::new(ErrorKind::InvalidInput, "Usage: cobaltfs <mkfs|put|ls|get|migrate> <image> [args...]")
+}
+
+fn mkfs(image: &mut Image, kind: &str) -> io::Result<()> {
+    match kind {
+        "inode" => InodeFs::new(image).format(),
+        "cobalt" => cobalt::format(image),
+        _ => Err(Error::new(ErrorKind::InvalidInput, format!("Unknown Filesystem '{}'", kind))),
+    }
+}
+
+fn put(image: &mut Image, host: &Path, dest: &str) -> io::Result<()> {
+    let mut fs = InodeFs::new(image);
+    fs.check_version()?;
+    let mut dir = fs.lookup(dest)?;
+    if let Some(dir) = &dir {
+        if !dir.is_dir() {
+            return Err(Error::new(ErrorKind::InvalidInput, format!("'{}' Is Not A Directory", dest)));
+        }
+    }
+    fs.put_tree(dir.as_mut(), host)
+}
+
+fn ls(image: &mut Image) -> io::Result<()> {
+    let mut fs = InodeFs::new(image);
+    fs.check_version()?;
+    fs.print_tree(None, 0)?;
+
+    if cobalt::is_formatted(image)? {
+        let mount = cobalt::mount(image)?;
+        match mount.journal {
+            Some((addr, size)) => println!("COBALTFS Version {}, Journal At 0x{:06x} ({} Blocks)", mount.version, addr, size),
+            None => println!("COBALTFS Version {}, No Journal", mount.version),
+        }
+        println!("Data At 0x{:06x} ({} Blocks)", mount.data_addr, mount.data_size);
+        println!("COBALTFS File Table:");
+        for entry in cobalt::entries(image)? {
+            println!("  0x{:06x}: {}/{} (Type {}, Flags 0b{:08b}, Head 0x{:06x})",
+                entry.record, entry.parent, entry.name, entry.file_type, entry.flags, entry.head);
+        }
+    }
+    Ok(())
+}
+
+fn get(image: &mut Image, path: &str, host: &Path) -> io::Result<()> {
+    let mut fs = InodeFs::new(image);
+    fs.check_version()?;
+    let inode = fs.lookup(path)?;
+    fs.get_tree(inode.as_ref(), host)
+}
+
+fn migrate(image: &mut Image) -> io::Result<()> {
+    let count = InodeFs::new(image).migrate()?;
+    println!("Migrated {} Inodes", count);
+    Ok(())
+}
+
+fn run(args: &[String]) -> io::Result<()> {
+    if args.len() < 3 { return Err(usage()); }
+    let mut image = Image::open(Path::new(&args[2]))?;
+    let arg = |i: usize| args.get(i).map(|arg| arg.as_str());
+
+    match (args[1].as_str(), arg(3), arg(4)) {
+        ("mkfs", kind, _) => mkfs(&mut image, kind.unwrap_or("inode")),
+        ("put", Some(host), dest) => put(&mut image, Path::new(host), dest.unwrap_or("")),
+        ("ls", _, _) => ls(&mut image),
+        ("get", Some(path), Some(host)) => get(&mut image, path, Path::new(host)),
+        ("migrate", _, _) => migrate(&mut image),
+        _ => Err(usage()),
+    }
+}
+
+fn main() {
+    let args: Vec<String> = env::args().collect();
+    if let Err(e) = run(&args) {
+        eprintln!("cobaltfs: {}", e);
+        process::exit(1);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use super::*;
+
+    #[test]
+    fn put_then_get_round_trips() {
+        let root = env::temp_dir().join(format!("cobaltfs-test-{}", process::id()));
+        let host = root.join("in");
+        fs::create_dir_all(host.join("docs")).unwrap();
+        fs::write(host.join("hello.txt"), b"Hello, Cobalt!").unwrap();
+        fs::write(host.join("docs").join("big.bin"), vec![0xAB; 3000]).unwrap();
+        // Reaches Into The Double Indirect Block & Has A Hole In The Middle
+        let mut huge: Vec<u8> = (0..1 << 20).map(|i| (i % 251) as u8).collect();
+        huge[100_000..200_000].fill(0);
+        fs::write(host.join("huge.bin"), &huge).unwrap();
+
+        let mut image = Image::open(&root.join("drive.img")).unwrap();
+        mkfs(&mut image, "inode").unwrap();
+        // As If The Kernel Had Cached Its Free Counts, Which The Put Makes Stale
+        let superblock = inode_layout::addressing::superblock();
+        let mut block = image.read(superblock).unwrap();
+        image::write_u16(&mut block, inode_layout::superblock::FLAGS_OFFSET, inode_layout::superblock::flags::SUMMARY);
+        image.write(superblock, &block).unwrap();
+        put(&mut image, &host, "").unwrap();
+        get(&mut image, "", &root.join("out")).unwrap();
+        assert_eq!(image::read_u16(&image.read(superblock).unwrap(), inode_layout::superblock::FLAGS_OFFSET), 0);
+
+        assert_eq!(fs::read(root.join("out").join("hello.txt")).unwrap(), b"Hello, Cobalt!");
+        assert_eq!(fs::read(root.join("out").join("docs").join("big.bin")).unwrap(), vec![0xAB; 3000]);
+        assert_eq!(fs::read(root.join("out").join("huge.bin")).unwrap(), huge);
+        assert!(put(&mut image, &host, "").is_err(), "Duplicate Names Should Be Rejected");
+
+        fs::remove_dir_all(root).unwrap();
+    }
+
+    #[test]
+    fn migrates_version_1_images() {
+        let root = env::temp_dir().join(format!("cobaltfs-migrate-{}", process::id()));
+        fs::create_dir_all(&root).unwrap();
+        let mut image = Image::open(&root.join("drive.img")).unwrap();
+        mkfs(&mut image, "inode").unwrap();
+
+        // A Version 1 Image Has No Superblock & Its Children Start Right After PARENT
+        image.zero(inode_layout::addressing::superblock()).unwrap();
+        let mut block = [0; image::BLOCK_SIZE];
+        block[..5].copy_from_slice(b"a.txt");
+        image::write_u16(&mut block, inode_layout::inode_meta::FLAGS_OFFSET,
+            inode_layout::inode_flags::FILE | inode_layout::inode_flags::ROOT_READ | inode_layout::inode_flags::ROOT_WRITE);
+        image::write_u16(&mut block, inode_layout::inode_meta::PARENT_OFFSET, inode_layout::inode_meta::NO_PARENT);
+        let count_offset = inode_layout::old_layout::child_count_offset(1);
+        block[count_offset] = 1;
+        let offset = image::write_u32(&mut block, count_offset + 1, 7);
+        image::write_u32(&mut block, offset, 300);
+        image.write(inode_layout::old_layout::inode(1, 0), &block).unwrap();
+        image.write(inode_layout::old_layout::data(1, 7), &[0x5A; image::BLOCK_SIZE]).unwrap();
+        image.set_bit(inode_layout::addressing::data_bitmap(7), true).unwrap();
+        image.set_bit(inode_layout::addressing::inode_bitmap(0), true).unwrap();
+
+        assert!(ls(&mut image).is_err(), "Old Images Should Be Rejected Until Migrated");
+        migrate(&mut image).unwrap();
+
+        let inode = InodeFs::new(&mut image).read(0).unwrap();
+        assert_eq!((inode.name.as_str(), inode.children.clone(), inode.size), ("a.txt", vec![7], 300));
+        assert_eq!((inode.mode, inode.uid, inode.flags, inode.links), (0o644, 0, inode_layout::inode_flags::FILE, 1));
+        assert!(inode.created > 0);
+        assert_eq!(InodeFs::new(&mut image).read_file(&inode).unwrap(), vec![0x5A; 300]);
+        assert_eq!(InodeFs::new(&mut image).migrate().unwrap(), 0);
+
+        fs::remove_dir_all(root).unwrap();
+    }
+
+    #[test]
+    fn migrates_version_0_images() {
+        use inode_layout::{addressing, inode_flags::{DIR, FILE, ROOT_READ, ROOT_WRITE}, inode_meta::{FLAGS_OFFSET, NO_PARENT, PARENT_OFFSET}, old_layout};
+
+        let root = env::temp_dir().join(format!("cobaltfs-v0-inode-{}", process::id()));
+        fs::create_dir_all(&root).unwrap();
+        let mut image = Image::open(&root.join("drive.img")).unwrap();
+
+        // What The Kernel Wrote Before Its Addressing Was Fixed: No Superblock, Everything A Further
+        // PHYSICAL_OFFSET In & Both Bitmaps In Inode 0's Block, Setting Bits In Its Name
+        let record = |name: &[u8], flags: u16, parent: u16, children: &[u32], size: u32| {
+            let mut block = [0; image::BLOCK_SIZE];
+            block[..name.len()].copy_from_slice(name);
+            image::write_u16(&mut block, FLAGS_OFFSET, flags);
+            image::write_u16(&mut block, PARENT_OFFSET, parent);
+            let count_offset = old_layout::child_count_offset(0);
+            block[count_offset] = children.len() as u8;
+            let mut offset = count_offset + 1;
+            for child in children {
+                offset = image::write_u32(&mut block, offset, *child);
+            }
+            image::write_u32(&mut block, offset, size);
+            block
+        };
+        let mut dir = record(b"old", DIR | ROOT_READ | ROOT_WRITE, NO_PARENT, &[2], 0);
+        dir[0] |= 0b1111; // Inode 0, Data 1, Inode 2 & Data 3 Allocated
+        image.write(old_layout::inode(0, 0), &dir).unwrap();
+        image.write(old_layout::inode(0, 2), &record(b"a.txt", FILE | ROOT_READ | ROOT_WRITE, 0, &[1, 3], 700)).unwrap();
+        image.write(old_layout::inode(0, 5), &[0xFF; image::BLOCK_SIZE]).unwrap();
+        image.write(old_layout::data(0, 1), &[0x11; image::BLOCK_SIZE]).unwrap();
+        image.write(old_layout::data(0, 3), &[0x33; image::BLOCK_SIZE]).unwrap();
+
+        let mut inodes = InodeFs::new(&mut image);
+        assert_eq!(inodes.version().unwrap(), 0);
+        assert_eq!(inodes.migrate().unwrap(), 2);
+        assert_eq!(inodes.version().unwrap(), inode_layout::superblock::FORMAT_VERSION);
+
+        let dir = inodes.read(0).unwrap();
+        assert_eq!((dir.name.as_str(), dir.children.clone(), dir.parent), ("old", vec![2], None));
+        let file = inodes.lookup("old/a.txt").unwrap().unwrap();
+        assert_eq!((file.index, file.children.clone(), file.parent), (2, vec![1, 3], Some(0)));
+        let mut data = vec![0x11; image::BLOCK_SIZE];
+        data.extend_from_slice(&[0x33; 700 - image::BLOCK_SIZE]);
+        assert_eq!(inodes.read_file(&file).unwrap(), data);
+
+        let allocated = |image: &mut Image, bit| image.get_bit(bit).unwrap();
+        assert_eq!([0, 1, 2, 5].map(|i| allocated(&mut image, addressing::inode_bitmap(i))), [true, false, true, false]);
+        assert_eq!([0, 1, 2, 3].map(|i| allocated(&mut image, addressing::data_bitmap(i))), [false, true, false, true]);
+
+        fs::remove_dir_all(root).unwrap();
+    }
+
+    #[test]
+    fn migrates_version_4_images() {
+        use inode_layout::{addressing, filesystem_values::{DATA_BITMAP_BASE, DATA_BITMAP_SIZE}, old_layout, superblock};
+
+        let root = env::temp_dir().join(format!("cobaltfs-v4-{}", process::id()));
+        let host = root.join("in");
+        fs::create_dir_all(&host).unwrap();
+        fs::write(host.join("hello.txt"), b"Hello, Version 4!").unwrap();
+        let mut image = Image::open(&root.join("drive.img")).unwrap();
+        mkfs(&mut image, "inode").unwrap();
+        put(&mut image, &host, "").unwrap();
+
+        // Version 4 Kept Its Inodes & Data Further Out, With Four More Free Counts Before Its Quotas
+        let file = InodeFs::new(&mut image).lookup("hello.txt").unwrap().unwrap();
+        let data = image.read(addressing::data(file.children[0])).unwrap();
+        image.write(old_layout::data(4, file.children[0]), &data).unwrap();
+        image.zero(addressing::data(file.children[0])).unwrap();
+        let inode = image.read(addressing::inode(file.index)).unwrap();
+        image.write(old_layout::inode(4, file.index), &inode).unwrap();
+        image.zero(addressing::inode(file.index)).unwrap();
+        let mut block = image.read(addressing::superblock()).unwrap();
+        image::write_u16(&mut block, superblock::VERSION_OFFSET, 4);
+        image::write_u16(&mut block, superblock::FLAGS_OFFSET, superblock::flags::SUMMARY);
+        block[old_layout::V4_QUOTA_COUNT_OFFSET] = 1;
+        image::write_u16(&mut block, old_layout::V4_QUOTA_COUNT_OFFSET + 1, 1000);
+        image.write(addressing::superblock(), &block).unwrap();
+
+        let past_end = DATA_BITMAP_BASE + DATA_BITMAP_SIZE as u32;
+        image.write(past_end, &[1; image::BLOCK_SIZE]).unwrap();
+        assert!(migrate(&mut image).is_err(), "Blocks Past The New Data Region Should Stop Migration");
+        image.zero(past_end).unwrap();
+        migrate(&mut image).unwrap();
+
+        let block = image.read(addressing::superblock()).unwrap();
+        assert_eq!(image::read_u16(&block, superblock::VERSION_OFFSET), superblock::FORMAT_VERSION);
+        assert_eq!(image::read_u16(&block, superblock::FLAGS_OFFSET) & superblock::flags::SUMMARY, 0);
+        assert_eq!((block[superblock::QUOTA_COUNT_OFFSET], image::read_u16(&block, superblock::QUOTAS_OFFSET)), (1, 1000));
+        let mut inodes = InodeFs::new(&mut image);
+        let file = inodes.lookup("hello.txt").unwrap().unwrap();
+        assert_eq!(inodes.read_file(&file).unwrap(), b"Hello, Version 4!");
+
+        fs::remove_dir_all(root).unwrap();
+    }
+
+    #[test]
+    fn mounts_version_0_cobalt_images() {
+        use cobalt_layout::{addressing, superblock_meta, DATA_ADDR, JOURNAL_ADDR, JOURNAL_SIZE, SUPER_BLOCK_ADDR, V0_DATA_ADDR};
+
+        let root = env::temp_dir().join(format!("cobaltfs-v0-{}", process::id()));
+        fs::create_dir_all(&root).unwrap();
+        let mut image = Image::open(&root.join("drive.img")).unwrap();
+        mkfs(&mut image, "cobalt").unwrap();
+        let mount = cobalt::mount(&mut image).unwrap();
+        assert_eq!((mount.data_addr, mount.journal), (DATA_ADDR, Some((JOURNAL_ADDR, JOURNAL_SIZE as u32))));
+
+        // A VERSION 0 Superblock Is Just The Magic, Its First Data Block Is Where The Journal Now Is
+        let mut block = image.read(SUPER_BLOCK_ADDR).unwrap();
+        block[superblock_meta::VERSION_OFFSET..].fill(0);
+        image.write(SUPER_BLOCK_ADDR, &block).unwrap();
+        image.set_bit(addressing::bitmap(V0_DATA_ADDR, V0_DATA_ADDR), true).unwrap();
+
+        let mount = cobalt::mount(&mut image).unwrap();
+        assert_eq!((mount.version, mount.data_addr, mount.journal), (0, V0_DATA_ADDR, None));
+        assert_eq!(mount.data_size, cobalt_layout::DATA_SIZE + JOURNAL_SIZE);
+        // The Bitmap Is Read Relative To Where The Image's Data Actually Starts
+        assert!(image.get_bit(addressing::bitmap(mount.data_addr, V0_DATA_ADDR)).unwrap());
+        assert!(!image.get_bit(addressing::bitmap(mount.data_addr, V0_DATA_ADDR + 1)).unwrap());
+
+        fs::remove_dir_all(root).unwrap();
+    }
+}